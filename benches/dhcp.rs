@@ -0,0 +1,27 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+fn discover() -> Vec<u8> {
+    let mut packet = vec![0x01, 0x01, 0x06, 0x00, 0x3d, 0x1d, 0x63, 0x2a, 0x00, 0x00, 0x80, 0x00];
+    packet.extend_from_slice(&[0; 16]);
+    packet.extend_from_slice(&[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42]);
+    packet.extend_from_slice(&[0; 10 + 64 + 128]);
+    packet.extend_from_slice(&[0x63, 0x82, 0x53, 0x63, 0x35, 0x01, 0x01, 0x3d, 0x07, 0x01, 0x00, 0x0b, 0x82, 0x01,
+                               0xfc, 0x42, 0x32, 0x04, 0xc0, 0xa8, 0x00, 0x0a, 0x37, 0x04, 0x01, 0x03, 0x06, 0x2a,
+                               0xff]);
+    packet
+}
+
+#[bench]
+fn dhcp_discover(bencher: &mut Bencher) {
+    let mut parser = DhcpParser;
+    let input = discover();
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
@@ -0,0 +1,36 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static QUERY: &'static [u8] =
+    &[0x12, 0x34, 0x01, 0x20, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70,
+      0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x29, 0x04, 0xd0, 0x00, 0x00,
+      0x80, 0x00, 0x00, 0x17, 0x00, 0x08, 0x00, 0x07, 0x00, 0x01, 0x18, 0x00, 0xc0, 0x00, 0x02, 0x00, 0x0a, 0x00,
+      0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+
+static RESPONSE: &'static [u8] =
+    &[0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x03, 0x77, 0x77, 0x77, 0x07, 0x65,
+      0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0x01, 0xc0, 0x0c, 0x00,
+      0x05, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x02, 0xc0, 0x10, 0xc0, 0x10, 0x00, 0x01, 0x00, 0x01, 0x00,
+      0x00, 0x01, 0x2c, 0x00, 0x04, 0x5d, 0xb8, 0xd8, 0x22];
+
+#[bench]
+fn dns_query(bencher: &mut Bencher) {
+    let mut parser = DnsParser::new();
+    bencher.iter(|| {
+        parser.parse(QUERY, None, None).unwrap();
+    });
+    bencher.bytes = QUERY.len() as u64;
+}
+
+#[bench]
+fn dns_compressed_response(bencher: &mut Bencher) {
+    let mut parser = DnsParser::new();
+    bencher.iter(|| {
+        parser.parse(RESPONSE, None, None).unwrap();
+    });
+    bencher.bytes = RESPONSE.len() as u64;
+}
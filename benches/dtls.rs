@@ -0,0 +1,22 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x01, 0x00, 0x00, 0x35, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x35, 0xfe, 0xfd, 0x5b, 0x0e, 0x6a, 0x13, 0x8c, 0x44, 0x2a, 0x71, 0x0f,
+      0x9e, 0x52, 0x33, 0xd1, 0x0a, 0x7c, 0x61, 0xe8, 0x04, 0x93, 0x2f, 0xb7, 0x19, 0x5d, 0xc2, 0x86, 0x3e, 0xa0,
+      0x77, 0x08, 0xf1, 0x4b, 0x26, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x2b, 0x01, 0x00, 0x00, 0x09, 0x00, 0x10,
+      0x00, 0x05, 0x00, 0x03, 0x02, 0x68, 0x32];
+
+#[bench]
+fn dtls_small_packet(bencher: &mut Bencher) {
+    let mut parser = DtlsParser::new();
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
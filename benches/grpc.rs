@@ -0,0 +1,50 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+// A length-prefixed message with a string, a varint, packed varints and a nested message
+static BODY: &'static [u8] = &[0x00, 0x00, 0x00, 0x00, 0x0f, 0x0a, 0x02, 0x68, 0x69, 0x10, 0x03, 0x1a, 0x03, 0x01,
+                               0xac, 0x02, 0x22, 0x02, 0x08, 0x01];
+
+#[bench]
+fn grpc_request(bencher: &mut Bencher) {
+    let mut parser = GrpcParser::new();
+    let header = |key: &str, value: &str| {
+        HttpHeader {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            raw_value: None,
+        }
+    };
+    let result: ParserResultVec = vec![Box::new(Http2Packet {
+                                                    preface: false,
+                                                    frames: vec![],
+                                                    messages: vec![Http2Message {
+                                                                       stream_id: 1,
+                                                                       from_client: true,
+                                                                       promised: false,
+                                                                       headers: vec![header(":path", "/echo.Echo/Say"),
+                                                                                     header("content-type",
+                                                                                            "application/grpc")],
+                                                                       body: BODY.to_vec(),
+                                                                       trailers: vec![],
+                                                                       truncated: false,
+                                                                   }],
+                                                })];
+    bencher.iter(|| {
+        parser.parse(&[], Some(&result), None).unwrap();
+    });
+    bencher.bytes = BODY.len() as u64;
+}
+
+#[bench]
+fn protobuf_decode_raw(bencher: &mut Bencher) {
+    let message = &BODY[5..];
+    bencher.iter(|| {
+        ProtobufDescriptorSet::decode_raw(message).unwrap();
+    });
+    bencher.bytes = message.len() as u64;
+}
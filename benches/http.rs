@@ -42,7 +42,7 @@ static HTTP_RESPONSE: &'static [u8] =
 
 #[bench]
 fn http_request(bencher: &mut Bencher) {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     bencher.iter(|| {
         parser.parse(HTTP_REQUEST, None, None).unwrap();
     });
@@ -51,7 +51,7 @@ fn http_request(bencher: &mut Bencher) {
 
 #[bench]
 fn http_request_large_payload(bencher: &mut Bencher) {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let mut input = Vec::from(HTTP_REQUEST);
     input.extend_from_slice(&[0xff; 1450]);
     bencher.iter(|| {
@@ -62,7 +62,7 @@ fn http_request_large_payload(bencher: &mut Bencher) {
 
 #[bench]
 fn http_response(bencher: &mut Bencher) {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     bencher.iter(|| {
         parser.parse(HTTP_RESPONSE, None, None).unwrap();
     });
@@ -71,7 +71,7 @@ fn http_response(bencher: &mut Bencher) {
 
 #[bench]
 fn http_response_large_payload(bencher: &mut Bencher) {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let mut input = Vec::from(HTTP_RESPONSE);
     input.extend_from_slice(&[0xff; 1450]);
     bencher.iter(|| {
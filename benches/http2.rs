@@ -0,0 +1,31 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+// Preface, SETTINGS and HEADERS of POST /upload with a huffman encoded header block
+static PACKET: &'static [u8] =
+    &[0x50, 0x52, 0x49, 0x20, 0x2a, 0x20, 0x48, 0x54, 0x54, 0x50, 0x2f, 0x32, 0x2e, 0x30, 0x0d, 0x0a, 0x0d, 0x0a,
+      0x53, 0x4d, 0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1c,
+      0x01, 0x05, 0x00, 0x00, 0x00, 0x01, 0x83, 0x86, 0x44, 0x85, 0x62, 0xda, 0xe8, 0x38, 0xe4, 0x41, 0x88, 0x2f,
+      0x91, 0xd3, 0x5d, 0x05, 0x5c, 0x87, 0xa7, 0x5f, 0x87, 0x49, 0x7c, 0xa5, 0x8a, 0xe8, 0x19, 0xaa];
+
+#[bench]
+fn http2_request(bencher: &mut Bencher) {
+    let mut parser = Http2Parser::new();
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
+
+#[bench]
+fn hpack_decode(bencher: &mut Bencher) {
+    let block = &PACKET[42..];
+    bencher.iter(|| {
+        HpackDecoder::new().decode(block).unwrap();
+    });
+    bencher.bytes = block.len() as u64;
+}
@@ -0,0 +1,18 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static QUERY: &'static [u8] = &[0x8f, 0x2e, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x77,
+                                 0x70, 0x61, 0x64, 0x00, 0x00, 0x01, 0x00, 0x01];
+
+#[bench]
+fn llmnr_query(bencher: &mut Bencher) {
+    let mut parser = LlmnrParser;
+    bencher.iter(|| {
+        parser.parse(QUERY, None, None).unwrap();
+    });
+    bencher.bytes = QUERY.len() as u64;
+}
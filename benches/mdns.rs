@@ -0,0 +1,20 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static QUERY: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x09, 0x5f, 0x73, 0x65, 0x72, 0x76,
+      0x69, 0x63, 0x65, 0x73, 0x07, 0x5f, 0x64, 0x6e, 0x73, 0x2d, 0x73, 0x64, 0x04, 0x5f, 0x75, 0x64, 0x70, 0x05,
+      0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x80, 0x01];
+
+#[bench]
+fn mdns_query(bencher: &mut Bencher) {
+    let mut parser = MdnsParser;
+    bencher.iter(|| {
+        parser.parse(QUERY, None, None).unwrap();
+    });
+    bencher.bytes = QUERY.len() as u64;
+}
@@ -0,0 +1,33 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static RTP: &'static [u8] = &[0x80, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xa0, 0x12, 0x34, 0x56, 0x78, 0xff, 0xff,
+                              0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+
+static RTCP: &'static [u8] =
+    &[0x81, 0xc8, 0x00, 0x0c, 0x11, 0x11, 0x11, 0x11, 0xe4, 0xa0, 0x1c, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x01,
+      0x5f, 0x90, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x3e, 0x80, 0x22, 0x22, 0x22, 0x22, 0x40, 0xff, 0xff, 0xff,
+      0x00, 0x01, 0x00, 0x05, 0x00, 0x00, 0x00, 0x10, 0x12, 0x34, 0x56, 0x78, 0x00, 0x01, 0x00, 0x00, 0x81, 0xca,
+      0x00, 0x04, 0x11, 0x11, 0x11, 0x11, 0x01, 0x09, 0x75, 0x73, 0x65, 0x72, 0x40, 0x68, 0x6f, 0x73, 0x74, 0x00];
+
+#[bench]
+fn rtp(bencher: &mut Bencher) {
+    let mut parser = RtpParser::new();
+    bencher.iter(|| {
+        parser.parse(RTP, None, None).unwrap();
+    });
+    bencher.bytes = RTP.len() as u64;
+}
+
+#[bench]
+fn rtcp(bencher: &mut Bencher) {
+    let mut parser = RtpParser::new();
+    bencher.iter(|| {
+        parser.parse(RTCP, None, None).unwrap();
+    });
+    bencher.bytes = RTCP.len() as u64;
+}
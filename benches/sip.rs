@@ -0,0 +1,43 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static INVITE: &'static [u8] = b"INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+                                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+                                 Max-Forwards: 70\r\n\
+                                 To: Bob <sip:bob@biloxi.com>\r\n\
+                                 From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+                                 Call-ID: a84b4c76e66710@pc33.atlanta.com\r\n\
+                                 CSeq: 314159 INVITE\r\n\
+                                 Contact: <sip:alice@pc33.atlanta.com>\r\n\
+                                 Content-Type: application/sdp\r\n\
+                                 Content-Length: 119\r\n\
+                                 \r\n\
+                                 v=0\r\n\
+                                 o=alice 2890844526 2890844526 IN IP4 pc33.atlanta.com\r\n\
+                                 s=-\r\n\
+                                 c=IN IP4 192.0.2.101\r\n\
+                                 t=0 0\r\n\
+                                 m=audio 49172 RTP/AVP 0\r\n";
+
+#[bench]
+fn sip_invite(bencher: &mut Bencher) {
+    let mut parser = SipParser::new();
+    bencher.iter(|| {
+        parser.parse(INVITE, None, None).unwrap();
+    });
+    bencher.bytes = INVITE.len() as u64;
+}
+
+#[bench]
+fn sip_sdp(bencher: &mut Bencher) {
+    let mut parser = SipParser::new();
+    let packet = parser.parse(INVITE, None, None).unwrap().1;
+    let message = &packet.downcast_ref::<SipPacket>().unwrap().messages[0];
+    bencher.iter(|| {
+        message.media_endpoints();
+    });
+}
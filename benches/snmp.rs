@@ -0,0 +1,20 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static GET_REQUEST: &'static [u8] = &[0x30, 0x29, 0x02, 0x01, 0x01, 0x04, 0x06, 0x70, 0x75, 0x62, 0x6c, 0x69, 0x63,
+                                      0xa0, 0x1c, 0x02, 0x04, 0x12, 0x34, 0x56, 0x78, 0x02, 0x01, 0x00, 0x02, 0x01,
+                                      0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01,
+                                      0x01, 0x00, 0x05, 0x00];
+
+#[bench]
+fn snmp_get_request(bencher: &mut Bencher) {
+    let mut parser = SnmpParser;
+    bencher.iter(|| {
+        parser.parse(GET_REQUEST, None, None).unwrap();
+    });
+    bencher.bytes = GET_REQUEST.len() as u64;
+}
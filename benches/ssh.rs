@@ -0,0 +1,54 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static ALGORITHMS: &'static [&'static str] =
+    &["curve25519-sha256,ecdh-sha2-nistp256,diffie-hellman-group14-sha256", "ssh-ed25519,rsa-sha2-512",
+      "chacha20-poly1305@openssh.com,aes128-ctr", "chacha20-poly1305@openssh.com,aes128-ctr",
+      "umac-64-etm@openssh.com,hmac-sha2-256", "umac-64-etm@openssh.com,hmac-sha2-256", "none,zlib@openssh.com",
+      "none,zlib@openssh.com", "", ""];
+
+fn handshake() -> Vec<u8> {
+    let mut payload = vec![20];
+    payload.extend_from_slice(&[0; 16]);
+    for list in ALGORITHMS {
+        payload.extend_from_slice(&[0, 0, (list.len() >> 8) as u8, list.len() as u8]);
+        payload.extend_from_slice(list.as_bytes());
+    }
+    payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let length = 1 + payload.len() + padding;
+
+    let mut input = b"SSH-2.0-OpenSSH_9.6\r\n".to_vec();
+    input.extend_from_slice(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8,
+                              padding as u8]);
+    input.extend(payload);
+    input.extend(vec![0; padding]);
+    input
+}
+
+#[bench]
+fn ssh_kexinit(bencher: &mut Bencher) {
+    let mut parser = SshParser::new();
+    let input = handshake();
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
+
+#[bench]
+fn ssh_hassh(bencher: &mut Bencher) {
+    let mut parser = SshParser::new();
+    let packet = parser.parse(&handshake(), None, None).unwrap().1;
+    let packet = packet.downcast_ref::<SshPacket>().unwrap();
+    bencher.iter(|| {
+        packet.fingerprint().unwrap();
+    });
+}
@@ -0,0 +1,30 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static RFC5424: &'static [u8] = b"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+                                  [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"] \
+                                  An application event log entry";
+
+static BSD: &'static [u8] = b"<86>Feb  5 17:32:18 10.0.0.99 sshd[1234]: Accepted publickey for root";
+
+#[bench]
+fn syslog_rfc5424(bencher: &mut Bencher) {
+    let mut parser = SyslogParser::new();
+    bencher.iter(|| {
+        parser.parse(RFC5424, None, None).unwrap();
+    });
+    bencher.bytes = RFC5424.len() as u64;
+}
+
+#[bench]
+fn syslog_bsd(bencher: &mut Bencher) {
+    let mut parser = SyslogParser::new();
+    bencher.iter(|| {
+        parser.parse(BSD, None, None).unwrap();
+    });
+    bencher.bytes = BSD.len() as u64;
+}
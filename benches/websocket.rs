@@ -0,0 +1,46 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x00, 0x50, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+fn packet(payload: &[u8], from_client: bool) -> Vec<u8> {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(payload);
+    if !from_client {
+        for i in 0..4 {
+            packet.swap(26 + i, 30 + i);
+        }
+        for i in 0..2 {
+            packet.swap(34 + i, 36 + i);
+        }
+    }
+    packet
+}
+
+#[bench]
+fn websocket_masked_frame(bencher: &mut Bencher) {
+    let mut peel = PeelIp::default();
+    peel.traverse(&packet(b"GET /chat HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+                          true),
+                  vec![]);
+    peel.traverse(&packet(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                            Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n",
+                          false),
+                  vec![]);
+
+    // A masked text message "Hello"
+    let frame = packet(&[0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58], true);
+    bencher.iter(|| {
+        peel.traverse(&frame, vec![]);
+    });
+    bencher.bytes = frame.len() as u64;
+}
@@ -19,7 +19,7 @@ impl Parsable<PathIp> for ArpParser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // Check the parent node for the correct EtherType
-                    Some(ref any) => {
+                    Some(any) => {
                         if let Some(eth) = any.downcast_ref::<EthernetPacket>() {
                             if eth.ethertype == EtherType::Arp {
                                 Some(())
@@ -18,7 +18,7 @@ impl Parsable<PathIp> for IcmpParser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // ICMP on top of IPv4
-                    Some(ref any) => if let Some(ipv4) = any.downcast_ref::<Ipv4Packet>() {
+                    Some(any) => if let Some(ipv4) = any.downcast_ref::<Ipv4Packet>() {
                         if ipv4.protocol == IpProtocol::Icmp {
                             Some(())
                         } else {
@@ -44,7 +44,7 @@ impl Parsable<PathIp> for IcmpParser {
             data: cond!((message_type == IcmpType::EchoReply ||
                          message_type == IcmpType::EchoRequest) &&
                         code == 0,
-                        map!(IcmpEcho::parse, |x| IcmpData::Echo(x))) >>
+                        map!(IcmpEcho::parse, IcmpData::Echo)) >>
 
             // Return the parsing result
             (Box::new(IcmpPacket {
@@ -18,7 +18,7 @@ impl Parsable<PathIp> for Icmpv6Parser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // ICMPv6 on top of IPv6
-                    Some(ref any) => if let Some(ipv6) = any.downcast_ref::<Ipv6Packet>() {
+                    Some(any) => if let Some(ipv6) = any.downcast_ref::<Ipv6Packet>() {
                         if ipv6.next_header == IpProtocol::Icmpv6 {
                             Some(())
                         } else {
@@ -44,7 +44,7 @@ impl Parsable<PathIp> for Icmpv6Parser {
             data: cond!((message_type == Icmpv6Type::EchoReply ||
                          message_type == Icmpv6Type::EchoRequest) &&
                         code == 0,
-                        map!(IcmpEcho::parse, |x| Icmpv6Data::Echo(x))) >>
+                        map!(IcmpEcho::parse, Icmpv6Data::Echo)) >>
 
             // Return the parsing result
             (Box::new(Icmpv6Packet {
@@ -19,7 +19,7 @@ impl Parsable<PathIp> for Ipv4Parser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // Check the correct EtherType or IPv4 in IPv4 encapsulation
-                    Some(ref any) => match (any.downcast_ref::<EthernetPacket>(),
+                    Some(any) => match (any.downcast_ref::<EthernetPacket>(),
                                             any.downcast_ref::<Ipv4Packet>()) {
 
                         // Ethernet
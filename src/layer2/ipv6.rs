@@ -20,7 +20,7 @@ impl Parsable<PathIp> for Ipv6Parser {
                 Some(vector) => match vector.last() {
 
                     // Check the correct EtherType or IP encapsulation
-                    Some(ref any) => match (any.downcast_ref::<EthernetPacket>(),
+                    Some(any) => match (any.downcast_ref::<EthernetPacket>(),
                                             any.downcast_ref::<Ipv4Packet>(),
                                             any.downcast_ref::<Ipv6Packet>()) {
 
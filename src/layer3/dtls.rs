@@ -0,0 +1,507 @@
+//! Datagram Transport Layer Security related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+/// The maximum length of a fragmented handshake message which will be reassembled, which is
+/// also the limit for all messages in reassembly per direction
+const MAX_HANDSHAKE_LENGTH: u32 = 0x40000;
+
+/// The maximum amount of handshake messages per direction which are reassembled at the same time
+const MAX_REASSEMBLIES: usize = 8;
+
+/// The maximum amount of connections which are tracked at the same time, where the least recently
+/// used one is dropped first
+const MAX_CONNECTIONS: usize = 4096;
+
+/// The amount of records covered by the anti-replay window
+const REPLAY_WINDOW_SIZE: u64 = 64;
+
+/// The DTLS parser
+#[derive(Debug, Default)]
+pub struct DtlsParser {
+    /// Tracked connections, where the sequence numbers and handshake fragments are stored
+    connections: HashMap<Option<Identifier<IpProtocol>>, DtlsConnection>,
+
+    /// The number of the next datagram, which orders the connections by their use
+    next_datagram: u64,
+}
+
+impl DtlsParser {
+    /// Create a new DTLS parser without any tracked connections
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process the records of a datagram with respect to the state of the connection
+    fn process(
+        &mut self,
+        connection: Option<(Identifier<IpProtocol>, bool)>,
+        records: Vec<DtlsRawRecord>,
+    ) -> Option<DtlsPacket> {
+        let (identifier, direction) = match connection {
+            Some((identifier, direction)) => (Some(identifier), direction),
+            None => (None, true),
+        };
+
+        // Encrypted records are only accepted if a handshake was seen before, since the unified
+        // header is too weak to identify DTLS on its own
+        if !self.connections.contains_key(&identifier) {
+            match records.first() {
+                Some(&DtlsRawRecord::Plaintext { .. }) => {}
+                _ => return None,
+            }
+        }
+
+        // Make room for a new connection by dropping the least recently used one
+        if self.connections.len() >= MAX_CONNECTIONS && !self.connections.contains_key(&identifier) {
+            let oldest = self.connections
+                .iter()
+                .min_by_key(|&(_, connection)| connection.last_datagram)
+                .map(|(identifier, _)| identifier.clone());
+            if let Some(oldest) = oldest {
+                debug!("Dropping the least recently used DTLS connection");
+                self.connections.remove(&oldest);
+            }
+        }
+
+        let mut closed = false;
+        let packet = {
+            let connection = self.connections
+                .entry(identifier.clone())
+                .or_default();
+            connection.last_datagram = self.next_datagram;
+            self.next_datagram += 1;
+            let flow = &mut connection.flows[direction as usize];
+
+            DtlsPacket {
+                records: records
+                    .into_iter()
+                    .map(|record| match record {
+                        DtlsRawRecord::Plaintext {
+                            content_type,
+                            version,
+                            epoch,
+                            sequence_number,
+                            fragment,
+                        } => {
+                            let replay = flow.is_replay(epoch, sequence_number);
+                            let body = if epoch == 0 {
+                                flow.decode_body(&content_type, fragment)
+                            } else {
+                                // Encrypted record
+                                None
+                            };
+                            if let Some(DtlsRecordBody::Alert(ref alert)) = body {
+                                closed |= alert.level == TlsAlertLevel::Fatal
+                                    || alert.description == TlsAlertDescription::CloseNotify;
+                            }
+                            DtlsRecord::Plaintext(DtlsPlaintext {
+                                content_type: content_type,
+                                version: version,
+                                epoch: epoch,
+                                sequence_number: sequence_number,
+                                length: fragment.len() as u16,
+                                replay: replay,
+                                body: body,
+                            })
+                        }
+                        DtlsRawRecord::Ciphertext(ciphertext) => DtlsRecord::Ciphertext(ciphertext),
+                    })
+                    .collect(),
+            }
+        };
+
+        // Forget about connections which are not usable any more
+        if closed {
+            self.connections.remove(&identifier);
+        }
+
+        Some(packet)
+    }
+}
+
+impl Parsable<PathIp> for DtlsParser {
+    /// Parse a `DtlsPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        do_parse!(
+            input,
+            // Check the transport protocol from the parent parser (UDP)
+            connection: expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    // Check the parent node for the correct transport protocol
+                    Some(any) => any
+                        .downcast_ref::<UdpPacket>()
+                        .map(|udp| connection_identifier(result, udp.header.source_port, udp.header.dest_port)),
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(None),
+            }) >> records: many1!(complete!(DtlsRawRecord::parse))
+                >> eof!()
+                >> packet: expr_opt!(self.process(connection, records))
+                >> (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for DtlsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DTLS")
+    }
+}
+
+#[derive(Debug, Default)]
+/// The state of a single DTLS connection, separated by direction
+struct DtlsConnection {
+    flows: [DtlsFlow; 2],
+
+    /// The number of the last datagram of the connection
+    last_datagram: u64,
+}
+
+#[derive(Debug, Default)]
+/// The state of a single direction of a DTLS connection
+struct DtlsFlow {
+    /// The highest sequence number and the anti-replay window per epoch
+    windows: HashMap<u16, (u64, u64)>,
+
+    /// Handshake messages in reassembly per message sequence number
+    handshakes: HashMap<u16, DtlsReassembly>,
+}
+
+impl DtlsFlow {
+    /// Check if a record was already seen before and update the anti-replay window
+    fn is_replay(&mut self, epoch: u16, sequence_number: u64) -> bool {
+        let window = self.windows.entry(epoch).or_insert((sequence_number, 0));
+        if sequence_number > window.0 {
+            let shift = sequence_number - window.0;
+            window.1 = if shift >= REPLAY_WINDOW_SIZE {
+                0
+            } else {
+                window.1 << shift
+            };
+            window.0 = sequence_number;
+        }
+
+        // Records outside of the window are treated like replayed ones
+        let offset = window.0 - sequence_number;
+        if offset >= REPLAY_WINDOW_SIZE {
+            return true;
+        }
+        let bit = 1 << offset;
+        let replay = window.1 & bit != 0;
+        window.1 |= bit;
+        replay
+    }
+
+    /// Decode an unencrypted record body
+    fn decode_body(
+        &mut self,
+        content_type: &TlsRecordContentType,
+        fragment: &[u8],
+    ) -> Option<DtlsRecordBody> {
+        match *content_type {
+            TlsRecordContentType::ChangeCipherSpec if fragment.len() == 1 => {
+                Some(DtlsRecordBody::ChangeCipherSpec(fragment[0]))
+            }
+            TlsRecordContentType::Alert if fragment.len() == 2 => match (
+                TlsAlertLevel::from_u8(fragment[0]),
+                TlsAlertDescription::from_u8(fragment[1]),
+            ) {
+                (Some(level), Some(description)) => Some(DtlsRecordBody::Alert(TlsAlert {
+                    level: level,
+                    description: description,
+                })),
+                _ => None,
+            },
+            TlsRecordContentType::Handshake => {
+                match DtlsHandshakeFragment::parse_list(fragment) {
+                    IResult::Done(_, fragments) => Some(DtlsRecordBody::Handshake(
+                        fragments
+                            .into_iter()
+                            .map(|(mut handshake, data)| {
+                                handshake.message = self.reassemble(&handshake, data);
+                                handshake
+                            })
+                            .collect(),
+                    )),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Add a handshake fragment and return the decoded message if it is complete
+    fn reassemble(&mut self, handshake: &DtlsHandshakeFragment, data: &[u8]) -> Option<TlsHandshake> {
+        // Unfragmented messages need no buffering
+        if handshake.fragment_offset == 0 && handshake.fragment_length == handshake.length {
+            return TlsHandshake::decode(handshake.message_type, data, true);
+        }
+        if handshake.length > MAX_HANDSHAKE_LENGTH {
+            return None;
+        }
+
+        // Limit the amount and the total size of the messages in reassembly
+        let buffered: usize = self.handshakes
+            .iter()
+            .filter(|&(message_seq, _)| *message_seq != handshake.message_seq)
+            .map(|(_, reassembly)| reassembly.data.len())
+            .sum();
+        if (!self.handshakes.contains_key(&handshake.message_seq) && self.handshakes.len() >= MAX_REASSEMBLIES)
+            || buffered + handshake.length as usize > MAX_HANDSHAKE_LENGTH as usize
+        {
+            debug!("Dropping DTLS handshake fragment because too many messages are in reassembly");
+            return None;
+        }
+
+        let complete = {
+            let reassembly = self.handshakes
+                .entry(handshake.message_seq)
+                .or_insert_with(|| DtlsReassembly::new(handshake.message_type, handshake.length));
+
+            // The message was restarted with different parameters
+            if reassembly.message_type != handshake.message_type
+                || reassembly.data.len() != handshake.length as usize
+            {
+                *reassembly = DtlsReassembly::new(handshake.message_type, handshake.length);
+            }
+            reassembly.add(handshake.fragment_offset as usize, data);
+            reassembly.missing == 0
+        };
+
+        if complete {
+            self.handshakes
+                .remove(&handshake.message_seq)
+                .and_then(|r| TlsHandshake::decode(r.message_type, &r.data, true))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A handshake message in reassembly
+struct DtlsReassembly {
+    message_type: TlsHandshakeType,
+    data: Vec<u8>,
+    received: Vec<bool>,
+    missing: usize,
+}
+
+impl DtlsReassembly {
+    fn new(message_type: TlsHandshakeType, length: u32) -> Self {
+        DtlsReassembly {
+            message_type: message_type,
+            data: vec![0; length as usize],
+            received: vec![false; length as usize],
+            missing: length as usize,
+        }
+    }
+
+    /// Add the data at the given offset, overlapping fragments are allowed
+    fn add(&mut self, offset: usize, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            let position = offset + i;
+            if !self.received[position] {
+                self.received[position] = true;
+                self.data[position] = *byte;
+                self.missing -= 1;
+            }
+        }
+    }
+}
+
+/// A DTLS record before the connection state was applied
+enum DtlsRawRecord<'a> {
+    Plaintext {
+        content_type: TlsRecordContentType,
+        version: TlsRecordVersion,
+        epoch: u16,
+        sequence_number: u64,
+        fragment: &'a [u8],
+    },
+    Ciphertext(DtlsCiphertext),
+}
+
+impl<'a> DtlsRawRecord<'a> {
+    fn parse(input: &'a [u8]) -> IResult<&'a [u8], DtlsRawRecord<'a>> {
+        alt!(
+            input,
+            do_parse!(
+                content_type: map_opt!(be_u8, TlsRecordContentType::from_u8)
+                    >> version: verify!(take!(2), |v: &[u8]| v == [0xfe, 0xff] || v == [0xfe, 0xfd])
+                    >> epoch: be_u16
+                    >> sequence_number: bits!(take_bits!(u64, 48))
+                    >> fragment: length_data!(be_u16)
+                    >> (DtlsRawRecord::Plaintext {
+                        content_type: content_type,
+                        version: TlsRecordVersion {
+                            major: version[0],
+                            minor: version[1],
+                        },
+                        epoch: epoch,
+                        sequence_number: sequence_number,
+                        fragment: fragment,
+                    })
+            ) | map!(call!(DtlsCiphertext::parse), DtlsRawRecord::Ciphertext)
+        )
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a Datagram Transport Layer Security packet, which contains one or more
+/// records
+pub struct DtlsPacket {
+    /// The records of the datagram
+    pub records: Vec<DtlsRecord>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DTLS record
+pub enum DtlsRecord {
+    /// A record with the full header, which is used by DTLS 1.0, 1.2 and the unprotected records
+    /// of DTLS 1.3
+    Plaintext(DtlsPlaintext),
+
+    /// A protected DTLS 1.3 record with the unified header
+    Ciphertext(DtlsCiphertext),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DTLS record with the full header
+pub struct DtlsPlaintext {
+    /// Content type of the record
+    pub content_type: TlsRecordContentType,
+
+    /// DTLS version of the record, where 254.255 is DTLS 1.0 and 254.253 is DTLS 1.2
+    pub version: TlsRecordVersion,
+
+    /// Counter which is incremented on every cipher state change
+    pub epoch: u16,
+
+    /// The 48 bit sequence number of the record within the epoch
+    pub sequence_number: u64,
+
+    /// Record length
+    pub length: u16,
+
+    /// Set if the same epoch and sequence number were already seen before, or if the record is
+    /// too old to be checked
+    pub replay: bool,
+
+    /// The decoded record body, if the content type is supported and the body is not encrypted
+    pub body: Option<DtlsRecordBody>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A decoded DTLS record body
+pub enum DtlsRecordBody {
+    /// Change Cipher Spec message, which should always contain the value 1
+    ChangeCipherSpec(u8),
+
+    /// Alert message
+    Alert(TlsAlert),
+
+    /// Handshake message fragments
+    Handshake(Vec<DtlsHandshakeFragment>),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A fragment of a DTLS handshake message
+pub struct DtlsHandshakeFragment {
+    /// The type of the handshake message
+    pub message_type: TlsHandshakeType,
+
+    /// The length of the whole message
+    pub length: u32,
+
+    /// The sequence number of the message within the handshake
+    pub message_seq: u16,
+
+    /// The offset of this fragment within the message
+    pub fragment_offset: u32,
+
+    /// The length of this fragment
+    pub fragment_length: u32,
+
+    /// The decoded handshake message, available as soon as all fragments have been received
+    pub message: Option<TlsHandshake>,
+}
+
+impl DtlsHandshakeFragment {
+    named!(parse_list<&[u8], Vec<(DtlsHandshakeFragment, &[u8])> >,
+        terminated!(many1!(complete!(DtlsHandshakeFragment::parse)), eof!())
+    );
+
+    named!(parse<&[u8], (DtlsHandshakeFragment, &[u8])>,
+        do_parse!(
+            message_type: map_opt!(be_u8, TlsHandshakeType::from_u8) >>
+            length: be_u24 >>
+            message_seq: be_u16 >>
+            fragment_offset: be_u24 >>
+            data: verify!(length_data!(be_u24),
+                          |d: &[u8]| fragment_offset as usize + d.len() <= length as usize) >>
+            ((DtlsHandshakeFragment {
+                message_type: message_type,
+                length: length,
+                message_seq: message_seq,
+                fragment_offset: fragment_offset,
+                fragment_length: data.len() as u32,
+                message: None,
+            }, data))
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A protected DTLS 1.3 record with the unified header
+pub struct DtlsCiphertext {
+    /// Set if a connection ID is present. Its length is negotiated during the encrypted part of
+    /// the handshake, which is why the remaining header fields cannot be read in this case.
+    pub connection_id: bool,
+
+    /// The low order two bits of the epoch
+    pub epoch: u8,
+
+    /// The low order 8 or 16 bits of the encrypted sequence number
+    pub sequence_number: Option<u16>,
+
+    /// The length of the encrypted record
+    pub length: u16,
+}
+
+impl DtlsCiphertext {
+    named!(parse<&[u8], DtlsCiphertext>,
+        do_parse!(
+            flags: bits!(tuple!(tag_bits!(u8, 3, 0b001),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 2))) >>
+            header: switch!(value!(flags.1 == 1),
+                // The connection ID length is unknown, so consume the rest of the datagram
+                true => map!(call!(rest), |r: &[u8]| (None, r.len() as u16)) |
+                false => do_parse!(
+                    sequence_number: alt!(cond_reduce!(flags.2 == 1, be_u16) |
+                                          map!(be_u8, u16::from)) >>
+                    encrypted: alt!(cond_reduce!(flags.3 == 1, length_data!(be_u16)) | call!(rest)) >>
+                    (Some(sequence_number), encrypted.len() as u16)
+                )
+            ) >>
+            (DtlsCiphertext {
+                connection_id: flags.1 == 1,
+                epoch: flags.4,
+                sequence_number: header.0,
+                length: header.1,
+            })
+        )
+    );
+}
@@ -1,44 +1,113 @@
 //! Transport Layer packets
+pub mod dtls;
 pub mod tcp;
 pub mod tls;
 pub mod udp;
 
 use path::error::PathResult;
 use prelude::*;
+use std::collections::HashMap;
 
 /// Track a connection based in the current parsing result and return the connection data
-pub fn track_connection<'a>(
-    path: Option<&'a mut PathIp>,
+pub fn track_connection(
+    path: Option<&mut PathIp>,
     result: Option<&ParserResultVec>,
     src_port: u16,
     dst_port: u16,
 ) -> PathResult<()> {
     // Get the identifier
-    let identifier = match result {
+    let identifier = ip_endpoints(result)
+        .map(|(src, dst, protocol)| Identifier::new(src, src_port, dst, dst_port, protocol));
+
+    // Just track the connection, do nothing additional with the data
+    if let (Some(path), Some(identifier)) = (path, identifier) {
+        path.track(identifier)?;
+    }
+
+    Ok(())
+}
+
+/// Get the identifier of the connection for the current parsing result, which is the same for
+/// both directions of the connection. The returned flag is true if the packet was sent from the
+/// lower to the greater endpoint, which distinguishes the directions.
+pub fn connection_identifier(
+    result: Option<&ParserResultVec>,
+    src_port: u16,
+    dst_port: u16,
+) -> Option<(Identifier<IpProtocol>, bool)> {
+    ip_endpoints(result).map(|(src, dst, protocol)| {
+        if (src, src_port) <= (dst, dst_port) {
+            (Identifier::new(src, src_port, dst, dst_port, protocol), true)
+        } else {
+            (Identifier::new(dst, dst_port, src, src_port, protocol), false)
+        }
+    })
+}
+
+/// The state of TCP streams per connection and direction, like the data of incomplete messages
+/// which is buffered until the next segment of the same direction
+#[derive(Debug)]
+pub(crate) struct TcpStreams<T> {
+    streams: HashMap<(Option<Identifier<IpProtocol>>, bool), T>,
+}
+
+impl<T: Default + PartialEq> TcpStreams<T> {
+    /// Create a new empty set of streams
+    pub fn new() -> Self {
+        TcpStreams { streams: HashMap::new() }
+    }
+
+    /// Process a segment with the state of its direction, where the given direction is used if
+    /// the connection can not be identified. The state of the connection is removed if the
+    /// processing fails.
+    pub fn process<F, O>(
+        &mut self,
+        result: Option<&ParserResultVec>,
+        tcp: &TcpPacket,
+        direction: bool,
+        f: F,
+    ) -> Option<O>
+    where
+        F: FnOnce(&mut T) -> Option<O>,
+    {
+        let key = match connection_identifier(result, tcp.header.source_port, tcp.header.dest_port) {
+            Some((identifier, direction)) => (Some(identifier), direction),
+            None => (None, direction),
+        };
+        let mut state = self.streams.remove(&key).unwrap_or_default();
+        let output = f(&mut state);
+
+        // The connection is closed or can not be followed any more
+        if tcp.header.flag_fin || tcp.header.flag_rst || output.is_none() {
+            self.streams.remove(&(key.0, !key.1));
+        } else if state != T::default() {
+            self.streams.insert(key, state);
+        }
+        output
+    }
+}
+
+impl<T: Default + PartialEq> Default for TcpStreams<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the source address, destination address and protocol of the IP packet within the result
+pub fn ip_endpoints(result: Option<&ParserResultVec>) -> Option<(IpAddr, IpAddr, IpProtocol)> {
+    match result {
         Some(vector) => {
             match vector.get(1) {
-                Some(ref any) => {
+                Some(any) => {
                     match (
                         any.downcast_ref::<Ipv4Packet>(),
                         any.downcast_ref::<Ipv6Packet>(),
                     ) {
                         // IPv4
-                        (Some(p), _) => Some(Identifier::new(
-                            IpAddr::V4(p.src),
-                            src_port,
-                            IpAddr::V4(p.dst),
-                            dst_port,
-                            p.protocol,
-                        )),
+                        (Some(p), _) => Some((IpAddr::V4(p.src), IpAddr::V4(p.dst), p.protocol)),
 
                         // IPv6
-                        (_, Some(p)) => Some(Identifier::new(
-                            IpAddr::V6(p.src),
-                            src_port,
-                            IpAddr::V6(p.dst),
-                            dst_port,
-                            p.next_header,
-                        )),
+                        (_, Some(p)) => Some((IpAddr::V6(p.src), IpAddr::V6(p.dst), p.next_header)),
 
                         _ => None,
                     }
@@ -49,12 +118,5 @@ pub fn track_connection<'a>(
             }
         }
         None => None,
-    };
-
-    // Just track the connection, do nothing additional with the data
-    if let (Some(path), Some(identifier)) = (path, identifier) {
-        path.track(identifier)?;
     }
-
-    Ok(())
 }
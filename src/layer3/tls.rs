@@ -19,8 +19,8 @@ impl Parsable<PathIp> for TlsParser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // Check the parent node for the correct transport protocol
-                    Some(ref any) => {
-                        if let Some(_) = any.downcast_ref::<TcpPacket>() {
+                    Some(any) => {
+                        if any.downcast_ref::<TcpPacket>().is_some() {
                             Some(())
                         } else {
                             None
@@ -35,6 +35,7 @@ impl Parsable<PathIp> for TlsParser {
             }) >> content_type: map_opt!(be_u8, TlsRecordContentType::from_u8)
                 >> version: take!(2)
                 >> length: be_u16
+                >> body: peek!(apply!(TlsRecordBody::parse, &content_type, length))
                 >> (Box::new(TlsPacket {
                     content_type: content_type,
                     version: TlsRecordVersion {
@@ -42,6 +43,7 @@ impl Parsable<PathIp> for TlsParser {
                         minor: version[1],
                     },
                     length: length,
+                    body: body,
                 }))
         )
     }
@@ -64,6 +66,9 @@ pub struct TlsPacket {
 
     /// Record length
     pub length: u16,
+
+    /// The decoded record body, if the content type is supported and the body is not encrypted
+    pub body: Option<TlsRecordBody>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -100,7 +105,7 @@ impl TlsRecordContentType {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 /// TLS record protocol version
 pub struct TlsRecordVersion {
     /// Major part of the TLS version
@@ -109,3 +114,657 @@ pub struct TlsRecordVersion {
     /// Minor part of the TLS version
     pub minor: u8,
 }
+
+#[derive(Debug, Eq, PartialEq)]
+/// A decoded TLS record body
+pub enum TlsRecordBody {
+    /// Change Cipher Spec message, which should always contain the value 1
+    ChangeCipherSpec(u8),
+
+    /// Alert message
+    Alert(TlsAlert),
+
+    /// Heartbeat message
+    Heartbeat(TlsHeartbeat),
+
+    /// Handshake messages, decoded if they are completely contained in the record
+    Handshake(Vec<TlsHandshake>),
+}
+
+impl TlsRecordBody {
+    fn parse<'a>(
+        input: &'a [u8],
+        content_type: &TlsRecordContentType,
+        length: u16,
+    ) -> IResult<&'a [u8], Option<TlsRecordBody>> {
+        match *content_type {
+            // Encrypted messages have a different length and are not decoded
+            TlsRecordContentType::ChangeCipherSpec if length == 1 => opt!(
+                input,
+                complete!(map!(be_u8, TlsRecordBody::ChangeCipherSpec))
+            ),
+            TlsRecordContentType::Alert if length == 2 => opt!(
+                input,
+                complete!(do_parse!(
+                    level: map_opt!(be_u8, TlsAlertLevel::from_u8)
+                        >> description: map_opt!(be_u8, TlsAlertDescription::from_u8)
+                        >> (TlsRecordBody::Alert(TlsAlert {
+                            level: level,
+                            description: description,
+                        }))
+                ))
+            ),
+            TlsRecordContentType::Heartbeat => opt!(
+                input,
+                complete!(do_parse!(
+                    message_type: map_opt!(be_u8, TlsHeartbeatMessageType::from_u8)
+                        >> payload_length: be_u16
+                        >> (TlsRecordBody::Heartbeat(TlsHeartbeat {
+                            message_type: message_type,
+                            payload_length: payload_length,
+                            payload_overflow: TlsHeartbeat::overflows(payload_length, length),
+                        }))
+                ))
+            ),
+            TlsRecordContentType::Handshake => opt!(
+                input,
+                complete!(map!(
+                    flat_map!(
+                        take!(length),
+                        terminated!(many1!(complete!(TlsHandshake::parse)), eof!())
+                    ),
+                    TlsRecordBody::Handshake
+                ))
+            ),
+            _ => IResult::Done(input, None),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A TLS alert message
+pub struct TlsAlert {
+    /// The severity of the alert
+    pub level: TlsAlertLevel,
+
+    /// The reason for the alert
+    pub description: TlsAlertDescription,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// TLS alert severity levels
+pub enum TlsAlertLevel {
+    /// The connection may continue
+    Warning,
+
+    /// The connection will be terminated immediately
+    Fatal,
+}
+
+impl TlsAlertLevel {
+    /// Convert a u8 to an `TlsAlertLevel`. Returns None if the level is not supported or generally
+    /// invalid.
+    pub fn from_u8(input: u8) -> Option<TlsAlertLevel> {
+        match input {
+            1 => Some(TlsAlertLevel::Warning),
+            2 => Some(TlsAlertLevel::Fatal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// TLS alert descriptions
+pub enum TlsAlertDescription {
+    /// The sender will not send any more messages on this connection
+    CloseNotify,
+
+    /// An inappropriate message was received
+    UnexpectedMessage,
+
+    /// A record was received with an incorrect MAC
+    BadRecordMac,
+
+    /// A record was decrypted in an invalid way
+    DecryptionFailed,
+
+    /// A record exceeded the maximum allowed length
+    RecordOverflow,
+
+    /// The decompression function received improper input
+    DecompressionFailure,
+
+    /// No acceptable set of security parameters could be negotiated
+    HandshakeFailure,
+
+    /// No certificate is available (SSLv3 only)
+    NoCertificate,
+
+    /// A certificate was corrupt or its signature did not verify
+    BadCertificate,
+
+    /// A certificate was of an unsupported type
+    UnsupportedCertificate,
+
+    /// A certificate was revoked by its signer
+    CertificateRevoked,
+
+    /// A certificate has expired or is not currently valid
+    CertificateExpired,
+
+    /// Some other issue arose in processing the certificate
+    CertificateUnknown,
+
+    /// A field in the handshake was out of range or inconsistent with other fields
+    IllegalParameter,
+
+    /// The CA certificate could not be located or matched
+    UnknownCa,
+
+    /// A valid certificate was received, but the access control denied the negotiation
+    AccessDenied,
+
+    /// A message could not be decoded
+    DecodeError,
+
+    /// A handshake cryptographic operation failed
+    DecryptError,
+
+    /// A negotiation was not in compliance with export restrictions
+    ExportRestriction,
+
+    /// The protocol version is recognized but not supported
+    ProtocolVersion,
+
+    /// The server requires more secure parameters than the client supports
+    InsufficientSecurity,
+
+    /// An internal error unrelated to the peer occurred
+    InternalError,
+
+    /// An inappropriate protocol version fallback was detected
+    InappropriateFallback,
+
+    /// The handshake is being canceled for a reason unrelated to a protocol failure
+    UserCanceled,
+
+    /// A renegotiation request was rejected
+    NoRenegotiation,
+
+    /// A mandatory extension was not sent
+    MissingExtension,
+
+    /// An extension was received which is prohibited in the message
+    UnsupportedExtension,
+
+    /// A certificate could not be obtained from a given URL
+    CertificateUnobtainable,
+
+    /// The server name indication is not recognized
+    UnrecognizedName,
+
+    /// An invalid or unacceptable OCSP response was received
+    BadCertificateStatusResponse,
+
+    /// A certificate hash value did not match
+    BadCertificateHashValue,
+
+    /// No acceptable PSK identity was provided
+    UnknownPskIdentity,
+
+    /// A certificate was required but none was provided
+    CertificateRequired,
+
+    /// None of the protocols advertised via ALPN are supported
+    NoApplicationProtocol,
+}
+
+impl TlsAlertDescription {
+    /// Convert a u8 to an `TlsAlertDescription`. Returns None if the description is not supported
+    /// or generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsAlertDescription> {
+        match input {
+            0 => Some(TlsAlertDescription::CloseNotify),
+            10 => Some(TlsAlertDescription::UnexpectedMessage),
+            20 => Some(TlsAlertDescription::BadRecordMac),
+            21 => Some(TlsAlertDescription::DecryptionFailed),
+            22 => Some(TlsAlertDescription::RecordOverflow),
+            30 => Some(TlsAlertDescription::DecompressionFailure),
+            40 => Some(TlsAlertDescription::HandshakeFailure),
+            41 => Some(TlsAlertDescription::NoCertificate),
+            42 => Some(TlsAlertDescription::BadCertificate),
+            43 => Some(TlsAlertDescription::UnsupportedCertificate),
+            44 => Some(TlsAlertDescription::CertificateRevoked),
+            45 => Some(TlsAlertDescription::CertificateExpired),
+            46 => Some(TlsAlertDescription::CertificateUnknown),
+            47 => Some(TlsAlertDescription::IllegalParameter),
+            48 => Some(TlsAlertDescription::UnknownCa),
+            49 => Some(TlsAlertDescription::AccessDenied),
+            50 => Some(TlsAlertDescription::DecodeError),
+            51 => Some(TlsAlertDescription::DecryptError),
+            60 => Some(TlsAlertDescription::ExportRestriction),
+            70 => Some(TlsAlertDescription::ProtocolVersion),
+            71 => Some(TlsAlertDescription::InsufficientSecurity),
+            80 => Some(TlsAlertDescription::InternalError),
+            86 => Some(TlsAlertDescription::InappropriateFallback),
+            90 => Some(TlsAlertDescription::UserCanceled),
+            100 => Some(TlsAlertDescription::NoRenegotiation),
+            109 => Some(TlsAlertDescription::MissingExtension),
+            110 => Some(TlsAlertDescription::UnsupportedExtension),
+            111 => Some(TlsAlertDescription::CertificateUnobtainable),
+            112 => Some(TlsAlertDescription::UnrecognizedName),
+            113 => Some(TlsAlertDescription::BadCertificateStatusResponse),
+            114 => Some(TlsAlertDescription::BadCertificateHashValue),
+            115 => Some(TlsAlertDescription::UnknownPskIdentity),
+            116 => Some(TlsAlertDescription::CertificateRequired),
+            120 => Some(TlsAlertDescription::NoApplicationProtocol),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A TLS heartbeat message
+pub struct TlsHeartbeat {
+    /// Request or response
+    pub message_type: TlsHeartbeatMessageType,
+
+    /// The length of the payload claimed by the sender
+    pub payload_length: u16,
+
+    /// Set if the claimed payload length exceeds the record length, which indicates a
+    /// Heartbleed (CVE-2014-0160) style probe
+    pub payload_overflow: bool,
+}
+
+impl TlsHeartbeat {
+    /// Returns true if the claimed payload together with the message type, the length field and
+    /// the minimum padding of 16 bytes does not fit into a record of the given length.
+    pub fn overflows(payload_length: u16, record_length: u16) -> bool {
+        payload_length as usize + 3 + 16 > record_length as usize
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// TLS heartbeat message types
+pub enum TlsHeartbeatMessageType {
+    /// Heartbeat request
+    Request,
+
+    /// Heartbeat response
+    Response,
+}
+
+impl TlsHeartbeatMessageType {
+    /// Convert a u8 to an `TlsHeartbeatMessageType`. Returns None if the type is not supported or
+    /// generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsHeartbeatMessageType> {
+        match input {
+            1 => Some(TlsHeartbeatMessageType::Request),
+            2 => Some(TlsHeartbeatMessageType::Response),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A decoded TLS handshake message
+pub enum TlsHandshake {
+    /// The first message sent by the client
+    ClientHello(TlsClientHello),
+
+    /// The answer of the server to the client hello
+    ServerHello(TlsServerHello),
+
+    /// A stateless cookie exchange used by DTLS servers
+    HelloVerifyRequest(TlsHelloVerifyRequest),
+
+    /// Any other handshake message, which is not decoded any further
+    Other(TlsHandshakeType),
+}
+
+impl TlsHandshake {
+    named!(parse<&[u8], TlsHandshake>,
+        do_parse!(
+            message_type: map_opt!(be_u8, TlsHandshakeType::from_u8) >>
+            body: length_data!(be_u24) >>
+            message: expr_opt!(TlsHandshake::decode(message_type, body, false)) >>
+            (message)
+        )
+    );
+
+    /// Decode a complete handshake message body of the given type. The `dtls` flag indicates
+    /// that the message was sent via Datagram Transport Layer Security, which adds a cookie to the
+    /// client hello. Returns None if the body is invalid.
+    pub fn decode(message_type: TlsHandshakeType, body: &[u8], dtls: bool) -> Option<TlsHandshake> {
+        match message_type {
+            TlsHandshakeType::ClientHello => TlsClientHello::parse(body, dtls)
+                .to_full_result()
+                .ok()
+                .map(TlsHandshake::ClientHello),
+            TlsHandshakeType::ServerHello => TlsServerHello::parse(body)
+                .to_full_result()
+                .ok()
+                .map(TlsHandshake::ServerHello),
+            TlsHandshakeType::HelloVerifyRequest => TlsHelloVerifyRequest::parse(body)
+                .to_full_result()
+                .ok()
+                .map(TlsHandshake::HelloVerifyRequest),
+            _ => Some(TlsHandshake::Other(message_type)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// TLS handshake message types
+pub enum TlsHandshakeType {
+    /// Hello Request
+    HelloRequest,
+
+    /// Client Hello
+    ClientHello,
+
+    /// Server Hello
+    ServerHello,
+
+    /// Hello Verify Request (DTLS only)
+    HelloVerifyRequest,
+
+    /// New Session Ticket
+    NewSessionTicket,
+
+    /// End Of Early Data
+    EndOfEarlyData,
+
+    /// Encrypted Extensions
+    EncryptedExtensions,
+
+    /// Certificate
+    Certificate,
+
+    /// Server Key Exchange
+    ServerKeyExchange,
+
+    /// Certificate Request
+    CertificateRequest,
+
+    /// Server Hello Done
+    ServerHelloDone,
+
+    /// Certificate Verify
+    CertificateVerify,
+
+    /// Client Key Exchange
+    ClientKeyExchange,
+
+    /// Finished
+    Finished,
+
+    /// Certificate Status
+    CertificateStatus,
+
+    /// Key Update
+    KeyUpdate,
+
+    /// Message Hash
+    MessageHash,
+}
+
+impl TlsHandshakeType {
+    /// Convert a u8 to an `TlsHandshakeType`. Returns None if the type is not supported or
+    /// generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsHandshakeType> {
+        match input {
+            0 => Some(TlsHandshakeType::HelloRequest),
+            1 => Some(TlsHandshakeType::ClientHello),
+            2 => Some(TlsHandshakeType::ServerHello),
+            3 => Some(TlsHandshakeType::HelloVerifyRequest),
+            4 => Some(TlsHandshakeType::NewSessionTicket),
+            5 => Some(TlsHandshakeType::EndOfEarlyData),
+            8 => Some(TlsHandshakeType::EncryptedExtensions),
+            11 => Some(TlsHandshakeType::Certificate),
+            12 => Some(TlsHandshakeType::ServerKeyExchange),
+            13 => Some(TlsHandshakeType::CertificateRequest),
+            14 => Some(TlsHandshakeType::ServerHelloDone),
+            15 => Some(TlsHandshakeType::CertificateVerify),
+            16 => Some(TlsHandshakeType::ClientKeyExchange),
+            20 => Some(TlsHandshakeType::Finished),
+            22 => Some(TlsHandshakeType::CertificateStatus),
+            24 => Some(TlsHandshakeType::KeyUpdate),
+            254 => Some(TlsHandshakeType::MessageHash),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A TLS client hello message
+pub struct TlsClientHello {
+    /// The highest protocol version supported by the client
+    pub version: TlsRecordVersion,
+
+    /// 32 bytes of random data
+    pub random: Vec<u8>,
+
+    /// The session to be resumed, may be empty
+    pub session_id: Vec<u8>,
+
+    /// The cookie from a previous hello verify request, only available for DTLS
+    pub cookie: Option<Vec<u8>>,
+
+    /// The cipher suites supported by the client in order of preference
+    pub cipher_suites: Vec<u16>,
+
+    /// The compression methods supported by the client
+    pub compression_methods: Vec<u8>,
+
+    /// The client hello extensions
+    pub extensions: Vec<TlsExtension>,
+}
+
+impl TlsClientHello {
+    fn parse(input: &[u8], dtls: bool) -> IResult<&[u8], TlsClientHello> {
+        do_parse!(
+            input,
+            version: take!(2)
+                >> random: take!(32)
+                >> session_id: length_data!(be_u8)
+                >> cookie: cond!(dtls, length_data!(be_u8))
+                >> cipher_suites: length_value!(be_u16, many0!(be_u16))
+                >> compression_methods: length_data!(be_u8)
+                >> extensions: call!(TlsExtension::parse_list)
+                >> (TlsClientHello {
+                    version: TlsRecordVersion {
+                        major: version[0],
+                        minor: version[1],
+                    },
+                    random: random.to_vec(),
+                    session_id: session_id.to_vec(),
+                    cookie: cookie.map(Vec::from),
+                    cipher_suites: cipher_suites,
+                    compression_methods: compression_methods.to_vec(),
+                    extensions: extensions,
+                })
+        )
+    }
+
+    /// The first host name of the server name indication extension, if available
+    pub fn server_name(&self) -> Option<&str> {
+        self.extensions
+            .iter()
+            .filter_map(|e| match *e {
+                TlsExtension::ServerName(ref names) => names.first(),
+                _ => None,
+            })
+            .next()
+            .map(|name| name.as_str())
+    }
+
+    /// The application layer protocols offered by the client
+    pub fn alpn_protocols(&self) -> Vec<&str> {
+        TlsExtension::alpn_protocols(&self.extensions)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A TLS server hello message
+pub struct TlsServerHello {
+    /// The protocol version selected by the server
+    pub version: TlsRecordVersion,
+
+    /// 32 bytes of random data
+    pub random: Vec<u8>,
+
+    /// The session identifier, may be empty
+    pub session_id: Vec<u8>,
+
+    /// The cipher suite selected by the server
+    pub cipher_suite: u16,
+
+    /// The compression method selected by the server
+    pub compression_method: u8,
+
+    /// The server hello extensions
+    pub extensions: Vec<TlsExtension>,
+}
+
+impl TlsServerHello {
+    named!(parse<&[u8], TlsServerHello>,
+        do_parse!(
+            version: take!(2) >>
+            random: take!(32) >>
+            session_id: length_data!(be_u8) >>
+            cipher_suite: be_u16 >>
+            compression_method: be_u8 >>
+            extensions: call!(TlsExtension::parse_list) >>
+            (TlsServerHello {
+                version: TlsRecordVersion {
+                    major: version[0],
+                    minor: version[1],
+                },
+                random: random.to_vec(),
+                session_id: session_id.to_vec(),
+                cipher_suite: cipher_suite,
+                compression_method: compression_method,
+                extensions: extensions,
+            })
+        )
+    );
+
+    /// The application layer protocol selected by the server, if available
+    pub fn alpn_protocol(&self) -> Option<&str> {
+        TlsExtension::alpn_protocols(&self.extensions).first().cloned()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DTLS hello verify request message
+pub struct TlsHelloVerifyRequest {
+    /// The protocol version of the server
+    pub version: TlsRecordVersion,
+
+    /// The cookie the client has to send back within its next client hello
+    pub cookie: Vec<u8>,
+}
+
+impl TlsHelloVerifyRequest {
+    named!(parse<&[u8], TlsHelloVerifyRequest>,
+        do_parse!(
+            version: take!(2) >>
+            cookie: length_data!(be_u8) >>
+            (TlsHelloVerifyRequest {
+                version: TlsRecordVersion {
+                    major: version[0],
+                    minor: version[1],
+                },
+                cookie: cookie.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A TLS hello extension
+pub enum TlsExtension {
+    /// Server Name Indication, a list of host names (empty if sent by the server)
+    ServerName(Vec<String>),
+
+    /// Application-Layer Protocol Negotiation, a list of protocol names like "h2"
+    Alpn(Vec<String>),
+
+    /// The supported protocol versions, where the server selects a single one
+    SupportedVersions(Vec<TlsRecordVersion>),
+
+    /// Any other extension type together with its raw data
+    Other(u16, Vec<u8>),
+}
+
+impl TlsExtension {
+    named!(parse_list<&[u8], Vec<TlsExtension> >,
+        alt_complete!(
+            // Extensions are optional
+            map!(eof!(), |_| vec![]) |
+            length_value!(be_u16, terminated!(many0!(complete!(TlsExtension::parse)), eof!()))
+        )
+    );
+
+    named!(parse<&[u8], TlsExtension>,
+        do_parse!(
+            extension_type: be_u16 >>
+            data: length_data!(be_u16) >>
+            (TlsExtension::decode(extension_type, data))
+        )
+    );
+
+    fn decode(extension_type: u16, data: &[u8]) -> TlsExtension {
+        let decoded = match extension_type {
+            0x0000 => TlsExtension::parse_server_name(data).to_full_result().ok(),
+            0x0010 => TlsExtension::parse_alpn(data).to_full_result().ok(),
+            0x002b => TlsExtension::parse_supported_versions(data).to_full_result().ok(),
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| TlsExtension::Other(extension_type, data.to_vec()))
+    }
+
+    named!(parse_server_name<&[u8], TlsExtension>,
+        alt_complete!(
+            map!(eof!(), |_| TlsExtension::ServerName(vec![])) |
+            map!(length_value!(be_u16, many0!(complete!(do_parse!(
+                name_type: be_u8 >>
+                name: map_res!(length_data!(be_u16), str::from_utf8) >>
+                (name_type, name)
+            )))), |names: Vec<(u8, &str)>| TlsExtension::ServerName(names
+                .into_iter()
+                .filter(|&(name_type, _)| name_type == 0)
+                .map(|(_, name)| name.to_owned())
+                .collect()))
+        )
+    );
+
+    named!(parse_alpn<&[u8], TlsExtension>,
+        map!(length_value!(be_u16, many0!(complete!(map_res!(length_data!(be_u8), str::from_utf8)))),
+             |protocols: Vec<&str>| TlsExtension::Alpn(protocols.into_iter().map(String::from).collect()))
+    );
+
+    named!(parse_supported_versions<&[u8], TlsExtension>,
+        map!(alt_complete!(
+            // The server selects exactly one version
+            terminated!(map!(take!(2), |v: &[u8]| vec![v]), eof!()) |
+            length_value!(be_u8, many0!(complete!(take!(2))))
+        ), |versions: Vec<&[u8]>| TlsExtension::SupportedVersions(versions
+            .into_iter()
+            .map(|v| TlsRecordVersion { major: v[0], minor: v[1] })
+            .collect()))
+    );
+
+    fn alpn_protocols(extensions: &[TlsExtension]) -> Vec<&str> {
+        extensions
+            .iter()
+            .filter_map(|e| match *e {
+                TlsExtension::Alpn(ref protocols) => Some(protocols),
+                _ => None,
+            })
+            .flat_map(|protocols| protocols.iter().map(|p| p.as_str()))
+            .collect()
+    }
+}
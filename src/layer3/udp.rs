@@ -19,7 +19,7 @@ impl Parsable<PathIp> for UdpParser {
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // Check the parent node for the correct IP protocol
-                    Some(ref any) => match (any.downcast_ref::<Ipv4Packet>(),
+                    Some(any) => match (any.downcast_ref::<Ipv4Packet>(),
                                             any.downcast_ref::<Ipv6Packet>()) {
 
                         // IPv4
@@ -0,0 +1,209 @@
+//! ASN.1 Basic Encoding Rules decoding
+use std::fmt;
+
+/// The maximum number of octets of a tag number or length
+const MAX_LENGTH_OCTETS: usize = 4;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The class of a tag
+pub enum BerClass {
+    /// Types defined by ASN.1, like INTEGER or SEQUENCE
+    Universal,
+
+    /// Types defined by an application, like the SNMP IpAddress
+    Application,
+
+    /// Types whose meaning depends on the context, like the SNMP PDUs
+    ContextSpecific,
+
+    /// Types defined by an enterprise
+    Private,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The identifier octets of an element
+pub struct BerTag {
+    /// The class of the tag
+    pub class: BerClass,
+
+    /// The content consists of further elements
+    pub constructed: bool,
+
+    /// The tag number within the class
+    pub number: u32,
+}
+
+impl BerTag {
+    /// Check if the tag is a universal tag with the given number
+    pub fn is_universal(&self, number: u32) -> bool {
+        self.class == BerClass::Universal && self.number == number
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A decoded element, which refers to the content within the encoded data. Only the definite
+/// length form is supported.
+pub struct BerElement<'a> {
+    /// The tag of the element
+    pub tag: BerTag,
+
+    /// The content octets
+    pub content: &'a [u8],
+}
+
+impl<'a> BerElement<'a> {
+    /// Decode an element and return it with the remaining data
+    pub fn parse(data: &'a [u8]) -> Option<(BerElement<'a>, &'a [u8])> {
+        let (&identifier, mut rest) = data.split_first()?;
+        let class = match identifier >> 6 {
+            0 => BerClass::Universal,
+            1 => BerClass::Application,
+            2 => BerClass::ContextSpecific,
+            _ => BerClass::Private,
+        };
+
+        // Tag numbers above 30 follow in base 128
+        let mut number = (identifier & 0x1f) as u32;
+        if number == 0x1f {
+            number = 0;
+            let mut octets = 0;
+            loop {
+                let (&byte, remaining) = rest.split_first()?;
+                rest = remaining;
+                octets += 1;
+                if octets > MAX_LENGTH_OCTETS {
+                    return None;
+                }
+                number = number << 7 | (byte & 0x7f) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        // Short and long definite length form
+        let (&first, mut rest) = rest.split_first()?;
+        let length = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let octets = (first & 0x7f) as usize;
+            if octets == 0 || octets > MAX_LENGTH_OCTETS || rest.len() < octets {
+                return None;
+            }
+            let length = rest[..octets].iter().fold(0usize, |length, &byte| length << 8 | byte as usize);
+            rest = &rest[octets..];
+            length
+        };
+        if rest.len() < length {
+            return None;
+        }
+
+        let element = BerElement {
+            tag: BerTag {
+                class: class,
+                constructed: identifier & 0x20 != 0,
+                number: number,
+            },
+            content: &rest[..length],
+        };
+        Some((element, &rest[length..]))
+    }
+
+    /// Decode all consecutive elements of the data
+    pub fn parse_all(mut data: &'a [u8]) -> Option<Vec<BerElement<'a>>> {
+        let mut elements = vec![];
+        while !data.is_empty() {
+            let (element, rest) = BerElement::parse(data)?;
+            elements.push(element);
+            data = rest;
+        }
+        Some(elements)
+    }
+
+    /// Decode the elements of a constructed element, like a SEQUENCE
+    pub fn children(&self) -> Option<Vec<BerElement<'a>>> {
+        if self.tag.constructed {
+            BerElement::parse_all(self.content)
+        } else {
+            None
+        }
+    }
+
+    /// Decode the content as a signed two's complement integer
+    pub fn integer(&self) -> Option<i64> {
+        if self.content.is_empty() || self.content.len() > 8 {
+            return None;
+        }
+        let initial = if self.content[0] & 0x80 != 0 { -1 } else { 0 };
+        Some(self.content.iter().fold(initial, |value, &byte| value << 8 | byte as i64))
+    }
+
+    /// Decode the content as an unsigned integer, where a leading zero octet is permitted
+    pub fn unsigned(&self) -> Option<u64> {
+        let content = match self.content.split_first() {
+            Some((&0, rest)) if !rest.is_empty() => rest,
+            Some(_) => self.content,
+            None => return None,
+        };
+        if content.len() > 8 {
+            return None;
+        }
+        Some(content.iter().fold(0u64, |value, &byte| value << 8 | byte as u64))
+    }
+
+    /// Decode the content as an object identifier
+    pub fn object_identifier(&self) -> Option<BerObjectIdentifier> {
+        let mut arcs = vec![];
+        let mut value = 0u64;
+        for (i, &byte) in self.content.iter().enumerate() {
+            if value > u64::MAX >> 7 {
+                return None;
+            }
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 != 0 {
+                if i + 1 == self.content.len() {
+                    return None;
+                }
+                continue;
+            }
+
+            // The first subidentifier combines the first two arcs
+            if arcs.is_empty() {
+                let first = if value < 80 { value / 40 } else { 2 };
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+        if arcs.is_empty() {
+            None
+        } else {
+            Some(BerObjectIdentifier(arcs))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An object identifier, like 1.3.6.1.2.1.1.1.0
+pub struct BerObjectIdentifier(pub Vec<u64>);
+
+impl BerObjectIdentifier {
+    /// Check if the identifier is within the subtree of another identifier
+    pub fn starts_with(&self, other: &BerObjectIdentifier) -> bool {
+        self.0.starts_with(&other.0)
+    }
+}
+
+impl fmt::Display for BerObjectIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, arc) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", arc)?;
+        }
+        Ok(())
+    }
+}
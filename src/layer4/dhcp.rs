@@ -0,0 +1,491 @@
+//! Dynamic Host Configuration Protocol related packet processing
+use prelude::*;
+
+/// The magic cookie which precedes the DHCP options
+const MAGIC_COOKIE: &[u8] = &[0x63, 0x82, 0x53, 0x63];
+
+/// The DHCP parser
+#[derive(Debug)]
+pub struct DhcpParser;
+
+impl Parsable<PathIp> for DhcpParser {
+    /// Parse a `DhcpPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        do_parse!(
+            input,
+            // Check the transport protocol and ports from the parent parser (UDP)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(any) => match any.downcast_ref::<UdpPacket>() {
+                        Some(udp) => {
+                            let ports = [udp.header.source_port, udp.header.dest_port];
+                            if ports.contains(&67) || ports.contains(&68) {
+                                Some(())
+                            } else {
+                                None
+                            }
+                        }
+                        None => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >> op: map_opt!(be_u8, DhcpOperation::from_u8)
+                >> htype: be_u8
+                >> hlen: be_u8
+                >> hops: be_u8
+                >> xid: be_u32
+                >> secs: be_u16
+                >> flags: be_u16
+                >> ciaddr: map!(be_u32, Ipv4Addr::from)
+                >> yiaddr: map!(be_u32, Ipv4Addr::from)
+                >> siaddr: map!(be_u32, Ipv4Addr::from)
+                >> giaddr: map!(be_u32, Ipv4Addr::from)
+                >> chaddr: take!(16)
+                >> sname: take!(64)
+                >> file: take!(128)
+                // Plain BOOTP messages contain no options
+                >> options: opt!(complete!(preceded!(tag!(MAGIC_COOKIE), call!(rest))))
+                >> options: value!(match options {
+                    Some(options) => DhcpOption::parse_all(options, sname, file),
+                    None => (vec![], false, false, false),
+                })
+                >> call!(rest)
+                >> (Box::new(DhcpPacket {
+                    op: op,
+                    htype: htype,
+                    hlen: hlen,
+                    hops: hops,
+                    xid: xid,
+                    secs: secs,
+                    broadcast: flags & 0x8000 != 0,
+                    ciaddr: ciaddr,
+                    yiaddr: yiaddr,
+                    siaddr: siaddr,
+                    giaddr: giaddr,
+                    chaddr: DhcpHardwareAddress::new(htype, hlen, chaddr),
+                    sname: if options.2 { String::new() } else { DhcpPacket::string(sname) },
+                    file: if options.1 { String::new() } else { DhcpPacket::string(file) },
+                    options: options.0,
+                    options_truncated: options.3,
+                }))
+        )
+    }
+}
+
+impl fmt::Display for DhcpParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DHCP")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a DHCP or BOOTP packet
+pub struct DhcpPacket {
+    /// The message is a request from a client or a reply from a server
+    pub op: DhcpOperation,
+
+    /// The hardware address type, where 1 is Ethernet
+    pub htype: u8,
+
+    /// The hardware address length
+    pub hlen: u8,
+
+    /// The number of relay agents the message passed
+    pub hops: u8,
+
+    /// The transaction identifier chosen by the client
+    pub xid: u32,
+
+    /// The seconds elapsed since the client began the address acquisition or renewal
+    pub secs: u16,
+
+    /// The client can not receive unicast messages until it is configured
+    pub broadcast: bool,
+
+    /// The current address of the client
+    pub ciaddr: Ipv4Addr,
+
+    /// The address assigned to the client
+    pub yiaddr: Ipv4Addr,
+
+    /// The address of the next server to use in bootstrap
+    pub siaddr: Ipv4Addr,
+
+    /// The address of the relay agent
+    pub giaddr: Ipv4Addr,
+
+    /// The hardware address of the client
+    pub chaddr: DhcpHardwareAddress,
+
+    /// The optional server host name, unless it is used for options
+    pub sname: String,
+
+    /// The boot file name, unless it is used for options
+    pub file: String,
+
+    /// The DHCP options in their order, where split options are concatenated
+    pub options: Vec<DhcpOption>,
+
+    /// The options area is malformed, where the options before the malformation are kept
+    pub options_truncated: bool,
+}
+
+impl DhcpPacket {
+    /// Get the DHCP message type, which is None for plain BOOTP messages
+    pub fn message_type(&self) -> Option<DhcpMessageType> {
+        self.options
+            .iter()
+            .filter_map(|option| match *option {
+                DhcpOption::MessageType(message_type) => Some(message_type),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Decode a null terminated string
+    fn string(input: &[u8]) -> String {
+        let end = input.iter().position(|&b| b == 0).unwrap_or(input.len());
+        String::from_utf8_lossy(&input[..end]).into_owned()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The hardware address of a DHCP client
+pub enum DhcpHardwareAddress {
+    /// An Ethernet address
+    Ethernet(MacAddress),
+
+    /// Any other hardware address by its octets, limited to the hardware address length
+    Other(Vec<u8>),
+}
+
+impl DhcpHardwareAddress {
+    /// Create a hardware address from the hardware type, length and the 16 octet chaddr field
+    fn new(htype: u8, hlen: u8, chaddr: &[u8]) -> DhcpHardwareAddress {
+        match (htype, hlen) {
+            (1, 6) => DhcpHardwareAddress::Ethernet(MacAddress(
+                chaddr[0], chaddr[1], chaddr[2], chaddr[3], chaddr[4], chaddr[5],
+            )),
+            _ => DhcpHardwareAddress::Other(chaddr[..chaddr.len().min(hlen as usize)].to_vec()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The BOOTP operation
+pub enum DhcpOperation {
+    /// A message from a client
+    BootRequest,
+
+    /// A message from a server
+    BootReply,
+}
+
+impl DhcpOperation {
+    /// Convert a u8 to a `DhcpOperation`. Returns None if the operation is invalid.
+    pub fn from_u8(input: u8) -> Option<DhcpOperation> {
+        match input {
+            1 => Some(DhcpOperation::BootRequest),
+            2 => Some(DhcpOperation::BootReply),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The DHCP message type
+pub enum DhcpMessageType {
+    /// A client locates servers
+    Discover,
+
+    /// A server offers configuration parameters
+    Offer,
+
+    /// A client requests the offered parameters or extends its lease
+    Request,
+
+    /// A client declines an address which is already in use
+    Decline,
+
+    /// A server acknowledges the configuration parameters
+    Ack,
+
+    /// A server refuses the request
+    Nak,
+
+    /// A client releases its address
+    Release,
+
+    /// A client asks for local configuration parameters only
+    Inform,
+
+    /// A server forces the client to renew
+    ForceRenew,
+
+    /// A lease query of a relay agent
+    LeaseQuery,
+
+    /// The address of a lease query is not leased
+    LeaseUnassigned,
+
+    /// The address of a lease query is unknown
+    LeaseUnknown,
+
+    /// The address of a lease query is leased
+    LeaseActive,
+}
+
+impl DhcpMessageType {
+    /// Convert a u8 to a `DhcpMessageType`. Returns None if the type is unknown.
+    pub fn from_u8(input: u8) -> Option<DhcpMessageType> {
+        match input {
+            1 => Some(DhcpMessageType::Discover),
+            2 => Some(DhcpMessageType::Offer),
+            3 => Some(DhcpMessageType::Request),
+            4 => Some(DhcpMessageType::Decline),
+            5 => Some(DhcpMessageType::Ack),
+            6 => Some(DhcpMessageType::Nak),
+            7 => Some(DhcpMessageType::Release),
+            8 => Some(DhcpMessageType::Inform),
+            9 => Some(DhcpMessageType::ForceRenew),
+            10 => Some(DhcpMessageType::LeaseQuery),
+            11 => Some(DhcpMessageType::LeaseUnassigned),
+            12 => Some(DhcpMessageType::LeaseUnknown),
+            13 => Some(DhcpMessageType::LeaseActive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DHCP option
+pub enum DhcpOption {
+    /// The subnet mask of the client
+    SubnetMask(Ipv4Addr),
+
+    /// The routers on the subnet of the client in order of preference
+    Router(Vec<Ipv4Addr>),
+
+    /// The DNS servers in order of preference
+    DomainNameServer(Vec<Ipv4Addr>),
+
+    /// The host name of the client
+    HostName(String),
+
+    /// The domain name for resolving host names
+    DomainName(String),
+
+    /// The broadcast address of the subnet
+    BroadcastAddress(Ipv4Addr),
+
+    /// The address requested by the client
+    RequestedIpAddress(Ipv4Addr),
+
+    /// The lease time in seconds
+    LeaseTime(u32),
+
+    /// The DHCP message type
+    MessageType(DhcpMessageType),
+
+    /// The address of the server
+    ServerIdentifier(Ipv4Addr),
+
+    /// The option codes requested by the client
+    ParameterRequestList(Vec<u8>),
+
+    /// An error message of the server
+    Message(String),
+
+    /// The maximum size of a DHCP message the client accepts
+    MaximumMessageSize(u16),
+
+    /// The time in seconds until the client should renew its lease (T1)
+    RenewalTime(u32),
+
+    /// The time in seconds until the client should rebind its lease (T2)
+    RebindingTime(u32),
+
+    /// The vendor and configuration of the client, like "MSFT 5.0"
+    VendorClassIdentifier(String),
+
+    /// The unique identifier of the client
+    ClientIdentifier {
+        /// The hardware type, or zero for other identifiers
+        hardware_type: u8,
+
+        /// The identifier, like the hardware address
+        identifier: Vec<u8>,
+    },
+
+    /// The sub-options added by a relay agent (option 82)
+    RelayAgentInformation(Vec<DhcpRelayAgentOption>),
+
+    /// Any other or malformed option by its code
+    Other(u8, Vec<u8>),
+}
+
+impl DhcpOption {
+    /// Parse the options and the options within the overloaded file and sname fields. Returns
+    /// the options, whether the file and sname fields are overloaded and whether an options area
+    /// is malformed.
+    fn parse_all(options: &[u8], sname: &[u8], file: &[u8]) -> (Vec<DhcpOption>, bool, bool, bool) {
+        let (mut raw, mut truncated) = DhcpOption::parse_area(options);
+        let overload = raw.iter().filter(|&&(code, _)| code == 52).filter_map(|(_, data)| data.first()).next();
+        let (overload_file, overload_sname) = match overload {
+            Some(&1) => (true, false),
+            Some(&2) => (false, true),
+            Some(&3) => (true, true),
+            _ => (false, false),
+        };
+        for &(overloaded, area) in &[(overload_file, file), (overload_sname, sname)] {
+            if overloaded {
+                let (area_raw, area_truncated) = DhcpOption::parse_area(area);
+                raw.extend(area_raw);
+                truncated |= area_truncated;
+            }
+        }
+
+        // Options with the same code are concatenated in their order (RFC3396)
+        let mut concatenated: Vec<(u8, Vec<u8>)> = vec![];
+        for (code, data) in raw {
+            if code == 52 {
+                continue;
+            }
+            match concatenated.iter_mut().find(|&&mut (c, _)| c == code) {
+                Some(&mut (_, ref mut existing)) => existing.extend_from_slice(&data),
+                None => concatenated.push((code, data)),
+            }
+        }
+
+        let options = concatenated
+            .into_iter()
+            .map(|(code, data)| DhcpOption::decode(code, data))
+            .collect();
+        (options, overload_file, overload_sname, truncated)
+    }
+
+    /// Split an options area. Returns the options before a malformation and whether the area is
+    /// malformed.
+    fn parse_area(area: &[u8]) -> (Vec<(u8, Vec<u8>)>, bool) {
+        match DhcpOption::parse_raw(area) {
+            IResult::Done(_, (raw, terminated)) => (raw, !terminated),
+            _ => (vec![], true),
+        }
+    }
+
+    // Split the options until the end option, where pad options are skipped and the flag is unset
+    // if the options are not terminated by the end option or the end of the area
+    named!(parse_raw<&[u8], (Vec<(u8, Vec<u8>)>, bool)>,
+        do_parse!(
+            options: many0!(complete!(alt!(
+                value!(None, tag!([0])) |
+                do_parse!(
+                    code: verify!(be_u8, |code: u8| code != 255) >>
+                    data: length_bytes!(be_u8) >>
+                    (Some((code, data.to_vec())))
+                )
+            ))) >>
+            end: opt!(alt!(complete!(tag!([255])) | eof!())) >>
+            (options.into_iter().flatten().collect(), end.is_some())
+        )
+    );
+
+    fn decode(code: u8, data: Vec<u8>) -> DhcpOption {
+        let decoded = match code {
+            1 => DhcpOption::address(&data).map(DhcpOption::SubnetMask),
+            3 => DhcpOption::addresses(&data).map(DhcpOption::Router),
+            6 => DhcpOption::addresses(&data).map(DhcpOption::DomainNameServer),
+            12 => Some(DhcpOption::HostName(DhcpOption::string(&data))),
+            15 => Some(DhcpOption::DomainName(DhcpOption::string(&data))),
+            28 => DhcpOption::address(&data).map(DhcpOption::BroadcastAddress),
+            50 => DhcpOption::address(&data).map(DhcpOption::RequestedIpAddress),
+            51 => DhcpOption::u32(&data).map(DhcpOption::LeaseTime),
+            53 if data.len() == 1 => DhcpMessageType::from_u8(data[0]).map(DhcpOption::MessageType),
+            54 => DhcpOption::address(&data).map(DhcpOption::ServerIdentifier),
+            55 => Some(DhcpOption::ParameterRequestList(data.clone())),
+            56 => Some(DhcpOption::Message(DhcpOption::string(&data))),
+            57 if data.len() == 2 => Some(DhcpOption::MaximumMessageSize((data[0] as u16) << 8 | data[1] as u16)),
+            58 => DhcpOption::u32(&data).map(DhcpOption::RenewalTime),
+            59 => DhcpOption::u32(&data).map(DhcpOption::RebindingTime),
+            60 => Some(DhcpOption::VendorClassIdentifier(DhcpOption::string(&data))),
+            61 if !data.is_empty() => Some(DhcpOption::ClientIdentifier {
+                hardware_type: data[0],
+                identifier: data[1..].to_vec(),
+            }),
+            82 => DhcpRelayAgentOption::parse_list(&data)
+                .to_full_result()
+                .ok()
+                .map(DhcpOption::RelayAgentInformation),
+            _ => None,
+        };
+        decoded.unwrap_or(DhcpOption::Other(code, data))
+    }
+
+    fn address(data: &[u8]) -> Option<Ipv4Addr> {
+        if data.len() == 4 {
+            Some(Ipv4Addr::new(data[0], data[1], data[2], data[3]))
+        } else {
+            None
+        }
+    }
+
+    fn addresses(data: &[u8]) -> Option<Vec<Ipv4Addr>> {
+        if data.is_empty() || !data.len().is_multiple_of(4) {
+            return None;
+        }
+        Some(data.chunks(4).filter_map(DhcpOption::address).collect())
+    }
+
+    fn u32(data: &[u8]) -> Option<u32> {
+        DhcpOption::address(data).map(u32::from)
+    }
+
+    /// Decode a string, where some clients append a null character
+    fn string(data: &[u8]) -> String {
+        String::from_utf8_lossy(data).trim_end_matches('\0').to_owned()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A sub-option of the relay agent information option
+pub enum DhcpRelayAgentOption {
+    /// The circuit on which the relay agent received the message, like the switch port
+    CircuitId(Vec<u8>),
+
+    /// The remote host of the circuit, like the modem of a subscriber
+    RemoteId(Vec<u8>),
+
+    /// The subscriber of the circuit
+    SubscriberId(String),
+
+    /// Any other sub-option by its code
+    Other(u8, Vec<u8>),
+}
+
+impl DhcpRelayAgentOption {
+    named!(parse_list<&[u8], Vec<DhcpRelayAgentOption>>,
+        terminated!(many0!(complete!(DhcpRelayAgentOption::parse)), eof!())
+    );
+
+    named!(parse<&[u8], DhcpRelayAgentOption>,
+        do_parse!(
+            code: be_u8 >>
+            value: map!(length_bytes!(be_u8), |value: &[u8]| value.to_vec()) >>
+            (match code {
+                1 => DhcpRelayAgentOption::CircuitId(value),
+                2 => DhcpRelayAgentOption::RemoteId(value),
+                6 => DhcpRelayAgentOption::SubscriberId(String::from_utf8_lossy(&value).into_owned()),
+                _ => DhcpRelayAgentOption::Other(code, value),
+            })
+        )
+    );
+}
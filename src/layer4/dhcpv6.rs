@@ -0,0 +1,711 @@
+//! Dynamic Host Configuration Protocol for IPv6 related packet processing
+use prelude::*;
+
+/// The UDP port of DHCPv6 clients
+const CLIENT_PORT: u16 = 546;
+
+/// The UDP port of DHCPv6 servers and relay agents
+const SERVER_PORT: u16 = 547;
+
+/// The maximum number of relay agents a message can pass (HOP_COUNT_LIMIT)
+const MAX_RELAY_DEPTH: usize = 32;
+
+/// The DHCPv6 parser
+#[derive(Debug)]
+pub struct Dhcpv6Parser;
+
+impl Parsable<PathIp> for Dhcpv6Parser {
+    /// Parse a `Dhcpv6Packet` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol and ports from the parent parser (UDP)
+        let valid = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<UdpPacket>()) {
+                Some(udp) => {
+                    let ports = [udp.header.source_port, udp.header.dest_port];
+                    ports.contains(&CLIENT_PORT) || ports.contains(&SERVER_PORT)
+                }
+                None => false,
+            },
+            // Parse also if no result is given, for testability
+            None => true,
+        };
+        if !valid {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        match Dhcpv6Packet::parse(input, 0) {
+            IResult::Done(remaining, packet) => IResult::Done(remaining, Box::new(packet)),
+            _ => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for Dhcpv6Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DHCPv6")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a DHCPv6 client/server or relay agent message
+pub struct Dhcpv6Packet {
+    /// The message type
+    pub message_type: Dhcpv6MessageType,
+
+    /// The message type specific header
+    pub header: Dhcpv6Header,
+
+    /// The options in their order
+    pub options: Vec<Dhcpv6Option>,
+}
+
+impl Dhcpv6Packet {
+    /// Parse a message, where relay messages may contain further relay messages
+    fn parse(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6Packet> {
+        do_parse!(
+            input,
+            message_type: map!(be_u8, Dhcpv6MessageType::from_u8)
+                >> header: call!(Dhcpv6Header::parse, message_type, depth)
+                >> options: call!(Dhcpv6Option::parse_list, depth)
+                >> (Dhcpv6Packet {
+                    message_type: message_type,
+                    header: header,
+                    options: options,
+                })
+        )
+    }
+
+    /// Get the message within a relay message option
+    pub fn relay_message(&self) -> Option<&Dhcpv6Packet> {
+        self.options
+            .iter()
+            .filter_map(|option| match *option {
+                Dhcpv6Option::RelayMessage(ref message) => Some(&**message),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Get the message of the client or server, which is the innermost message of nested relay
+    /// messages or the message itself
+    pub fn client_message(&self) -> &Dhcpv6Packet {
+        let mut message = self;
+        while let Some(relayed) = message.relay_message() {
+            message = relayed;
+        }
+        message
+    }
+
+    /// Get the DUID of the client
+    pub fn client_id(&self) -> Option<&Dhcpv6Duid> {
+        self.options
+            .iter()
+            .filter_map(|option| match *option {
+                Dhcpv6Option::ClientId(ref duid) => Some(duid),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Get the DUID of the server
+    pub fn server_id(&self) -> Option<&Dhcpv6Duid> {
+        self.options
+            .iter()
+            .filter_map(|option| match *option {
+                Dhcpv6Option::ServerId(ref duid) => Some(duid),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The DHCPv6 message type
+pub enum Dhcpv6MessageType {
+    /// A client locates servers
+    Solicit,
+
+    /// A server indicates that it is available
+    Advertise,
+
+    /// A client requests configuration parameters
+    Request,
+
+    /// A client checks whether its addresses are still appropriate for the link
+    Confirm,
+
+    /// A client extends the lifetimes at the server which assigned the leases
+    Renew,
+
+    /// A client extends the lifetimes at any server
+    Rebind,
+
+    /// A server responds with the assigned leases and configuration parameters
+    Reply,
+
+    /// A client releases its leases
+    Release,
+
+    /// A client declines addresses which are already in use
+    Decline,
+
+    /// A server informs the client about new or updated configuration parameters
+    Reconfigure,
+
+    /// A client requests configuration parameters without leases
+    InformationRequest,
+
+    /// A relay agent forwards a message to a server
+    RelayForward,
+
+    /// A server sends a message to a relay agent
+    RelayReply,
+
+    /// A lease query of a requestor
+    LeaseQuery,
+
+    /// The response to a lease query
+    LeaseQueryReply,
+
+    /// Any other message type
+    Other(u8),
+}
+
+impl Dhcpv6MessageType {
+    /// Convert a u8 to a `Dhcpv6MessageType`
+    pub fn from_u8(input: u8) -> Dhcpv6MessageType {
+        match input {
+            1 => Dhcpv6MessageType::Solicit,
+            2 => Dhcpv6MessageType::Advertise,
+            3 => Dhcpv6MessageType::Request,
+            4 => Dhcpv6MessageType::Confirm,
+            5 => Dhcpv6MessageType::Renew,
+            6 => Dhcpv6MessageType::Rebind,
+            7 => Dhcpv6MessageType::Reply,
+            8 => Dhcpv6MessageType::Release,
+            9 => Dhcpv6MessageType::Decline,
+            10 => Dhcpv6MessageType::Reconfigure,
+            11 => Dhcpv6MessageType::InformationRequest,
+            12 => Dhcpv6MessageType::RelayForward,
+            13 => Dhcpv6MessageType::RelayReply,
+            14 => Dhcpv6MessageType::LeaseQuery,
+            15 => Dhcpv6MessageType::LeaseQueryReply,
+            other => Dhcpv6MessageType::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The header of a DHCPv6 message
+pub enum Dhcpv6Header {
+    /// The header of messages between clients and servers
+    Client {
+        /// The 24 bit transaction identifier
+        transaction_id: u32,
+    },
+
+    /// The header of relay agent messages
+    Relay {
+        /// The number of relay agents which relayed the message
+        hop_count: u8,
+
+        /// The address which identifies the link of the client
+        link_address: Ipv6Addr,
+
+        /// The address of the client or relay agent from which the message was received
+        peer_address: Ipv6Addr,
+    },
+}
+
+impl Dhcpv6Header {
+    /// Parse the header of the message type, which fails for too deeply nested relay messages
+    fn parse(input: &[u8], message_type: Dhcpv6MessageType, depth: usize) -> IResult<&[u8], Dhcpv6Header> {
+        match message_type {
+            Dhcpv6MessageType::RelayForward | Dhcpv6MessageType::RelayReply => {
+                if depth > MAX_RELAY_DEPTH {
+                    return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                }
+                do_parse!(
+                    input,
+                    hop_count: be_u8
+                        >> link_address: call!(Dhcpv6Option::address)
+                        >> peer_address: call!(Dhcpv6Option::address)
+                        >> (Dhcpv6Header::Relay {
+                            hop_count: hop_count,
+                            link_address: link_address,
+                            peer_address: peer_address,
+                        })
+                )
+            }
+            _ => map!(input, be_u24, |transaction_id| Dhcpv6Header::Client {
+                transaction_id: transaction_id,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DHCPv6 option
+pub enum Dhcpv6Option {
+    /// The DUID of the client
+    ClientId(Dhcpv6Duid),
+
+    /// The DUID of the server
+    ServerId(Dhcpv6Duid),
+
+    /// An identity association for non-temporary addresses
+    IaNa {
+        /// The identifier of the association, which is unique for the client
+        iaid: u32,
+
+        /// The time in seconds until the client should renew the addresses
+        t1: u32,
+
+        /// The time in seconds until the client should rebind the addresses
+        t2: u32,
+
+        /// The addresses and status of the association
+        options: Vec<Dhcpv6Option>,
+    },
+
+    /// An identity association for temporary addresses
+    IaTa {
+        /// The identifier of the association, which is unique for the client
+        iaid: u32,
+
+        /// The addresses and status of the association
+        options: Vec<Dhcpv6Option>,
+    },
+
+    /// An address of an identity association
+    IaAddress {
+        /// The IPv6 address
+        address: Ipv6Addr,
+
+        /// The preferred lifetime in seconds
+        preferred_lifetime: u32,
+
+        /// The valid lifetime in seconds
+        valid_lifetime: u32,
+
+        /// The status of the address
+        options: Vec<Dhcpv6Option>,
+    },
+
+    /// The option codes requested by the client
+    OptionRequest(Vec<u16>),
+
+    /// The preference of the server, where a higher value is preferred
+    Preference(u8),
+
+    /// The time since the client began the exchange in hundredths of a second
+    ElapsedTime(u16),
+
+    /// A message of a client, server or further relay agent
+    RelayMessage(Box<Dhcpv6Packet>),
+
+    /// The status of a message or identity association
+    StatusCode {
+        /// The status code
+        code: Dhcpv6StatusCode,
+
+        /// The message for the user
+        message: String,
+    },
+
+    /// The client requests or the server accepts the two message exchange
+    RapidCommit,
+
+    /// The vendor and configuration of the client
+    VendorClass {
+        /// The enterprise number of the vendor
+        enterprise_number: u32,
+
+        /// The opaque class data
+        data: Vec<Vec<u8>>,
+    },
+
+    /// The interface on which the relay agent received the message
+    InterfaceId(Vec<u8>),
+
+    /// The DNS recursive name servers in order of preference
+    DnsServers(Vec<Ipv6Addr>),
+
+    /// The domain search list
+    DomainList(Vec<String>),
+
+    /// An identity association for prefix delegation
+    IaPd {
+        /// The identifier of the association, which is unique for the client
+        iaid: u32,
+
+        /// The time in seconds until the client should renew the prefixes
+        t1: u32,
+
+        /// The time in seconds until the client should rebind the prefixes
+        t2: u32,
+
+        /// The prefixes and status of the association
+        options: Vec<Dhcpv6Option>,
+    },
+
+    /// A delegated prefix of an identity association
+    IaPrefix {
+        /// The preferred lifetime in seconds
+        preferred_lifetime: u32,
+
+        /// The valid lifetime in seconds
+        valid_lifetime: u32,
+
+        /// The length of the prefix in bits
+        prefix_length: u8,
+
+        /// The IPv6 prefix
+        prefix: Ipv6Addr,
+
+        /// The status of the prefix
+        options: Vec<Dhcpv6Option>,
+    },
+
+    /// The remote host of the relay agent, like the modem of a subscriber
+    RemoteId {
+        /// The enterprise number of the vendor
+        enterprise_number: u32,
+
+        /// The vendor specific identifier
+        remote_id: Vec<u8>,
+    },
+
+    /// The link-layer address of the client added by the first relay agent
+    ClientLinkLayerAddress {
+        /// The hardware type, where 1 is Ethernet
+        hardware_type: u16,
+
+        /// The link-layer address
+        address: Vec<u8>,
+    },
+
+    /// Any other or malformed option by its code
+    Other(u16, Vec<u8>),
+}
+
+impl Dhcpv6Option {
+    /// Parse a list of options, which fails if an option exceeds the data
+    fn parse_list(input: &[u8], depth: usize) -> IResult<&[u8], Vec<Dhcpv6Option>> {
+        terminated!(input, many0!(complete!(call!(Dhcpv6Option::parse, depth))), eof!())
+    }
+
+    fn parse(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6Option> {
+        do_parse!(
+            input,
+            code: be_u16 >> data: length_bytes!(be_u16) >> (Dhcpv6Option::decode(code, data, depth))
+        )
+    }
+
+    /// Decode the value of an option, where a malformed value is kept as other option
+    fn decode(code: u16, data: &[u8], depth: usize) -> Dhcpv6Option {
+        match Dhcpv6Option::parse_value(data, code, depth).to_full_result() {
+            Ok(option) => option,
+            Err(_) => Dhcpv6Option::Other(code, data.to_vec()),
+        }
+    }
+
+    fn parse_value(input: &[u8], code: u16, depth: usize) -> IResult<&[u8], Dhcpv6Option> {
+        match code {
+            1 => map!(input, call!(Dhcpv6Duid::parse), Dhcpv6Option::ClientId),
+            2 => map!(input, call!(Dhcpv6Duid::parse), Dhcpv6Option::ServerId),
+            3 => do_parse!(
+                input,
+                iaid: be_u32
+                    >> t1: be_u32
+                    >> t2: be_u32
+                    >> options: call!(Dhcpv6Option::parse_list, depth)
+                    >> (Dhcpv6Option::IaNa {
+                        iaid: iaid,
+                        t1: t1,
+                        t2: t2,
+                        options: options,
+                    })
+            ),
+            4 => do_parse!(
+                input,
+                iaid: be_u32
+                    >> options: call!(Dhcpv6Option::parse_list, depth)
+                    >> (Dhcpv6Option::IaTa {
+                        iaid: iaid,
+                        options: options,
+                    })
+            ),
+            5 => do_parse!(
+                input,
+                address: call!(Dhcpv6Option::address)
+                    >> preferred_lifetime: be_u32
+                    >> valid_lifetime: be_u32
+                    >> options: call!(Dhcpv6Option::parse_list, depth)
+                    >> (Dhcpv6Option::IaAddress {
+                        address: address,
+                        preferred_lifetime: preferred_lifetime,
+                        valid_lifetime: valid_lifetime,
+                        options: options,
+                    })
+            ),
+            6 => map!(
+                input,
+                terminated!(many0!(complete!(be_u16)), eof!()),
+                Dhcpv6Option::OptionRequest
+            ),
+            7 => map!(input, terminated!(be_u8, eof!()), Dhcpv6Option::Preference),
+            8 => map!(input, terminated!(be_u16, eof!()), Dhcpv6Option::ElapsedTime),
+            9 => map!(input, call!(Dhcpv6Packet::parse, depth + 1), |message| {
+                Dhcpv6Option::RelayMessage(Box::new(message))
+            }),
+            13 => do_parse!(
+                input,
+                code: be_u16
+                    >> message: call!(rest)
+                    >> (Dhcpv6Option::StatusCode {
+                        code: Dhcpv6StatusCode::from_u16(code),
+                        message: String::from_utf8_lossy(message).into_owned(),
+                    })
+            ),
+            14 => value!(input, Dhcpv6Option::RapidCommit, eof!()),
+            16 => do_parse!(
+                input,
+                enterprise_number: be_u32
+                    >> data: call!(Dhcpv6Option::opaque_list)
+                    >> (Dhcpv6Option::VendorClass {
+                        enterprise_number: enterprise_number,
+                        data: data,
+                    })
+            ),
+            18 => map!(input, call!(rest), |id: &[u8]| Dhcpv6Option::InterfaceId(id.to_vec())),
+            23 => map!(
+                input,
+                terminated!(many0!(complete!(call!(Dhcpv6Option::address))), eof!()),
+                Dhcpv6Option::DnsServers
+            ),
+            24 => map!(input, call!(Dhcpv6Option::domain_list), Dhcpv6Option::DomainList),
+            25 => do_parse!(
+                input,
+                iaid: be_u32
+                    >> t1: be_u32
+                    >> t2: be_u32
+                    >> options: call!(Dhcpv6Option::parse_list, depth)
+                    >> (Dhcpv6Option::IaPd {
+                        iaid: iaid,
+                        t1: t1,
+                        t2: t2,
+                        options: options,
+                    })
+            ),
+            26 => do_parse!(
+                input,
+                preferred_lifetime: be_u32
+                    >> valid_lifetime: be_u32
+                    >> prefix_length: be_u8
+                    >> prefix: call!(Dhcpv6Option::address)
+                    >> options: call!(Dhcpv6Option::parse_list, depth)
+                    >> (Dhcpv6Option::IaPrefix {
+                        preferred_lifetime: preferred_lifetime,
+                        valid_lifetime: valid_lifetime,
+                        prefix_length: prefix_length,
+                        prefix: prefix,
+                        options: options,
+                    })
+            ),
+            37 => do_parse!(
+                input,
+                enterprise_number: be_u32
+                    >> remote_id: call!(rest)
+                    >> (Dhcpv6Option::RemoteId {
+                        enterprise_number: enterprise_number,
+                        remote_id: remote_id.to_vec(),
+                    })
+            ),
+            79 => do_parse!(
+                input,
+                hardware_type: be_u16
+                    >> address: call!(rest)
+                    >> (Dhcpv6Option::ClientLinkLayerAddress {
+                        hardware_type: hardware_type,
+                        address: address.to_vec(),
+                    })
+            ),
+            _ => IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+
+    fn octets(data: &[u8]) -> [u8; 16] {
+        let mut octets = [0; 16];
+        octets.copy_from_slice(data);
+        octets
+    }
+
+    named!(address<&[u8], Ipv6Addr>,
+        map!(take!(16), |octets| Ipv6Addr::from(Dhcpv6Option::octets(octets)))
+    );
+
+    // Split a list of opaque data with a two octet length prefix
+    named!(opaque_list<&[u8], Vec<Vec<u8>>>,
+        terminated!(many0!(complete!(map!(length_bytes!(be_u16), |data: &[u8]| data.to_vec()))), eof!())
+    );
+
+    // Decode a list of uncompressed domain names in DNS wire format
+    named!(domain_list<&[u8], Vec<String>>,
+        terminated!(many0!(complete!(Dhcpv6Option::domain)), eof!())
+    );
+
+    // Decode a domain name, where a partially qualified last name is not terminated
+    named!(domain<&[u8], String>,
+        do_parse!(
+            labels: many0!(complete!(map!(
+                length_bytes!(verify!(be_u8, |length: u8| length > 0 && length <= 63)),
+                String::from_utf8_lossy
+            ))) >>
+            alt!(complete!(tag!([0])) | eof!()) >>
+            (labels.join("."))
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A DHCP unique identifier of a client or server
+pub enum Dhcpv6Duid {
+    /// Based on the link-layer address and the time of generation (DUID-LLT)
+    LinkLayerTime {
+        /// The hardware type, where 1 is Ethernet
+        hardware_type: u16,
+
+        /// The seconds since midnight of January 1, 2000 (UTC)
+        time: u32,
+
+        /// The link-layer address
+        address: Vec<u8>,
+    },
+
+    /// Assigned by the vendor (DUID-EN)
+    EnterpriseNumber {
+        /// The enterprise number of the vendor
+        enterprise_number: u32,
+
+        /// The vendor specific identifier
+        identifier: Vec<u8>,
+    },
+
+    /// Based on the link-layer address (DUID-LL)
+    LinkLayer {
+        /// The hardware type, where 1 is Ethernet
+        hardware_type: u16,
+
+        /// The link-layer address
+        address: Vec<u8>,
+    },
+
+    /// Based on a universally unique identifier (DUID-UUID)
+    Uuid([u8; 16]),
+
+    /// Any other DUID by its type
+    Other(u16, Vec<u8>),
+}
+
+impl Dhcpv6Duid {
+    /// Parse a DUID, where a malformed DUID of a known type is kept as other DUID
+    fn parse(input: &[u8]) -> IResult<&[u8], Dhcpv6Duid> {
+        do_parse!(
+            input,
+            duid_type: be_u16
+                >> duid: alt!(
+                    complete!(call!(Dhcpv6Duid::parse_type, duid_type))
+                        | map!(call!(rest), |data: &[u8]| Dhcpv6Duid::Other(duid_type, data.to_vec()))
+                )
+                >> (duid)
+        )
+    }
+
+    fn parse_type(input: &[u8], duid_type: u16) -> IResult<&[u8], Dhcpv6Duid> {
+        match duid_type {
+            1 => do_parse!(
+                input,
+                hardware_type: be_u16
+                    >> time: be_u32
+                    >> address: call!(rest)
+                    >> (Dhcpv6Duid::LinkLayerTime {
+                        hardware_type: hardware_type,
+                        time: time,
+                        address: address.to_vec(),
+                    })
+            ),
+            2 => do_parse!(
+                input,
+                enterprise_number: be_u32
+                    >> identifier: call!(rest)
+                    >> (Dhcpv6Duid::EnterpriseNumber {
+                        enterprise_number: enterprise_number,
+                        identifier: identifier.to_vec(),
+                    })
+            ),
+            3 => do_parse!(
+                input,
+                hardware_type: be_u16
+                    >> address: call!(rest)
+                    >> (Dhcpv6Duid::LinkLayer {
+                        hardware_type: hardware_type,
+                        address: address.to_vec(),
+                    })
+            ),
+            4 => map!(input, terminated!(take!(16), eof!()), |uuid| {
+                Dhcpv6Duid::Uuid(Dhcpv6Option::octets(uuid))
+            }),
+            _ => IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The DHCPv6 status code
+pub enum Dhcpv6StatusCode {
+    /// Success
+    Success,
+
+    /// Failure for an unspecified reason
+    UnspecifiedFailure,
+
+    /// The server has no addresses available to assign
+    NoAddressesAvailable,
+
+    /// The binding of the client is not available
+    NoBinding,
+
+    /// The prefix of the address is not appropriate for the link
+    NotOnLink,
+
+    /// The client should use the multicast address of the server
+    UseMulticast,
+
+    /// The server has no prefixes available to delegate
+    NoPrefixAvailable,
+
+    /// Any other status code
+    Other(u16),
+}
+
+impl Dhcpv6StatusCode {
+    /// Convert a u16 to a `Dhcpv6StatusCode`
+    pub fn from_u16(input: u16) -> Dhcpv6StatusCode {
+        match input {
+            0 => Dhcpv6StatusCode::Success,
+            1 => Dhcpv6StatusCode::UnspecifiedFailure,
+            2 => Dhcpv6StatusCode::NoAddressesAvailable,
+            3 => Dhcpv6StatusCode::NoBinding,
+            4 => Dhcpv6StatusCode::NotOnLink,
+            5 => Dhcpv6StatusCode::UseMulticast,
+            6 => Dhcpv6StatusCode::NoPrefixAvailable,
+            other => Dhcpv6StatusCode::Other(other),
+        }
+    }
+}
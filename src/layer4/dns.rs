@@ -0,0 +1,1111 @@
+//! Domain Name System related packet processing
+use prelude::*;
+use std::collections::HashSet;
+
+/// The maximum amount of compression pointers followed for a single name
+const MAX_POINTERS: usize = 64;
+
+/// The DNS parser, which decodes messages over UDP and TCP
+#[derive(Debug)]
+pub struct DnsParser {
+    /// The ports on which UDP datagrams and TCP streams are decoded as DNS messages
+    pub ports: HashSet<u16>,
+
+    /// The data of incomplete messages per TCP connection and direction
+    buffers: TcpStreams<Vec<u8>>,
+}
+
+impl DnsParser {
+    /// Create a new DNS parser for the port 53
+    pub fn new() -> Self {
+        DnsParser {
+            ports: [53].iter().cloned().collect(),
+            buffers: TcpStreams::new(),
+        }
+    }
+
+    /// Decode the length prefixed messages of a TCP segment, where incomplete messages are
+    /// buffered until the next segment of the same direction
+    fn parse_stream(buffer: &mut Vec<u8>, input: &[u8]) -> Option<Vec<DnsMessage>> {
+        buffer.extend_from_slice(input);
+
+        let mut messages = vec![];
+        let mut position = 0;
+        while buffer.len() >= position + 2 {
+            let length = (buffer[position] as usize) << 8 | buffer[position + 1] as usize;
+            if buffer.len() < position + 2 + length {
+                break;
+            }
+            messages.push(DnsMessage::decode(&buffer[position + 2..position + 2 + length])?);
+            position += 2 + length;
+        }
+
+        buffer.drain(..position);
+        Some(messages)
+    }
+}
+
+impl Default for DnsParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for DnsParser {
+    /// Parse a `DnsPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let messages = match result.and_then(|vector| vector.last()) {
+            Some(any) => match (any.downcast_ref::<UdpPacket>(), any.downcast_ref::<TcpPacket>()) {
+                (Some(udp), _) => {
+                    if !self.ports.contains(&udp.header.source_port) && !self.ports.contains(&udp.header.dest_port) {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    DnsMessage::decode(input).map(|message| vec![message])
+                }
+                (_, Some(tcp)) => {
+                    if !self.ports.contains(&tcp.header.source_port) && !self.ports.contains(&tcp.header.dest_port) {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    if input.is_empty() {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    self.buffers.process(result, tcp, true, |buffer| DnsParser::parse_stream(buffer, input))
+                }
+
+                // Previous result found, but not correct parent
+                _ => None,
+            },
+
+            // Parse also if no result is given, for testability
+            None => DnsMessage::decode(input).map(|message| vec![message]),
+        };
+
+        match messages {
+            Some(messages) => IResult::Done(&input[input.len()..], Box::new(DnsPacket { messages: messages })),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for DnsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DNS")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The DNS messages of a UDP datagram or TCP segment
+pub struct DnsPacket {
+    /// The decoded messages, where a TCP segment may contain none or multiple messages
+    pub messages: Vec<DnsMessage>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a Domain Name System message
+pub struct DnsMessage {
+    /// Identifier to match responses with queries
+    pub id: u16,
+
+    /// The message is a response
+    pub response: bool,
+
+    /// The kind of query
+    pub opcode: DnsOpcode,
+
+    /// The responding server is an authority for the name
+    pub authoritative: bool,
+
+    /// The message was truncated
+    pub truncated: bool,
+
+    /// The query should be resolved recursively
+    pub recursion_desired: bool,
+
+    /// The server supports recursive queries
+    pub recursion_available: bool,
+
+    /// All data of the response was authenticated by DNSSEC
+    pub authentic_data: bool,
+
+    /// The DNSSEC validation is disabled
+    pub checking_disabled: bool,
+
+    /// The response code, which includes the upper bits of the EDNS extended response code
+    pub rcode: DnsResponseCode,
+
+    /// The question section
+    pub questions: Vec<DnsQuestion>,
+
+    /// The answer section
+    pub answers: Vec<DnsRecord>,
+
+    /// The authority section
+    pub authorities: Vec<DnsRecord>,
+
+    /// The additional section without the EDNS pseudo record
+    pub additionals: Vec<DnsRecord>,
+
+    /// The EDNS information of the OPT pseudo record
+    pub edns: Option<DnsEdns>,
+}
+
+impl DnsMessage {
+    /// Decode a complete message. Returns None if the message is invalid or truncated.
+    pub fn decode(data: &[u8]) -> Option<DnsMessage> {
+        DnsMessage::decode_message(data, false)
+    }
+
+    /// Decode a complete multicast DNS message, where the top bit of the class is the
+    /// unicast-response bit of questions and the cache-flush bit of records
+    pub fn decode_multicast(data: &[u8]) -> Option<DnsMessage> {
+        DnsMessage::decode_message(data, true)
+    }
+
+    fn decode_message(data: &[u8], multicast: bool) -> Option<DnsMessage> {
+        let mut reader = DnsReader::new(data, 0, data.len());
+        let id = reader.u16()?;
+        let flags = reader.u16()?;
+        let counts = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+
+        let mut questions = vec![];
+        for _ in 0..counts[0] {
+            let name = reader.name()?;
+            let record_type = DnsRecordType::from_u16(reader.u16()?);
+            let (class, unicast_response) = DnsClass::split(reader.u16()?, multicast);
+            questions.push(DnsQuestion {
+                name: name,
+                record_type: record_type,
+                class: class,
+                unicast_response: unicast_response,
+            });
+        }
+        let mut answers = vec![];
+        for _ in 0..counts[1] {
+            answers.push(reader.record(multicast)?);
+        }
+        let mut authorities = vec![];
+        for _ in 0..counts[2] {
+            authorities.push(reader.record(multicast)?);
+        }
+
+        // The first OPT pseudo record contains the EDNS information
+        let mut additionals = vec![];
+        let mut edns = None;
+        for _ in 0..counts[3] {
+            match reader.edns()? {
+                Some(opt) => if edns.is_none() {
+                    edns = Some(opt);
+                },
+                None => additionals.push(reader.record(multicast)?),
+            }
+        }
+
+        let extended_rcode = edns.as_ref().map_or(0, |edns: &DnsEdns| u16::from(edns.extended_rcode));
+        Some(DnsMessage {
+            id: id,
+            response: flags & 0x8000 != 0,
+            opcode: DnsOpcode::from_u8((flags >> 11 & 0x0f) as u8),
+            authoritative: flags & 0x0400 != 0,
+            truncated: flags & 0x0200 != 0,
+            recursion_desired: flags & 0x0100 != 0,
+            recursion_available: flags & 0x0080 != 0,
+            authentic_data: flags & 0x0020 != 0,
+            checking_disabled: flags & 0x0010 != 0,
+            rcode: DnsResponseCode::from_u16(extended_rcode << 4 | flags & 0x000f),
+            questions: questions,
+            answers: answers,
+            authorities: authorities,
+            additionals: additionals,
+            edns: edns,
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A question of a DNS message
+pub struct DnsQuestion {
+    /// The queried domain name
+    pub name: String,
+
+    /// The queried record type
+    pub record_type: DnsRecordType,
+
+    /// The queried class
+    pub class: DnsClass,
+
+    /// A unicast response is preferred, which is only used by multicast DNS
+    pub unicast_response: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A resource record of a DNS message
+pub struct DnsRecord {
+    /// The domain name of the record
+    pub name: String,
+
+    /// The record type
+    pub record_type: DnsRecordType,
+
+    /// The record class
+    pub class: DnsClass,
+
+    /// Other records of the same name, type and class should be removed from caches, which is
+    /// only used by multicast DNS
+    pub cache_flush: bool,
+
+    /// The time in seconds the record may be cached
+    pub ttl: u32,
+
+    /// The record data
+    pub data: DnsRecordData,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The typed data of a resource record
+pub enum DnsRecordData {
+    /// An IPv4 address
+    A(Ipv4Addr),
+
+    /// An IPv6 address
+    Aaaa(Ipv6Addr),
+
+    /// The canonical name of an alias
+    Cname(String),
+
+    /// An authoritative name server
+    Ns(String),
+
+    /// The domain name of a reverse lookup
+    Ptr(String),
+
+    /// A mail exchange
+    Mx {
+        /// The preference, where lower values are preferred
+        preference: u16,
+
+        /// The host name of the mail exchange
+        exchange: String,
+    },
+
+    /// The character strings of a text record
+    Txt(Vec<Vec<u8>>),
+
+    /// The start of a zone of authority
+    Soa {
+        /// The primary name server
+        mname: String,
+
+        /// The mailbox of the responsible person
+        rname: String,
+
+        /// The version of the zone
+        serial: u32,
+
+        /// The interval in seconds before the zone should be refreshed
+        refresh: u32,
+
+        /// The interval in seconds before a failed refresh should be retried
+        retry: u32,
+
+        /// The time in seconds after which the zone is no longer authoritative
+        expire: u32,
+
+        /// The time in seconds negative responses may be cached
+        minimum: u32,
+    },
+
+    /// The location of a service
+    Srv {
+        /// The priority, where lower values are preferred
+        priority: u16,
+
+        /// The relative weight of records with the same priority
+        weight: u16,
+
+        /// The port of the service
+        port: u16,
+
+        /// The host name of the service
+        target: String,
+    },
+
+    /// A certification authority authorization
+    Caa {
+        /// The issuer critical flag
+        critical: bool,
+
+        /// The property tag, like "issue"
+        tag: String,
+
+        /// The property value
+        value: Vec<u8>,
+    },
+
+    /// The service binding of a SVCB or HTTPS record
+    Svcb {
+        /// The priority, where zero is the alias mode
+        priority: u16,
+
+        /// The target name, where "." refers to the owner name
+        target: String,
+
+        /// The service parameters
+        params: Vec<DnsSvcParam>,
+    },
+
+    /// A delegation signer
+    Ds {
+        /// The key tag of the referenced key
+        key_tag: u16,
+
+        /// The algorithm of the referenced key
+        algorithm: u8,
+
+        /// The digest algorithm
+        digest_type: u8,
+
+        /// The digest of the referenced key
+        digest: Vec<u8>,
+    },
+
+    /// A public key of a zone
+    Dnskey {
+        /// The flags, like the zone key and secure entry point bits
+        flags: u16,
+
+        /// The protocol, which is always 3
+        protocol: u8,
+
+        /// The algorithm of the key
+        algorithm: u8,
+
+        /// The public key
+        public_key: Vec<u8>,
+    },
+
+    /// The signature of a record set
+    Rrsig {
+        /// The type of the signed records
+        type_covered: DnsRecordType,
+
+        /// The algorithm of the signature
+        algorithm: u8,
+
+        /// The number of labels of the owner name
+        labels: u8,
+
+        /// The TTL of the signed records
+        original_ttl: u32,
+
+        /// The end of the validity as seconds since the UNIX epoch (modulo 2^32)
+        expiration: u32,
+
+        /// The start of the validity as seconds since the UNIX epoch (modulo 2^32)
+        inception: u32,
+
+        /// The key tag of the signing key
+        key_tag: u16,
+
+        /// The zone of the signing key
+        signer_name: String,
+
+        /// The signature
+        signature: Vec<u8>,
+    },
+
+    /// The raw data of any other or malformed record
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A service parameter of a SVCB or HTTPS record
+pub enum DnsSvcParam {
+    /// The keys which are mandatory for the client
+    Mandatory(Vec<u16>),
+
+    /// The supported application layer protocols
+    Alpn(Vec<String>),
+
+    /// The default protocol is not supported
+    NoDefaultAlpn,
+
+    /// The alternative port
+    Port(u16),
+
+    /// IPv4 address hints
+    Ipv4Hint(Vec<Ipv4Addr>),
+
+    /// The Encrypted Client Hello configuration
+    Ech(Vec<u8>),
+
+    /// IPv6 address hints
+    Ipv6Hint(Vec<Ipv6Addr>),
+
+    /// Any other or malformed parameter by its key
+    Other(u16, Vec<u8>),
+}
+
+impl DnsSvcParam {
+    fn decode(key: u16, data: &[u8]) -> DnsSvcParam {
+        let mut reader = DnsReader::new(data, 0, data.len());
+        let decoded = match key {
+            0 => reader.list(|r| r.u16()).map(DnsSvcParam::Mandatory),
+            1 => reader
+                .list(|r| {
+                    let length = r.u8()? as usize;
+                    r.bytes(length).map(|alpn| String::from_utf8_lossy(alpn).into_owned())
+                })
+                .map(DnsSvcParam::Alpn),
+            2 if data.is_empty() => Some(DnsSvcParam::NoDefaultAlpn),
+            3 if data.len() == 2 => reader.u16().map(DnsSvcParam::Port),
+            4 => reader
+                .list(|r| r.bytes(4).map(|b| Ipv4Addr::new(b[0], b[1], b[2], b[3])))
+                .map(DnsSvcParam::Ipv4Hint),
+            5 => Some(DnsSvcParam::Ech(data.to_vec())),
+            6 => reader.list(|r| r.ipv6()).map(DnsSvcParam::Ipv6Hint),
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| DnsSvcParam::Other(key, data.to_vec()))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The EDNS information of an OPT pseudo record
+pub struct DnsEdns {
+    /// The maximum UDP payload size of the sender
+    pub udp_payload_size: u16,
+
+    /// The upper eight bits of the extended response code
+    pub extended_rcode: u8,
+
+    /// The EDNS version
+    pub version: u8,
+
+    /// The sender supports DNSSEC
+    pub dnssec_ok: bool,
+
+    /// The options
+    pub options: Vec<DnsEdnsOption>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// An EDNS option
+pub enum DnsEdnsOption {
+    /// The name server identifier, which is empty in queries
+    Nsid(Vec<u8>),
+
+    /// The subnet of the client for which the query was sent
+    ClientSubnet {
+        /// The leftmost significant bits of the address in the query
+        source_prefix_length: u8,
+
+        /// The leftmost significant bits of the address the response applies to
+        scope_prefix_length: u8,
+
+        /// The address, where the bits after the source prefix are zero
+        address: IpAddr,
+    },
+
+    /// The client cookie and the server cookie, which is unknown in the first query
+    Cookie {
+        /// The client cookie of 8 bytes
+        client: Vec<u8>,
+
+        /// The server cookie of 8 to 32 bytes
+        server: Option<Vec<u8>>,
+    },
+
+    /// Padding with the given amount of bytes
+    Padding(usize),
+
+    /// An extended error
+    ExtendedError {
+        /// The error code
+        info_code: u16,
+
+        /// The additional text
+        extra_text: String,
+    },
+
+    /// Any other or malformed option by its code
+    Other(u16, Vec<u8>),
+}
+
+impl DnsEdnsOption {
+    fn decode(code: u16, data: &[u8]) -> DnsEdnsOption {
+        let decoded = match code {
+            3 => Some(DnsEdnsOption::Nsid(data.to_vec())),
+            8 => DnsEdnsOption::decode_client_subnet(data),
+            10 => match data.len() {
+                8 => Some(DnsEdnsOption::Cookie {
+                    client: data.to_vec(),
+                    server: None,
+                }),
+                length if (16..41).contains(&length) => Some(DnsEdnsOption::Cookie {
+                    client: data[..8].to_vec(),
+                    server: Some(data[8..].to_vec()),
+                }),
+                _ => None,
+            },
+            12 => Some(DnsEdnsOption::Padding(data.len())),
+            15 if data.len() >= 2 => Some(DnsEdnsOption::ExtendedError {
+                info_code: (data[0] as u16) << 8 | data[1] as u16,
+                extra_text: String::from_utf8_lossy(&data[2..]).into_owned(),
+            }),
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| DnsEdnsOption::Other(code, data.to_vec()))
+    }
+
+    fn decode_client_subnet(data: &[u8]) -> Option<DnsEdnsOption> {
+        if data.len() < 4 {
+            return None;
+        }
+        let family = (data[0] as u16) << 8 | data[1] as u16;
+        let address = &data[4..];
+        let address = match family {
+            1 if address.len() <= 4 => {
+                let mut octets = [0; 4];
+                octets[..address.len()].copy_from_slice(address);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            2 if address.len() <= 16 => {
+                let mut octets = [0; 16];
+                octets[..address.len()].copy_from_slice(address);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return None,
+        };
+        Some(DnsEdnsOption::ClientSubnet {
+            source_prefix_length: data[2],
+            scope_prefix_length: data[3],
+            address: address,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The kind of query of a DNS message
+pub enum DnsOpcode {
+    /// A standard query
+    Query,
+
+    /// An inverse query, which is obsolete
+    InverseQuery,
+
+    /// A server status request
+    Status,
+
+    /// A notification about a changed zone
+    Notify,
+
+    /// A dynamic update
+    Update,
+
+    /// DNS stateful operations
+    Dso,
+
+    /// Any other opcode
+    Other(u8),
+}
+
+impl DnsOpcode {
+    /// Convert a u8 to a `DnsOpcode`
+    pub fn from_u8(input: u8) -> DnsOpcode {
+        match input {
+            0 => DnsOpcode::Query,
+            1 => DnsOpcode::InverseQuery,
+            2 => DnsOpcode::Status,
+            4 => DnsOpcode::Notify,
+            5 => DnsOpcode::Update,
+            6 => DnsOpcode::Dso,
+            other => DnsOpcode::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The response code of a DNS message
+pub enum DnsResponseCode {
+    /// No error
+    NoError,
+
+    /// The query was malformed
+    FormatError,
+
+    /// The server failed to process the query
+    ServerFailure,
+
+    /// The domain name does not exist
+    NameError,
+
+    /// The kind of query is not supported
+    NotImplemented,
+
+    /// The query was refused by policy
+    Refused,
+
+    /// A name exists which should not
+    YxDomain,
+
+    /// A record set exists which should not
+    YxRrSet,
+
+    /// A record set does not exist which should
+    NxRrSet,
+
+    /// The server is not authoritative for the zone
+    NotAuth,
+
+    /// A name is not within the zone
+    NotZone,
+
+    /// The EDNS version is not supported
+    BadVersion,
+
+    /// The server cookie is invalid
+    BadCookie,
+
+    /// Any other response code
+    Other(u16),
+}
+
+impl DnsResponseCode {
+    /// Convert a u16 to a `DnsResponseCode`
+    pub fn from_u16(input: u16) -> DnsResponseCode {
+        match input {
+            0 => DnsResponseCode::NoError,
+            1 => DnsResponseCode::FormatError,
+            2 => DnsResponseCode::ServerFailure,
+            3 => DnsResponseCode::NameError,
+            4 => DnsResponseCode::NotImplemented,
+            5 => DnsResponseCode::Refused,
+            6 => DnsResponseCode::YxDomain,
+            7 => DnsResponseCode::YxRrSet,
+            8 => DnsResponseCode::NxRrSet,
+            9 => DnsResponseCode::NotAuth,
+            10 => DnsResponseCode::NotZone,
+            16 => DnsResponseCode::BadVersion,
+            23 => DnsResponseCode::BadCookie,
+            other => DnsResponseCode::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The type of a resource record or question
+pub enum DnsRecordType {
+    /// IPv4 address
+    A,
+
+    /// Authoritative name server
+    Ns,
+
+    /// Canonical name
+    Cname,
+
+    /// Start of authority
+    Soa,
+
+    /// Domain name pointer
+    Ptr,
+
+    /// Host information
+    Hinfo,
+
+    /// Mail exchange
+    Mx,
+
+    /// Text strings
+    Txt,
+
+    /// IPv6 address
+    Aaaa,
+
+    /// Service locator
+    Srv,
+
+    /// EDNS pseudo record
+    Opt,
+
+    /// Delegation signer
+    Ds,
+
+    /// Record set signature
+    Rrsig,
+
+    /// Next secure record
+    Nsec,
+
+    /// DNS public key
+    Dnskey,
+
+    /// Hashed next secure record
+    Nsec3,
+
+    /// Service binding
+    Svcb,
+
+    /// HTTPS service binding
+    Https,
+
+    /// Incremental zone transfer
+    Ixfr,
+
+    /// Full zone transfer
+    Axfr,
+
+    /// All records
+    Any,
+
+    /// Certification authority authorization
+    Caa,
+
+    /// Any other type
+    Other(u16),
+}
+
+impl DnsRecordType {
+    /// Convert a u16 to a `DnsRecordType`
+    pub fn from_u16(input: u16) -> DnsRecordType {
+        match input {
+            1 => DnsRecordType::A,
+            2 => DnsRecordType::Ns,
+            5 => DnsRecordType::Cname,
+            6 => DnsRecordType::Soa,
+            12 => DnsRecordType::Ptr,
+            13 => DnsRecordType::Hinfo,
+            15 => DnsRecordType::Mx,
+            16 => DnsRecordType::Txt,
+            28 => DnsRecordType::Aaaa,
+            33 => DnsRecordType::Srv,
+            41 => DnsRecordType::Opt,
+            43 => DnsRecordType::Ds,
+            46 => DnsRecordType::Rrsig,
+            47 => DnsRecordType::Nsec,
+            48 => DnsRecordType::Dnskey,
+            50 => DnsRecordType::Nsec3,
+            64 => DnsRecordType::Svcb,
+            65 => DnsRecordType::Https,
+            251 => DnsRecordType::Ixfr,
+            252 => DnsRecordType::Axfr,
+            255 => DnsRecordType::Any,
+            257 => DnsRecordType::Caa,
+            other => DnsRecordType::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The class of a resource record or question
+pub enum DnsClass {
+    /// The Internet
+    Internet,
+
+    /// The Chaos network, which is used for server information like "version.bind"
+    Chaos,
+
+    /// Hesiod
+    Hesiod,
+
+    /// No class, as used by dynamic updates
+    None,
+
+    /// Any class
+    Any,
+
+    /// Any other class
+    Other(u16),
+}
+
+impl DnsClass {
+    /// Convert a u16 to a `DnsClass`
+    pub fn from_u16(input: u16) -> DnsClass {
+        match input {
+            1 => DnsClass::Internet,
+            3 => DnsClass::Chaos,
+            4 => DnsClass::Hesiod,
+            254 => DnsClass::None,
+            255 => DnsClass::Any,
+            other => DnsClass::Other(other),
+        }
+    }
+
+    /// Split the top bit from the class of multicast DNS messages
+    fn split(input: u16, multicast: bool) -> (DnsClass, bool) {
+        if multicast {
+            (DnsClass::from_u16(input & 0x7fff), input & 0x8000 != 0)
+        } else {
+            (DnsClass::from_u16(input), false)
+        }
+    }
+}
+
+/// A cursor within a complete message, which is needed to resolve compressed names. Unlike the
+/// nom parsers of the other protocols, which only see the remaining input, compression pointers
+/// may refer to any prior position of the message, and record data has to be read within its
+/// length but with access to the complete message.
+struct DnsReader<'a> {
+    /// The complete message
+    message: &'a [u8],
+
+    /// The current position within the message
+    position: usize,
+
+    /// The end of the readable data, like the end of the record data
+    end: usize,
+}
+
+impl<'a> DnsReader<'a> {
+    fn new(message: &'a [u8], position: usize, end: usize) -> Self {
+        DnsReader {
+            message: message,
+            position: position,
+            end: end,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.end
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.end - self.position < length {
+            return None;
+        }
+        self.position += length;
+        Some(&self.message[self.position - length..self.position])
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.message[self.position..self.end];
+        self.position = self.end;
+        rest
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| (b[0] as u16) << 8 | b[1] as u16)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32)
+    }
+
+    /// Read a domain name, where compression pointers have to point to a prior position in the
+    /// message. The root name is ".", other names have no trailing dot.
+    fn name(&mut self) -> Option<String> {
+        let mut labels = vec![];
+        let mut position = self.position;
+        let mut pointers = 0;
+        loop {
+            let length = *self.message.get(position)? as usize;
+            match length & 0xc0 {
+                0x00 if length == 0 => {
+                    position += 1;
+                    break;
+                }
+                0x00 => {
+                    let label = self.message.get(position + 1..position + 1 + length)?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    position += 1 + length;
+                }
+                0xc0 => {
+                    let pointer = (length & 0x3f) << 8 | *self.message.get(position + 1)? as usize;
+                    if pointer >= position || pointers == MAX_POINTERS {
+                        return None;
+                    }
+                    if pointers == 0 {
+                        self.position = position + 2;
+                    }
+                    pointers += 1;
+                    position = pointer;
+                }
+                _ => return None,
+            }
+        }
+        if pointers == 0 {
+            self.position = position;
+        }
+        if self.position > self.end {
+            return None;
+        }
+        Some(if labels.is_empty() {
+            ".".to_owned()
+        } else {
+            labels.join(".")
+        })
+    }
+
+    /// Read the OPT pseudo record at the current position, which does not advance otherwise
+    fn edns(&mut self) -> Option<Option<DnsEdns>> {
+        let start = self.position;
+        if self.name()? != "." || self.u16()? != 41 {
+            self.position = start;
+            return Some(None);
+        }
+        let udp_payload_size = self.u16()?;
+        let ttl = self.u32()?;
+        let length = self.u16()? as usize;
+        let mut data = DnsReader::new(self.message, self.position, self.position + length);
+        self.bytes(length)?;
+
+        let mut options = vec![];
+        while !data.is_empty() {
+            let code = data.u16()?;
+            let length = data.u16()? as usize;
+            options.push(DnsEdnsOption::decode(code, data.bytes(length)?));
+        }
+        Some(Some(DnsEdns {
+            udp_payload_size: udp_payload_size,
+            extended_rcode: (ttl >> 24) as u8,
+            version: (ttl >> 16) as u8,
+            dnssec_ok: ttl & 0x8000 != 0,
+            options: options,
+        }))
+    }
+
+    /// Read a resource record, where malformed data is kept raw
+    fn record(&mut self, multicast: bool) -> Option<DnsRecord> {
+        let name = self.name()?;
+        let record_type = DnsRecordType::from_u16(self.u16()?);
+        let (class, cache_flush) = DnsClass::split(self.u16()?, multicast);
+        let ttl = self.u32()?;
+        let length = self.u16()? as usize;
+        let start = self.position;
+        let raw = self.bytes(length)?;
+
+        let mut data = DnsReader::new(self.message, start, start + length);
+        let decoded = data.record_data(record_type).and_then(|decoded| {
+            if data.is_empty() {
+                Some(decoded)
+            } else {
+                None
+            }
+        });
+        Some(DnsRecord {
+            name: name,
+            record_type: record_type,
+            class: class,
+            cache_flush: cache_flush,
+            ttl: ttl,
+            data: decoded.unwrap_or_else(|| DnsRecordData::Other(raw.to_vec())),
+        })
+    }
+
+    fn record_data(&mut self, record_type: DnsRecordType) -> Option<DnsRecordData> {
+        Some(match record_type {
+            DnsRecordType::A => {
+                let b = self.bytes(4)?;
+                DnsRecordData::A(Ipv4Addr::new(b[0], b[1], b[2], b[3]))
+            }
+            DnsRecordType::Aaaa => DnsRecordData::Aaaa(self.ipv6()?),
+            DnsRecordType::Cname => DnsRecordData::Cname(self.name()?),
+            DnsRecordType::Ns => DnsRecordData::Ns(self.name()?),
+            DnsRecordType::Ptr => DnsRecordData::Ptr(self.name()?),
+            DnsRecordType::Mx => DnsRecordData::Mx {
+                preference: self.u16()?,
+                exchange: self.name()?,
+            },
+            DnsRecordType::Txt => {
+                let mut strings = vec![];
+                while !self.is_empty() {
+                    let length = self.u8()? as usize;
+                    strings.push(self.bytes(length)?.to_vec());
+                }
+                DnsRecordData::Txt(strings)
+            }
+            DnsRecordType::Soa => DnsRecordData::Soa {
+                mname: self.name()?,
+                rname: self.name()?,
+                serial: self.u32()?,
+                refresh: self.u32()?,
+                retry: self.u32()?,
+                expire: self.u32()?,
+                minimum: self.u32()?,
+            },
+            DnsRecordType::Srv => DnsRecordData::Srv {
+                priority: self.u16()?,
+                weight: self.u16()?,
+                port: self.u16()?,
+                target: self.name()?,
+            },
+            DnsRecordType::Caa => {
+                let flags = self.u8()?;
+                let length = self.u8()? as usize;
+                DnsRecordData::Caa {
+                    critical: flags & 0x80 != 0,
+                    tag: String::from_utf8_lossy(self.bytes(length)?).into_owned(),
+                    value: self.rest().to_vec(),
+                }
+            }
+            DnsRecordType::Svcb | DnsRecordType::Https => {
+                let priority = self.u16()?;
+                let target = self.name()?;
+                let mut params = vec![];
+                while !self.is_empty() {
+                    let key = self.u16()?;
+                    let length = self.u16()? as usize;
+                    params.push(DnsSvcParam::decode(key, self.bytes(length)?));
+                }
+                DnsRecordData::Svcb {
+                    priority: priority,
+                    target: target,
+                    params: params,
+                }
+            }
+            DnsRecordType::Ds => DnsRecordData::Ds {
+                key_tag: self.u16()?,
+                algorithm: self.u8()?,
+                digest_type: self.u8()?,
+                digest: self.rest().to_vec(),
+            },
+            DnsRecordType::Dnskey => DnsRecordData::Dnskey {
+                flags: self.u16()?,
+                protocol: self.u8()?,
+                algorithm: self.u8()?,
+                public_key: self.rest().to_vec(),
+            },
+            DnsRecordType::Rrsig => DnsRecordData::Rrsig {
+                type_covered: DnsRecordType::from_u16(self.u16()?),
+                algorithm: self.u8()?,
+                labels: self.u8()?,
+                original_ttl: self.u32()?,
+                expiration: self.u32()?,
+                inception: self.u32()?,
+                key_tag: self.u16()?,
+                signer_name: self.name()?,
+                signature: self.rest().to_vec(),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Read items until the end, which fails if the last item is incomplete
+    fn list<T, F>(&mut self, mut item: F) -> Option<Vec<T>>
+    where
+        F: FnMut(&mut DnsReader<'a>) -> Option<T>,
+    {
+        let mut items = vec![];
+        while !self.is_empty() {
+            items.push(item(self)?);
+        }
+        Some(items)
+    }
+
+    fn ipv6(&mut self) -> Option<Ipv6Addr> {
+        let b = self.bytes(16)?;
+        let mut octets = [0; 16];
+        octets.copy_from_slice(b);
+        Some(Ipv6Addr::from(octets))
+    }
+}
@@ -0,0 +1,427 @@
+//! gRPC related packet processing on top of HTTP/2 streams
+use prelude::*;
+use std::collections::HashMap;
+
+/// The maximum amount of calls per parser waiting for their response, where the oldest one is
+/// dropped first
+const MAX_CALLS: usize = 4096;
+
+/// The key of a call, which is the connection together with the stream
+type GrpcCallKey = (Option<Identifier<IpProtocol>>, u32);
+
+/// The gRPC parser, which extracts the messages of HTTP/2 streams
+#[derive(Debug)]
+pub struct GrpcParser {
+    /// The maximum size of a decompressed message, larger messages are not decompressed
+    pub max_decoded_size: usize,
+
+    /// The descriptors used to decode the messages into fields. Without descriptors, the
+    /// messages are only decoded into their raw wire format fields.
+    pub descriptors: Option<ProtobufDescriptorSet>,
+
+    /// The service and method of the calls per connection and stream, which are waiting for
+    /// their response, together with the number of the call
+    calls: HashMap<GrpcCallKey, ((String, String), u64)>,
+
+    /// The number of the next call
+    next_call: u64,
+}
+
+impl GrpcParser {
+    /// Create a new gRPC parser without descriptors
+    pub fn new() -> Self {
+        GrpcParser {
+            max_decoded_size: 10 * 1024 * 1024,
+            descriptors: None,
+            calls: HashMap::new(),
+            next_call: 0,
+        }
+    }
+
+    /// Convert a HTTP/2 message into a gRPC message, which returns None for non gRPC messages
+    fn process(&mut self, identifier: &Option<Identifier<IpProtocol>>, message: &Http2Message) -> Option<GrpcMessage> {
+        let key = (identifier.clone(), message.stream_id);
+        let call = if message.from_client {
+            if !GrpcParser::is_grpc(message.header("content-type")) {
+                return None;
+            }
+            let call = message.path().and_then(GrpcParser::parse_path);
+            if let Some(ref call) = call {
+                if self.calls.len() >= MAX_CALLS && !self.calls.contains_key(&key) {
+                    let oldest = self.calls
+                        .iter()
+                        .min_by_key(|&(_, &(_, number))| number)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        debug!("Dropping the oldest gRPC call because too many calls are pending");
+                        self.calls.remove(&oldest);
+                    }
+                }
+                self.calls.insert(key, (call.clone(), self.next_call));
+                self.next_call += 1;
+            }
+            call
+        } else {
+            // Responses are identified by their request, or by their content type if the
+            // request was not seen
+            match self.calls.remove(&key) {
+                Some((call, _)) => Some(call),
+                None if GrpcParser::is_grpc(message.header("content-type")) => None,
+                None => return None,
+            }
+        };
+
+        let encoding = message.header("grpc-encoding").map(|encoding| encoding.to_owned());
+        let type_name = match (&self.descriptors, &call) {
+            (&Some(ref descriptors), &Some((ref service, ref method))) => descriptors
+                .method_types(service, method)
+                .map(|(input, output)| if message.from_client { input } else { output }),
+            _ => None,
+        };
+
+        let payloads = GrpcParser::split(&message.body)
+            .into_iter()
+            .map(|(compressed, data)| {
+                let decompressed = match encoding {
+                    Some(ref encoding) if compressed => {
+                        decode_content_coding(encoding, data, self.max_decoded_size)
+                    }
+                    _ => None,
+                };
+                let fields = {
+                    let plain = match decompressed {
+                        Some(ref decompressed) => Some(&decompressed[..]),
+                        None if !compressed => Some(data),
+                        None => None,
+                    };
+                    match (plain, &self.descriptors, type_name) {
+                        (Some(plain), &Some(ref descriptors), Some(type_name)) => {
+                            descriptors.decode(type_name, plain)
+                        }
+                        (Some(plain), _, _) => ProtobufDescriptorSet::decode_raw(plain),
+                        (None, _, _) => None,
+                    }
+                };
+                GrpcPayload {
+                    compressed: compressed,
+                    data: data.to_vec(),
+                    decompressed: decompressed,
+                    fields: fields,
+                }
+            })
+            .collect();
+
+        // Responses without messages carry the status within their headers
+        let status_headers = if message.trailers.is_empty() {
+            &message.headers
+        } else {
+            &message.trailers
+        };
+        let status_header = |name: &str| {
+            status_headers
+                .iter()
+                .find(|header| header.key == name)
+                .map(|header| header.value.as_str())
+        };
+        let (status, status_message) = if message.from_client {
+            (None, None)
+        } else {
+            (
+                status_header("grpc-status")
+                    .and_then(|status| status.parse().ok())
+                    .map(GrpcStatusCode::from_u32),
+                status_header("grpc-message").map(percent_decode),
+            )
+        };
+
+        let (service, method) = match call {
+            Some((service, method)) => (Some(service), Some(method)),
+            None => (None, None),
+        };
+        Some(GrpcMessage {
+            stream_id: message.stream_id,
+            from_client: message.from_client,
+            service: service,
+            method: method,
+            encoding: encoding,
+            payloads: payloads,
+            truncated: message.truncated,
+            status: status,
+            status_message: status_message,
+        })
+    }
+
+    /// Drop the calls of the streams which were reset or will not be processed any more
+    fn reset(&mut self, identifier: &Option<Identifier<IpProtocol>>, frames: &[Http2Frame]) {
+        for frame in frames {
+            match frame.payload {
+                Http2FramePayload::RstStream(_) => {
+                    self.calls.remove(&(identifier.clone(), frame.stream_id));
+                }
+
+                // All calls of a connection error are dropped
+                Http2FramePayload::Goaway {
+                    last_stream_id,
+                    ref error_code,
+                    ..
+                } => {
+                    let error = *error_code != Http2ErrorCode::NoError;
+                    self.calls.retain(|&(ref connection, stream_id), _| {
+                        connection != identifier || (!error && stream_id <= last_stream_id)
+                    });
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    /// Drop all calls of a connection
+    fn close(&mut self, identifier: &Option<Identifier<IpProtocol>>) {
+        self.calls.retain(|&(ref connection, _), _| connection != identifier);
+    }
+
+    /// Check for the content types "application/grpc", "application/grpc+proto" and similar
+    fn is_grpc(content_type: Option<&str>) -> bool {
+        match content_type {
+            Some(content_type) => {
+                let content_type = content_type.to_ascii_lowercase();
+                content_type == "application/grpc" || content_type.starts_with("application/grpc+")
+                    || content_type.starts_with("application/grpc;")
+            }
+            None => false,
+        }
+    }
+
+    /// Split a path like "/package.Service/Method" into service and method
+    fn parse_path(path: &str) -> Option<(String, String)> {
+        let mut parts = path.splitn(3, '/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(""), Some(service), Some(method)) if !service.is_empty() && !method.is_empty() => {
+                Some((service.to_owned(), method.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Split the body into its length-prefixed messages, an incomplete last message is dropped
+    fn split(body: &[u8]) -> Vec<(bool, &[u8])> {
+        match GrpcParser::parse_messages(body) {
+            IResult::Done(rest, messages) => {
+                if !rest.is_empty() {
+                    debug!("Dropping incomplete gRPC message of {} bytes", rest.len());
+                }
+                messages
+            }
+            _ => vec![],
+        }
+    }
+
+    named!(parse_messages<&[u8], Vec<(bool, &[u8])>>,
+        many0!(complete!(do_parse!(
+            compressed: be_u8 >>
+            data: length_bytes!(be_u32) >>
+            (compressed & 0x01 != 0, data)
+        )))
+    );
+}
+
+impl Default for GrpcParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for GrpcParser {
+    /// Parse a `GrpcPacket` from the messages of the parent `Http2Packet`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let http2 = match result
+            .and_then(|vector| vector.last())
+            .and_then(|any| any.downcast_ref::<Http2Packet>())
+        {
+            Some(http2) => http2,
+            None => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        };
+
+        let tcp = result.and_then(|vector| {
+            vector
+                .iter()
+                .rev()
+                .filter_map(|any| any.downcast_ref::<TcpPacket>())
+                .next()
+        });
+        let identifier = tcp
+            .and_then(|tcp| connection_identifier(result, tcp.header.source_port, tcp.header.dest_port))
+            .map(|(identifier, _)| identifier);
+
+        // A new connection does not continue the calls of a previous one
+        if http2.preface {
+            self.close(&identifier);
+        }
+
+        let messages: Vec<GrpcMessage> = http2
+            .messages
+            .iter()
+            .filter_map(|message| self.process(&identifier, message))
+            .collect();
+
+        self.reset(&identifier, &http2.frames);
+        if tcp.is_some_and(|tcp| tcp.header.flag_fin || tcp.header.flag_rst) {
+            self.close(&identifier);
+        }
+        if messages.is_empty() {
+            return IResult::Error(error_position!(ErrorKind::Custom(1), input));
+        }
+
+        IResult::Done(input, Box::new(GrpcPacket { messages: messages }))
+    }
+}
+
+impl fmt::Display for GrpcParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gRPC")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Representation of the gRPC messages of a HTTP/2 packet
+pub struct GrpcPacket {
+    /// The requests and responses which were completed by the packet
+    pub messages: Vec<GrpcMessage>,
+}
+
+#[derive(Debug, PartialEq)]
+/// A gRPC request or response
+pub struct GrpcMessage {
+    /// The HTTP/2 stream of the call
+    pub stream_id: u32,
+
+    /// The message was sent by the client, which means it is a request
+    pub from_client: bool,
+
+    /// The fully qualified service name, like "package.Service"
+    pub service: Option<String>,
+
+    /// The method name
+    pub method: Option<String>,
+
+    /// The compression of the messages, from the "grpc-encoding" header
+    pub encoding: Option<String>,
+
+    /// The length-prefixed messages of the stream
+    pub payloads: Vec<GrpcPayload>,
+
+    /// The body exceeded the maximum body size of the HTTP/2 parser and is incomplete
+    pub truncated: bool,
+
+    /// The status of a response
+    pub status: Option<GrpcStatusCode>,
+
+    /// The status message of a response, with its percent-encoding removed
+    pub status_message: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+/// A single length-prefixed message
+pub struct GrpcPayload {
+    /// The message is compressed with the encoding of the stream
+    pub compressed: bool,
+
+    /// The message as transferred
+    pub data: Vec<u8>,
+
+    /// The decompressed message, if it was compressed with a supported encoding
+    pub decompressed: Option<Vec<u8>>,
+
+    /// The fields of the message, which are named if a descriptor of the method is available
+    pub fields: Option<Vec<ProtobufField>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The status codes of a gRPC call
+pub enum GrpcStatusCode {
+    /// Successful completion
+    Ok,
+
+    /// The operation was cancelled
+    Cancelled,
+
+    /// Unknown error
+    Unknown,
+
+    /// The client specified an invalid argument
+    InvalidArgument,
+
+    /// The deadline expired before the operation could complete
+    DeadlineExceeded,
+
+    /// Some requested entity was not found
+    NotFound,
+
+    /// The entity that a client attempted to create already exists
+    AlreadyExists,
+
+    /// The caller does not have permission
+    PermissionDenied,
+
+    /// Some resource has been exhausted
+    ResourceExhausted,
+
+    /// The system is not in a state required for the operation
+    FailedPrecondition,
+
+    /// The operation was aborted
+    Aborted,
+
+    /// The operation was attempted past the valid range
+    OutOfRange,
+
+    /// The operation is not implemented or supported
+    Unimplemented,
+
+    /// Internal error
+    Internal,
+
+    /// The service is currently unavailable
+    Unavailable,
+
+    /// Unrecoverable data loss or corruption
+    DataLoss,
+
+    /// The request does not have valid authentication credentials
+    Unauthenticated,
+
+    /// A status code which is not defined
+    Other(u32),
+}
+
+impl GrpcStatusCode {
+    /// Convert a numeric status code
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            0 => GrpcStatusCode::Ok,
+            1 => GrpcStatusCode::Cancelled,
+            2 => GrpcStatusCode::Unknown,
+            3 => GrpcStatusCode::InvalidArgument,
+            4 => GrpcStatusCode::DeadlineExceeded,
+            5 => GrpcStatusCode::NotFound,
+            6 => GrpcStatusCode::AlreadyExists,
+            7 => GrpcStatusCode::PermissionDenied,
+            8 => GrpcStatusCode::ResourceExhausted,
+            9 => GrpcStatusCode::FailedPrecondition,
+            10 => GrpcStatusCode::Aborted,
+            11 => GrpcStatusCode::OutOfRange,
+            12 => GrpcStatusCode::Unimplemented,
+            13 => GrpcStatusCode::Internal,
+            14 => GrpcStatusCode::Unavailable,
+            15 => GrpcStatusCode::DataLoss,
+            16 => GrpcStatusCode::Unauthenticated,
+            other => GrpcStatusCode::Other(other),
+        }
+    }
+}
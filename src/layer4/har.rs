@@ -0,0 +1,320 @@
+//! Export of HTTP transactions in the HTTP Archive (HAR) 1.2 format
+use prelude::*;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use base64;
+
+/// Records HTTP transactions as HAR entries
+#[derive(Debug)]
+pub struct HarRecorder {
+    /// The requests which are still waiting for a response, with their capture time and the
+    /// identifier of their connection
+    pending: HttpPendingRequests<(HttpRequest, Duration, Option<String>)>,
+
+    /// The completed transactions in the order of their responses
+    entries: Vec<HarEntry>,
+}
+
+impl HarRecorder {
+    /// Create a new HAR recorder without any entries
+    pub fn new() -> Self {
+        HarRecorder {
+            pending: HttpPendingRequests::new(),
+            entries: vec![],
+        }
+    }
+
+    /// Record the result of a traversal, where the timestamp is the capture time of the packet
+    /// since the UNIX epoch. Returns true if the result completed at least one entry.
+    pub fn record(&mut self, result: &ParserResultVec, timestamp: Duration) -> bool {
+        // The connection is identified by the endpoints of the client, which sends the requests
+        let tcp = result
+            .iter()
+            .rev()
+            .filter_map(|any| any.downcast_ref::<TcpPacket>())
+            .next();
+        let id = match (ip_endpoints(Some(result)), tcp) {
+            (Some((source, destination, _)), Some(tcp)) => Some(format!(
+                "{}-{}",
+                SocketAddr::new(source, tcp.header.source_port),
+                SocketAddr::new(destination, tcp.header.dest_port)
+            )),
+            _ => None,
+        };
+        let pairs = match self.pending
+            .pair(result, |_, request| (request.clone(), timestamp, id.clone()))
+        {
+            Some(pairs) => pairs,
+            None => return false,
+        };
+
+        let mut completed = false;
+        for (request, response) in pairs.responses {
+            if let Some((request, request_timestamp, id)) = request {
+                self.entries.push(HarEntry {
+                    request: request,
+                    response: response.clone(),
+                    request_timestamp: request_timestamp,
+                    response_timestamp: timestamp,
+                    server_ip: ip_endpoints(Some(result)).map(|(source, _, _)| source),
+                    connection: id,
+                });
+                completed = true;
+            }
+        }
+        completed
+    }
+
+    /// Get the recorded entries
+    pub fn entries(&self) -> &[HarEntry] {
+        &self.entries
+    }
+
+    /// Create the HAR document of all recorded entries
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self.entries.iter().map(HarEntry::to_json).collect();
+        format!(
+            "{{\"log\":{{\"version\":\"1.2\",\"creator\":{{\"name\":\"peel-ip\",\"version\":\"{}\"}},\
+             \"entries\":[{}]}}}}",
+            env!("CARGO_PKG_VERSION"),
+            entries.join(",")
+        )
+    }
+}
+
+impl Default for HarRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A HTTP transaction of the archive
+pub struct HarEntry {
+    /// The request
+    pub request: HttpRequest,
+
+    /// The final response to the request
+    pub response: HttpResponse,
+
+    /// The capture time of the request since the UNIX epoch
+    pub request_timestamp: Duration,
+
+    /// The capture time of the response since the UNIX epoch
+    pub response_timestamp: Duration,
+
+    /// The address of the server, which sent the response
+    pub server_ip: Option<IpAddr>,
+
+    /// The client and server endpoints of the connection like "10.0.0.1:51781-10.0.0.2:80",
+    /// which identify it uniquely within the archive
+    pub connection: Option<String>,
+}
+
+impl HarEntry {
+    /// Get the time between request and response, which is zero if the response was captured
+    /// before the request
+    pub fn time(&self) -> Duration {
+        self.response_timestamp
+            .checked_sub(self.request_timestamp)
+            .unwrap_or_else(|| Duration::new(0, 0))
+    }
+
+    /// Create the JSON object of the entry
+    pub fn to_json(&self) -> String {
+        let time = HarEntry::milliseconds(self.time());
+        let mut fields = vec![
+            format!("\"startedDateTime\":{}", json_string(&iso_8601(self.request_timestamp))),
+            format!("\"time\":{}", time),
+            format!("\"request\":{}", self.request_json()),
+            format!("\"response\":{}", self.response_json()),
+            "\"cache\":{}".to_owned(),
+            format!(
+                "\"timings\":{{\"blocked\":-1,\"dns\":-1,\"connect\":-1,\"send\":0,\"wait\":{},\
+                 \"receive\":0,\"ssl\":-1}}",
+                time
+            ),
+        ];
+        if let Some(server_ip) = self.server_ip {
+            fields.push(format!("\"serverIPAddress\":{}", json_string(&server_ip.to_string())));
+        }
+        if let Some(ref connection) = self.connection {
+            fields.push(format!("\"connection\":{}", json_string(connection)));
+        }
+        format!("{{{}}}", fields.join(","))
+    }
+
+    fn request_json(&self) -> String {
+        let request = &self.request;
+        let cookies: Vec<String> = request
+            .cookies()
+            .iter()
+            .map(|cookie| HarEntry::pair_json(&cookie.name, &cookie.value))
+            .collect();
+        let query: Vec<String> = request
+            .query_params()
+            .iter()
+            .map(|&(ref name, ref value)| HarEntry::pair_json(name, value))
+            .collect();
+
+        let mut fields = vec![
+            format!("\"method\":{}", json_string(&request.request_method.to_string())),
+            format!("\"url\":{}", json_string(&request.url())),
+            format!("\"httpVersion\":{}", json_string(&request.version.to_string())),
+            format!("\"cookies\":[{}]", cookies.join(",")),
+            format!("\"headers\":{}", HarEntry::headers_json(&request.headers)),
+            format!("\"queryString\":[{}]", query.join(",")),
+        ];
+        if !request.body.is_empty() {
+            let body = request.decoded_body.as_ref().unwrap_or(&request.body);
+            let mime_type = request.header("Content-Type").unwrap_or("");
+            let mut post_data = vec![format!("\"mimeType\":{}", json_string(mime_type))];
+            if mime_type
+                .split(';')
+                .next()
+                .is_some_and(|media_type| {
+                    media_type.trim().eq_ignore_ascii_case("application/x-www-form-urlencoded")
+                }) {
+                let params: Vec<String> = parse_form_urlencoded(&String::from_utf8_lossy(body))
+                    .iter()
+                    .map(|&(ref name, ref value)| HarEntry::pair_json(name, value))
+                    .collect();
+                post_data.push(format!("\"params\":[{}]", params.join(",")));
+            }
+            post_data.push(format!("\"text\":{}", json_string(&String::from_utf8_lossy(body))));
+            fields.push(format!("\"postData\":{{{}}}", post_data.join(",")));
+        }
+        fields.push("\"headersSize\":-1".to_owned());
+        fields.push(format!("\"bodySize\":{}", request.body.len()));
+        format!("{{{}}}", fields.join(","))
+    }
+
+    fn response_json(&self) -> String {
+        let response = &self.response;
+        let cookies: Vec<String> = response
+            .set_cookies()
+            .iter()
+            .map(|cookie| {
+                let mut fields = vec![
+                    format!("\"name\":{}", json_string(&cookie.name)),
+                    format!("\"value\":{}", json_string(&cookie.value)),
+                ];
+                if let Some(ref path) = cookie.path {
+                    fields.push(format!("\"path\":{}", json_string(path)));
+                }
+                if let Some(ref domain) = cookie.domain {
+                    fields.push(format!("\"domain\":{}", json_string(domain)));
+                }
+                if let Some(ref expires) = cookie.expires {
+                    fields.push(format!("\"expires\":{}", json_string(expires)));
+                }
+                fields.push(format!("\"httpOnly\":{}", cookie.http_only));
+                fields.push(format!("\"secure\":{}", cookie.secure));
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect();
+
+        // Binary content is encoded as base64
+        let body = response.decoded_body.as_ref().unwrap_or(&response.body);
+        let mut content = vec![
+            format!("\"size\":{}", body.len()),
+            format!(
+                "\"mimeType\":{}",
+                json_string(response.header("Content-Type").unwrap_or(""))
+            ),
+        ];
+        if response.decoded_body.is_some() {
+            content.push(format!(
+                "\"compression\":{}",
+                body.len() as i64 - response.body.len() as i64
+            ));
+        }
+        if !body.is_empty() {
+            match str::from_utf8(body) {
+                Ok(text) => content.push(format!("\"text\":{}", json_string(text))),
+                Err(_) => {
+                    content.push(format!("\"text\":{}", json_string(&base64::encode(body))));
+                    content.push("\"encoding\":\"base64\"".to_owned());
+                }
+            }
+        }
+
+        format!(
+            "{{\"status\":{},\"statusText\":{},\"httpVersion\":{},\"cookies\":[{}],\"headers\":{},\
+             \"content\":{{{}}},\"redirectURL\":{},\"headersSize\":-1,\"bodySize\":{}}}",
+            response.code,
+            json_string(&response.reason),
+            json_string(&response.version.to_string()),
+            cookies.join(","),
+            HarEntry::headers_json(&response.headers),
+            content.join(","),
+            json_string(response.header("Location").unwrap_or("")),
+            response.body.len()
+        )
+    }
+
+    fn headers_json(headers: &[HttpHeader]) -> String {
+        let headers: Vec<String> = headers
+            .iter()
+            .map(|header| HarEntry::pair_json(&header.key, &header.value))
+            .collect();
+        format!("[{}]", headers.join(","))
+    }
+
+    fn pair_json(name: &str, value: &str) -> String {
+        format!("{{\"name\":{},\"value\":{}}}", json_string(name), json_string(value))
+    }
+
+    /// Get a duration in milliseconds with microsecond precision
+    fn milliseconds(duration: Duration) -> f64 {
+        (duration.as_secs() * 1_000_000 + duration.subsec_nanos() as u64 / 1_000) as f64 / 1_000.0
+    }
+}
+
+/// Quote and escape a string for JSON
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Format a time since the UNIX epoch like "2009-07-24T19:20:30.450Z"
+fn iso_8601(timestamp: Duration) -> String {
+    let seconds = timestamp.as_secs();
+    let days = (seconds / 86_400) as i64;
+
+    // Convert the days to a civil date in the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds % 86_400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        timestamp.subsec_millis()
+    )
+}
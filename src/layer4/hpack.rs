@@ -0,0 +1,322 @@
+//! HPACK header compression for HTTP/2 (RFC 7541)
+use prelude::*;
+use std::collections::VecDeque;
+
+/// The initial size of the dynamic table in bytes
+const DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// The overhead of an entry within the dynamic table in bytes
+const ENTRY_OVERHEAD: usize = 32;
+
+/// A header field, which is the name together with the value
+type HpackField = (Vec<u8>, Vec<u8>);
+
+/// The static table, which is addressed by the indexes 1 to 61
+const STATIC_TABLE: &[(&[u8], &[u8])] = &[
+    (b":authority", b""),
+    (b":method", b"GET"),
+    (b":method", b"POST"),
+    (b":path", b"/"),
+    (b":path", b"/index.html"),
+    (b":scheme", b"http"),
+    (b":scheme", b"https"),
+    (b":status", b"200"),
+    (b":status", b"204"),
+    (b":status", b"206"),
+    (b":status", b"304"),
+    (b":status", b"400"),
+    (b":status", b"404"),
+    (b":status", b"500"),
+    (b"accept-charset", b""),
+    (b"accept-encoding", b"gzip, deflate"),
+    (b"accept-language", b""),
+    (b"accept-ranges", b""),
+    (b"accept", b""),
+    (b"access-control-allow-origin", b""),
+    (b"age", b""),
+    (b"allow", b""),
+    (b"authorization", b""),
+    (b"cache-control", b""),
+    (b"content-disposition", b""),
+    (b"content-encoding", b""),
+    (b"content-language", b""),
+    (b"content-length", b""),
+    (b"content-location", b""),
+    (b"content-range", b""),
+    (b"content-type", b""),
+    (b"cookie", b""),
+    (b"date", b""),
+    (b"etag", b""),
+    (b"expect", b""),
+    (b"expires", b""),
+    (b"from", b""),
+    (b"host", b""),
+    (b"if-match", b""),
+    (b"if-modified-since", b""),
+    (b"if-none-match", b""),
+    (b"if-range", b""),
+    (b"if-unmodified-since", b""),
+    (b"last-modified", b""),
+    (b"link", b""),
+    (b"location", b""),
+    (b"max-forwards", b""),
+    (b"proxy-authenticate", b""),
+    (b"proxy-authorization", b""),
+    (b"range", b""),
+    (b"referer", b""),
+    (b"refresh", b""),
+    (b"retry-after", b""),
+    (b"server", b""),
+    (b"set-cookie", b""),
+    (b"strict-transport-security", b""),
+    (b"transfer-encoding", b""),
+    (b"user-agent", b""),
+    (b"vary", b""),
+    (b"via", b""),
+    (b"www-authenticate", b""),
+];
+
+/// The amount of symbols per code length of the canonical Huffman code
+const HUFFMAN_COUNTS: [u16; 31] = [
+    0, 0, 0, 0, 0, 10, 26, 32, 6, 0, 5, 3, 2, 6, 2, 3, 0, 0, 0, 3, 8, 13, 26, 29, 12, 4, 15, 19, 29,
+    0, 4,
+];
+
+/// The symbols of the canonical Huffman code, ordered by code length and value
+const HUFFMAN_SYMBOLS: [u16; 257] = [
+    48, 49, 50, 97, 99, 101, 105, 111, 115, 116, 32, 37, 45, 46, 47, 51, 52, 53, 54, 55, 56, 57, 61,
+    65, 95, 98, 100, 102, 103, 104, 108, 109, 110, 112, 114, 117, 58, 66, 67, 68, 69, 70, 71, 72,
+    73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 89, 106, 107, 113, 118, 119, 120,
+    121, 122, 38, 42, 44, 59, 88, 90, 33, 34, 40, 41, 63, 39, 43, 124, 35, 62, 0, 36, 64, 91, 93,
+    126, 94, 125, 60, 96, 123, 92, 195, 208, 128, 130, 131, 162, 184, 194, 224, 226, 153, 161, 167,
+    172, 176, 177, 179, 209, 216, 217, 227, 229, 230, 129, 132, 133, 134, 136, 146, 154, 156, 160,
+    163, 164, 169, 170, 173, 178, 181, 185, 186, 187, 189, 190, 196, 198, 228, 232, 233, 1, 135,
+    137, 138, 139, 140, 141, 143, 147, 149, 150, 151, 152, 155, 157, 158, 165, 166, 168, 174, 175,
+    180, 182, 183, 188, 191, 197, 231, 239, 9, 142, 144, 145, 148, 159, 171, 206, 215, 225, 236,
+    237, 199, 207, 234, 235, 192, 193, 200, 201, 202, 205, 210, 213, 218, 219, 238, 240, 242, 243,
+    255, 203, 204, 211, 212, 214, 221, 222, 223, 241, 244, 245, 246, 247, 248, 250, 251, 252, 253,
+    254, 2, 3, 4, 5, 6, 7, 8, 11, 12, 14, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25, 26, 27, 28, 29,
+    30, 31, 127, 220, 249, 10, 13, 22, 256,
+];
+
+/// The end of string symbol, which must not be part of the encoded data
+const HUFFMAN_EOS: u16 = 256;
+
+/// A HPACK decoder with its dynamic table, which is needed for every direction of a HTTP/2
+/// connection
+#[derive(Debug)]
+pub struct HpackDecoder {
+    /// The dynamic table, where the newest entry is the first one
+    table: VecDeque<HpackField>,
+
+    /// The current size of the dynamic table
+    size: usize,
+
+    /// The maximum size of the dynamic table set by the encoder
+    max_size: usize,
+
+    /// The upper limit of the maximum size, announced by the decoding endpoint
+    allowed_size: usize,
+}
+
+impl HpackDecoder {
+    /// Create a new HPACK decoder with the default dynamic table size of 4096 bytes
+    pub fn new() -> Self {
+        HpackDecoder {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: DEFAULT_TABLE_SIZE,
+            allowed_size: DEFAULT_TABLE_SIZE,
+        }
+    }
+
+    /// Set the upper limit of the dynamic table size, which is announced by the decoding endpoint
+    /// via the SETTINGS_HEADER_TABLE_SIZE parameter. The table keeps its size until the encoder
+    /// signals a dynamic table size update within the limit (RFC 7541 section 4.2).
+    pub fn set_allowed_size(&mut self, size: usize) {
+        self.allowed_size = size;
+    }
+
+    /// Get the current size of the dynamic table in bytes
+    pub fn table_size(&self) -> usize {
+        self.size
+    }
+
+    /// Get the amount of entries within the dynamic table
+    pub fn table_len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Decode a complete header block. Returns None on a decoding error, after which the state of
+    /// the dynamic table is undefined.
+    pub fn decode(&mut self, block: &[u8]) -> Option<Vec<HttpHeader>> {
+        let mut headers = vec![];
+        let mut input = block;
+
+        while let Some(&first) = input.first() {
+            let (rest, header) = if first & 0x80 != 0 {
+                // Indexed header field
+                let (rest, index) = HpackDecoder::parse_integer(input, 7)?;
+                let (name, value) = self.get(index)?;
+                (rest, Some((name, value)))
+            } else if first & 0x40 != 0 {
+                // Literal header field with incremental indexing
+                let (rest, (name, value)) = self.parse_literal(input, 6)?;
+                self.insert(name.clone(), value.clone());
+                (rest, Some((name, value)))
+            } else if first & 0x20 != 0 {
+                // Dynamic table size update
+                let (rest, size) = HpackDecoder::parse_integer(input, 5)?;
+                if size > self.allowed_size {
+                    return None;
+                }
+                self.resize(size);
+                (rest, None)
+            } else {
+                // Literal header field without indexing or never indexed
+                let (rest, field) = self.parse_literal(input, 4)?;
+                (rest, Some(field))
+            };
+
+            if let Some((name, value)) = header {
+                headers.push(HttpHeader::from_raw(&name, value));
+            }
+            input = rest;
+        }
+
+        Some(headers)
+    }
+
+    /// Get an entry of the static or dynamic table
+    fn get(&self, index: usize) -> Option<HpackField> {
+        match index {
+            0 => None,
+            1..=61 => STATIC_TABLE
+                .get(index - 1)
+                .map(|&(name, value)| (name.to_vec(), value.to_vec())),
+            _ => self.table.get(index - 62).cloned(),
+        }
+    }
+
+    /// Add an entry to the dynamic table, which evicts the oldest entries if needed
+    fn insert(&mut self, name: Vec<u8>, value: Vec<u8>) {
+        let size = name.len() + value.len() + ENTRY_OVERHEAD;
+        self.evict(self.max_size.saturating_sub(size));
+
+        // Entries larger than the table empty it
+        if size <= self.max_size {
+            self.size += size;
+            self.table.push_front((name, value));
+        }
+    }
+
+    fn resize(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict(max_size);
+    }
+
+    /// Remove the oldest entries until the table size does not exceed the given size
+    fn evict(&mut self, size: usize) {
+        while self.size > size {
+            match self.table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + ENTRY_OVERHEAD,
+                None => break,
+            }
+        }
+    }
+
+    /// Parse a literal header field with a name index of the given prefix length
+    fn parse_literal<'a>(&self, input: &'a [u8], prefix: u8) -> Option<(&'a [u8], HpackField)> {
+        let (rest, index) = HpackDecoder::parse_integer(input, prefix)?;
+        let (rest, name) = if index == 0 {
+            HpackDecoder::parse_string(rest)?
+        } else {
+            (rest, self.get(index)?.0)
+        };
+        let (rest, value) = HpackDecoder::parse_string(rest)?;
+        Some((rest, (name, value)))
+    }
+
+    /// Parse an integer with a prefix of the given amount of bits
+    fn parse_integer(input: &[u8], prefix: u8) -> Option<(&[u8], usize)> {
+        let mask = (1u8 << prefix) - 1;
+        let first = input.first()? & mask;
+        if first < mask {
+            return Some((&input[1..], first as usize));
+        }
+
+        // Integers are limited to 28 bits in addition to the prefix
+        let mut value = mask as usize;
+        for (i, &byte) in input[1..].iter().take(4).enumerate() {
+            value += ((byte & 0x7f) as usize) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Some((&input[i + 2..], value));
+            }
+        }
+        None
+    }
+
+    /// Parse a string literal, which may be Huffman encoded
+    fn parse_string(input: &[u8]) -> Option<(&[u8], Vec<u8>)> {
+        let huffman = input.first()? & 0x80 != 0;
+        let (rest, length) = HpackDecoder::parse_integer(input, 7)?;
+        if rest.len() < length {
+            return None;
+        }
+        let (data, rest) = rest.split_at(length);
+        if huffman {
+            HpackDecoder::decode_huffman(data).map(|decoded| (rest, decoded))
+        } else {
+            Some((rest, data.to_vec()))
+        }
+    }
+
+    /// Decode a Huffman encoded string, where the padding has to consist of the most significant
+    /// bits of the end of string symbol
+    fn decode_huffman(data: &[u8]) -> Option<Vec<u8>> {
+        let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+        let (mut code, mut first, mut index, mut length) = (0usize, 0usize, 0usize, 0usize);
+        let mut padding = true;
+
+        for byte in data {
+            for shift in (0..8).rev() {
+                let bit = (byte >> shift) & 1;
+                padding &= bit == 1;
+                code |= bit as usize;
+                length += 1;
+                if length >= HUFFMAN_COUNTS.len() {
+                    return None;
+                }
+
+                let count = HUFFMAN_COUNTS[length] as usize;
+                if code < first + count {
+                    let symbol = HUFFMAN_SYMBOLS[index + code - first];
+                    if symbol == HUFFMAN_EOS {
+                        return None;
+                    }
+                    decoded.push(symbol as u8);
+                    code = 0;
+                    first = 0;
+                    index = 0;
+                    length = 0;
+                    padding = true;
+                } else {
+                    index += count;
+                    first = (first + count) << 1;
+                    code <<= 1;
+                }
+            }
+        }
+
+        if length > 7 || !padding {
+            return None;
+        }
+        Some(decoded)
+    }
+}
+
+impl Default for HpackDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,400 @@
 //! Hypertext Transfer Protocol related packet processing
 use prelude::*;
+use std::collections::{HashMap, HashSet};
+#[cfg(any(feature = "gzip", feature = "brotli"))]
+use std::io::Read;
+
+#[cfg(feature = "brotli")]
+use brotli_decompressor::Decompressor;
+#[cfg(feature = "gzip")]
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use base64;
+use sha1_smol::Sha1;
+
+/// Application layer protocol names negotiated via ALPN which identify HTTP
+const HTTP_ALPN_PROTOCOLS: &[&str] = &["http/0.9", "http/1.0", "http/1.1", "h2"];
+
+/// The GUID which is appended to the key of a WebSocket opening handshake
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The maximum amount of connections with a state, where the least recently used one is dropped
+/// first
+const MAX_CONNECTIONS: usize = 4096;
+
+/// The key of a buffer, which is the connection together with the direction
+type HttpBufferKey = (Option<Identifier<IpProtocol>>, bool);
 
 /// The HTTP parser
 #[derive(Debug)]
-pub struct HttpParser;
+pub struct HttpParser {
+    /// Ports on which encrypted traffic is treated as HTTP if no protocol was negotiated via ALPN
+    pub ports: HashSet<u16>,
+
+    /// The maximum size of a body after removing its content encoding, which protects against
+    /// decompression bombs. Bodies exceeding this size will not be decoded.
+    pub max_decoded_size: usize,
+
+    /// The maximum size of a request or response head which is buffered until it is complete
+    pub max_header_size: usize,
+
+    /// The maximum size of a body which is buffered until it is complete
+    pub max_body_size: usize,
+
+    /// The data of incomplete messages per connection and direction
+    buffers: HashMap<HttpBufferKey, Vec<u8>>,
+
+    /// The application layer protocols negotiated per connection
+    protocols: HashMap<Option<Identifier<IpProtocol>>, String>,
+
+    /// The application layer protocols offered by the client per connection
+    offered: HashMap<Option<Identifier<IpProtocol>>, Vec<String>>,
+
+    /// The methods of the requests which are still waiting for a response
+    requests: HttpPendingRequests<HttpRequestMethod>,
+
+    /// The number of the last segment per connection with a state, which orders them by their use
+    connections: HashMap<Option<Identifier<IpProtocol>>, u64>,
+
+    /// The number of the next segment
+    next_segment: u64,
+}
+
+impl HttpParser {
+    /// Create a new HTTP parser, which treats encrypted traffic on the ports 443, 4443 and 8443
+    /// as HTTP. Heads are buffered up to 64 KiB, bodies are buffered and decoded up to 10 MiB.
+    pub fn new() -> Self {
+        HttpParser {
+            ports: [443, 4443, 8443].iter().cloned().collect(),
+            max_decoded_size: 10 * 1024 * 1024,
+            max_header_size: 64 * 1024,
+            max_body_size: 10 * 1024 * 1024,
+            buffers: HashMap::new(),
+            protocols: HashMap::new(),
+            offered: HashMap::new(),
+            requests: HttpPendingRequests::new(),
+            connections: HashMap::new(),
+            next_segment: 0,
+        }
+    }
+
+    /// Update the use of a connection after a segment, where the state of the least recently
+    /// used connection is dropped if too many connections have a state
+    fn follow(&mut self, identifier: &Option<Identifier<IpProtocol>>) {
+        let stateful = self.buffers.contains_key(&(identifier.clone(), true))
+            || self.buffers.contains_key(&(identifier.clone(), false))
+            || self.protocols.contains_key(identifier)
+            || self.offered.contains_key(identifier)
+            || self.requests.front(identifier).is_some();
+        if !stateful {
+            self.connections.remove(identifier);
+            return;
+        }
+        if self.connections.len() >= MAX_CONNECTIONS && !self.connections.contains_key(identifier) {
+            let oldest = self.connections
+                .iter()
+                .min_by_key(|&(_, &number)| number)
+                .map(|(identifier, _)| identifier.clone());
+            if let Some(oldest) = oldest {
+                debug!("Dropping the state of the least recently used HTTP connection");
+                self.close(&oldest);
+            }
+        }
+        self.connections.insert(identifier.clone(), self.next_segment);
+        self.next_segment += 1;
+    }
+
+    /// Drop the state of a connection
+    fn close(&mut self, identifier: &Option<Identifier<IpProtocol>>) {
+        self.buffers.remove(&(identifier.clone(), true));
+        self.buffers.remove(&(identifier.clone(), false));
+        self.protocols.remove(identifier);
+        self.offered.remove(identifier);
+        self.requests.close(identifier);
+        self.connections.remove(identifier);
+    }
+
+    fn parse_plain<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let tcp = result
+            .and_then(|vector| vector.last())
+            .and_then(|any| any.downcast_ref::<TcpPacket>());
+        let connection = tcp.and_then(|tcp| {
+            connection_identifier(result, tcp.header.source_port, tcp.header.dest_port)
+        });
+        let identifier = connection.as_ref().map(|&(ref identifier, _)| identifier.clone());
+        let key = (identifier.clone(), connection.is_none_or(|(_, direction)| direction));
+
+        // Bodies without a length end with the connection, which is assumed if no result is given
+        let closed = tcp.is_none_or(|tcp| tcp.header.flag_fin || tcp.header.flag_rst);
+
+        // Decrypted traffic is recognized by the TLS handshake of its connection or its port
+        let tls = self.protocols.contains_key(&identifier) || self.offered.contains_key(&identifier)
+            || tcp.is_some_and(|tcp| {
+                self.ports.contains(&tcp.header.source_port) || self.ports.contains(&tcp.header.dest_port)
+            });
+
+        let packet = self.parse_messages(key, input, closed, tls);
+
+        // The connection is closed, a body ending with it was completed above
+        match tcp {
+            Some(tcp) if tcp.header.flag_fin || tcp.header.flag_rst => self.close(&identifier),
+            _ => self.follow(&identifier),
+        }
+
+        packet.map(|p| Box::new(p) as ParserResult)
+    }
+
+    /// Parse all messages of a segment, which are multiple ones if they are pipelined. Parsing
+    /// stops after a response which switches the connection to another protocol.
+    fn parse_messages<'a>(
+        &mut self,
+        key: HttpBufferKey,
+        input: &'a [u8],
+        closed: bool,
+        tls: bool,
+    ) -> IResult<&'a [u8], HttpPacket> {
+        let identifier = key.0.clone();
+        let mut packets = vec![];
+        let mut remaining = input;
+
+        loop {
+            // The request method is needed to know if a response contains a body
+            let method = self.requests.front(&identifier).cloned();
+
+            // Continue a message of the previous segments in the same direction
+            let buffer = if packets.is_empty() {
+                self.buffers.remove(&key)
+            } else {
+                None
+            };
+            let message = match buffer {
+                Some(mut buffer) => {
+                    buffer.extend_from_slice(input);
+                    match HttpParser::parse_message(&buffer, method.as_ref(), closed) {
+                        // The remaining data is always part of the current segment
+                        IResult::Done(rest, packet) => {
+                            IResult::Done(&input[input.len().saturating_sub(rest.len())..], packet)
+                        }
+                        IResult::Incomplete(needed) => self.buffer(key.clone(), buffer, input, needed),
+
+                        // The segment may start a new message
+                        IResult::Error(_) => self.parse_segment(key.clone(), input, method.as_ref(), closed),
+                    }
+                }
+                None => self.parse_segment(key.clone(), remaining, method.as_ref(), closed),
+            };
+
+            let mut packet = match message {
+                IResult::Done(rest, packet) => {
+                    remaining = rest;
+                    packet
+                }
+
+                // The following message was buffered
+                IResult::Incomplete(_) if !packets.is_empty() => {
+                    remaining = &remaining[remaining.len()..];
+                    break;
+                }
+                IResult::Error(_) if !packets.is_empty() => break,
+                IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+                IResult::Error(e) => return IResult::Error(e),
+            };
+
+            // Remove the content encoding and pair the responses with their requests
+            let switched = match packet {
+                HttpPacket::Request(ref mut r) => {
+                    r.decoded_body = HttpBody::decode(&r.headers, &r.body, self.max_decoded_size);
+                    r.tls = tls;
+                    self.requests.push(&identifier, r.request_method.clone());
+                    false
+                }
+                HttpPacket::Response(ref mut r) => {
+                    r.decoded_body = HttpBody::decode(&r.headers, &r.body, self.max_decoded_size);
+                    self.requests.complete(&identifier, r);
+                    matches!((method.as_ref(), r.code), (_, 101) | (Some(&HttpRequestMethod::Connect), 200..=299))
+                }
+                _ => false,
+            };
+            packets.push(packet);
+
+            if switched || remaining.is_empty() {
+                break;
+            }
+        }
+
+        match packets.len() {
+            1 => IResult::Done(remaining, packets.remove(0)),
+            _ => IResult::Done(remaining, HttpPacket::Pipelined(packets)),
+        }
+    }
+
+    /// Parse a message from a single segment, which gets buffered if it is incomplete
+    fn parse_segment<'a>(
+        &mut self,
+        key: HttpBufferKey,
+        input: &'a [u8],
+        method: Option<&HttpRequestMethod>,
+        closed: bool,
+    ) -> IResult<&'a [u8], HttpPacket> {
+        match HttpParser::parse_message(input, method, closed) {
+            IResult::Incomplete(needed) if !input.is_empty() => self.buffer(key, input.to_vec(), input, needed),
+            packet => packet,
+        }
+    }
+
+    /// Keep the data of an incomplete message for the next segment, which fails if the data
+    /// exceeds the maximum header or body size
+    fn buffer<'a>(
+        &mut self,
+        key: HttpBufferKey,
+        data: Vec<u8>,
+        input: &'a [u8],
+        needed: Needed,
+    ) -> IResult<&'a [u8], HttpPacket> {
+        let limit = match HttpParser::head_length(&data) {
+            Some(length) => length + self.max_body_size,
+            None => self.max_header_size,
+        };
+        if data.len() > limit {
+            debug!("Dropping incomplete HTTP message of {} bytes", data.len());
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+        self.buffers.insert(key, data);
+        IResult::Incomplete(needed)
+    }
+
+    /// Get the length of the head including the terminating empty line, if it is complete
+    fn head_length(data: &[u8]) -> Option<usize> {
+        data.windows(2)
+            .position(|window| window == b"\n\n")
+            .map(|position| position + 2)
+            .into_iter()
+            .chain(
+                data.windows(3)
+                    .position(|window| window == b"\n\r\n")
+                    .map(|position| position + 3),
+            )
+            .min()
+    }
+
+    fn parse_message<'a>(
+        input: &'a [u8],
+        method: Option<&HttpRequestMethod>,
+        closed: bool,
+    ) -> IResult<&'a [u8], HttpPacket> {
+        alt!(
+            input,
+            map!(call!(HttpRequest::parse), HttpPacket::Request)
+                | map!(apply!(HttpResponse::parse, method, closed), HttpPacket::Response)
+        )
+    }
+
+    fn parse_encrypted(&mut self, result: Option<&ParserResultVec>) -> Option<ParserResult> {
+        match result {
+            Some(vector) => match (vector.last(), vector.iter().rev().nth(1)) {
+                (Some(any_tls), Some(any_tcp)) => {
+                    match (
+                        any_tls.downcast_ref::<TlsPacket>(),
+                        any_tcp.downcast_ref::<TcpPacket>(),
+                    ) {
+                        // TLS and TCP combination matches
+                        (Some(tls), Some(tcp)) => {
+                            let identifier =
+                                connection_identifier(result, tcp.header.source_port, tcp.header.dest_port)
+                                    .map(|(identifier, _)| identifier);
+
+                            // Remember the protocols offered by the client and selected by the server
+                            if let Some(protocols) = HttpParser::offered_protocols(tls) {
+                                self.offered.insert(identifier.clone(), protocols);
+                            }
+                            if let Some(protocol) = HttpParser::negotiated_protocol(tls) {
+                                self.protocols.insert(identifier.clone(), protocol.to_owned());
+                            }
+
+                            let http = |protocol: &String| HTTP_ALPN_PROTOCOLS.contains(&protocol.as_str());
+                            let detection = match (self.protocols.get(&identifier), self.offered.get(&identifier)) {
+                                (Some(protocol), _) => if http(protocol) {
+                                    Some(HttpDetection::Alpn(protocol.clone()))
+                                } else {
+                                    // Some other protocol than HTTP was negotiated
+                                    None
+                                },
+
+                                // The selected protocol is not visible, for example encrypted by TLS 1.3
+                                (None, Some(protocols)) if protocols.iter().all(&http) => {
+                                    Some(HttpDetection::Offered(protocols.clone()))
+                                }
+                                (None, Some(protocols)) if !protocols.iter().any(&http) => None,
+
+                                _ => if self.ports.contains(&tcp.header.dest_port) {
+                                    Some(HttpDetection::Port(tcp.header.dest_port))
+                                } else if self.ports.contains(&tcp.header.source_port) {
+                                    Some(HttpDetection::Port(tcp.header.source_port))
+                                } else {
+                                    None
+                                },
+                            };
+
+                            // The connection is closed
+                            if tcp.header.flag_fin || tcp.header.flag_rst {
+                                self.close(&identifier);
+                            } else {
+                                self.follow(&identifier);
+                            }
+
+                            detection.map(|d| Box::new(HttpPacket::Any(d)) as ParserResult)
+                        }
+
+                        _ => None,
+                    }
+                }
+
+                _ => None, // Previous result found, but not correct parent
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the application layer protocols offered by a client hello within the TLS packet
+    fn offered_protocols(tls: &TlsPacket) -> Option<Vec<String>> {
+        match tls.body {
+            Some(TlsRecordBody::Handshake(ref messages)) => messages
+                .iter()
+                .filter_map(|message| match *message {
+                    TlsHandshake::ClientHello(ref hello) => Some(hello.alpn_protocols()),
+                    _ => None,
+                })
+                .find(|protocols| !protocols.is_empty())
+                .map(|protocols| protocols.iter().map(|p| (*p).to_owned()).collect()),
+            _ => None,
+        }
+    }
+
+    /// Get the application layer protocol from a server hello within the TLS packet. Handshake
+    /// records are not reassembled, so only a server hello within the first record of a single
+    /// segment is found. Otherwise the protocols offered by the client are used for detection.
+    fn negotiated_protocol(tls: &TlsPacket) -> Option<&str> {
+        match tls.body {
+            Some(TlsRecordBody::Handshake(ref messages)) => messages
+                .iter()
+                .filter_map(|message| match *message {
+                    TlsHandshake::ServerHello(ref hello) => hello.alpn_protocol(),
+                    _ => None,
+                })
+                .next(),
+            _ => None,
+        }
+    }
+}
+
+impl Default for HttpParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Parsable<PathIp> for HttpParser {
     /// Parse a `HttpPacket` from an `&[u8]`
@@ -21,18 +412,14 @@ impl Parsable<PathIp> for HttpParser {
                     // TCP based plain text transfer
                     cond_reduce!(match result {
                     Some(vector) => match vector.last() {
-                        Some(ref any) => if let Some(_) = any.downcast_ref::<TcpPacket>() {
-                            true
-                        } else {
-                            false
-                        },
+                        Some(any) => any.downcast_ref::<TcpPacket>().is_some(),
                         _ => false, // Previous result found, but not correct parent
                     },
                     None => true, // Parse also if no result is given, for testability
-                }, HttpPacket::parse_plain) |
+                }, call!(|i| self.parse_plain(i, result))) |
 
                 // TLS based encrypted traffic
-                apply!(HttpPacket::parse_encrypted, result)
+                expr_opt!(self.parse_encrypted(result))
                 )
                 >> (result)
         )
@@ -55,48 +442,37 @@ pub enum HttpPacket {
     Response(HttpResponse),
 
     /// Could be a plain or encrypted HTTP packet, but there is no further data parsable
-    Any,
+    Any(HttpDetection),
+
+    /// Multiple requests or responses of a single segment in their order, which are pipelined
+    Pipelined(Vec<HttpPacket>),
 }
 
 impl HttpPacket {
-    named!(parse_plain<&[u8], ParserResult>,
-           alt!(call!(HttpRequest::parse) | call!(HttpResponse::parse))
-    );
+    /// Get the requests or responses of the packet in their order
+    pub fn messages(&self) -> Vec<&HttpPacket> {
+        match *self {
+            HttpPacket::Pipelined(ref packets) => packets.iter().collect(),
+            _ => vec![self],
+        }
+    }
+}
 
-    fn parse_encrypted<'a>(
-        input: &'a [u8],
-        result: Option<&ParserResultVec>,
-    ) -> IResult<&'a [u8], ParserResult> {
-        expr_opt!(
-            input,
-            match result {
-                Some(vector) => match (vector.last(), vector.iter().rev().nth(1)) {
-                    (Some(ref any_tls), Some(ref any_tcp)) => {
-                        match (
-                            any_tls.downcast_ref::<TlsPacket>(),
-                            any_tcp.downcast_ref::<TcpPacket>(),
-                        ) {
-                            /// TLS and TCP combination matches
-                            (Some(_), Some(tcp))
-                                if (tcp.header.source_port == 443
-                                    || tcp.header.dest_port == 443) =>
-                            {
-                                Some(Box::new(HttpPacket::Any))
-                            }
+#[derive(Debug, Eq, PartialEq)]
+/// The reason why an encrypted packet is treated as HTTP
+pub enum HttpDetection {
+    /// Inferred from a configured port of the connection
+    Port(u16),
 
-                            _ => None,
-                        }
-                    }
+    /// Negotiated via Application-Layer Protocol Negotiation, like "http/1.1" or "h2"
+    Alpn(String),
 
-                    _ => None, // Previous result found, but not correct parent
-                },
-                _ => None,
-            }
-        )
-    }
+    /// Offered by the client via Application-Layer Protocol Negotiation, where all protocols are
+    /// HTTP, but the protocol selected by the server is not visible
+    Offered(Vec<String>),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// A HTTP request representation
 pub struct HttpRequest {
     /// The HTTP request method
@@ -110,40 +486,133 @@ pub struct HttpRequest {
 
     /// Available HTTP headers
     pub headers: Vec<HttpHeader>,
+
+    /// The message body, which is empty if no body was sent
+    pub body: Vec<u8>,
+
+    /// Headers sent after a chunked message body
+    pub trailers: Vec<HttpHeader>,
+
+    /// The message body after removing the content encoding. None if the body is not encoded,
+    /// the encoding is not supported or the decoding failed.
+    pub decoded_body: Option<Vec<u8>>,
+
+    /// The request was sent over TLS, which is known from the handshake or the port of its
+    /// connection
+    pub tls: bool,
 }
 
 impl HttpRequest {
-    named!(parse<&[u8], ParserResult>,
-        ws!(do_parse!(
+    /// Get the value of the first header with the given name, ignoring the case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        HttpHeader::find(&self.headers, name)
+    }
+
+    /// Get the values of all headers with the given name in their order, ignoring the case
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        HttpHeader::find_all(&self.headers, name)
+    }
+
+    /// Get the effective request URI as defined in RFC 7230, section 5.5, which is only the
+    /// request target if the host is unknown
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        match (self.target(), self.header("Host")) {
+            (Some(HttpRequestTarget::Absolute { .. }), _) => self.path.clone(),
+            (Some(HttpRequestTarget::Authority { ref host, port }), _) => format!("{}://{}:{}", scheme, host, port),
+            (Some(HttpRequestTarget::Asterisk), Some(host)) => format!("{}://{}", scheme, host),
+            (Some(HttpRequestTarget::Origin { .. }), Some(host)) => format!("{}://{}{}", scheme, host, self.path),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// Get the parsed request target, which is None if it is malformed
+    pub fn target(&self) -> Option<HttpRequestTarget> {
+        HttpRequestTarget::parse(&self.path, &self.request_method)
+    }
+
+    /// Get the percent-decoded segments of the request path
+    pub fn path_segments(&self) -> Vec<String> {
+        self.target()
+            .map(|target| target.path_segments())
+            .unwrap_or_default()
+    }
+
+    /// Get the decoded query parameters of the request target in their order
+    pub fn query_params(&self) -> Vec<(String, String)> {
+        self.target()
+            .map(|target| target.query_params())
+            .unwrap_or_default()
+    }
+
+    /// Get the cookies of all `Cookie` headers
+    pub fn cookies(&self) -> Vec<HttpCookie> {
+        self.header_values("Cookie")
+            .into_iter()
+            .flat_map(HttpCookie::parse_list)
+            .collect()
+    }
+
+    /// Get the `Sec-WebSocket-Key` if the request is a WebSocket opening handshake
+    pub fn websocket_key(&self) -> Option<&str> {
+        if self.request_method != HttpRequestMethod::Get || !HttpHeader::is_upgrade(&self.headers, "websocket") {
+            return None;
+        }
+        HttpHeader::find(&self.headers, "Sec-WebSocket-Key")
+    }
+
+    /// Get the `Sec-WebSocket-Accept` value a server has to respond with to accept the WebSocket
+    /// opening handshake
+    pub fn websocket_accept_key(&self) -> Option<String> {
+        self.websocket_key().map(|key| {
+            let mut sha1 = Sha1::from(key.trim());
+            sha1.update(WEBSOCKET_GUID.as_bytes());
+            base64::encode(sha1.digest().bytes())
+        })
+    }
+
+    named!(parse<&[u8], HttpRequest>,
+        do_parse!(
+            request: call!(HttpRequest::parse_head) >>
+            body: apply!(HttpBody::parse, HttpBody::request_framing(&request.headers), true) >>
+            (HttpRequest {
+                body: body.0,
+                trailers: body.1,
+                ..request
+            })
+        )
+    );
+
+    named!(parse_head<&[u8], HttpRequest>,
+        do_parse!(
+            // Empty lines preceding the request line are ignored
+            many0!(call!(HttpHeader::parse_line_end)) >>
+
             // HTTP Request parsing
-            method: alt!(
-                map!(tag!("GET"), |_| HttpRequestMethod::Get) |
-                map!(tag!("POST"), |_| HttpRequestMethod::Post) |
-                map!(tag!("HEAD"), |_| HttpRequestMethod::Head) |
-                map!(tag!("PUT"), |_| HttpRequestMethod::Put) |
-                map!(tag!("DELETE"), |_| HttpRequestMethod::Delete) |
-                map!(tag!("TRACE"), |_| HttpRequestMethod::Trace) |
-                map!(tag!("OPTIONS"), |_| HttpRequestMethod::Options) |
-                map!(tag!("CONNECT"), |_| HttpRequestMethod::Connect) |
-                map!(tag!("PATCH"), |_| HttpRequestMethod::Patch)
-            ) >>
-
-            path: map_res!(take_until!(" "), str::from_utf8) >>
+            method: map!(take_while1!(HttpHeader::is_token), HttpRequestMethod::from_token) >>
+            take_while1!(HttpHeader::is_space) >>
+            path: map_res!(take_till1!(HttpHeader::is_whitespace), str::from_utf8) >>
+            take_while1!(HttpHeader::is_space) >>
             tag!("HTTP/") >>
             version: call!(HttpVersion::parse) >>
+            call!(HttpHeader::parse_line_end) >>
             headers: call!(HttpHeader::parse) >>
 
-            (Box::new(HttpPacket::Request(HttpRequest {
+            (HttpRequest {
                 request_method: method,
                 path: path.to_owned(),
                 version: version,
                 headers: headers,
-            })))
-        ))
+                body: vec![],
+                trailers: vec![],
+                decoded_body: None,
+                tls: false,
+            })
+        )
     );
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// List of supported HTTP request methods
 pub enum HttpRequestMethod {
     /// The GET method requests a representation of the specified resource.
@@ -176,9 +645,47 @@ pub enum HttpRequestMethod {
 
     /// The PATCH method applies partial modifications to a resource.
     Patch,
+
+    /// Any other method, like the WebDAV methods PROPFIND or MKCOL
+    Extension(String),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+impl HttpRequestMethod {
+    /// Get the method for a token, where the method names are case-sensitive
+    fn from_token(token: &[u8]) -> Self {
+        match token {
+            b"GET" => HttpRequestMethod::Get,
+            b"POST" => HttpRequestMethod::Post,
+            b"HEAD" => HttpRequestMethod::Head,
+            b"PUT" => HttpRequestMethod::Put,
+            b"DELETE" => HttpRequestMethod::Delete,
+            b"TRACE" => HttpRequestMethod::Trace,
+            b"OPTIONS" => HttpRequestMethod::Options,
+            b"CONNECT" => HttpRequestMethod::Connect,
+            b"PATCH" => HttpRequestMethod::Patch,
+            _ => HttpRequestMethod::Extension(String::from_utf8_lossy(token).into_owned()),
+        }
+    }
+}
+
+impl fmt::Display for HttpRequestMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HttpRequestMethod::Get => write!(f, "GET"),
+            HttpRequestMethod::Post => write!(f, "POST"),
+            HttpRequestMethod::Head => write!(f, "HEAD"),
+            HttpRequestMethod::Put => write!(f, "PUT"),
+            HttpRequestMethod::Delete => write!(f, "DELETE"),
+            HttpRequestMethod::Trace => write!(f, "TRACE"),
+            HttpRequestMethod::Options => write!(f, "OPTIONS"),
+            HttpRequestMethod::Connect => write!(f, "CONNECT"),
+            HttpRequestMethod::Patch => write!(f, "PATCH"),
+            HttpRequestMethod::Extension(ref method) => write!(f, "{}", method),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// HTTP protocol version
 pub struct HttpVersion {
     /// Major part of the HTTP version
@@ -188,6 +695,12 @@ pub struct HttpVersion {
     pub minor: u8,
 }
 
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HTTP/{}.{}", self.major, self.minor)
+    }
+}
+
 impl HttpVersion {
     named!(parse<&[u8], HttpVersion>,
         do_parse!(
@@ -202,37 +715,158 @@ impl HttpVersion {
     );
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// A generic HTTP header field
 pub struct HttpHeader {
     /// A Key, like "Host"
     pub key: String,
 
-    /// A value, like "www.domain.com"
+    /// A value, like "www.domain.com", without the surrounding whitespace. Obsolete line folding
+    /// is replaced by a single space.
     pub value: String,
+
+    /// The original bytes of the value if it is not valid UTF-8, in which case the invalid
+    /// sequences of `value` are replaced by U+FFFD
+    pub raw_value: Option<Vec<u8>>,
 }
 
 impl HttpHeader {
-    fn from_tuple(tuple: (&str, &str)) -> Self {
+    /// Find the value of the first header with the given key, ignoring the case
+    fn find<'a>(headers: &'a [HttpHeader], key: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|header| header.key.eq_ignore_ascii_case(key))
+            .map(|header| header.value.as_str())
+    }
+
+    /// Find the values of all headers with the given key, ignoring the case
+    fn find_all<'a>(headers: &'a [HttpHeader], key: &str) -> Vec<&'a str> {
+        headers
+            .iter()
+            .filter(|header| header.key.eq_ignore_ascii_case(key))
+            .map(|header| header.value.as_str())
+            .collect()
+    }
+
+    /// Check if the headers contain the given token within a comma separated list
+    fn contains_token(headers: &[HttpHeader], key: &str, token: &str) -> bool {
+        headers
+            .iter()
+            .filter(|header| header.key.eq_ignore_ascii_case(key))
+            .flat_map(|header| header.value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    }
+
+    /// Check if the headers request an upgrade to the given protocol
+    fn is_upgrade(headers: &[HttpHeader], protocol: &str) -> bool {
+        HttpHeader::contains_token(headers, "Connection", "upgrade")
+            && HttpHeader::contains_token(headers, "Upgrade", protocol)
+    }
+
+    /// Create a header from its raw name and value, which keeps the value bytes if they are not
+    /// valid UTF-8
+    pub fn from_raw(key: &[u8], value: Vec<u8>) -> Self {
+        let (value, raw_value) = match String::from_utf8(value) {
+            Ok(value) => (value, None),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), Some(e.into_bytes())),
+        };
         HttpHeader {
-            key: tuple.0.to_owned(),
-            value: tuple.1.to_owned(),
+            key: String::from_utf8_lossy(key).into_owned(),
+            value: value,
+            raw_value: raw_value,
         }
     }
 
-    named!(parse<&[u8], Vec<HttpHeader> >,
-        do_parse!(
-            result: many_till!(map!(separated_pair!(
-                map_res!(ws!(take_until!(":")), str::from_utf8),
-                tag!(": "),
-                map_res!(take_until!("\r"), str::from_utf8)), HttpHeader::from_tuple),
-                tag!("\r\n\r\n")) >>
-            (result.0)
+    /// Parse the header fields up to and including the terminating empty line
+    pub(crate) fn parse(input: &[u8]) -> IResult<&[u8], Vec<HttpHeader>> {
+        let mut fields: Vec<(&[u8], Vec<u8>)> = vec![];
+        let mut remaining = input;
+
+        loop {
+            let (rest, line) = try_parse!(remaining, HttpHeader::parse_line);
+            remaining = rest;
+
+            match line.first() {
+                // The empty line terminates the header section
+                None => break,
+
+                // Obsolete line folding continues the value of the previous field
+                Some(&b' ') | Some(&b'\t') => match fields.last_mut() {
+                    Some(&mut (_, ref mut value)) => {
+                        let folded = HttpHeader::trim(line);
+                        if !value.is_empty() && !folded.is_empty() {
+                            value.push(b' ');
+                        }
+                        value.extend_from_slice(folded);
+                    }
+                    None => return IResult::Error(error_position!(ErrorKind::Custom(0), remaining)),
+                },
+
+                // No whitespace is allowed between the field name and the colon
+                _ => match line.iter().position(|&c| c == b':') {
+                    Some(colon) if colon > 0 && line[..colon].iter().all(|&c| HttpHeader::is_token(c)) => {
+                        fields.push((&line[..colon], HttpHeader::trim(&line[colon + 1..]).to_vec()))
+                    }
+                    _ => return IResult::Error(error_position!(ErrorKind::Custom(0), remaining)),
+                },
+            }
+        }
+
+        IResult::Done(
+            remaining,
+            fields
+                .into_iter()
+                .map(|(key, value)| HttpHeader::from_raw(key, value))
+                .collect(),
         )
-    );
+    }
+
+    /// Remove the optional whitespace around a value
+    fn trim(value: &[u8]) -> &[u8] {
+        let start = value
+            .iter()
+            .position(|&c| !HttpHeader::is_space(c))
+            .unwrap_or(value.len());
+        let end = value
+            .iter()
+            .rposition(|&c| !HttpHeader::is_space(c))
+            .map_or(start, |end| end + 1);
+        &value[start..end]
+    }
+
+    /// Characters allowed in methods and field names
+    fn is_token(c: u8) -> bool {
+        c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)
+    }
+
+    fn is_space(c: u8) -> bool {
+        c == b' ' || c == b'\t'
+    }
+
+    fn is_whitespace(c: u8) -> bool {
+        HttpHeader::is_space(c) || c == b'\r' || c == b'\n'
+    }
+
+    // Parse a line ending with CRLF or a bare LF
+    named!(parse_line_end<&[u8], &[u8]>, alt!(tag!("\r\n") | tag!("\n")));
+
+    /// Parse a line without its ending, which is incomplete if the line ending is missing
+    pub(crate) fn parse_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        match input.iter().position(|&c| c == b'\n') {
+            Some(end) => {
+                let line = if end > 0 && input[end - 1] == b'\r' {
+                    &input[..end - 1]
+                } else {
+                    &input[..end]
+                };
+                IResult::Done(&input[end + 1..], line)
+            }
+            None => IResult::Incomplete(Needed::Unknown),
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// A HTTP response representation
 pub struct HttpResponse {
     /// The HTTP version
@@ -246,24 +880,271 @@ pub struct HttpResponse {
 
     /// Available HTTP headers
     pub headers: Vec<HttpHeader>,
+
+    /// The message body, which is empty if no body was sent
+    pub body: Vec<u8>,
+
+    /// Headers sent after a chunked message body
+    pub trailers: Vec<HttpHeader>,
+
+    /// The message body after removing the content encoding. None if the body is not encoded,
+    /// the encoding is not supported or the decoding failed.
+    pub decoded_body: Option<Vec<u8>>,
 }
 
 impl HttpResponse {
-    named!(parse<&[u8], ParserResult>,
-        ws!(do_parse!(
+    /// Get the value of the first header with the given name, ignoring the case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        HttpHeader::find(&self.headers, name)
+    }
+
+    /// Get the values of all headers with the given name in their order, ignoring the case
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        HttpHeader::find_all(&self.headers, name)
+    }
+
+    /// Get the cookies of all valid `Set-Cookie` headers
+    pub fn set_cookies(&self) -> Vec<HttpSetCookie> {
+        self.header_values("Set-Cookie")
+            .into_iter()
+            .filter_map(HttpSetCookie::parse)
+            .collect()
+    }
+
+    /// Get the `Sec-WebSocket-Accept` if the response switches the connection to WebSocket
+    pub fn websocket_accept(&self) -> Option<&str> {
+        if self.code != 101 || !HttpHeader::is_upgrade(&self.headers, "websocket") {
+            return None;
+        }
+        HttpHeader::find(&self.headers, "Sec-WebSocket-Accept")
+    }
+
+    /// Check if the response completes its request. Informational responses are followed by the
+    /// final response, except for `101 Switching Protocols` which ends HTTP on the connection.
+    pub fn is_final(&self) -> bool {
+        self.code >= 200 || self.code == 101
+    }
+
+    /// Check if the response accepts the WebSocket opening handshake of the request
+    pub fn accepts_websocket(&self, request: &HttpRequest) -> bool {
+        match (self.websocket_accept(), request.websocket_accept_key()) {
+            (Some(accept), Some(expected)) => accept.trim() == expected,
+            _ => false,
+        }
+    }
+
+    /// Parse a response to a request with the given method, if known. A body without a length is
+    /// incomplete until the connection is closed.
+    fn parse<'a>(
+        input: &'a [u8],
+        method: Option<&HttpRequestMethod>,
+        closed: bool,
+    ) -> IResult<&'a [u8], HttpResponse> {
+        do_parse!(
+            input,
+            response: call!(HttpResponse::parse_head)
+                >> body: apply!(
+                    HttpBody::parse,
+                    HttpBody::response_framing(method, response.code, &response.headers),
+                    closed
+                ) >> (HttpResponse {
+                    body: body.0,
+                    trailers: body.1,
+                    ..response
+                })
+        )
+    }
+
+    named!(parse_head<&[u8], HttpResponse>,
+        do_parse!(
             // HTTP response parsing
             tag!("HTTP/") >>
             version: call!(HttpVersion::parse) >>
-            code: map_res!(map_res!(take_until!(" "), str::from_utf8), FromStr::from_str) >>
-            reason: map_res!(take_until!("\r"), str::from_utf8) >>
+            take_while1!(HttpHeader::is_space) >>
+            code: map_res!(map_res!(verify!(digit, |d: &[u8]| d.len() == 3), str::from_utf8), FromStr::from_str) >>
+            take_while!(HttpHeader::is_space) >>
+            reason: map!(call!(HttpHeader::parse_line), String::from_utf8_lossy) >>
             headers: call!(HttpHeader::parse) >>
 
-            (Box::new(HttpPacket::Response(HttpResponse {
+            (HttpResponse {
                 version: version,
                 code: code,
-                reason: reason.to_owned(),
+                reason: reason.into_owned(),
                 headers: headers,
-            })))
-        ))
+                body: vec![],
+                trailers: vec![],
+                decoded_body: None,
+            })
+        )
+    );
+}
+
+/// The framing of a HTTP message body
+enum HttpBody {
+    /// The message has no body
+    Empty,
+
+    /// The body length is given by the Content-Length header
+    Length(usize),
+
+    /// The body uses the chunked transfer encoding
+    Chunked,
+
+    /// The body ends when the connection gets closed
+    Close,
+}
+
+impl HttpBody {
+    /// Get the body framing of a request
+    fn request_framing(headers: &[HttpHeader]) -> Option<HttpBody> {
+        match HttpBody::transfer_encoding(headers) {
+            Some(true) => Some(HttpBody::Chunked),
+            Some(false) => None, // A request body length can not be determined without chunking
+            None => HttpBody::content_length(headers).unwrap_or(Some(HttpBody::Empty)),
+        }
+    }
+
+    /// Get the body framing of a response to a request with the given method
+    fn response_framing(
+        method: Option<&HttpRequestMethod>,
+        code: u16,
+        headers: &[HttpHeader],
+    ) -> Option<HttpBody> {
+        match (method, code) {
+            // Responses to HEAD requests, informational, no content and not modified responses
+            (Some(&HttpRequestMethod::Head), _) | (_, 100..=199) | (_, 204) | (_, 304) => {
+                Some(HttpBody::Empty)
+            }
+
+            // The connection switched to a tunnel
+            (Some(&HttpRequestMethod::Connect), 200..=299) => Some(HttpBody::Empty),
+
+            _ => match HttpBody::transfer_encoding(headers) {
+                Some(true) => Some(HttpBody::Chunked),
+                Some(false) => Some(HttpBody::Close),
+                None => HttpBody::content_length(headers).unwrap_or(Some(HttpBody::Close)),
+            },
+        }
+    }
+
+    /// Returns Some(true) if chunked is the final transfer encoding, None if there is none
+    fn transfer_encoding(headers: &[HttpHeader]) -> Option<bool> {
+        HttpHeader::find(headers, "Transfer-Encoding").map(|value| {
+            value
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+        })
+    }
+
+    /// Returns None if there is no Content-Length and Some(None) if it is invalid
+    fn content_length(headers: &[HttpHeader]) -> Option<Option<HttpBody>> {
+        HttpHeader::find(headers, "Content-Length").map(|value| {
+            value
+                .trim()
+                .parse()
+                .ok()
+                .map(|length| if length == 0 {
+                    HttpBody::Empty
+                } else {
+                    HttpBody::Length(length)
+                })
+        })
+    }
+
+    /// Parse the body with the given framing, which returns the body together with the trailers.
+    /// A body ending with the connection is incomplete as long as the connection is not closed.
+    fn parse(input: &[u8], framing: Option<HttpBody>, closed: bool) -> IResult<&[u8], (Vec<u8>, Vec<HttpHeader>)> {
+        match framing {
+            Some(HttpBody::Empty) => IResult::Done(input, (vec![], vec![])),
+            Some(HttpBody::Length(length)) => map!(input, take!(length), |b: &[u8]| (b.to_vec(), vec![])),
+            Some(HttpBody::Chunked) => HttpBody::parse_chunked(input),
+            Some(HttpBody::Close) if !closed => IResult::Incomplete(Needed::Unknown),
+            Some(HttpBody::Close) => map!(input, call!(rest), |b: &[u8]| (b.to_vec(), vec![])),
+            None => IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+
+    /// Remove the content codings from a body, which are applied in the listed order
+    fn decode(headers: &[HttpHeader], body: &[u8], limit: usize) -> Option<Vec<u8>> {
+        let codings: Vec<&str> = match HttpHeader::find(headers, "Content-Encoding") {
+            Some(value) => value
+                .split(',')
+                .map(|coding| coding.trim())
+                .filter(|coding| !coding.is_empty() && !coding.eq_ignore_ascii_case("identity"))
+                .collect(),
+            None => return None,
+        };
+        if codings.is_empty() || body.is_empty() {
+            return None;
+        }
+
+        let mut decoded = body.to_vec();
+        for coding in codings.iter().rev() {
+            decoded = match decode_content_coding(coding, &decoded, limit) {
+                Some(data) => data,
+                None => {
+                    debug!("Unable to decode {} content coding", coding);
+                    return None;
+                }
+            };
+        }
+        Some(decoded)
+    }
+
+    named!(parse_chunked<&[u8], (Vec<u8>, Vec<HttpHeader>)>,
+        do_parse!(
+            chunks: many0!(verify!(call!(HttpBody::parse_chunk), |c: &[u8]| !c.is_empty())) >>
+            // The last chunk is followed by optional trailers
+            tag!("0") >>
+            call!(HttpHeader::parse_line) >>
+            trailers: call!(HttpHeader::parse) >>
+            (chunks.concat(), trailers)
+        )
     );
+
+    named!(parse_chunk<&[u8], &[u8]>,
+        do_parse!(
+            size: map_res!(map_res!(hex_digit, str::from_utf8), |s| usize::from_str_radix(s, 16)) >>
+            // Chunk extensions are ignored
+            call!(HttpHeader::parse_line) >>
+            data: take!(size) >>
+            call!(HttpHeader::parse_line_end) >>
+            (data)
+        )
+    );
+}
+
+/// Remove a single content coding like "gzip", "deflate" or "br" from the data, which fails if
+/// the coding is not supported or the decoded data exceeds the limit
+pub(crate) fn decode_content_coding(coding: &str, data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    match coding.to_ascii_lowercase().as_str() {
+        #[cfg(feature = "gzip")]
+        "gzip" | "x-gzip" => read_limited(GzDecoder::new(data), limit),
+
+        // Some servers send raw deflate data instead of the zlib format
+        #[cfg(feature = "gzip")]
+        "deflate" => read_limited(ZlibDecoder::new(data), limit)
+            .or_else(|| read_limited(DeflateDecoder::new(data), limit)),
+
+        #[cfg(feature = "brotli")]
+        "br" => read_limited(Decompressor::new(data, 4096), limit),
+
+        _ => {
+            // The data and limit are only used by the decoders of the enabled features
+            #[cfg(not(any(feature = "gzip", feature = "brotli")))]
+            let _ = (data, limit);
+            None
+        }
+    }
+}
+
+/// Read the whole decoder output, which fails if it exceeds the limit
+#[cfg(any(feature = "gzip", feature = "brotli"))]
+fn read_limited<R: Read>(reader: R, limit: usize) -> Option<Vec<u8>> {
+    let mut data = vec![];
+    match reader.take(limit as u64 + 1).read_to_end(&mut data) {
+        Ok(length) if length <= limit => Some(data),
+        _ => None,
+    }
 }
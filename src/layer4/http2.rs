@@ -0,0 +1,960 @@
+//! Hypertext Transfer Protocol Version 2 related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+/// The connection preface sent by the client, which starts every HTTP/2 connection
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// The length of the frame header
+const FRAME_HEADER_LENGTH: usize = 9;
+
+/// The maximum frame size until a different one is announced via SETTINGS
+const DEFAULT_MAX_FRAME_SIZE: u32 = 16_384;
+
+/// The maximum amount of streams per connection which are tracked at the same time
+const MAX_STREAMS: usize = 1024;
+
+/// The maximum amount of connections which are followed at the same time, where the least
+/// recently used one is dropped first
+const MAX_CONNECTIONS: usize = 4096;
+
+const FLAG_END_STREAM: u8 = 0x01;
+const FLAG_ACK: u8 = 0x01;
+const FLAG_END_HEADERS: u8 = 0x04;
+const FLAG_PADDED: u8 = 0x08;
+const FLAG_PRIORITY: u8 = 0x20;
+
+/// The HTTP/2 parser, which follows connections in cleartext (h2c) from their preface on
+#[derive(Debug)]
+pub struct Http2Parser {
+    /// The maximum size of a reassembled message body, further data of the stream is dropped
+    pub max_body_size: usize,
+
+    /// The maximum size of the data buffered for all connections, where the least recently used
+    /// connections are dropped if it is exceeded
+    pub max_buffered_size: usize,
+
+    /// The state of the known connections
+    connections: HashMap<Option<Identifier<IpProtocol>>, Http2Connection>,
+
+    /// The size of the data buffered for all connections
+    buffered: usize,
+
+    /// The number of the next segment
+    next_segment: u64,
+}
+
+impl Http2Parser {
+    /// Create a new HTTP/2 parser, which reassembles bodies up to 10 MiB and buffers up to
+    /// 256 MiB for all connections
+    pub fn new() -> Self {
+        Http2Parser {
+            max_body_size: 10 * 1024 * 1024,
+            max_buffered_size: 256 * 1024 * 1024,
+            connections: HashMap::new(),
+            buffered: 0,
+            next_segment: 0,
+        }
+    }
+
+    /// Start to follow a new connection, where the least recently used one is dropped if too
+    /// many connections are followed
+    fn open(&mut self, identifier: &Option<Identifier<IpProtocol>>, client: bool) {
+        self.close(identifier);
+        if self.connections.len() >= MAX_CONNECTIONS {
+            debug!("Dropping the least recently used HTTP/2 connection");
+            self.close_oldest();
+        }
+        self.connections.insert(identifier.clone(), Http2Connection::new(client));
+    }
+
+    /// Stop to follow a connection
+    fn close(&mut self, identifier: &Option<Identifier<IpProtocol>>) {
+        if let Some(connection) = self.connections.remove(identifier) {
+            self.buffered -= connection.buffered;
+        }
+    }
+
+    /// Stop to follow the least recently used connection
+    fn close_oldest(&mut self) {
+        let oldest = self.connections
+            .iter()
+            .min_by_key(|&(_, connection)| connection.last_segment)
+            .map(|(identifier, _)| identifier.clone());
+        if let Some(oldest) = oldest {
+            self.close(&oldest);
+        }
+    }
+
+    /// Update the buffered size of a connection after a segment, where the least recently used
+    /// connections are dropped until all buffered data fits into the maximum size
+    fn update(&mut self, identifier: &Option<Identifier<IpProtocol>>) {
+        if let Some(connection) = self.connections.get_mut(identifier) {
+            let buffered = connection.buffered_size();
+            self.buffered = self.buffered - connection.buffered + buffered;
+            connection.buffered = buffered;
+            connection.last_segment = self.next_segment;
+            self.next_segment += 1;
+        }
+        while self.buffered > self.max_buffered_size && !self.connections.is_empty() {
+            debug!("Dropping the least recently used HTTP/2 connection because too much data is buffered");
+            self.close_oldest();
+        }
+    }
+
+    /// Process the frames of a segment, which returns None on a connection error
+    fn process(
+        &mut self,
+        identifier: &Option<Identifier<IpProtocol>>,
+        direction: bool,
+        input: &[u8],
+    ) -> Option<(Vec<Http2Frame>, Vec<Http2Message>)> {
+        let max_body_size = self.max_body_size;
+        let connection = self.connections.get_mut(identifier)?;
+        let index = direction as usize;
+        let from_client = direction == connection.client;
+
+        // Continue a frame of the previous segments
+        let mut data = ::std::mem::take(&mut connection.endpoints[index].buffer);
+        data.extend_from_slice(input);
+
+        let mut frames = vec![];
+        let mut messages = vec![];
+        let mut remaining = &data[..];
+        while remaining.len() >= FRAME_HEADER_LENGTH {
+            let length = (remaining[0] as u32) << 16 | (remaining[1] as u32) << 8 | remaining[2] as u32;
+            if length > connection.endpoints[index].max_frame_size {
+                debug!("HTTP/2 frame of {} bytes exceeds the maximum frame size", length);
+                return None;
+            }
+
+            let (rest, raw) = match Http2RawFrame::parse(remaining) {
+                IResult::Done(rest, raw) => (rest, raw),
+                _ => break,
+            };
+            remaining = rest;
+
+            let (mut frame, fragment) = Http2Frame::from_raw(&raw)?;
+            connection.process(&mut frame, fragment, direction, from_client, max_body_size, &mut messages)?;
+            frames.push(frame);
+        }
+        connection.endpoints[index].buffer = remaining.to_vec();
+
+        Some((frames, messages))
+    }
+}
+
+impl Default for Http2Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for Http2Parser {
+    /// Parse a `Http2Packet` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol from the parent parser, parse also if no result is given for
+        // testability
+        let tcp = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<TcpPacket>()) {
+                Some(tcp) => Some(tcp),
+                None => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+            },
+            None => None,
+        };
+        let (identifier, direction) = match tcp.and_then(|tcp| {
+            connection_identifier(result, tcp.header.source_port, tcp.header.dest_port)
+        }) {
+            Some((identifier, direction)) => (Some(identifier), direction),
+            None => (None, true),
+        };
+
+        // A new connection starts with the preface of the client
+        let preface = input.starts_with(HTTP2_PREFACE);
+        let frames = if preface {
+            self.open(&identifier, direction);
+            &input[HTTP2_PREFACE.len()..]
+        } else if self.connections.contains_key(&identifier) {
+            input
+        } else {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        };
+
+        let packet = match self.process(&identifier, direction, frames) {
+            Some((frames, messages)) => Http2Packet {
+                preface: preface,
+                frames: frames,
+                messages: messages,
+            },
+            None => {
+                // The connection can not be followed any more
+                self.close(&identifier);
+                return IResult::Error(error_position!(ErrorKind::Custom(1), input));
+            }
+        };
+
+        // The connection is closed
+        match tcp {
+            Some(tcp) if tcp.header.flag_fin || tcp.header.flag_rst => self.close(&identifier),
+            _ => self.update(&identifier),
+        }
+
+        IResult::Done(&input[input.len()..], Box::new(packet))
+    }
+}
+
+impl fmt::Display for Http2Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HTTP/2")
+    }
+}
+
+#[derive(Debug)]
+/// The state of a HTTP/2 connection
+struct Http2Connection {
+    /// The direction of the client, which sent the preface
+    client: bool,
+
+    /// The state per endpoint, indexed by the direction of the frames sent by the endpoint
+    endpoints: [Http2Endpoint; 2],
+
+    /// The streams which are not closed yet
+    streams: HashMap<u32, Http2Stream>,
+
+    /// The size of the buffered data after the last segment
+    buffered: usize,
+
+    /// The number of the last segment of the connection
+    last_segment: u64,
+}
+
+impl Http2Connection {
+    fn new(client: bool) -> Self {
+        Http2Connection {
+            client: client,
+            endpoints: [Http2Endpoint::new(), Http2Endpoint::new()],
+            streams: HashMap::new(),
+            buffered: 0,
+            last_segment: 0,
+        }
+    }
+
+    /// Get the size of the incomplete frames, header blocks and bodies of the connection
+    fn buffered_size(&self) -> usize {
+        let endpoints: usize = self.endpoints
+            .iter()
+            .map(|endpoint| {
+                endpoint.buffer.len() + endpoint.header_block.as_ref().map_or(0, |block| block.fragment.len())
+            })
+            .sum();
+        let streams: usize = self.streams
+            .values()
+            .flat_map(|stream| stream.halves.iter())
+            .map(|half| half.body.len())
+            .sum();
+        endpoints + streams
+    }
+
+    /// Apply a frame to the connection state, which returns None on a connection error
+    fn process(
+        &mut self,
+        frame: &mut Http2Frame,
+        fragment: Option<&[u8]>,
+        direction: bool,
+        from_client: bool,
+        max_body_size: usize,
+        messages: &mut Vec<Http2Message>,
+    ) -> Option<()> {
+        let index = direction as usize;
+        let stream_id = frame.stream_id;
+
+        // A header block has to be continued without any other frames in between
+        if let Some(ref block) = self.endpoints[index].header_block {
+            match frame.payload {
+                Http2FramePayload::Continuation { .. } if block.stream_id == stream_id => {}
+                _ => return None,
+            }
+        }
+
+        match frame.payload {
+            Http2FramePayload::Data { end_stream, ref data } => {
+                if let Some(stream) = self.stream(stream_id) {
+                    let half = stream.half(from_client);
+                    if half.body.len() + data.len() > max_body_size {
+                        half.truncated = true;
+                    } else {
+                        half.body.extend_from_slice(data);
+                    }
+                }
+                if end_stream {
+                    self.end_stream(stream_id, from_client, messages);
+                }
+            }
+
+            Http2FramePayload::Headers {
+                end_stream,
+                end_headers,
+                ref mut headers,
+                ..
+            } => {
+                self.endpoints[index].header_block = Some(Http2HeaderBlock {
+                    stream_id: stream_id,
+                    promised_stream_id: None,
+                    end_stream: end_stream,
+                    fragment: fragment.unwrap_or(&[]).to_vec(),
+                });
+                if end_headers {
+                    *headers = Some(self.end_headers(index, from_client, messages)?);
+                }
+            }
+
+            Http2FramePayload::PushPromise {
+                promised_stream_id,
+                end_headers,
+                ref mut headers,
+            } => {
+                self.endpoints[index].header_block = Some(Http2HeaderBlock {
+                    stream_id: stream_id,
+                    promised_stream_id: Some(promised_stream_id),
+                    end_stream: false,
+                    fragment: fragment.unwrap_or(&[]).to_vec(),
+                });
+                if end_headers {
+                    *headers = Some(self.end_headers(index, from_client, messages)?);
+                }
+            }
+
+            Http2FramePayload::Continuation {
+                end_headers,
+                ref mut headers,
+            } => {
+                match self.endpoints[index].header_block {
+                    Some(ref mut block) => block.fragment.extend_from_slice(fragment.unwrap_or(&[])),
+                    None => return None,
+                }
+                if end_headers {
+                    *headers = Some(self.end_headers(index, from_client, messages)?);
+                }
+            }
+
+            Http2FramePayload::RstStream(_) => {
+                self.streams.remove(&stream_id);
+            }
+
+            // The settings of an endpoint apply to the frames sent by its peer
+            Http2FramePayload::Settings { ref settings, .. } => {
+                let peer = &mut self.endpoints[1 - index];
+                for setting in settings {
+                    match *setting {
+                        Http2Setting::HeaderTableSize(size) => peer.decoder.set_allowed_size(size as usize),
+                        Http2Setting::MaxFrameSize(size) => peer.max_frame_size = size,
+                        _ => {}
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        Some(())
+    }
+
+    /// Decode a complete header block and assign it to its stream
+    fn end_headers(
+        &mut self,
+        index: usize,
+        from_client: bool,
+        messages: &mut Vec<Http2Message>,
+    ) -> Option<Vec<HttpHeader>> {
+        let block = self.endpoints[index].header_block.take()?;
+        let headers = self.endpoints[index].decoder.decode(&block.fragment)?;
+
+        match block.promised_stream_id {
+            // The promised request is sent by the server on behalf of the client
+            Some(promised_stream_id) => {
+                if let Some(stream) = self.stream(promised_stream_id) {
+                    stream.promised = true;
+                    stream.half(true).headers = Some(headers.clone());
+                }
+                self.end_stream(promised_stream_id, true, messages);
+            }
+
+            None => {
+                if let Some(stream) = self.stream(block.stream_id) {
+                    let half = stream.half(from_client);
+                    match half.headers {
+                        // Informational responses are followed by the final response
+                        Some(ref previous) if !Http2Message::is_informational(previous) => {
+                            half.trailers = headers.clone()
+                        }
+                        _ => half.headers = Some(headers.clone()),
+                    }
+                }
+                if block.end_stream {
+                    self.end_stream(block.stream_id, from_client, messages);
+                }
+            }
+        }
+
+        Some(headers)
+    }
+
+    /// Get the state of a stream, which gets created if it does not exist
+    fn stream(&mut self, stream_id: u32) -> Option<&mut Http2Stream> {
+        if !self.streams.contains_key(&stream_id) && self.streams.len() >= MAX_STREAMS {
+            debug!("Not tracking HTTP/2 stream {} because too many streams are open", stream_id);
+            return None;
+        }
+        Some(self.streams.entry(stream_id).or_default())
+    }
+
+    /// Finish the message of a stream in one direction
+    fn end_stream(&mut self, stream_id: u32, from_client: bool, messages: &mut Vec<Http2Message>) {
+        let closed = match self.streams.get_mut(&stream_id) {
+            Some(stream) => {
+                let promised = stream.promised;
+                let half = stream.half(from_client);
+                half.closed = true;
+                messages.push(Http2Message {
+                    stream_id: stream_id,
+                    from_client: from_client,
+                    promised: promised,
+                    headers: half.headers.take().unwrap_or_else(Vec::new),
+                    body: ::std::mem::take(&mut half.body),
+                    trailers: ::std::mem::take(&mut half.trailers),
+                    truncated: half.truncated,
+                });
+                stream.halves.iter().all(|half| half.closed)
+            }
+            None => false,
+        };
+        if closed {
+            self.streams.remove(&stream_id);
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The state of an endpoint of a HTTP/2 connection
+struct Http2Endpoint {
+    /// The decoder for the header blocks sent by the endpoint
+    decoder: HpackDecoder,
+
+    /// The data of an incomplete frame
+    buffer: Vec<u8>,
+
+    /// The maximum size of frames sent by the endpoint, announced by its peer
+    max_frame_size: u32,
+
+    /// A header block which is not finished yet
+    header_block: Option<Http2HeaderBlock>,
+}
+
+impl Http2Endpoint {
+    fn new() -> Self {
+        Http2Endpoint {
+            decoder: HpackDecoder::new(),
+            buffer: vec![],
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            header_block: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A header block which may be split over HEADERS or PUSH_PROMISE and CONTINUATION frames
+struct Http2HeaderBlock {
+    stream_id: u32,
+    promised_stream_id: Option<u32>,
+    end_stream: bool,
+    fragment: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+/// The state of a HTTP/2 stream
+struct Http2Stream {
+    /// The request was promised by the server
+    promised: bool,
+
+    /// The state of the request (index 1) and response (index 0)
+    halves: [Http2StreamHalf; 2],
+}
+
+impl Http2Stream {
+    fn half(&mut self, from_client: bool) -> &mut Http2StreamHalf {
+        &mut self.halves[from_client as usize]
+    }
+}
+
+#[derive(Debug, Default)]
+/// The state of a stream in one direction
+struct Http2StreamHalf {
+    headers: Option<Vec<HttpHeader>>,
+    body: Vec<u8>,
+    trailers: Vec<HttpHeader>,
+    truncated: bool,
+    closed: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of the HTTP/2 data within a TCP segment
+pub struct Http2Packet {
+    /// The segment starts with the connection preface of the client
+    pub preface: bool,
+
+    /// The frames which were completed by the segment
+    pub frames: Vec<Http2Frame>,
+
+    /// The messages which were completed by the segment
+    pub messages: Vec<Http2Message>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A HTTP/2 frame
+pub struct Http2Frame {
+    /// The length of the frame payload
+    pub length: u32,
+
+    /// The type specific flags
+    pub flags: u8,
+
+    /// The stream identifier, which is zero for frames of the connection
+    pub stream_id: u32,
+
+    /// The type specific payload
+    pub payload: Http2FramePayload,
+}
+
+impl Http2Frame {
+    /// Decode the payload of a raw frame, which also returns the header block fragment if
+    /// available. Returns None if the frame is malformed.
+    fn from_raw<'a>(raw: &Http2RawFrame<'a>) -> Option<(Http2Frame, Option<&'a [u8]>)> {
+        let flags = raw.flags;
+        let payload = raw.payload;
+        let (stream, connection) = (raw.stream_id != 0, raw.stream_id == 0);
+        let mut fragment = None;
+
+        let payload = match raw.frame_type {
+            0 if stream => Http2FramePayload::Data {
+                end_stream: flags & FLAG_END_STREAM != 0,
+                data: Http2Frame::unpad(flags, payload)?.to_vec(),
+            },
+
+            1 if stream => {
+                let data = Http2Frame::unpad(flags, payload)?;
+                let (data, priority) = if flags & FLAG_PRIORITY != 0 {
+                    if data.len() < 5 {
+                        return None;
+                    }
+                    (&data[5..], Some(Http2Priority::parse(&data[..5])))
+                } else {
+                    (data, None)
+                };
+                fragment = Some(data);
+                Http2FramePayload::Headers {
+                    end_stream: flags & FLAG_END_STREAM != 0,
+                    end_headers: flags & FLAG_END_HEADERS != 0,
+                    priority: priority,
+                    headers: None,
+                }
+            }
+
+            2 if stream && payload.len() == 5 => Http2FramePayload::Priority(Http2Priority::parse(payload)),
+
+            3 if stream && payload.len() == 4 => {
+                Http2FramePayload::RstStream(Http2ErrorCode::from_u32(Http2Frame::read_u32(payload)))
+            }
+
+            4 if connection && payload.len().is_multiple_of(6) => {
+                let ack = flags & FLAG_ACK != 0;
+                if ack && !payload.is_empty() {
+                    return None;
+                }
+                Http2FramePayload::Settings {
+                    ack: ack,
+                    settings: payload.chunks(6).map(Http2Setting::parse).collect(),
+                }
+            }
+
+            5 if stream => {
+                let data = Http2Frame::unpad(flags, payload)?;
+                if data.len() < 4 {
+                    return None;
+                }
+                fragment = Some(&data[4..]);
+                Http2FramePayload::PushPromise {
+                    end_headers: flags & FLAG_END_HEADERS != 0,
+                    promised_stream_id: Http2Frame::read_u32(data) & 0x7fff_ffff,
+                    headers: None,
+                }
+            }
+
+            6 if connection && payload.len() == 8 => Http2FramePayload::Ping {
+                ack: flags & FLAG_ACK != 0,
+                data: (Http2Frame::read_u32(payload) as u64) << 32 | Http2Frame::read_u32(&payload[4..]) as u64,
+            },
+
+            7 if connection && payload.len() >= 8 => Http2FramePayload::Goaway {
+                last_stream_id: Http2Frame::read_u32(payload) & 0x7fff_ffff,
+                error_code: Http2ErrorCode::from_u32(Http2Frame::read_u32(&payload[4..])),
+                debug_data: payload[8..].to_vec(),
+            },
+
+            8 if payload.len() == 4 => Http2FramePayload::WindowUpdate(Http2Frame::read_u32(payload) & 0x7fff_ffff),
+
+            9 if stream => {
+                fragment = Some(payload);
+                Http2FramePayload::Continuation {
+                    end_headers: flags & FLAG_END_HEADERS != 0,
+                    headers: None,
+                }
+            }
+
+            // Unknown frame types have to be ignored
+            frame_type @ 10..=255 => Http2FramePayload::Other(frame_type),
+
+            _ => return None,
+        };
+
+        Some((
+            Http2Frame {
+                length: raw.payload.len() as u32,
+                flags: flags,
+                stream_id: raw.stream_id,
+                payload: payload,
+            },
+            fragment,
+        ))
+    }
+
+    /// Remove the padding of a payload if the padded flag is set
+    fn unpad(flags: u8, payload: &[u8]) -> Option<&[u8]> {
+        if flags & FLAG_PADDED == 0 {
+            return Some(payload);
+        }
+        let padding = *payload.first()? as usize;
+        if padding >= payload.len() {
+            return None;
+        }
+        Some(&payload[1..payload.len() - padding])
+    }
+
+    fn read_u32(data: &[u8]) -> u32 {
+        (data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The type specific payload of a HTTP/2 frame
+pub enum Http2FramePayload {
+    /// Application data of a stream, without padding
+    Data {
+        /// This is the last frame of the stream in this direction
+        end_stream: bool,
+
+        /// The data
+        data: Vec<u8>,
+    },
+
+    /// Opens a stream and carries the (first part of the) header block
+    Headers {
+        /// This is the last frame of the stream in this direction
+        end_stream: bool,
+
+        /// The header block is complete with this frame
+        end_headers: bool,
+
+        /// The priority of the stream
+        priority: Option<Http2Priority>,
+
+        /// The decoded header block if it was completed by this frame
+        headers: Option<Vec<HttpHeader>>,
+    },
+
+    /// The priority of a stream
+    Priority(Http2Priority),
+
+    /// Terminates a stream
+    RstStream(Http2ErrorCode),
+
+    /// Configuration parameters of the sender
+    Settings {
+        /// Acknowledges the settings of the peer
+        ack: bool,
+
+        /// The parameters
+        settings: Vec<Http2Setting>,
+    },
+
+    /// Announces a stream which will be initiated by the server
+    PushPromise {
+        /// The header block is complete with this frame
+        end_headers: bool,
+
+        /// The identifier of the announced stream
+        promised_stream_id: u32,
+
+        /// The decoded request header block if it was completed by this frame
+        headers: Option<Vec<HttpHeader>>,
+    },
+
+    /// Measures the round trip time or checks the connection
+    Ping {
+        /// This is a response to a ping
+        ack: bool,
+
+        /// Opaque data, which is echoed by the response
+        data: u64,
+    },
+
+    /// Initiates the shutdown of the connection
+    Goaway {
+        /// The last stream which was or might be processed by the sender
+        last_stream_id: u32,
+
+        /// The reason for closing the connection
+        error_code: Http2ErrorCode,
+
+        /// Additional diagnostic information
+        debug_data: Vec<u8>,
+    },
+
+    /// Flow control window increment of a stream or the whole connection
+    WindowUpdate(u32),
+
+    /// Continues a header block
+    Continuation {
+        /// The header block is complete with this frame
+        end_headers: bool,
+
+        /// The decoded header block if it was completed by this frame
+        headers: Option<Vec<HttpHeader>>,
+    },
+
+    /// A frame of an unknown type
+    Other(u8),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The priority of a stream
+pub struct Http2Priority {
+    /// The stream becomes the only dependency of the parent stream
+    pub exclusive: bool,
+
+    /// The parent stream
+    pub dependency: u32,
+
+    /// The weight between 1 and 256, minus one
+    pub weight: u8,
+}
+
+impl Http2Priority {
+    fn parse(data: &[u8]) -> Self {
+        let dependency = Http2Frame::read_u32(data);
+        Http2Priority {
+            exclusive: dependency & 0x8000_0000 != 0,
+            dependency: dependency & 0x7fff_ffff,
+            weight: data[4],
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A HTTP/2 connection setting
+pub enum Http2Setting {
+    /// The maximum size of the HPACK dynamic table of the sender
+    HeaderTableSize(u32),
+
+    /// Server push is permitted
+    EnablePush(u32),
+
+    /// The maximum number of concurrent streams the sender allows
+    MaxConcurrentStreams(u32),
+
+    /// The initial flow control window size of streams
+    InitialWindowSize(u32),
+
+    /// The largest frame payload the sender is willing to receive
+    MaxFrameSize(u32),
+
+    /// The maximum size of a header list the sender is prepared to accept
+    MaxHeaderListSize(u32),
+
+    /// An unknown setting with its identifier and value
+    Other(u16, u32),
+}
+
+impl Http2Setting {
+    fn parse(data: &[u8]) -> Self {
+        let value = Http2Frame::read_u32(&data[2..]);
+        match (data[0] as u16) << 8 | data[1] as u16 {
+            1 => Http2Setting::HeaderTableSize(value),
+            2 => Http2Setting::EnablePush(value),
+            3 => Http2Setting::MaxConcurrentStreams(value),
+            4 => Http2Setting::InitialWindowSize(value),
+            5 => Http2Setting::MaxFrameSize(value),
+            6 => Http2Setting::MaxHeaderListSize(value),
+            identifier => Http2Setting::Other(identifier, value),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The reason of a stream or connection error
+pub enum Http2ErrorCode {
+    /// Graceful shutdown
+    NoError,
+
+    /// Protocol error detected
+    ProtocolError,
+
+    /// Implementation fault
+    InternalError,
+
+    /// Flow-control limits exceeded
+    FlowControlError,
+
+    /// Settings not acknowledged
+    SettingsTimeout,
+
+    /// Frame received for closed stream
+    StreamClosed,
+
+    /// Frame size incorrect
+    FrameSizeError,
+
+    /// Stream not processed
+    RefusedStream,
+
+    /// Stream cancelled
+    Cancel,
+
+    /// Compression state not updated
+    CompressionError,
+
+    /// TCP connection error for CONNECT method
+    ConnectError,
+
+    /// Processing capacity exceeded
+    EnhanceYourCalm,
+
+    /// Negotiated TLS parameters not acceptable
+    InadequateSecurity,
+
+    /// Use HTTP/1.1 for the request
+    Http11Required,
+
+    /// An unknown error code
+    Other(u32),
+}
+
+impl Http2ErrorCode {
+    /// Convert a u32 to an `Http2ErrorCode`
+    pub fn from_u32(input: u32) -> Self {
+        match input {
+            0x0 => Http2ErrorCode::NoError,
+            0x1 => Http2ErrorCode::ProtocolError,
+            0x2 => Http2ErrorCode::InternalError,
+            0x3 => Http2ErrorCode::FlowControlError,
+            0x4 => Http2ErrorCode::SettingsTimeout,
+            0x5 => Http2ErrorCode::StreamClosed,
+            0x6 => Http2ErrorCode::FrameSizeError,
+            0x7 => Http2ErrorCode::RefusedStream,
+            0x8 => Http2ErrorCode::Cancel,
+            0x9 => Http2ErrorCode::CompressionError,
+            0xa => Http2ErrorCode::ConnectError,
+            0xb => Http2ErrorCode::EnhanceYourCalm,
+            0xc => Http2ErrorCode::InadequateSecurity,
+            0xd => Http2ErrorCode::Http11Required,
+            code => Http2ErrorCode::Other(code),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A complete request or response of a stream
+pub struct Http2Message {
+    /// The stream of the message
+    pub stream_id: u32,
+
+    /// The message was sent by the client, which means it is a request
+    pub from_client: bool,
+
+    /// The request was promised by the server via PUSH_PROMISE
+    pub promised: bool,
+
+    /// The headers including the pseudo-headers like ":method" or ":status"
+    pub headers: Vec<HttpHeader>,
+
+    /// The reassembled body
+    pub body: Vec<u8>,
+
+    /// Headers sent after the body
+    pub trailers: Vec<HttpHeader>,
+
+    /// The body exceeded the maximum body size and is incomplete
+    pub truncated: bool,
+}
+
+impl Http2Message {
+    /// Get the value of the first header with the given name, like ":path"
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.key == name)
+            .map(|header| header.value.as_str())
+    }
+
+    /// Get the request method
+    pub fn method(&self) -> Option<&str> {
+        self.header(":method")
+    }
+
+    /// Get the request path
+    pub fn path(&self) -> Option<&str> {
+        self.header(":path")
+    }
+
+    /// Get the response status code
+    pub fn status(&self) -> Option<u16> {
+        self.header(":status").and_then(|status| status.parse().ok())
+    }
+
+    fn is_informational(headers: &[HttpHeader]) -> bool {
+        headers
+            .iter()
+            .any(|header| header.key == ":status" && header.value.starts_with('1'))
+    }
+}
+
+/// A frame with its payload still encoded
+struct Http2RawFrame<'a> {
+    frame_type: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: &'a [u8],
+}
+
+impl<'a> Http2RawFrame<'a> {
+    fn parse(input: &'a [u8]) -> IResult<&'a [u8], Http2RawFrame<'a>> {
+        do_parse!(
+            input,
+            length: be_u24
+                >> frame_type: be_u8
+                >> flags: be_u8
+                >> stream_id: map!(be_u32, |id| id & 0x7fff_ffff)
+                >> payload: take!(length)
+                >> (Http2RawFrame {
+                    frame_type: frame_type,
+                    flags: flags,
+                    stream_id: stream_id,
+                    payload: payload,
+                })
+        )
+    }
+}
@@ -0,0 +1,119 @@
+//! Cookies of the HTTP `Cookie` and `Set-Cookie` headers
+
+#[derive(Debug, Eq, PartialEq)]
+/// A cookie sent by the client
+pub struct HttpCookie {
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie value without surrounding double quotes
+    pub value: String,
+}
+
+impl HttpCookie {
+    /// Parse the value of a `Cookie` header, like "a=1; b=2". Pairs without a name are skipped.
+    pub fn parse_list(value: &str) -> Vec<HttpCookie> {
+        value
+            .split(';')
+            .filter_map(|pair| {
+                let (name, value) = HttpCookie::split_pair(pair)?;
+                Some(HttpCookie {
+                    name: name.to_owned(),
+                    value: value.unwrap_or("").to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Split a "name=value" pair, where the value is optional
+    fn split_pair(pair: &str) -> Option<(&str, Option<&str>)> {
+        let mut parts = pair.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        if name.is_empty() {
+            return None;
+        }
+        let value = parts.next().map(|value| {
+            let value = value.trim();
+            if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                &value[1..value.len() - 1]
+            } else {
+                value
+            }
+        });
+        Some((name, value))
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+/// A cookie set by the server
+pub struct HttpSetCookie {
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie value without surrounding double quotes
+    pub value: String,
+
+    /// The raw date of the `Expires` attribute
+    pub expires: Option<String>,
+
+    /// The `Max-Age` attribute in seconds, where zero or less expires the cookie immediately
+    pub max_age: Option<i64>,
+
+    /// The `Domain` attribute
+    pub domain: Option<String>,
+
+    /// The `Path` attribute
+    pub path: Option<String>,
+
+    /// The cookie is only sent over secure connections
+    pub secure: bool,
+
+    /// The cookie is not accessible by scripts
+    pub http_only: bool,
+
+    /// The `SameSite` attribute, like "Strict", "Lax" or "None"
+    pub same_site: Option<String>,
+
+    /// Any other attributes with their optional value
+    pub extensions: Vec<(String, Option<String>)>,
+}
+
+impl HttpSetCookie {
+    /// Parse the value of a `Set-Cookie` header, like "id=a3fWa; Max-Age=2592000; Secure".
+    /// Attribute names are matched case-insensitively and invalid attribute values are ignored.
+    pub fn parse(value: &str) -> Option<HttpSetCookie> {
+        let mut parts = value.split(';');
+        let (name, value) = HttpCookie::split_pair(parts.next()?)?;
+        let mut cookie = HttpSetCookie {
+            name: name.to_owned(),
+            value: value?.to_owned(),
+            ..Default::default()
+        };
+
+        for attribute in parts {
+            let (name, value) = match HttpCookie::split_pair(attribute) {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "expires" => cookie.expires = value.map(|value| value.to_owned()),
+                "max-age" => if let Some(max_age) = value.and_then(|value| value.parse().ok()) {
+                    cookie.max_age = Some(max_age);
+                },
+                // A leading dot is ignored
+                "domain" => cookie.domain = value.map(|value| {
+                    let value = value.strip_prefix('.').unwrap_or(value);
+                    value.to_ascii_lowercase()
+                }),
+                "path" => cookie.path = value.map(|value| value.to_owned()),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => cookie.same_site = value.map(|value| value.to_owned()),
+                _ => cookie
+                    .extensions
+                    .push((name.to_owned(), value.map(|value| value.to_owned()))),
+            }
+        }
+        Some(cookie)
+    }
+}
@@ -0,0 +1,519 @@
+//! Extraction of the objects transferred via HTTP, like downloaded or uploaded files
+use prelude::*;
+use std::collections::{BTreeMap, HashMap};
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// The maximum amount of objects which are reassembled from ranges at the same time, where the
+/// oldest object is dropped first
+const MAX_PARTIAL_OBJECTS: usize = 64;
+
+/// Extracts the transferred objects from HTTP requests and responses
+#[derive(Debug)]
+pub struct HttpObjectExtractor {
+    /// The maximum size of an object reassembled from ranges, larger objects are dropped
+    pub max_object_size: usize,
+
+    /// The maximum size of the ranges of all incomplete objects, where the oldest objects are
+    /// dropped first if it gets exceeded
+    pub max_partial_size: usize,
+
+    /// The URLs of the requests which are still waiting for a response
+    pending: HttpPendingRequests<String>,
+
+    /// The objects per URL which are transferred via `206 Partial Content` responses
+    partials: HashMap<String, HttpPartialObject>,
+
+    /// The number of the next incomplete object, which orders the objects by their age
+    next_partial: u64,
+}
+
+impl HttpObjectExtractor {
+    /// Create a new extractor, which reassembles objects from ranges up to 100 MiB and keeps up
+    /// to 256 MiB of ranges of incomplete objects
+    pub fn new() -> Self {
+        HttpObjectExtractor {
+            max_object_size: 100 * 1024 * 1024,
+            max_partial_size: 256 * 1024 * 1024,
+            pending: HttpPendingRequests::new(),
+            partials: HashMap::new(),
+            next_partial: 0,
+        }
+    }
+
+    /// Extract the objects of a traversal result. Request bodies yield uploads, where every file
+    /// of a multipart/form-data body is a separate object, and successful response bodies yield
+    /// downloads. Ranges of `206 Partial Content` responses are collected until the object is
+    /// complete.
+    pub fn extract(&mut self, result: &ParserResultVec) -> Vec<HttpObject> {
+        let mut objects = vec![];
+        let pairs = match self.pending.pair(result, |connection, request| {
+            let url = request.url();
+            objects.extend(HttpObjectExtractor::uploads(connection, url.clone(), request));
+            url
+        }) {
+            Some(pairs) => pairs,
+            None => return vec![],
+        };
+
+        for (url, response) in pairs.responses {
+            let url = url.unwrap_or_else(String::new);
+            objects.extend(self.downloads(&pairs.connection, url, response));
+        }
+        objects
+    }
+
+    /// Get the amount of objects which are still incomplete because of missing ranges
+    pub fn partial_count(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Get the size of the ranges of all incomplete objects
+    pub fn partial_size(&self) -> usize {
+        self.partials.values().map(HttpPartialObject::size).sum()
+    }
+
+    /// Drop the incomplete object which was started first
+    fn evict_partial(&mut self) {
+        let oldest = self.partials
+            .iter()
+            .min_by_key(|&(_, partial)| partial.number)
+            .map(|(url, _)| url.clone());
+        if let Some(url) = oldest {
+            debug!("Dropping incomplete HTTP object {}", url);
+            self.partials.remove(&url);
+        }
+    }
+
+    fn uploads(connection: &Option<Identifier<IpProtocol>>, url: String, request: &HttpRequest) -> Vec<HttpObject> {
+        let data = request.decoded_body.as_ref().unwrap_or(&request.body);
+        if data.is_empty() {
+            return vec![];
+        }
+        let content_type = request.header("Content-Type");
+        let boundary = content_type.and_then(|content_type| HttpObjectExtractor::boundary(content_type, "form-data"));
+
+        if let Some(boundary) = boundary {
+            return HttpMultipart::parse(data, &boundary)
+                .into_iter()
+                .filter_map(|part| {
+                    let disposition = part.header("Content-Disposition")?;
+                    let filename = HttpObjectExtractor::filename(disposition)?;
+                    Some(HttpObject::new(
+                        connection.clone(),
+                        url.clone(),
+                        HttpObjectSource::Multipart {
+                            name: HttpObjectExtractor::parameter(disposition, "name"),
+                        },
+                        part.header("Content-Type").map(|content_type| content_type.to_owned()),
+                        Some(filename),
+                        part.body,
+                    ))
+                })
+                .collect();
+        }
+
+        vec![HttpObject::new(
+            connection.clone(),
+            url,
+            HttpObjectSource::Request,
+            content_type.map(|content_type| content_type.to_owned()),
+            None,
+            data.clone(),
+        )]
+    }
+
+    fn downloads(
+        &mut self,
+        connection: &Option<Identifier<IpProtocol>>,
+        url: String,
+        response: &HttpResponse,
+    ) -> Vec<HttpObject> {
+        let data = response.decoded_body.as_ref().unwrap_or(&response.body);
+        let content_type = response.header("Content-Type");
+
+        if response.code == 206 {
+            // A response with multiple ranges contains a part per range
+            let parts = match content_type
+                .and_then(|content_type| HttpObjectExtractor::boundary(content_type, "byteranges"))
+            {
+                Some(boundary) => HttpMultipart::parse(data, &boundary),
+                None => vec![HttpMultipart {
+                    headers: response.headers.clone(),
+                    body: data.clone(),
+                }],
+            };
+            return parts
+                .into_iter()
+                .filter_map(|part| {
+                    self.add_range(
+                        connection,
+                        &url,
+                        part.header("Content-Range"),
+                        part.header("Content-Type"),
+                        part.body.clone(),
+                    )
+                })
+                .collect();
+        }
+
+        if response.code >= 300 || data.is_empty() {
+            return vec![];
+        }
+        vec![HttpObject::new(
+            connection.clone(),
+            url,
+            HttpObjectSource::Response,
+            content_type.map(|content_type| content_type.to_owned()),
+            response
+                .header("Content-Disposition")
+                .and_then(HttpObjectExtractor::filename),
+            data.clone(),
+        )]
+    }
+
+    /// Add a range of an object, which returns the object if it is complete
+    fn add_range(
+        &mut self,
+        connection: &Option<Identifier<IpProtocol>>,
+        url: &str,
+        range: Option<&str>,
+        content_type: Option<&str>,
+        data: Vec<u8>,
+    ) -> Option<HttpObject> {
+        let (start, total) = match range.and_then(HttpObjectExtractor::content_range) {
+            Some((start, end, total))
+                if end - start + 1 == data.len() as u64 && total <= self.max_object_size as u64 =>
+            {
+                (start, total)
+            }
+            _ => {
+                debug!("Dropping HTTP range without a valid Content-Range");
+                return None;
+            }
+        };
+
+        if data.len() > self.max_partial_size {
+            debug!("Dropping HTTP range which exceeds the maximum partial size");
+            return None;
+        }
+        if !self.partials.contains_key(url) {
+            while self.partials.len() >= MAX_PARTIAL_OBJECTS {
+                self.evict_partial();
+            }
+            self.next_partial += 1;
+        }
+        let complete = {
+            let number = self.next_partial;
+            let partial = self.partials
+                .entry(url.to_owned())
+                .or_insert_with(|| HttpPartialObject {
+                    number: number,
+                    total: total,
+                    content_type: None,
+                    ranges: BTreeMap::new(),
+                });
+            if partial.total != total {
+                debug!("Dropping HTTP range with a different object size");
+                return None;
+            }
+            if partial.content_type.is_none() {
+                partial.content_type = content_type.map(|content_type| content_type.to_owned());
+            }
+            partial.ranges.insert(start, data);
+            partial.is_complete()
+        };
+        if !complete {
+            while self.partial_size() > self.max_partial_size {
+                self.evict_partial();
+            }
+            return None;
+        }
+
+        let partial = self.partials.remove(url)?;
+        let parts = partial.ranges.len();
+        let mut data = Vec::with_capacity(partial.total as usize);
+        for (start, range) in partial.ranges {
+            // Overlapping ranges are merged
+            let skip = (data.len() as u64 - start.min(data.len() as u64)) as usize;
+            data.extend_from_slice(&range[skip.min(range.len())..]);
+        }
+        Some(HttpObject::new(
+            connection.clone(),
+            url.to_owned(),
+            HttpObjectSource::PartialContent { parts: parts },
+            partial.content_type,
+            None,
+            data,
+        ))
+    }
+
+    /// Parse a `Content-Range` like "bytes 0-499/1234" into start, end and complete length
+    fn content_range(value: &str) -> Option<(u64, u64, u64)> {
+        let value = value.trim();
+        if value.len() < 6 || !value[..6].eq_ignore_ascii_case("bytes ") {
+            return None;
+        }
+        let mut parts = value[6..].splitn(2, '/');
+        let mut range = parts.next()?.splitn(2, '-');
+        let start: u64 = range.next()?.trim().parse().ok()?;
+        let end: u64 = range.next()?.trim().parse().ok()?;
+        let total: u64 = parts.next()?.trim().parse().ok()?;
+        if start > end || end >= total {
+            return None;
+        }
+        Some((start, end, total))
+    }
+
+    /// Get the boundary of a multipart content type with the given subtype
+    fn boundary(content_type: &str, subtype: &str) -> Option<String> {
+        let media_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        if media_type != format!("multipart/{}", subtype) {
+            return None;
+        }
+        HttpObjectExtractor::parameter(content_type, "boundary").filter(|boundary| !boundary.is_empty())
+    }
+
+    /// Get the file name of a `Content-Disposition`, where "filename*" is preferred
+    fn filename(disposition: &str) -> Option<String> {
+        HttpObjectExtractor::parameter(disposition, "filename*")
+            .and_then(|value| {
+                // The extended notation is "charset'language'percent-encoded-value"
+                let mut parts = value.splitn(3, '\'');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(_), Some(_), Some(encoded)) => Some(percent_decode(encoded)),
+                    _ => None,
+                }
+            })
+            .or_else(|| HttpObjectExtractor::parameter(disposition, "filename"))
+            .filter(|filename| !filename.is_empty())
+    }
+
+    /// Get a parameter of a header value like `form-data; name="file"`, ignoring the case of the
+    /// parameter name
+    fn parameter(value: &str, name: &str) -> Option<String> {
+        value
+            .split(';')
+            .skip(1)
+            .filter_map(|parameter| {
+                let mut parts = parameter.splitn(2, '=');
+                let key = parts.next()?.trim();
+                let value = parts.next()?.trim();
+                if !key.eq_ignore_ascii_case(name) {
+                    return None;
+                }
+                if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                    Some(value[1..value.len() - 1].replace("\\\"", "\""))
+                } else {
+                    Some(value.to_owned())
+                }
+            })
+            .next()
+    }
+}
+
+impl Default for HttpObjectExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// An object which is transferred in ranges
+struct HttpPartialObject {
+    /// The number of the object in the order the objects were started
+    number: u64,
+
+    /// The complete length of the object
+    total: u64,
+
+    /// The content type of the first range
+    content_type: Option<String>,
+
+    /// The received ranges by their start
+    ranges: BTreeMap<u64, Vec<u8>>,
+}
+
+impl HttpPartialObject {
+    /// Get the size of the received ranges
+    fn size(&self) -> usize {
+        self.ranges.values().map(|range| range.len()).sum()
+    }
+
+    /// Check if the ranges cover the whole object
+    fn is_complete(&self) -> bool {
+        let mut covered = 0;
+        for (&start, range) in &self.ranges {
+            if start > covered {
+                return false;
+            }
+            covered = covered.max(start + range.len() as u64);
+        }
+        covered >= self.total
+    }
+}
+
+/// A single part of a multipart body
+struct HttpMultipart {
+    headers: Vec<HttpHeader>,
+    body: Vec<u8>,
+}
+
+impl HttpMultipart {
+    /// Split a multipart body into its parts, where parts after a missing delimiter are dropped
+    fn parse(data: &[u8], boundary: &str) -> Vec<HttpMultipart> {
+        let delimiter = format!("--{}", boundary).into_bytes();
+        let mut parts = vec![];
+
+        // The preamble before the first delimiter is ignored
+        let mut position = match HttpMultipart::find(data, &delimiter, 0) {
+            Some(position) => position + delimiter.len(),
+            None => return parts,
+        };
+        loop {
+            // The close delimiter ends the body
+            if data[position..].starts_with(b"--") {
+                break;
+            }
+            let start = match HttpMultipart::find(data, b"\n", position) {
+                Some(line_end) => line_end + 1,
+                None => break,
+            };
+            let end = match HttpMultipart::find(data, &delimiter, start) {
+                Some(end) => end,
+                None => break,
+            };
+            if let Some(part) = HttpMultipart::parse_part(&data[start..end]) {
+                parts.push(part);
+            }
+            position = end + delimiter.len();
+        }
+        parts
+    }
+
+    /// Parse the headers and body of a part, where the line break before the next delimiter
+    /// belongs to the delimiter
+    fn parse_part(data: &[u8]) -> Option<HttpMultipart> {
+        let data = if data.ends_with(b"\r\n") {
+            &data[..data.len() - 2]
+        } else if data.ends_with(b"\n") {
+            &data[..data.len() - 1]
+        } else {
+            data
+        };
+
+        let mut headers = vec![];
+        let mut position = 0;
+        loop {
+            let line_end = HttpMultipart::find(data, b"\n", position)?;
+            let line = &data[position..line_end];
+            let line = if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line };
+            position = line_end + 1;
+            if line.is_empty() {
+                break;
+            }
+            let colon = line.iter().position(|&b| b == b':')?;
+            let value = String::from_utf8_lossy(&line[colon + 1..]).trim().to_owned();
+            headers.push(HttpHeader::from_raw(&line[..colon], value.into_bytes()));
+        }
+        Some(HttpMultipart {
+            headers: headers,
+            body: data[position..].to_vec(),
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.key.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+        data[from..]
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .map(|position| position + from)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The way an object was transferred
+pub enum HttpObjectSource {
+    /// The body of a request, like an upload via PUT
+    Request,
+
+    /// A file of a multipart/form-data request body
+    Multipart {
+        /// The name of the form field
+        name: Option<String>,
+    },
+
+    /// The body of a successful response
+    Response,
+
+    /// The ranges of `206 Partial Content` responses, which were stitched together
+    PartialContent {
+        /// The amount of received ranges
+        parts: usize,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// An object transferred via HTTP
+pub struct HttpObject {
+    /// The connection of the last transfer, if an IP packet was part of the result
+    pub connection: Option<Identifier<IpProtocol>>,
+
+    /// The URL of the request, which is empty for responses to unknown requests
+    pub url: String,
+
+    /// The way the object was transferred
+    pub source: HttpObjectSource,
+
+    /// The content type
+    pub content_type: Option<String>,
+
+    /// The file name given by a `Content-Disposition`
+    pub filename: Option<String>,
+
+    /// The size of the object in bytes
+    pub size: usize,
+
+    /// The MD5 hash as lowercase hex string
+    pub md5: String,
+
+    /// The SHA-256 hash as lowercase hex string
+    pub sha256: String,
+
+    /// The object with its content encoding removed
+    pub data: Vec<u8>,
+}
+
+impl HttpObject {
+    fn new(
+        connection: Option<Identifier<IpProtocol>>,
+        url: String,
+        source: HttpObjectSource,
+        content_type: Option<String>,
+        filename: Option<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        HttpObject {
+            connection: connection,
+            url: url,
+            source: source,
+            content_type: content_type,
+            filename: filename,
+            size: data.len(),
+            md5: HttpObject::hex(&Md5::digest(&data)),
+            sha256: HttpObject::hex(&Sha256::digest(&data)),
+            data: data,
+        }
+    }
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
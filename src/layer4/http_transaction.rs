@@ -0,0 +1,236 @@
+//! Pairing of HTTP requests and responses into transactions
+use prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// The maximum amount of requests per connection waiting for a response
+const MAX_PENDING_REQUESTS: usize = 128;
+
+/// Tracks HTTP requests per connection and pairs them with their responses
+#[derive(Debug)]
+pub struct HttpTransactionTracker {
+    /// The requests which are still waiting for a response
+    pending: HttpPendingRequests<HttpPendingRequest>,
+}
+
+impl HttpTransactionTracker {
+    /// Create a new HTTP transaction tracker
+    pub fn new() -> Self {
+        HttpTransactionTracker {
+            pending: HttpPendingRequests::new(),
+        }
+    }
+
+    /// Track the result of a traversal, where the timestamp is the capture time of the packet.
+    /// Returns the completed transactions of the responses to pending requests within the result.
+    /// Pipelined requests are paired in order with the responses of the connection.
+    pub fn track(&mut self, result: &ParserResultVec, timestamp: Duration) -> Vec<HttpTransaction> {
+        let pairs = match self.pending.pair(result, |_, request| HttpPendingRequest {
+            method: request.request_method.clone(),
+            path: request.path.clone(),
+            size: request.body.len(),
+            timestamp: timestamp,
+        }) {
+            Some(pairs) => pairs,
+            None => return vec![],
+        };
+
+        let connection = pairs.connection;
+        pairs
+            .responses
+            .into_iter()
+            .filter_map(|(request, response)| {
+                let request = request?;
+                Some(HttpTransaction {
+                    connection: connection.clone(),
+                    method: request.method,
+                    path: request.path,
+                    status: response.code,
+                    request_size: request.size,
+                    response_size: response.body.len(),
+                    request_timestamp: request.timestamp,
+                    response_timestamp: timestamp,
+                    latency: timestamp
+                        .checked_sub(request.timestamp)
+                        .unwrap_or_else(|| Duration::new(0, 0)),
+                })
+            })
+            .collect()
+    }
+
+    /// Get the amount of requests of all connections which are still waiting for a response
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl Default for HttpTransactionTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// The requests per connection which are still waiting for a response, in sending order. This
+/// pairs the responses with their requests for the parser as well as the trackers.
+pub(crate) struct HttpPendingRequests<T> {
+    requests: HashMap<Option<Identifier<IpProtocol>>, VecDeque<T>>,
+}
+
+impl<T> HttpPendingRequests<T> {
+    /// Create an empty queue of pending requests
+    pub fn new() -> Self {
+        HttpPendingRequests {
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Add a request of the connection, which is dropped if too many requests are pending
+    pub fn push(&mut self, connection: &Option<Identifier<IpProtocol>>, request: T) {
+        let requests = self.requests
+            .entry(connection.clone())
+            .or_default();
+        if requests.len() < MAX_PENDING_REQUESTS {
+            requests.push_back(request);
+        } else {
+            debug!("Dropping HTTP request because too many requests are pending");
+        }
+    }
+
+    /// Get the oldest request of the connection, which is answered by the next response
+    pub fn front(&self, connection: &Option<Identifier<IpProtocol>>) -> Option<&T> {
+        self.requests.get(connection).and_then(|requests| requests.front())
+    }
+
+    /// Remove the oldest request of the connection if the response is final
+    pub fn complete(&mut self, connection: &Option<Identifier<IpProtocol>>, response: &HttpResponse) -> Option<T> {
+        if !response.is_final() {
+            return None;
+        }
+        let (request, empty) = {
+            let requests = self.requests.get_mut(connection)?;
+            (requests.pop_front(), requests.is_empty())
+        };
+        if empty {
+            self.requests.remove(connection);
+        }
+        request
+    }
+
+    /// Pair the messages of the HTTP packet within a traversal result, where every request is
+    /// kept as returned by the given function until its final response. Pipelined messages are
+    /// paired in their order and the requests of a closed connection are dropped. Returns None if
+    /// the result contains no HTTP packet.
+    pub fn pair<'a, F>(&mut self, result: &'a ParserResultVec, mut request: F) -> Option<HttpPairs<'a, T>>
+    where
+        F: FnMut(&Option<Identifier<IpProtocol>>, &'a HttpRequest) -> T,
+    {
+        let tcp = result
+            .iter()
+            .rev()
+            .filter_map(|any| any.downcast_ref::<TcpPacket>())
+            .next();
+        let connection = tcp.and_then(|tcp| {
+            connection_identifier(Some(result), tcp.header.source_port, tcp.header.dest_port)
+                .map(|(identifier, _)| identifier)
+        });
+
+        let http = result.last().and_then(|any| any.downcast_ref::<HttpPacket>());
+        let mut responses = vec![];
+        for message in http.map(HttpPacket::messages).unwrap_or_default() {
+            match *message {
+                HttpPacket::Request(ref r) => {
+                    let pending = request(&connection, r);
+                    self.push(&connection, pending);
+                }
+
+                // Informational responses are followed by the final response
+                HttpPacket::Response(ref r) if r.is_final() => {
+                    responses.push((self.complete(&connection, r), r));
+                }
+
+                _ => {}
+            }
+        }
+
+        // The connection is closed, which may also happen without any HTTP data
+        if let Some(tcp) = tcp {
+            if tcp.header.flag_fin || tcp.header.flag_rst {
+                self.close(&connection);
+            }
+        }
+
+        http.map(|_| HttpPairs {
+            connection: connection,
+            responses: responses,
+        })
+    }
+
+    /// Remove all requests of a closed connection
+    pub fn close(&mut self, connection: &Option<Identifier<IpProtocol>>) {
+        self.requests.remove(connection);
+    }
+
+    /// Get the amount of pending requests of all connections
+    pub fn len(&self) -> usize {
+        self.requests.values().map(|requests| requests.len()).sum()
+    }
+}
+
+impl<T> Default for HttpPendingRequests<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// The final responses of a traversal result, paired by `HttpPendingRequests::pair`
+pub(crate) struct HttpPairs<'a, T> {
+    /// The connection of the messages, if an IP packet was part of the result
+    pub connection: Option<Identifier<IpProtocol>>,
+
+    /// The final responses in their order together with their request, which is None if the
+    /// request was not seen
+    pub responses: Vec<(Option<T>, &'a HttpResponse)>,
+}
+
+#[derive(Debug)]
+/// A request which is still waiting for a response
+struct HttpPendingRequest {
+    method: HttpRequestMethod,
+    path: String,
+    size: usize,
+    timestamp: Duration,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A completed HTTP transaction, which is a request together with its response
+pub struct HttpTransaction {
+    /// The connection of the transaction, if an IP packet was part of the result
+    pub connection: Option<Identifier<IpProtocol>>,
+
+    /// The HTTP request method
+    pub method: HttpRequestMethod,
+
+    /// The HTTP path which was accessed
+    pub path: String,
+
+    /// The HTTP response status code
+    pub status: u16,
+
+    /// The size of the request body in bytes
+    pub request_size: usize,
+
+    /// The size of the response body in bytes, as transferred
+    pub response_size: usize,
+
+    /// The capture time of the request
+    pub request_timestamp: Duration,
+
+    /// The capture time of the response
+    pub response_timestamp: Duration,
+
+    /// The time between request and response, which is zero if the response was captured before
+    /// the request
+    pub latency: Duration,
+}
@@ -0,0 +1,180 @@
+//! Request targets, path segments and query strings of HTTP requests
+use prelude::*;
+
+#[derive(Debug, Eq, PartialEq)]
+/// The request target of a HTTP request line
+pub enum HttpRequestTarget {
+    /// An absolute path with an optional query, like "/search?q=peel"
+    Origin {
+        /// The absolute path
+        path: String,
+
+        /// The query without the leading "?"
+        query: Option<String>,
+    },
+
+    /// An absolute URI as sent to proxies, like "http://example.com/search?q=peel"
+    Absolute {
+        /// The scheme, like "http"
+        scheme: String,
+
+        /// The authority, which consists of the host and an optional port
+        authority: String,
+
+        /// The absolute path, which is "/" if the URI contains none
+        path: String,
+
+        /// The query without the leading "?"
+        query: Option<String>,
+    },
+
+    /// The host and port of a CONNECT request, like "example.com:443"
+    Authority {
+        /// The host name or address, where IPv6 addresses are enclosed in brackets
+        host: String,
+
+        /// The port
+        port: u16,
+    },
+
+    /// The asterisk of a server-wide OPTIONS request
+    Asterisk,
+}
+
+impl HttpRequestTarget {
+    /// Parse the request target of a request with the given method. A fragment, which is not
+    /// allowed by the specification but sent by some clients, is removed.
+    pub fn parse(target: &str, method: &HttpRequestMethod) -> Option<HttpRequestTarget> {
+        let target = target.split('#').next().unwrap_or("");
+
+        if *method == HttpRequestMethod::Connect {
+            return HttpRequestTarget::parse_authority(target);
+        }
+        if target == "*" {
+            return Some(HttpRequestTarget::Asterisk);
+        }
+        if target.starts_with('/') {
+            let (path, query) = HttpRequestTarget::split_query(target);
+            return Some(HttpRequestTarget::Origin {
+                path: path.to_owned(),
+                query: query.map(|query| query.to_owned()),
+            });
+        }
+
+        let scheme_end = target.find("://")?;
+        let scheme = &target[..scheme_end];
+        let mut scheme_chars = scheme.chars();
+        if !scheme_chars.next()?.is_ascii_alphabetic()
+            || !scheme_chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        {
+            return None;
+        }
+        let rest = &target[scheme_end + 3..];
+        let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let authority = &rest[..authority_end];
+        if authority.is_empty() {
+            return None;
+        }
+        let (path, query) = HttpRequestTarget::split_query(&rest[authority_end..]);
+        Some(HttpRequestTarget::Absolute {
+            scheme: scheme.to_ascii_lowercase(),
+            authority: authority.to_owned(),
+            path: if path.is_empty() { "/".to_owned() } else { path.to_owned() },
+            query: query.map(|query| query.to_owned()),
+        })
+    }
+
+    /// Get the path of origin and absolute form targets
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            HttpRequestTarget::Origin { ref path, .. } | HttpRequestTarget::Absolute { ref path, .. } => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the raw query of origin and absolute form targets
+    pub fn query(&self) -> Option<&str> {
+        match *self {
+            HttpRequestTarget::Origin { ref query, .. } | HttpRequestTarget::Absolute { ref query, .. } => {
+                query.as_ref().map(|query| query.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the percent-decoded segments of the path, like ["a b", "c"] for "/a%20b/c"
+    pub fn path_segments(&self) -> Vec<String> {
+        match self.path() {
+            Some(path) => path[1..].split('/').map(percent_decode).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Get the decoded parameters of an "application/x-www-form-urlencoded" query in their
+    /// order, where parameters without "=" have an empty value
+    pub fn query_params(&self) -> Vec<(String, String)> {
+        self.query().map(parse_form_urlencoded).unwrap_or_default()
+    }
+
+    /// Split a path from its query
+    fn split_query(target: &str) -> (&str, Option<&str>) {
+        match target.find('?') {
+            Some(position) => (&target[..position], Some(&target[position + 1..])),
+            None => (target, None),
+        }
+    }
+
+    /// Parse the "host:port" target of CONNECT requests
+    fn parse_authority(target: &str) -> Option<HttpRequestTarget> {
+        let position = target.rfind(':')?;
+        let (host, port) = (&target[..position], &target[position + 1..]);
+        let valid_host = if host.starts_with('[') {
+            host.ends_with(']') && host[1..host.len() - 1].parse::<Ipv6Addr>().is_ok()
+        } else {
+            !host.is_empty() && !host.contains([':', '/', '@'])
+        };
+        if !valid_host || port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(HttpRequestTarget::Authority {
+            host: host.to_owned(),
+            port: port.parse().ok()?,
+        })
+    }
+}
+
+/// Decode "%XX" sequences, where invalid sequences are kept and invalid UTF-8 is replaced by
+/// U+FFFD
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode the name value pairs of "application/x-www-form-urlencoded" data, like a query
+pub fn parse_form_urlencoded(data: &str) -> Vec<(String, String)> {
+    data.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next().unwrap_or("").replace('+', " ");
+            let value = parts.next().unwrap_or("").replace('+', " ");
+            (percent_decode(&name), percent_decode(&value))
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+//! Link-Local Multicast Name Resolution related packet processing
+use prelude::*;
+
+/// The UDP port of LLMNR
+const LLMNR_PORT: u16 = 5355;
+
+/// The LLMNR parser
+#[derive(Debug)]
+pub struct LlmnrParser;
+
+impl Parsable<PathIp> for LlmnrParser {
+    /// Parse a `LlmnrPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol and port from the parent parser (UDP)
+        let valid = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<UdpPacket>()) {
+                Some(udp) => udp.header.source_port == LLMNR_PORT || udp.header.dest_port == LLMNR_PORT,
+                None => false,
+            },
+            // Parse also if no result is given, for testability
+            None => true,
+        };
+        if !valid {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        match DnsMessage::decode(input) {
+            Some(mut message) => {
+                // The header flags at the position of AA and RD have a different meaning
+                let packet = LlmnrPacket {
+                    conflict: message.authoritative,
+                    tentative: message.recursion_desired,
+                    message: {
+                        message.authoritative = false;
+                        message.recursion_desired = false;
+                        message
+                    },
+                };
+                IResult::Done(&input[input.len()..], Box::new(packet))
+            }
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for LlmnrParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LLMNR")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a Link-Local Multicast Name Resolution packet
+pub struct LlmnrPacket {
+    /// The name is not considered unique, since multiple responders answered
+    pub conflict: bool,
+
+    /// The responder is still verifying the uniqueness of the name
+    pub tentative: bool,
+
+    /// The DNS message, where the authoritative and recursion flags are unused
+    pub message: DnsMessage,
+}
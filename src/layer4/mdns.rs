@@ -0,0 +1,167 @@
+//! Multicast DNS and DNS-Based Service Discovery related packet processing
+use prelude::*;
+
+/// The UDP port of multicast DNS
+const MDNS_PORT: u16 = 5353;
+
+/// The multicast DNS parser
+#[derive(Debug)]
+pub struct MdnsParser;
+
+impl Parsable<PathIp> for MdnsParser {
+    /// Parse a `MdnsPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol and port from the parent parser (UDP)
+        let valid = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<UdpPacket>()) {
+                Some(udp) => udp.header.source_port == MDNS_PORT || udp.header.dest_port == MDNS_PORT,
+                None => false,
+            },
+            // Parse also if no result is given, for testability
+            None => true,
+        };
+        if !valid {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        match DnsMessage::decode_multicast(input) {
+            Some(message) => IResult::Done(&input[input.len()..], Box::new(MdnsPacket { message: message })),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for MdnsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mDNS")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a multicast DNS packet
+pub struct MdnsPacket {
+    /// The DNS message
+    pub message: DnsMessage,
+}
+
+impl MdnsPacket {
+    /// Get the announced service instances of DNS-Based Service Discovery, which are combined
+    /// from the PTR, SRV, TXT and address records of all sections
+    pub fn services(&self) -> Vec<DnsSdService> {
+        let records: Vec<&DnsRecord> = self.message
+            .answers
+            .iter()
+            .chain(self.message.authorities.iter())
+            .chain(self.message.additionals.iter())
+            .collect();
+
+        // Service instances are referenced by PTR records or own a SRV or TXT record
+        let mut services: Vec<DnsSdService> = vec![];
+        for record in &records {
+            let name = match record.data {
+                DnsRecordData::Ptr(ref target) => target,
+                DnsRecordData::Srv { .. } | DnsRecordData::Txt(_) => &record.name,
+                _ => continue,
+            };
+            if services.iter().all(|service| !service.name().eq_ignore_ascii_case(name)) {
+                if let Some(service) = DnsSdService::from_name(name) {
+                    services.push(service);
+                }
+            }
+        }
+
+        for service in &mut services {
+            let name = service.name();
+            for record in records.iter().filter(|record| record.name.eq_ignore_ascii_case(&name)) {
+                match record.data {
+                    DnsRecordData::Srv { port, ref target, .. } => {
+                        service.host = Some(target.clone());
+                        service.port = Some(port);
+                    }
+                    DnsRecordData::Txt(ref strings) => {
+                        service.txt = strings
+                            .iter()
+                            .filter(|string| !string.is_empty())
+                            .map(|string| {
+                                let mut parts = string.splitn(2, |&b| b == b'=');
+                                let key = String::from_utf8_lossy(parts.next().unwrap_or(&[]));
+                                let value = parts.next().map(|value| String::from_utf8_lossy(value).into_owned());
+                                (key.into_owned(), value)
+                            })
+                            .collect()
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(ref host) = service.host {
+                service.addresses = records
+                    .iter()
+                    .filter(|record| record.name.eq_ignore_ascii_case(host))
+                    .filter_map(|record| match record.data {
+                        DnsRecordData::A(address) => Some(IpAddr::V4(address)),
+                        DnsRecordData::Aaaa(address) => Some(IpAddr::V6(address)),
+                        _ => None,
+                    })
+                    .collect();
+            }
+        }
+        services
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A service instance of DNS-Based Service Discovery, like "Printer._ipp._tcp.local"
+pub struct DnsSdService {
+    /// The user friendly instance name, like "Printer"
+    pub instance: String,
+
+    /// The service type with the protocol, like "_ipp._tcp"
+    pub service_type: String,
+
+    /// The domain, like "local"
+    pub domain: String,
+
+    /// The host name of the SRV record
+    pub host: Option<String>,
+
+    /// The port of the SRV record
+    pub port: Option<u16>,
+
+    /// The key value pairs of the TXT record, where boolean attributes have no value
+    pub txt: Vec<(String, Option<String>)>,
+
+    /// The addresses of the host
+    pub addresses: Vec<IpAddr>,
+}
+
+impl DnsSdService {
+    /// Split a service instance name, where the instance may contain dots
+    fn from_name(name: &str) -> Option<DnsSdService> {
+        let labels: Vec<&str> = name.split('.').collect();
+        let protocol = labels
+            .iter()
+            .rposition(|label| label.eq_ignore_ascii_case("_tcp") || label.eq_ignore_ascii_case("_udp"))?;
+        if protocol < 2 || !labels[protocol - 1].starts_with('_') {
+            return None;
+        }
+        Some(DnsSdService {
+            instance: labels[..protocol - 1].join("."),
+            service_type: labels[protocol - 1..protocol + 1].join("."),
+            domain: labels[protocol + 1..].join("."),
+            host: None,
+            port: None,
+            txt: vec![],
+            addresses: vec![],
+        })
+    }
+
+    /// Get the full name of the service instance
+    pub fn name(&self) -> String {
+        format!("{}.{}.{}", self.instance, self.service_type, self.domain)
+    }
+}
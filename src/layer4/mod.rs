@@ -1,3 +1,25 @@
 //! Application Layer packets
+pub mod ber;
+pub mod dhcp;
+pub mod dhcpv6;
+pub mod dns;
+pub mod grpc;
+pub mod har;
+pub mod hpack;
 pub mod http;
+pub mod http2;
+pub mod http_cookie;
+pub mod http_object;
+pub mod http_transaction;
+pub mod http_url;
+pub mod llmnr;
+pub mod mdns;
 pub mod ntp;
+pub mod protobuf;
+pub mod rtp;
+pub mod sdp;
+pub mod sip;
+pub mod snmp;
+pub mod ssh;
+pub mod syslog;
+pub mod websocket;
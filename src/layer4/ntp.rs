@@ -1,5 +1,14 @@
 //! Network Time Protocol related packet processing
 use prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The seconds between the NTP epoch (1900-01-01) and the UNIX epoch (1970-01-01)
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// The maximum amount of requests waiting for a response, where the oldest request is dropped
+/// first
+const MAX_PENDING_REQUESTS: usize = 4096;
 
 /// The UDP parser
 #[derive(Debug)]
@@ -20,8 +29,8 @@ impl Parsable<PathIp> for NtpParser {
                 Some(vector) => {
                     match vector.last() {
                         // Check the parent node for the correct transport protocol
-                        Some(ref any) => {
-                            if let Some(_) = any.downcast_ref::<UdpPacket>() {
+                        Some(any) => {
+                            if any.downcast_ref::<UdpPacket>().is_some() {
                                 Some(())
                             } else {
                                 None
@@ -34,41 +43,25 @@ impl Parsable<PathIp> for NtpParser {
                 }
                 // Parse also if no result is given, for testability
                 None => Some(()),
-            }) >> b0: bits!(tuple!(
-                take_bits!(u8, 2),
-                take_bits!(u8, 3),
-                take_bits!(u8, 3)
-            )) >> st: be_u8
-                >> pl: be_i8
-                >> pr: be_i8
-                >> rde: be_u32
-                >> rdi: be_u32
-                >> rid: be_u32
-                >> tsr: be_u64
-                >> tso: be_u64
-                >> tsv: be_u64
-                >> tsx: be_u64
-                >> auth: opt!(complete!(pair!(be_u32, map!(take!(16), Vec::from))))
-                >> (Box::new(NtpPacket {
-                    li: b0.0,
-                    version: b0.1,
-                    mode: b0.2,
-                    stratum: st,
-                    poll: pl,
-                    precision: pr,
-                    root_delay: rde,
-                    root_dispersion: rdi,
-                    ref_id: rid,
-                    ts_ref: tsr,
-                    ts_orig: tso,
-                    ts_recv: tsv,
-                    ts_xmit: tsx,
-                    auth: auth,
-                }))
+            }) >> packet: switch!(peek!(map!(be_u8, |b| b & 0x07)),
+                6 => map!(call!(NtpControlMessage::parse), |m| Box::new(m) as ParserResult) |
+                7 => map!(call!(NtpPrivateMessage::parse), |m| Box::new(m) as ParserResult) |
+                _ => map!(call!(NtpPacket::parse), |p| Box::new(p) as ParserResult)
+            ) >> (packet)
         )
     }
 }
 
+/// Parse the optional message authentication code, which consists of the key identifier and the
+/// MD5 or SHA-1 digest, or only the key identifier for a crypto-NAK. It is identified by the
+/// length of the remaining input.
+fn parse_mac(input: &[u8]) -> IResult<&[u8], Option<(u32, Vec<u8>)>> {
+    match input.len() {
+        4 | 20 | 24 => map!(input, pair!(be_u32, map!(call!(rest), Vec::from)), Some),
+        _ => IResult::Done(input, None),
+    }
+}
+
 impl fmt::Display for NtpParser {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "NTP")
@@ -80,13 +73,13 @@ impl fmt::Display for NtpParser {
 pub struct NtpPacket {
     /// Leap Indicator (2 Bit)
     /// This field is used to warn of an impending leap second
-    pub li: u8,
+    pub li: NtpLeapIndicator,
 
     /// NTP version number (3 Bit)
     pub version: u8,
 
     /// Mode (3 Bit)
-    pub mode: u8,
+    pub mode: NtpMode,
 
     /// Stratum level of the local clock
     pub stratum: u8,
@@ -120,6 +113,785 @@ pub struct NtpPacket {
     /// Transmit timestamp. The local time when the reply was sent from the server.
     pub ts_xmit: u64,
 
-    /// Authenticator. (0 or 96 Bit) See section 7.5 of [RFC5905] and [RFC7822]
+    /// The extension fields of NTPv4, see [RFC7822]
+    pub extensions: Vec<NtpExtensionField>,
+
+    /// Authenticator. (0, 32, 160 or 192 Bit) See section 7.5 of [RFC5905] and [RFC7822]
+    pub auth: Option<(u32, Vec<u8>)>,
+}
+
+impl NtpPacket {
+    fn parse(input: &[u8]) -> IResult<&[u8], NtpPacket> {
+        do_parse!(
+            input,
+            b0: bits!(tuple!(
+                map_opt!(take_bits!(u8, 2), NtpLeapIndicator::from_u8),
+                take_bits!(u8, 3),
+                map_opt!(take_bits!(u8, 3), NtpMode::from_u8)
+            )) >> st: be_u8
+                >> pl: be_i8
+                >> pr: be_i8
+                >> rde: be_u32
+                >> rdi: be_u32
+                >> rid: be_u32
+                >> tsr: be_u64
+                >> tso: be_u64
+                >> tsv: be_u64
+                >> tsx: be_u64
+                >> extensions: call!(NtpPacket::parse_extensions)
+                >> auth: call!(parse_mac)
+                >> (NtpPacket {
+                    li: b0.0,
+                    version: b0.1,
+                    mode: b0.2,
+                    stratum: st,
+                    poll: pl,
+                    precision: pr,
+                    root_delay: rde,
+                    root_dispersion: rdi,
+                    ref_id: rid,
+                    ts_ref: tsr,
+                    ts_orig: tso,
+                    ts_recv: tsv,
+                    ts_xmit: tsx,
+                    extensions: extensions,
+                    auth: auth,
+                })
+        )
+    }
+
+    /// Parse extension fields until only the optional MAC remains
+    fn parse_extensions(input: &[u8]) -> IResult<&[u8], Vec<NtpExtensionField>> {
+        let mut extensions = vec![];
+        let mut input = input;
+        loop {
+            match input.len() {
+                0 | 4 | 20 | 24 => break,
+                _ => match NtpExtensionField::parse(input) {
+                    IResult::Done(remaining, extension) => {
+                        extensions.push(extension);
+                        input = remaining;
+                    }
+                    _ => break,
+                },
+            }
+        }
+        IResult::Done(input, extensions)
+    }
+
+    /// Get the reference timestamp as system time
+    pub fn reference_time(&self) -> Option<SystemTime> {
+        ntp_timestamp_to_system_time(self.ts_ref)
+    }
+
+    /// Get the originate timestamp as system time
+    pub fn origin_time(&self) -> Option<SystemTime> {
+        ntp_timestamp_to_system_time(self.ts_orig)
+    }
+
+    /// Get the receive timestamp as system time
+    pub fn receive_time(&self) -> Option<SystemTime> {
+        ntp_timestamp_to_system_time(self.ts_recv)
+    }
+
+    /// Get the transmit timestamp as system time
+    pub fn transmit_time(&self) -> Option<SystemTime> {
+        ntp_timestamp_to_system_time(self.ts_xmit)
+    }
+
+    /// Interpret the reference clock identifier depending on the stratum. Servers with an IPv6
+    /// upstream send a hash of its address, which is assumed if the packet was sent over IPv6.
+    pub fn reference(&self, ipv6: bool) -> NtpReference {
+        let bytes = [
+            (self.ref_id >> 24) as u8,
+            (self.ref_id >> 16) as u8,
+            (self.ref_id >> 8) as u8,
+            self.ref_id as u8,
+        ];
+        let ascii = || {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        };
+        match self.stratum {
+            0 => NtpReference::KissOfDeath(NtpKissCode::from_code(&ascii())),
+            1 => NtpReference::Clock(ascii()),
+            _ if ipv6 => NtpReference::Hash(self.ref_id),
+            _ => NtpReference::Address(Ipv4Addr::from(self.ref_id)),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A NTPv4 extension field
+pub enum NtpExtensionField {
+    /// The NTS unique identifier, which is echoed by the server to prevent replay attacks
+    UniqueIdentifier(Vec<u8>),
+
+    /// A NTS cookie, which is sent by the client and returned encrypted by the server
+    NtsCookie(Vec<u8>),
+
+    /// A NTS cookie placeholder with the length of its body, which requests an additional cookie
+    NtsCookiePlaceholder(usize),
+
+    /// The NTS authenticator and encrypted extension fields
+    NtsAuthenticator {
+        /// The nonce of the AEAD algorithm
+        nonce: Vec<u8>,
+
+        /// The ciphertext of the encrypted extension fields and the authentication tag
+        ciphertext: Vec<u8>,
+    },
+
+    /// Any other extension field by its type
+    Other(u16, Vec<u8>),
+}
+
+impl NtpExtensionField {
+    named!(parse<&[u8], NtpExtensionField>,
+        do_parse!(
+            field_type: be_u16 >>
+            length: verify!(be_u16, |l: u16| l >= 4 && l.is_multiple_of(4)) >>
+            value: take!(length as usize - 4) >>
+            (NtpExtensionField::decode(field_type, value))
+        )
+    );
+
+    fn decode(field_type: u16, value: &[u8]) -> NtpExtensionField {
+        match field_type {
+            0x0104 => NtpExtensionField::UniqueIdentifier(value.to_vec()),
+            0x0204 => NtpExtensionField::NtsCookie(value.to_vec()),
+            0x0304 => NtpExtensionField::NtsCookiePlaceholder(value.len()),
+            // The ciphertext may be followed by padding
+            0x0404 => match NtpExtensionField::parse_nts_authenticator(value) {
+                IResult::Done(_, authenticator) => authenticator,
+                _ => NtpExtensionField::Other(field_type, value.to_vec()),
+            },
+            _ => NtpExtensionField::Other(field_type, value.to_vec()),
+        }
+    }
+
+    named!(parse_nts_authenticator<&[u8], NtpExtensionField>,
+        do_parse!(
+            nonce_length: be_u16 >>
+            ciphertext_length: be_u16 >>
+            nonce: take!(nonce_length as usize) >>
+            // The nonce is padded to a multiple of four bytes
+            take!((4 - nonce_length as usize % 4) % 4) >>
+            ciphertext: take!(ciphertext_length as usize) >>
+            (NtpExtensionField::NtsAuthenticator {
+                nonce: nonce.to_vec(),
+                ciphertext: ciphertext.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A mode 6 control message, as used by `ntpq`
+pub struct NtpControlMessage {
+    /// Leap Indicator (2 Bit)
+    pub li: NtpLeapIndicator,
+
+    /// NTP version number (3 Bit)
+    pub version: u8,
+
+    /// The message is a response
+    pub response: bool,
+
+    /// The response is an error
+    pub error: bool,
+
+    /// More fragments of the response follow
+    pub more: bool,
+
+    /// The requested operation
+    pub opcode: NtpControlOpcode,
+
+    /// The sequence number, which is echoed in responses
+    pub sequence: u16,
+
+    /// The system or peer status
+    pub status: u16,
+
+    /// The association identifier of the peer, or zero for the system
+    pub association_id: u16,
+
+    /// The offset of the data within the response
+    pub offset: u16,
+
+    /// The data, which mostly consists of ASCII "name=value" pairs
+    pub data: Vec<u8>,
+
+    /// Authenticator. (0, 32, 160 or 192 Bit)
     pub auth: Option<(u32, Vec<u8>)>,
 }
+
+impl NtpControlMessage {
+    fn parse(input: &[u8]) -> IResult<&[u8], NtpControlMessage> {
+        do_parse!(
+            input,
+            b0: bits!(tuple!(
+                map_opt!(take_bits!(u8, 2), NtpLeapIndicator::from_u8),
+                take_bits!(u8, 3),
+                tag_bits!(u8, 3, 6)
+            )) >> b1: bits!(tuple!(
+                take_bits!(u8, 1),
+                take_bits!(u8, 1),
+                take_bits!(u8, 1),
+                take_bits!(u8, 5)
+            )) >> sequence: be_u16
+                >> status: be_u16
+                >> association_id: be_u16
+                >> offset: be_u16
+                >> count: be_u16
+                >> data: take!(count)
+                // The data is padded to a multiple of four bytes, if followed by a MAC
+                >> opt!(complete!(take!((4 - count as usize % 4) % 4)))
+                >> auth: call!(parse_mac)
+                >> (NtpControlMessage {
+                    li: b0.0,
+                    version: b0.1,
+                    response: b1.0 == 1,
+                    error: b1.1 == 1,
+                    more: b1.2 == 1,
+                    opcode: NtpControlOpcode::from_u8(b1.3),
+                    sequence: sequence,
+                    status: status,
+                    association_id: association_id,
+                    offset: offset,
+                    data: data.to_vec(),
+                    auth: auth,
+                })
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The operations of mode 6 control messages
+pub enum NtpControlOpcode {
+    /// Read the status of the system or a peer
+    ReadStatus,
+
+    /// Read system or peer variables
+    ReadVariables,
+
+    /// Write system or peer variables
+    WriteVariables,
+
+    /// Read reference clock variables
+    ReadClockVariables,
+
+    /// Write reference clock variables
+    WriteClockVariables,
+
+    /// Set the trap address
+    SetTrapAddress,
+
+    /// Asynchronous message sent to a trap address
+    AsyncMessage,
+
+    /// Change the runtime configuration
+    Configure,
+
+    /// Save the configuration to a file
+    SaveConfig,
+
+    /// Read the most recently used list of clients
+    ReadMru,
+
+    /// Read an ordered list, like the interfaces or restrictions
+    ReadOrderedList,
+
+    /// Request a nonce for reading the most recently used list
+    RequestNonce,
+
+    /// Unset the trap address
+    UnsetTrapAddress,
+
+    /// Any other operation
+    Other(u8),
+}
+
+impl NtpControlOpcode {
+    /// Convert a u8 to a `NtpControlOpcode`
+    pub fn from_u8(input: u8) -> NtpControlOpcode {
+        match input {
+            1 => NtpControlOpcode::ReadStatus,
+            2 => NtpControlOpcode::ReadVariables,
+            3 => NtpControlOpcode::WriteVariables,
+            4 => NtpControlOpcode::ReadClockVariables,
+            5 => NtpControlOpcode::WriteClockVariables,
+            6 => NtpControlOpcode::SetTrapAddress,
+            7 => NtpControlOpcode::AsyncMessage,
+            8 => NtpControlOpcode::Configure,
+            9 => NtpControlOpcode::SaveConfig,
+            10 => NtpControlOpcode::ReadMru,
+            11 => NtpControlOpcode::ReadOrderedList,
+            12 => NtpControlOpcode::RequestNonce,
+            31 => NtpControlOpcode::UnsetTrapAddress,
+            other => NtpControlOpcode::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A mode 7 private message of the reference implementation, as used by `ntpdc`
+pub struct NtpPrivateMessage {
+    /// The message is a response
+    pub response: bool,
+
+    /// More fragments of the response follow
+    pub more: bool,
+
+    /// NTP version number (3 Bit)
+    pub version: u8,
+
+    /// The request is authenticated
+    pub authenticated: bool,
+
+    /// The sequence number of the response fragment (7 Bit)
+    pub sequence: u8,
+
+    /// The implementation, where 0 is universal and 2 or 3 the reference implementation
+    pub implementation: u8,
+
+    /// The request code
+    pub request_code: NtpPrivateRequestCode,
+
+    /// The error code of the response (4 Bit)
+    pub error: u8,
+
+    /// The number of data items (12 Bit)
+    pub item_count: u16,
+
+    /// The size of a data item (12 Bit)
+    pub item_size: u16,
+
+    /// The data items, which are truncated to the available input
+    pub data: Vec<u8>,
+}
+
+impl NtpPrivateMessage {
+    fn parse(input: &[u8]) -> IResult<&[u8], NtpPrivateMessage> {
+        do_parse!(
+            input,
+            b0: bits!(tuple!(
+                take_bits!(u8, 1),
+                take_bits!(u8, 1),
+                take_bits!(u8, 3),
+                tag_bits!(u8, 3, 7)
+            )) >> b1: bits!(pair!(take_bits!(u8, 1), take_bits!(u8, 7)))
+                >> implementation: be_u8
+                >> request_code: be_u8
+                >> items: bits!(pair!(take_bits!(u8, 4), take_bits!(u16, 12)))
+                >> item_size: bits!(preceded!(take_bits!(u8, 4), take_bits!(u16, 12)))
+                >> data: alt!(complete!(take!(items.1 as usize * item_size as usize)) | call!(rest))
+                >> (NtpPrivateMessage {
+                    response: b0.0 == 1,
+                    more: b0.1 == 1,
+                    version: b0.2,
+                    authenticated: b1.0 == 1,
+                    sequence: b1.1,
+                    implementation: implementation,
+                    request_code: NtpPrivateRequestCode::from_u8(request_code),
+                    error: items.0,
+                    item_count: items.1,
+                    item_size: item_size,
+                    data: data.to_vec(),
+                })
+        )
+    }
+
+    /// Check if the message is a response to a monitor list request, which returns the last
+    /// clients of the server and is abused for traffic amplification
+    pub fn is_monlist_response(&self) -> bool {
+        self.response && self.request_code.is_monlist()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The request codes of mode 7 private messages
+pub enum NtpPrivateRequestCode {
+    /// List the peers
+    PeerList,
+
+    /// Summary of the peers
+    PeerListSummary,
+
+    /// Information about peers
+    PeerInfo,
+
+    /// Statistics of peers
+    PeerStats,
+
+    /// System information
+    SystemInfo,
+
+    /// System statistics
+    SystemStats,
+
+    /// I/O statistics
+    IoStats,
+
+    /// Memory statistics
+    MemoryStats,
+
+    /// Loop filter information
+    LoopInfo,
+
+    /// Timer statistics
+    TimerStats,
+
+    /// Get the monitor list
+    MonitorGetList,
+
+    /// Get the monitor list in version 1 format
+    MonitorGetList1,
+
+    /// Any other request
+    Other(u8),
+}
+
+impl NtpPrivateRequestCode {
+    /// Convert a u8 to a `NtpPrivateRequestCode`
+    pub fn from_u8(input: u8) -> NtpPrivateRequestCode {
+        match input {
+            0 => NtpPrivateRequestCode::PeerList,
+            1 => NtpPrivateRequestCode::PeerListSummary,
+            2 => NtpPrivateRequestCode::PeerInfo,
+            3 => NtpPrivateRequestCode::PeerStats,
+            4 => NtpPrivateRequestCode::SystemInfo,
+            5 => NtpPrivateRequestCode::SystemStats,
+            6 => NtpPrivateRequestCode::IoStats,
+            7 => NtpPrivateRequestCode::MemoryStats,
+            8 => NtpPrivateRequestCode::LoopInfo,
+            9 => NtpPrivateRequestCode::TimerStats,
+            20 => NtpPrivateRequestCode::MonitorGetList,
+            42 => NtpPrivateRequestCode::MonitorGetList1,
+            other => NtpPrivateRequestCode::Other(other),
+        }
+    }
+
+    /// Check if the code requests the monitor list
+    pub fn is_monlist(&self) -> bool {
+        *self == NtpPrivateRequestCode::MonitorGetList || *self == NtpPrivateRequestCode::MonitorGetList1
+    }
+}
+
+/// Convert a 64 bit NTP timestamp to system time. Timestamps with the most significant bit
+/// unset are considered to be in era 1, which starts in 2036, as defined in RFC4330. Zero is an
+/// unknown time and returns None.
+pub fn ntp_timestamp_to_system_time(timestamp: u64) -> Option<SystemTime> {
+    if timestamp == 0 {
+        return None;
+    }
+    let mut seconds = timestamp >> 32;
+    if seconds & 0x8000_0000 == 0 {
+        seconds += 1 << 32;
+    }
+    let nanos = Duration::new(0, (((timestamp & 0xffff_ffff) * 1_000_000_000) >> 32) as u32);
+    if seconds >= NTP_UNIX_OFFSET {
+        Some(UNIX_EPOCH + Duration::from_secs(seconds - NTP_UNIX_OFFSET) + nanos)
+    } else {
+        Some(UNIX_EPOCH - Duration::from_secs(NTP_UNIX_OFFSET - seconds) + nanos)
+    }
+}
+
+/// Get the signed difference `a - b` of two NTP timestamps in seconds, which is correct over era
+/// boundaries as long as the timestamps are less than 68 years apart
+fn ntp_timestamp_difference(a: u64, b: u64) -> f64 {
+    a.wrapping_sub(b) as i64 as f64 / 4_294_967_296.0
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The leap second warning of a NTP packet
+pub enum NtpLeapIndicator {
+    /// No warning
+    NoWarning,
+
+    /// The last minute of the day has 61 seconds
+    LastMinute61,
+
+    /// The last minute of the day has 59 seconds
+    LastMinute59,
+
+    /// The clock is unsynchronized
+    Unknown,
+}
+
+impl NtpLeapIndicator {
+    /// Convert a u8 to a `NtpLeapIndicator`. Returns None if the value is invalid.
+    pub fn from_u8(input: u8) -> Option<NtpLeapIndicator> {
+        match input {
+            0 => Some(NtpLeapIndicator::NoWarning),
+            1 => Some(NtpLeapIndicator::LastMinute61),
+            2 => Some(NtpLeapIndicator::LastMinute59),
+            3 => Some(NtpLeapIndicator::Unknown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The association mode of a NTP packet
+pub enum NtpMode {
+    /// Reserved
+    Reserved,
+
+    /// Symmetric active
+    SymmetricActive,
+
+    /// Symmetric passive
+    SymmetricPassive,
+
+    /// Client request
+    Client,
+
+    /// Server response
+    Server,
+
+    /// Broadcast or multicast server
+    Broadcast,
+
+    /// NTP control message
+    Control,
+
+    /// Reserved for private use
+    Private,
+}
+
+impl NtpMode {
+    /// Convert a u8 to a `NtpMode`. Returns None if the value is invalid.
+    pub fn from_u8(input: u8) -> Option<NtpMode> {
+        match input {
+            0 => Some(NtpMode::Reserved),
+            1 => Some(NtpMode::SymmetricActive),
+            2 => Some(NtpMode::SymmetricPassive),
+            3 => Some(NtpMode::Client),
+            4 => Some(NtpMode::Server),
+            5 => Some(NtpMode::Broadcast),
+            6 => Some(NtpMode::Control),
+            7 => Some(NtpMode::Private),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The interpretation of the reference clock identifier
+pub enum NtpReference {
+    /// The Kiss-o'-Death code of a stratum 0 packet
+    KissOfDeath(NtpKissCode),
+
+    /// The ASCII identifier of the reference clock of a stratum 1 server, like "GPS"
+    Clock(String),
+
+    /// The IPv4 address of the upstream server
+    Address(Ipv4Addr),
+
+    /// The first four bytes of the MD5 hash of the IPv6 address of the upstream server
+    Hash(u32),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The Kiss-o'-Death codes of RFC5905 and RFC8915
+pub enum NtpKissCode {
+    /// The association belongs to an anycast server
+    Acst,
+
+    /// Server authentication failed
+    Auth,
+
+    /// Autokey sequence failed
+    Auto,
+
+    /// The association belongs to a broadcast server
+    Bcst,
+
+    /// Cryptographic authentication or identification failed
+    Cryp,
+
+    /// Access denied by remote server
+    Deny,
+
+    /// Lost peer in symmetric mode
+    Drop,
+
+    /// Access denied due to local policy
+    Rstr,
+
+    /// The association has not yet synchronized for the first time
+    Init,
+
+    /// The association belongs to a dynamically discovered server
+    Mcst,
+
+    /// No key found
+    Nkey,
+
+    /// The NTS cookie could not be decrypted
+    Ntsn,
+
+    /// Rate exceeded, the client should reduce its polling interval
+    Rate,
+
+    /// Alteration of association from a remote host running ntpdc
+    Rmot,
+
+    /// A step change in system time has occurred
+    Step,
+
+    /// Any other code
+    Other(String),
+}
+
+impl NtpKissCode {
+    /// Convert the ASCII code to a `NtpKissCode`
+    pub fn from_code(input: &str) -> NtpKissCode {
+        match input {
+            "ACST" => NtpKissCode::Acst,
+            "AUTH" => NtpKissCode::Auth,
+            "AUTO" => NtpKissCode::Auto,
+            "BCST" => NtpKissCode::Bcst,
+            "CRYP" => NtpKissCode::Cryp,
+            "DENY" => NtpKissCode::Deny,
+            "DROP" => NtpKissCode::Drop,
+            "RSTR" => NtpKissCode::Rstr,
+            "INIT" => NtpKissCode::Init,
+            "MCST" => NtpKissCode::Mcst,
+            "NKEY" => NtpKissCode::Nkey,
+            "NTSN" => NtpKissCode::Ntsn,
+            "RATE" => NtpKissCode::Rate,
+            "RMOT" => NtpKissCode::Rmot,
+            "STEP" => NtpKissCode::Step,
+            other => NtpKissCode::Other(other.to_owned()),
+        }
+    }
+}
+
+/// Pairs client requests with server responses to calculate the clock offset and delay
+#[derive(Debug)]
+pub struct NtpExchangeTracker {
+    /// The time after which a request without a response is dropped
+    pub timeout: Duration,
+
+    /// The capture times of the requests by connection and transmit timestamp
+    pending: HashMap<(Option<Identifier<IpProtocol>>, u64), Duration>,
+}
+
+impl NtpExchangeTracker {
+    /// Create a new tracker without any pending requests, which waits up to 60 seconds for a
+    /// response
+    pub fn new() -> Self {
+        NtpExchangeTracker {
+            timeout: Duration::from_secs(60),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Track the result of a traversal, where the timestamp is the capture time of the packet.
+    /// Returns the exchange if the result is the server response to a tracked client request,
+    /// which is identified by its originate timestamp.
+    pub fn track(&mut self, result: &ParserResultVec, timestamp: Duration) -> Option<NtpExchange> {
+        let ntp = result.last().and_then(|any| any.downcast_ref::<NtpPacket>())?;
+        let udp = result
+            .iter()
+            .rev()
+            .filter_map(|any| any.downcast_ref::<UdpPacket>())
+            .next()?;
+        let connection = connection_identifier(Some(result), udp.header.source_port, udp.header.dest_port)
+            .map(|(identifier, _)| identifier);
+
+        match ntp.mode {
+            NtpMode::Client if ntp.ts_xmit != 0 => {
+                self.expire(timestamp);
+                if self.pending.len() >= MAX_PENDING_REQUESTS {
+                    let oldest = self.pending
+                        .iter()
+                        .min_by_key(|&(_, &request_timestamp)| request_timestamp)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        debug!("Dropping NTP request because too many requests are pending");
+                        self.pending.remove(&oldest);
+                    }
+                }
+                self.pending.insert((connection, ntp.ts_xmit), timestamp);
+                None
+            }
+            NtpMode::Server => {
+                let request_timestamp = self.pending.remove(&(connection.clone(), ntp.ts_orig))?;
+                if timestamp > request_timestamp + self.timeout {
+                    return None;
+                }
+                Some(NtpExchange::new(connection, request_timestamp, timestamp, ntp))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the amount of requests waiting for a response
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Drop the requests which were captured longer than the timeout before the given time
+    fn expire(&mut self, timestamp: Duration) {
+        let timeout = self.timeout;
+        self.pending
+            .retain(|_, &mut request_timestamp| request_timestamp + timeout >= timestamp);
+    }
+}
+
+impl Default for NtpExchangeTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A client request and the corresponding server response
+pub struct NtpExchange {
+    /// The identifier of the connection
+    pub connection: Option<Identifier<IpProtocol>>,
+
+    /// The capture time of the request
+    pub request_timestamp: Duration,
+
+    /// The capture time of the response
+    pub response_timestamp: Duration,
+
+    /// The offset of the server clock relative to the client clock in seconds
+    pub offset: f64,
+
+    /// The round trip delay between client and server in seconds
+    pub delay: f64,
+}
+
+impl NtpExchange {
+    /// Calculate the offset and delay as defined in RFC5905. The client receive time is derived
+    /// from the client transmit time and the time between the captured packets, which is exact if
+    /// the capture happens on the client.
+    fn new(
+        connection: Option<Identifier<IpProtocol>>,
+        request_timestamp: Duration,
+        response_timestamp: Duration,
+        response: &NtpPacket,
+    ) -> NtpExchange {
+        let elapsed = if response_timestamp >= request_timestamp {
+            let elapsed = response_timestamp - request_timestamp;
+            elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9
+        } else {
+            0.0
+        };
+        // The receive and transmit time of the server relative to the client transmit time
+        let receive = ntp_timestamp_difference(response.ts_recv, response.ts_orig);
+        let transmit = ntp_timestamp_difference(response.ts_xmit, response.ts_orig);
+        NtpExchange {
+            connection: connection,
+            request_timestamp: request_timestamp,
+            response_timestamp: response_timestamp,
+            offset: (receive + transmit - elapsed) / 2.0,
+            delay: elapsed - (transmit - receive),
+        }
+    }
+}
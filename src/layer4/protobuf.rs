@@ -0,0 +1,396 @@
+//! Protocol Buffers wire format decoding, optionally guided by a descriptor set
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path as FilePath;
+
+/// The maximum nesting depth of decoded messages
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+/// A decoded field of a message
+pub struct ProtobufField {
+    /// The field number
+    pub number: u32,
+
+    /// The field name, if a descriptor of the message is available
+    pub name: Option<String>,
+
+    /// The decoded value
+    pub value: ProtobufValue,
+}
+
+#[derive(Debug, PartialEq)]
+/// The value of a field. Without a descriptor, only the raw wire types are available.
+pub enum ProtobufValue {
+    /// A raw variable length integer
+    Varint(u64),
+
+    /// A raw 64 bit value
+    Fixed64(u64),
+
+    /// A raw 32 bit value
+    Fixed32(u32),
+
+    /// Raw length delimited data
+    Bytes(Vec<u8>),
+
+    /// A signed integer of the types int32, int64, sint32, sint64, sfixed32 or sfixed64
+    Int(i64),
+
+    /// An unsigned integer of the types uint32, uint64, fixed32 or fixed64
+    Uint(u64),
+
+    /// A boolean
+    Bool(bool),
+
+    /// A double precision floating point number
+    Double(f64),
+
+    /// A single precision floating point number
+    Float(f32),
+
+    /// A string
+    String(String),
+
+    /// The number of an enum value
+    Enum(i32),
+
+    /// A nested message
+    Message(Vec<ProtobufField>),
+}
+
+/// The message types and RPC methods of a compiled descriptor set, as created by
+/// `protoc --include_imports --descriptor_set_out`
+#[derive(Debug, Default)]
+pub struct ProtobufDescriptorSet {
+    /// The messages by their fully qualified name, like ".package.Message"
+    messages: HashMap<String, ProtobufMessageDescriptor>,
+
+    /// The input and output types of the methods by their path, like "/package.Service/Method"
+    methods: HashMap<String, (String, String)>,
+}
+
+impl ProtobufDescriptorSet {
+    /// Read a descriptor set from a file
+    pub fn from_file<P: AsRef<FilePath>>(path: P) -> io::Result<Self> {
+        let mut data = vec![];
+        File::open(path)?.read_to_end(&mut data)?;
+        ProtobufDescriptorSet::from_bytes(&data)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid protobuf descriptor set"))
+    }
+
+    /// Decode a serialized `google.protobuf.FileDescriptorSet`
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut set = ProtobufDescriptorSet::default();
+
+        for (number, file) in ProtobufWire::fields(data)? {
+            if let (1, ProtobufWire::Bytes(file)) = (number, file) {
+                set.add_file(file)?;
+            }
+        }
+        Some(set)
+    }
+
+    /// Get the fully qualified input and output type of a method
+    pub fn method_types(&self, service: &str, method: &str) -> Option<(&str, &str)> {
+        self.methods
+            .get(&format!("/{}/{}", service, method))
+            .map(|&(ref input, ref output)| (input.as_str(), output.as_str()))
+    }
+
+    /// Decode a message of the given fully qualified type, like ".package.Message". Unknown
+    /// fields are decoded with their raw wire type.
+    pub fn decode(&self, type_name: &str, data: &[u8]) -> Option<Vec<ProtobufField>> {
+        self.decode_message(self.messages.get(type_name), data, 0)
+    }
+
+    /// Decode a message without any type information
+    pub fn decode_raw(data: &[u8]) -> Option<Vec<ProtobufField>> {
+        ProtobufDescriptorSet::default().decode_message(None, data, 0)
+    }
+
+    fn decode_message(
+        &self,
+        message: Option<&ProtobufMessageDescriptor>,
+        data: &[u8],
+        depth: usize,
+    ) -> Option<Vec<ProtobufField>> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let mut fields = vec![];
+        for (number, wire) in ProtobufWire::fields(data)? {
+            let descriptor = message.and_then(|message| message.fields.get(&number));
+            let name = descriptor.map(|descriptor| descriptor.name.clone());
+            let values = match descriptor {
+                Some(descriptor) => self.decode_value(descriptor, wire, depth)?,
+                None => vec![wire.into_value()],
+            };
+            for value in values {
+                fields.push(ProtobufField {
+                    number: number,
+                    name: name.clone(),
+                    value: value,
+                });
+            }
+        }
+        Some(fields)
+    }
+
+    /// Decode a value with its field descriptor, where packed repeated fields result in
+    /// multiple values
+    fn decode_value(
+        &self,
+        descriptor: &ProtobufFieldDescriptor,
+        wire: ProtobufWire,
+        depth: usize,
+    ) -> Option<Vec<ProtobufValue>> {
+        let value = match (descriptor.field_type, wire) {
+            // Strings, bytes and nested messages
+            (9, ProtobufWire::Bytes(data)) => ProtobufValue::String(String::from_utf8_lossy(data).into_owned()),
+            (11, ProtobufWire::Bytes(data)) => ProtobufValue::Message(self.decode_message(
+                self.messages.get(&descriptor.type_name),
+                data,
+                depth + 1,
+            )?),
+            (12, ProtobufWire::Bytes(data)) => ProtobufValue::Bytes(data.to_vec()),
+
+            // Packed repeated scalars
+            (field_type, ProtobufWire::Bytes(mut data)) => {
+                let mut values = vec![];
+                while !data.is_empty() {
+                    let (wire, rest) = match field_type {
+                        1 | 6 | 16 => ProtobufWire::fixed64(data)?,
+                        2 | 7 | 15 => ProtobufWire::fixed32(data)?,
+                        _ => ProtobufWire::varint(data)?,
+                    };
+                    values.push(ProtobufDescriptorSet::decode_scalar(field_type, wire));
+                    data = rest;
+                }
+                return Some(values);
+            }
+
+            (field_type, wire) => ProtobufDescriptorSet::decode_scalar(field_type, wire),
+        };
+        Some(vec![value])
+    }
+
+    fn decode_scalar(field_type: u32, wire: ProtobufWire) -> ProtobufValue {
+        match (field_type, wire) {
+            (1, ProtobufWire::Fixed64(v)) => ProtobufValue::Double(f64::from_bits(v)),
+            (2, ProtobufWire::Fixed32(v)) => ProtobufValue::Float(f32::from_bits(v)),
+            (3, ProtobufWire::Varint(v)) => ProtobufValue::Int(v as i64),
+            (5, ProtobufWire::Varint(v)) => ProtobufValue::Int(v as i32 as i64),
+            (4, ProtobufWire::Varint(v)) | (13, ProtobufWire::Varint(v)) => ProtobufValue::Uint(v),
+            (6, ProtobufWire::Fixed64(v)) => ProtobufValue::Uint(v),
+            (7, ProtobufWire::Fixed32(v)) => ProtobufValue::Uint(v as u64),
+            (8, ProtobufWire::Varint(v)) => ProtobufValue::Bool(v != 0),
+            (14, ProtobufWire::Varint(v)) => ProtobufValue::Enum(v as i32),
+            (15, ProtobufWire::Fixed32(v)) => ProtobufValue::Int(v as i32 as i64),
+            (16, ProtobufWire::Fixed64(v)) => ProtobufValue::Int(v as i64),
+            (17, ProtobufWire::Varint(v)) | (18, ProtobufWire::Varint(v)) => {
+                ProtobufValue::Int((v >> 1) as i64 ^ -((v & 1) as i64))
+            }
+
+            // The wire type does not match the descriptor
+            (_, wire) => wire.into_value(),
+        }
+    }
+
+    /// Add the messages and services of a `google.protobuf.FileDescriptorProto`
+    fn add_file(&mut self, data: &[u8]) -> Option<()> {
+        let fields = ProtobufWire::fields(data)?;
+        let package = fields
+            .iter()
+            .filter_map(|field| match *field {
+                (2, ProtobufWire::Bytes(package)) => Some(String::from_utf8_lossy(package).into_owned()),
+                _ => None,
+            })
+            .next();
+        let scope = match package {
+            Some(ref package) if !package.is_empty() => format!(".{}", package),
+            _ => String::new(),
+        };
+
+        for field in fields {
+            match field {
+                (4, ProtobufWire::Bytes(message)) => self.add_message(&scope, message)?,
+                (6, ProtobufWire::Bytes(service)) => self.add_service(&scope, service)?,
+                _ => {}
+            }
+        }
+        Some(())
+    }
+
+    /// Add a `google.protobuf.DescriptorProto` including its nested messages
+    fn add_message(&mut self, scope: &str, data: &[u8]) -> Option<()> {
+        let fields = ProtobufWire::fields(data)?;
+        let name = format!("{}.{}", scope, ProtobufWire::string(&fields, 1)?);
+
+        let mut message = ProtobufMessageDescriptor::default();
+        for field in &fields {
+            match *field {
+                (2, ProtobufWire::Bytes(field)) => {
+                    let field = ProtobufWire::fields(field)?;
+                    let number = ProtobufWire::integer(&field, 3)?;
+                    message.fields.insert(
+                        number as u32,
+                        ProtobufFieldDescriptor {
+                            name: ProtobufWire::string(&field, 1)?,
+                            field_type: ProtobufWire::integer(&field, 5)? as u32,
+                            type_name: ProtobufWire::string(&field, 6).unwrap_or_default(),
+                        },
+                    );
+                }
+                (3, ProtobufWire::Bytes(nested)) => self.add_message(&name, nested)?,
+                _ => {}
+            }
+        }
+        self.messages.insert(name, message);
+        Some(())
+    }
+
+    /// Add the methods of a `google.protobuf.ServiceDescriptorProto`
+    fn add_service(&mut self, scope: &str, data: &[u8]) -> Option<()> {
+        let fields = ProtobufWire::fields(data)?;
+        let service = format!("{}.{}", scope, ProtobufWire::string(&fields, 1)?);
+
+        for field in &fields {
+            if let (2, ProtobufWire::Bytes(method)) = *field {
+                let method = ProtobufWire::fields(method)?;
+                self.methods.insert(
+                    format!("/{}/{}", &service[1..], ProtobufWire::string(&method, 1)?),
+                    (ProtobufWire::string(&method, 2)?, ProtobufWire::string(&method, 3)?),
+                );
+            }
+        }
+        Some(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProtobufMessageDescriptor {
+    fields: HashMap<u32, ProtobufFieldDescriptor>,
+}
+
+#[derive(Debug)]
+struct ProtobufFieldDescriptor {
+    name: String,
+
+    /// The value of `google.protobuf.FieldDescriptorProto.Type`
+    field_type: u32,
+
+    /// The fully qualified type name of message and enum fields
+    type_name: String,
+}
+
+#[derive(Clone, Copy)]
+/// A raw value of the wire format
+enum ProtobufWire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> ProtobufWire<'a> {
+    /// Decode all fields of a message with their numbers
+    fn fields(mut data: &'a [u8]) -> Option<Vec<(u32, ProtobufWire<'a>)>> {
+        let mut fields = vec![];
+        while !data.is_empty() {
+            let (key, rest) = ProtobufWire::read_varint(data)?;
+            let number = (key >> 3) as u32;
+            if number == 0 {
+                return None;
+            }
+            let (wire, rest) = match key & 0x7 {
+                0 => ProtobufWire::varint(rest)?,
+                1 => ProtobufWire::fixed64(rest)?,
+                2 => {
+                    let (length, rest) = ProtobufWire::read_varint(rest)?;
+                    if (rest.len() as u64) < length {
+                        return None;
+                    }
+                    let (bytes, rest) = rest.split_at(length as usize);
+                    (ProtobufWire::Bytes(bytes), rest)
+                }
+                5 => ProtobufWire::fixed32(rest)?,
+                // Groups are deprecated and not supported
+                _ => return None,
+            };
+            fields.push((number, wire));
+            data = rest;
+        }
+        Some(fields)
+    }
+
+    fn read_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+        let mut value = 0u64;
+        for (i, &byte) in data.iter().take(10).enumerate() {
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Some((value, &data[i + 1..]));
+            }
+        }
+        None
+    }
+
+    fn varint(data: &'a [u8]) -> Option<(ProtobufWire<'a>, &'a [u8])> {
+        ProtobufWire::read_varint(data).map(|(value, rest)| (ProtobufWire::Varint(value), rest))
+    }
+
+    fn fixed64(data: &'a [u8]) -> Option<(ProtobufWire<'a>, &'a [u8])> {
+        if data.len() < 8 {
+            return None;
+        }
+        let value = data[..8]
+            .iter()
+            .rev()
+            .fold(0u64, |value, &byte| value << 8 | byte as u64);
+        Some((ProtobufWire::Fixed64(value), &data[8..]))
+    }
+
+    fn fixed32(data: &'a [u8]) -> Option<(ProtobufWire<'a>, &'a [u8])> {
+        if data.len() < 4 {
+            return None;
+        }
+        let value = data[..4]
+            .iter()
+            .rev()
+            .fold(0u32, |value, &byte| value << 8 | byte as u32);
+        Some((ProtobufWire::Fixed32(value), &data[4..]))
+    }
+
+    /// Get the first string field with the given number
+    fn string(fields: &[(u32, ProtobufWire)], number: u32) -> Option<String> {
+        fields
+            .iter()
+            .filter_map(|field| match *field {
+                (n, ProtobufWire::Bytes(data)) if n == number => Some(String::from_utf8_lossy(data).into_owned()),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Get the first integer field with the given number
+    fn integer(fields: &[(u32, ProtobufWire)], number: u32) -> Option<u64> {
+        fields
+            .iter()
+            .filter_map(|field| match *field {
+                (n, ProtobufWire::Varint(value)) if n == number => Some(value),
+                _ => None,
+            })
+            .next()
+    }
+
+    fn into_value(self) -> ProtobufValue {
+        match self {
+            ProtobufWire::Varint(value) => ProtobufValue::Varint(value),
+            ProtobufWire::Fixed64(value) => ProtobufValue::Fixed64(value),
+            ProtobufWire::Bytes(data) => ProtobufValue::Bytes(data.to_vec()),
+            ProtobufWire::Fixed32(value) => ProtobufValue::Fixed32(value),
+        }
+    }
+}
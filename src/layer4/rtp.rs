@@ -0,0 +1,798 @@
+//! Real-time Transport Protocol and RTP Control Protocol related packet processing
+use prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+/// The only protocol version in use, as defined in RFC3550
+const RTP_VERSION: u8 = 2;
+
+/// The lowest port of the dynamic media streams which are detected heuristically
+const MIN_HEURISTIC_PORT: u16 = 1024;
+
+/// The maximum gap of sequence numbers which is still considered as in order (MAX_DROPOUT)
+const MAX_DROPOUT: u16 = 3000;
+
+/// The maximum distance of a sequence number behind the highest one which is considered as
+/// duplicate or reordered packet (MAX_MISORDER)
+const MAX_MISORDER: u16 = 100;
+
+/// The maximum amount of tracked streams, where the longest inactive stream is dropped first
+const MAX_STREAMS: usize = 4096;
+
+/// The RTP and RTCP parser. Media streams use dynamic ports, which are either configured,
+/// predicted from the session descriptions of SIP calls or detected heuristically if enabled.
+#[derive(Debug)]
+pub struct RtpParser {
+    /// The UDP ports on which datagrams are always decoded as RTP or RTCP
+    pub ports: HashSet<u16>,
+
+    /// The media endpoints of the SIP calls, on which datagrams are decoded as RTP or RTCP. The
+    /// default parser tree shares them with its SIP parser.
+    pub media_endpoints: SipMediaEndpoints,
+
+    /// Detect RTP and RTCP on all unprivileged ports, where only packets with a common payload
+    /// type and compound RTCP packets starting with a report are accepted. This is disabled by
+    /// default, since other UDP protocols on dynamic ports may be mistaken for RTP.
+    pub heuristic: bool,
+}
+
+impl RtpParser {
+    /// Create a new RTP parser without configured ports, without media endpoints and without
+    /// heuristic detection
+    pub fn new() -> Self {
+        RtpParser {
+            ports: HashSet::new(),
+            media_endpoints: SipMediaEndpoints::new(),
+            heuristic: false,
+        }
+    }
+
+    /// Check if the datagram is sent to or from a predicted media endpoint
+    fn is_media_endpoint(&self, result: Option<&ParserResultVec>, udp: &UdpPacket) -> bool {
+        match ip_endpoints(result) {
+            Some((source, destination, _)) => {
+                self.media_endpoints.contains(destination, udp.header.dest_port)
+                    || self.media_endpoints.contains(source, udp.header.source_port)
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for RtpParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for RtpParser {
+    /// Parse a `RtpPacket` or `RtcpCompoundPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol and ports from the parent parser (UDP)
+        let (configured, heuristic) = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<UdpPacket>()) {
+                Some(udp) => {
+                    let ports = [udp.header.source_port, udp.header.dest_port];
+                    (
+                        ports.iter().any(|port| self.ports.contains(port)) || self.is_media_endpoint(result, udp),
+                        self.heuristic && ports.iter().all(|&port| port >= MIN_HEURISTIC_PORT),
+                    )
+                }
+                None => (false, false),
+            },
+            // Parse also if no result is given, for testability
+            None => (true, false),
+        };
+        if !configured && !heuristic {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        // RTCP packet types do not overlap with RTP payload types and the marker (RFC5761)
+        let packet = match input.get(1) {
+            Some(&(192..=223)) => RtcpCompoundPacket::decode(input)
+                .filter(|packet| configured || packet.is_valid())
+                .map(|packet| Box::new(packet) as ParserResult),
+            _ => RtpPacket::decode(input)
+                .filter(|packet| configured || packet.has_common_payload_type())
+                .map(|packet| Box::new(packet) as ParserResult),
+        };
+        match packet {
+            Some(packet) => IResult::Done(&input[input.len()..], packet),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for RtpParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RTP")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a RTP data packet
+pub struct RtpPacket {
+    /// The protocol version, which is 2
+    pub version: u8,
+
+    /// The packet contains padding octets at the end, which are not part of the payload
+    pub padding: bool,
+
+    /// The profile specific marker, like the start of a talkspurt or the end of a video frame
+    pub marker: bool,
+
+    /// The format of the payload, as defined by the profile or the session description
+    pub payload_type: u8,
+
+    /// The sequence number, which increments by one for each packet
+    pub sequence_number: u16,
+
+    /// The sampling instant of the first octet of the payload in units of the clock rate
+    pub timestamp: u32,
+
+    /// The synchronization source identifier of the stream
+    pub ssrc: u32,
+
+    /// The contributing source identifiers, if the payload was mixed from multiple sources
+    pub csrc: Vec<u32>,
+
+    /// The header extension, if present
+    pub extension: Option<RtpExtension>,
+
+    /// The payload without padding
+    pub payload: Vec<u8>,
+}
+
+impl RtpPacket {
+    fn decode(input: &[u8]) -> Option<RtpPacket> {
+        if input.len() < 12 || input[0] >> 6 != RTP_VERSION {
+            return None;
+        }
+        let csrc_count = (input[0] & 0x0f) as usize;
+        let mut offset = 12 + 4 * csrc_count;
+        if input.len() < offset {
+            return None;
+        }
+        let csrc = input[12..offset].chunks(4).map(read_u32).collect();
+
+        let extension = if input[0] & 0x10 != 0 {
+            let header = input.get(offset..offset + 4)?;
+            let length = 4 * ((header[2] as usize) << 8 | header[3] as usize);
+            let data = input.get(offset + 4..offset + 4 + length)?;
+            offset += 4 + length;
+            Some(RtpExtension {
+                profile: (header[0] as u16) << 8 | header[1] as u16,
+                data: data.to_vec(),
+            })
+        } else {
+            None
+        };
+
+        // The last octet contains the amount of padding octets including itself
+        let padding = input[0] & 0x20 != 0;
+        let end = if padding {
+            let count = input[input.len() - 1] as usize;
+            if count == 0 || offset + count > input.len() {
+                return None;
+            }
+            input.len() - count
+        } else {
+            input.len()
+        };
+
+        Some(RtpPacket {
+            version: RTP_VERSION,
+            padding: padding,
+            marker: input[1] & 0x80 != 0,
+            payload_type: input[1] & 0x7f,
+            sequence_number: (input[2] as u16) << 8 | input[3] as u16,
+            timestamp: read_u32(&input[4..8]),
+            ssrc: read_u32(&input[8..12]),
+            csrc: csrc,
+            extension: extension,
+            payload: input[offset..end].to_vec(),
+        })
+    }
+
+    /// Check if the payload type is a static one or from the dynamic range
+    fn has_common_payload_type(&self) -> bool {
+        self.payload_type >= 96 || SdpCodec::static_payload_type(self.payload_type).is_some()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A RTP header extension
+pub struct RtpExtension {
+    /// The profile specific identifier, like 0xBEDE for one-byte header extensions (RFC8285)
+    pub profile: u16,
+
+    /// The extension data
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a compound RTCP packet, which are multiple RTCP packets within a datagram
+pub struct RtcpCompoundPacket {
+    /// The RTCP packets in their order
+    pub packets: Vec<RtcpPacket>,
+}
+
+impl RtcpCompoundPacket {
+    fn decode(mut input: &[u8]) -> Option<RtcpCompoundPacket> {
+        let mut packets = vec![];
+        while !input.is_empty() {
+            if input.len() < 4 || input[0] >> 6 != RTP_VERSION {
+                return None;
+            }
+            let length = 4 * (((input[2] as usize) << 8 | input[3] as usize) + 1);
+            if input.len() < length {
+                return None;
+            }
+
+            // The last octet contains the amount of padding octets including itself
+            let end = if input[0] & 0x20 != 0 {
+                let count = input[length - 1] as usize;
+                if count == 0 || 4 + count > length {
+                    return None;
+                }
+                length - count
+            } else {
+                length
+            };
+            packets.push(RtcpPacket::decode(input[0] & 0x1f, input[1], &input[4..end])?);
+            input = &input[length..];
+        }
+        Some(RtcpCompoundPacket { packets: packets })
+    }
+
+    /// Check if the compound packet starts with a sender or receiver report, as required by
+    /// RFC3550
+    fn is_valid(&self) -> bool {
+        matches!(
+            self.packets.first(),
+            Some(&RtcpPacket::SenderReport(_)) | Some(&RtcpPacket::ReceiverReport(_))
+        )
+    }
+
+    /// Get the canonical name of a source from the source descriptions
+    pub fn cname(&self, ssrc: u32) -> Option<&str> {
+        self.packets
+            .iter()
+            .filter_map(|packet| match *packet {
+                RtcpPacket::SourceDescription(ref chunks) => Some(chunks),
+                _ => None,
+            })
+            .flat_map(|chunks| chunks.iter())
+            .filter(|chunk| chunk.source == ssrc)
+            .flat_map(|chunk| chunk.items.iter())
+            .find(|item| item.item_type == RtcpSdesType::Cname)
+            .map(|item| item.value.as_str())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A RTCP packet within a compound packet
+pub enum RtcpPacket {
+    /// Transmission and reception statistics of an active sender (SR)
+    SenderReport(RtcpSenderReport),
+
+    /// Reception statistics of a participant which is not an active sender (RR)
+    ReceiverReport(RtcpReceiverReport),
+
+    /// Descriptions of sources, like their canonical name (SDES)
+    SourceDescription(Vec<RtcpSdesChunk>),
+
+    /// Sources which are leaving the session (BYE)
+    Goodbye(RtcpGoodbye),
+
+    /// Application defined data (APP)
+    ApplicationDefined(RtcpApplicationDefined),
+
+    /// Any other packet type, like feedback messages or extended reports
+    Other {
+        /// The packet type
+        packet_type: u8,
+
+        /// The packet type specific five bit count or subtype
+        count: u8,
+
+        /// The data after the common header without padding
+        data: Vec<u8>,
+    },
+}
+
+impl RtcpPacket {
+    fn decode(count: u8, packet_type: u8, data: &[u8]) -> Option<RtcpPacket> {
+        Some(match packet_type {
+            200 => {
+                if data.len() < 24 {
+                    return None;
+                }
+                let (reports, extension) = RtcpReportBlock::decode_list(count, &data[24..])?;
+                RtcpPacket::SenderReport(RtcpSenderReport {
+                    ssrc: read_u32(&data[0..4]),
+                    ntp_timestamp: (read_u32(&data[4..8]) as u64) << 32 | read_u32(&data[8..12]) as u64,
+                    rtp_timestamp: read_u32(&data[12..16]),
+                    packet_count: read_u32(&data[16..20]),
+                    octet_count: read_u32(&data[20..24]),
+                    reports: reports,
+                    extension: extension,
+                })
+            }
+            201 => {
+                if data.len() < 4 {
+                    return None;
+                }
+                let (reports, extension) = RtcpReportBlock::decode_list(count, &data[4..])?;
+                RtcpPacket::ReceiverReport(RtcpReceiverReport {
+                    ssrc: read_u32(&data[0..4]),
+                    reports: reports,
+                    extension: extension,
+                })
+            }
+            202 => RtcpPacket::SourceDescription(RtcpSdesChunk::decode_list(count, data)?),
+            203 => {
+                let length = 4 * count as usize;
+                if data.len() < length {
+                    return None;
+                }
+                let reason = match data[length..].split_first() {
+                    Some((&size, rest)) => Some(String::from_utf8_lossy(rest.get(..size as usize)?).into_owned()),
+                    None => None,
+                };
+                RtcpPacket::Goodbye(RtcpGoodbye {
+                    sources: data[..length].chunks(4).map(read_u32).collect(),
+                    reason: reason,
+                })
+            }
+            204 => {
+                if data.len() < 8 {
+                    return None;
+                }
+                RtcpPacket::ApplicationDefined(RtcpApplicationDefined {
+                    subtype: count,
+                    ssrc: read_u32(&data[0..4]),
+                    name: String::from_utf8_lossy(&data[4..8]).into_owned(),
+                    data: data[8..].to_vec(),
+                })
+            }
+            _ => RtcpPacket::Other {
+                packet_type: packet_type,
+                count: count,
+                data: data.to_vec(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A sender report
+pub struct RtcpSenderReport {
+    /// The synchronization source identifier of the sender
+    pub ssrc: u32,
+
+    /// The wallclock time at which the report was sent
+    pub ntp_timestamp: u64,
+
+    /// The same time as the NTP timestamp in units of the RTP clock
+    pub rtp_timestamp: u32,
+
+    /// The amount of RTP packets sent since the start of the transmission
+    pub packet_count: u32,
+
+    /// The amount of payload octets sent since the start of the transmission
+    pub octet_count: u32,
+
+    /// The reception statistics of the sources received by the sender
+    pub reports: Vec<RtcpReportBlock>,
+
+    /// The profile specific extension
+    pub extension: Vec<u8>,
+}
+
+impl RtcpSenderReport {
+    /// Get the NTP timestamp as system time
+    pub fn ntp_time(&self) -> Option<SystemTime> {
+        ntp_timestamp_to_system_time(self.ntp_timestamp)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A receiver report
+pub struct RtcpReceiverReport {
+    /// The synchronization source identifier of the receiver
+    pub ssrc: u32,
+
+    /// The reception statistics of the received sources
+    pub reports: Vec<RtcpReportBlock>,
+
+    /// The profile specific extension
+    pub extension: Vec<u8>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The reception statistics of a single source
+pub struct RtcpReportBlock {
+    /// The synchronization source identifier of the received source
+    pub ssrc: u32,
+
+    /// The fraction of packets lost since the previous report in units of 1/256
+    pub fraction_lost: u8,
+
+    /// The amount of packets lost since the beginning of the reception, which is negative if
+    /// duplicates were received
+    pub cumulative_lost: i32,
+
+    /// The highest sequence number received, extended by the count of sequence number cycles
+    pub highest_sequence: u32,
+
+    /// The interarrival jitter in units of the RTP clock
+    pub jitter: u32,
+
+    /// The middle 32 bits of the NTP timestamp of the last sender report received (LSR)
+    pub last_sender_report: u32,
+
+    /// The delay since receiving the last sender report in units of 1/65536 seconds (DLSR)
+    pub delay_since_last_sender_report: u32,
+}
+
+impl RtcpReportBlock {
+    /// Decode the given amount of report blocks and return them with the remaining data
+    fn decode_list(count: u8, data: &[u8]) -> Option<(Vec<RtcpReportBlock>, Vec<u8>)> {
+        let length = 24 * count as usize;
+        if data.len() < length {
+            return None;
+        }
+        let reports = data[..length]
+            .chunks(24)
+            .map(|block| RtcpReportBlock {
+                ssrc: read_u32(&block[0..4]),
+                fraction_lost: block[4],
+                // Sign extension of the 24 bit value
+                cumulative_lost: (read_u32(&block[4..8]) << 8) as i32 >> 8,
+                highest_sequence: read_u32(&block[8..12]),
+                jitter: read_u32(&block[12..16]),
+                last_sender_report: read_u32(&block[16..20]),
+                delay_since_last_sender_report: read_u32(&block[20..24]),
+            })
+            .collect();
+        Some((reports, data[length..].to_vec()))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The description of a source
+pub struct RtcpSdesChunk {
+    /// The synchronization or contributing source identifier
+    pub source: u32,
+
+    /// The description items
+    pub items: Vec<RtcpSdesItem>,
+}
+
+impl RtcpSdesChunk {
+    fn decode_list(count: u8, mut data: &[u8]) -> Option<Vec<RtcpSdesChunk>> {
+        let mut chunks = vec![];
+        for _ in 0..count {
+            if data.len() < 4 {
+                return None;
+            }
+            let mut chunk = RtcpSdesChunk {
+                source: read_u32(&data[0..4]),
+                items: vec![],
+            };
+
+            // The item list ends with a null octet and padding up to the next 32 bit boundary
+            let mut offset = 4;
+            loop {
+                let item_type = *data.get(offset)?;
+                if item_type == 0 {
+                    offset += 1;
+                    break;
+                }
+                let length = *data.get(offset + 1)? as usize;
+                let value = data.get(offset + 2..offset + 2 + length)?;
+                chunk.items.push(RtcpSdesItem {
+                    item_type: RtcpSdesType::from_u8(item_type),
+                    value: String::from_utf8_lossy(value).into_owned(),
+                });
+                offset += 2 + length;
+            }
+            data = &data[((offset + 3) & !3).min(data.len())..];
+            chunks.push(chunk);
+        }
+        Some(chunks)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A description item of a source
+pub struct RtcpSdesItem {
+    /// The type of the item
+    pub item_type: RtcpSdesType,
+
+    /// The text of the item, which starts with the prefix for private extensions
+    pub value: String,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The type of a source description item
+pub enum RtcpSdesType {
+    /// The canonical name, which is persistent for a participant
+    Cname,
+
+    /// The user name
+    Name,
+
+    /// The electronic mail address
+    Email,
+
+    /// The phone number
+    Phone,
+
+    /// The geographic user location
+    Location,
+
+    /// The name and version of the application
+    Tool,
+
+    /// A notice about the state of the source
+    Note,
+
+    /// A private extension
+    Private,
+
+    /// Any other item type
+    Other(u8),
+}
+
+impl RtcpSdesType {
+    /// Convert an `u8` to a `RtcpSdesType`
+    pub fn from_u8(input: u8) -> RtcpSdesType {
+        match input {
+            1 => RtcpSdesType::Cname,
+            2 => RtcpSdesType::Name,
+            3 => RtcpSdesType::Email,
+            4 => RtcpSdesType::Phone,
+            5 => RtcpSdesType::Location,
+            6 => RtcpSdesType::Tool,
+            7 => RtcpSdesType::Note,
+            8 => RtcpSdesType::Private,
+            other => RtcpSdesType::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Sources which are leaving the session
+pub struct RtcpGoodbye {
+    /// The synchronization or contributing source identifiers
+    pub sources: Vec<u32>,
+
+    /// The reason for leaving, if given
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Application defined data
+pub struct RtcpApplicationDefined {
+    /// The application specific subtype
+    pub subtype: u8,
+
+    /// The synchronization or contributing source identifier of the sender
+    pub ssrc: u32,
+
+    /// The four ASCII characters naming the application
+    pub name: String,
+
+    /// The application dependent data
+    pub data: Vec<u8>,
+}
+
+/// Collects the reception statistics of RTP streams as defined in RFC3550, appendix A
+#[derive(Debug)]
+pub struct RtpStreamTracker {
+    /// The clock rates of dynamic payload types, like from the codecs of a session description.
+    /// The clock rates of static payload types are known.
+    pub clock_rates: HashMap<u8, u32>,
+
+    /// The statistics by connection and synchronization source identifier
+    streams: HashMap<(Option<Identifier<IpProtocol>>, u32), RtpStreamStatistics>,
+}
+
+impl RtpStreamTracker {
+    /// Create a new tracker without any streams
+    pub fn new() -> Self {
+        RtpStreamTracker {
+            clock_rates: HashMap::new(),
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Track the result of a traversal, where the timestamp is the capture time of the packet.
+    /// Returns the updated statistics of the stream if the result contains a RTP packet.
+    pub fn track(&mut self, result: &ParserResultVec, timestamp: Duration) -> Option<&RtpStreamStatistics> {
+        let rtp = result.last().and_then(|any| any.downcast_ref::<RtpPacket>())?;
+        let udp = result
+            .iter()
+            .rev()
+            .filter_map(|any| any.downcast_ref::<UdpPacket>())
+            .next()?;
+        let connection = connection_identifier(Some(result), udp.header.source_port, udp.header.dest_port)
+            .map(|(identifier, _)| identifier);
+        let clock_rate = self.clock_rates
+            .get(&rtp.payload_type)
+            .cloned()
+            .or_else(|| SdpCodec::static_payload_type(rtp.payload_type).map(|(_, clock_rate)| clock_rate));
+
+        let key = (connection.clone(), rtp.ssrc);
+        if !self.streams.contains_key(&key) && self.streams.len() >= MAX_STREAMS {
+            // Make room for the new stream
+            let inactive = self.streams
+                .iter()
+                .min_by_key(|&(_, stream)| stream.last_timestamp)
+                .map(|(key, _)| key.clone());
+            if let Some(inactive) = inactive {
+                debug!("Dropping RTP stream {:08x} because too many streams are tracked", inactive.1);
+                self.streams.remove(&inactive);
+            }
+        }
+        let stream = self.streams
+            .entry(key)
+            .or_insert_with(|| RtpStreamStatistics::new(connection, rtp, timestamp));
+        stream.update(rtp, timestamp, clock_rate);
+        Some(stream)
+    }
+
+    /// Get the statistics of all tracked streams in no particular order
+    pub fn streams(&self) -> Vec<&RtpStreamStatistics> {
+        self.streams.values().collect()
+    }
+}
+
+impl Default for RtpStreamTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// The reception statistics of a RTP stream
+pub struct RtpStreamStatistics {
+    /// The identifier of the connection
+    pub connection: Option<Identifier<IpProtocol>>,
+
+    /// The synchronization source identifier
+    pub ssrc: u32,
+
+    /// The payload type of the last packet
+    pub payload_type: u8,
+
+    /// The capture time of the first packet
+    pub first_timestamp: Duration,
+
+    /// The capture time of the last packet
+    pub last_timestamp: Duration,
+
+    /// The amount of received packets, including duplicates
+    pub received: u64,
+
+    /// The first sequence number, or the one after the last restart of the source
+    pub base_sequence: u16,
+
+    /// The highest sequence number received
+    pub max_sequence: u16,
+
+    /// The amount of sequence number wraparounds
+    pub cycles: u32,
+
+    /// The amount of packets with a sequence number far off the expected one, which are not
+    /// counted as received unless the source restarted with a new sequence
+    pub sequence_errors: u64,
+
+    /// The interarrival jitter in units of the RTP clock
+    pub jitter: f64,
+
+    /// The clock rate of the last packet, if known
+    pub clock_rate: Option<u32>,
+
+    /// The sequence number which confirms a restart of the source (bad_seq)
+    bad_sequence: Option<u16>,
+
+    /// The relative transit time of the last packet in units of the RTP clock
+    transit: Option<u32>,
+}
+
+impl RtpStreamStatistics {
+    fn new(connection: Option<Identifier<IpProtocol>>, rtp: &RtpPacket, timestamp: Duration) -> RtpStreamStatistics {
+        RtpStreamStatistics {
+            connection: connection,
+            ssrc: rtp.ssrc,
+            payload_type: rtp.payload_type,
+            first_timestamp: timestamp,
+            last_timestamp: timestamp,
+            received: 0,
+            base_sequence: rtp.sequence_number,
+            max_sequence: rtp.sequence_number,
+            cycles: 0,
+            sequence_errors: 0,
+            jitter: 0.0,
+            clock_rate: None,
+            bad_sequence: None,
+            transit: None,
+        }
+    }
+
+    /// Update the statistics with a received packet, where the sequence number validation
+    /// follows the algorithm of RFC3550, appendix A.1
+    fn update(&mut self, rtp: &RtpPacket, timestamp: Duration, clock_rate: Option<u32>) {
+        self.last_timestamp = timestamp;
+        let sequence = rtp.sequence_number;
+        let delta = sequence.wrapping_sub(self.max_sequence);
+        if delta < MAX_DROPOUT {
+            // In order, with a permissible gap
+            if sequence < self.max_sequence {
+                self.cycles += 1;
+            }
+            self.max_sequence = sequence;
+        } else if delta as u32 <= 0x1_0000 - MAX_MISORDER as u32 {
+            // A very large jump, where two sequential packets indicate a restart of the source
+            if self.bad_sequence == Some(sequence) {
+                self.base_sequence = sequence;
+                self.max_sequence = sequence;
+                self.cycles = 0;
+                self.received = 0;
+                self.bad_sequence = None;
+            } else {
+                self.bad_sequence = Some(sequence.wrapping_add(1));
+                self.sequence_errors += 1;
+                return;
+            }
+        }
+        self.received += 1;
+        self.payload_type = rtp.payload_type;
+
+        // The interarrival jitter of RFC3550, appendix A.8, which requires the arrival time in
+        // units of the RTP clock
+        if let Some(rate) = clock_rate {
+            let rate = rate as u64;
+            let arrival = (timestamp.as_secs() * rate + timestamp.subsec_nanos() as u64 * rate / 1_000_000_000) as u32;
+            let transit = arrival.wrapping_sub(rtp.timestamp);
+            if let (Some(last), true) = (self.transit, self.clock_rate == Some(rate as u32)) {
+                let difference = (transit.wrapping_sub(last) as i32 as i64).abs() as f64;
+                self.jitter += (difference - self.jitter) / 16.0;
+            }
+            self.transit = Some(transit);
+        }
+        self.clock_rate = clock_rate;
+    }
+
+    /// Get the highest sequence number extended by the sequence number cycles
+    pub fn extended_max_sequence(&self) -> u64 {
+        (self.cycles as u64) << 16 | self.max_sequence as u64
+    }
+
+    /// Get the amount of expected packets, derived from the sequence numbers
+    pub fn expected(&self) -> u64 {
+        self.extended_max_sequence() - self.base_sequence as u64 + 1
+    }
+
+    /// Get the amount of lost packets, which is negative if duplicates were received
+    pub fn lost(&self) -> i64 {
+        self.expected() as i64 - self.received as i64
+    }
+
+    /// Get the interarrival jitter in seconds, if the clock rate is known
+    pub fn jitter_seconds(&self) -> Option<f64> {
+        self.clock_rate.map(|rate| self.jitter / f64::from(rate))
+    }
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    (data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
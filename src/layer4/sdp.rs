@@ -0,0 +1,339 @@
+//! Session Description Protocol decoding
+use std::net::IpAddr;
+
+#[derive(Debug, Default, Eq, PartialEq)]
+/// A session description
+pub struct SdpSession {
+    /// The protocol version, which is 0
+    pub version: u8,
+
+    /// The originator and identifier of the session (o=)
+    pub origin: Option<SdpOrigin>,
+
+    /// The name of the session (s=)
+    pub session_name: String,
+
+    /// The session level connection data (c=)
+    pub connection: Option<SdpConnection>,
+
+    /// The session level attributes (a=)
+    pub attributes: Vec<SdpAttribute>,
+
+    /// The media descriptions (m=)
+    pub media: Vec<SdpMedia>,
+}
+
+impl SdpSession {
+    /// Decode a session description, where unknown lines are ignored
+    pub fn decode(input: &[u8]) -> Option<SdpSession> {
+        let text = String::from_utf8_lossy(input);
+        let mut session = SdpSession::default();
+        let mut lines = text.lines().map(|line| line.trim_end()).filter(|line| !line.is_empty());
+
+        // The description starts with the version
+        if !lines.next()?.starts_with("v=") {
+            return None;
+        }
+        for line in lines {
+            let bytes = line.as_bytes();
+            if bytes.len() < 2 || bytes[1] != b'=' {
+                return None;
+            }
+            let value = &line[2..];
+            match (bytes[0], session.media.last_mut()) {
+                (b'm', _) => session.media.push(SdpMedia::parse(value)?),
+                (b'c', Some(media)) => media.connection = SdpConnection::parse(value),
+                (b'a', Some(media)) => media.attributes.push(SdpAttribute::parse(value)),
+                (b'o', None) => session.origin = SdpOrigin::parse(value),
+                (b's', None) => session.session_name = value.to_owned(),
+                (b'c', None) => session.connection = SdpConnection::parse(value),
+                (b'a', None) => session.attributes.push(SdpAttribute::parse(value)),
+                _ => {}
+            }
+        }
+        Some(session)
+    }
+
+    /// Get the addresses and ports on which the media streams are expected, which are the
+    /// destinations of the RTP and RTCP packets sent to the originator of the description
+    pub fn media_endpoints(&self) -> Vec<SdpMediaEndpoint> {
+        self.media
+            .iter()
+            .filter(|media| media.port != 0 && media.protocol.contains("RTP"))
+            .filter_map(|media| {
+                let connection = media.connection.as_ref().or(self.connection.as_ref())?;
+                let rtcp_port = match media.attribute("rtcp") {
+                    Some(value) => value.split(' ').next().and_then(|port| port.parse().ok()),
+                    None if media.attribute("rtcp-mux").is_some() => Some(media.port),
+                    None => media.port.checked_add(1),
+                };
+                Some(SdpMediaEndpoint {
+                    media: media.media.clone(),
+                    address: connection.ip()?,
+                    rtp_port: media.port,
+                    rtcp_port: rtcp_port,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The originator and identifier of a session
+pub struct SdpOrigin {
+    /// The login of the originating user, or "-"
+    pub username: String,
+
+    /// The unique identifier of the session
+    pub session_id: String,
+
+    /// The version of the description, which increases on changes
+    pub session_version: String,
+
+    /// The network type, like "IN"
+    pub network_type: String,
+
+    /// The address type, like "IP4"
+    pub address_type: String,
+
+    /// The address of the originating host
+    pub address: String,
+}
+
+impl SdpOrigin {
+    fn parse(value: &str) -> Option<SdpOrigin> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() != 6 {
+            return None;
+        }
+        Some(SdpOrigin {
+            username: fields[0].to_owned(),
+            session_id: fields[1].to_owned(),
+            session_version: fields[2].to_owned(),
+            network_type: fields[3].to_owned(),
+            address_type: fields[4].to_owned(),
+            address: fields[5].to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The connection data of a session or media
+pub struct SdpConnection {
+    /// The network type, like "IN"
+    pub network_type: String,
+
+    /// The address type, like "IP4"
+    pub address_type: String,
+
+    /// The connection address, which may contain a TTL and address count for multicast
+    pub address: String,
+}
+
+impl SdpConnection {
+    fn parse(value: &str) -> Option<SdpConnection> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() != 3 {
+            return None;
+        }
+        Some(SdpConnection {
+            network_type: fields[0].to_owned(),
+            address_type: fields[1].to_owned(),
+            address: fields[2].to_owned(),
+        })
+    }
+
+    /// Get the IP address without the multicast TTL and address count
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.address.split('/').next()?.parse().ok()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// An attribute, like "rtpmap:0 PCMU/8000" or the flag "sendrecv"
+pub struct SdpAttribute {
+    /// The attribute name
+    pub name: String,
+
+    /// The attribute value, which is None for flags
+    pub value: Option<String>,
+}
+
+impl SdpAttribute {
+    fn parse(value: &str) -> SdpAttribute {
+        match value.find(':') {
+            Some(colon) => SdpAttribute {
+                name: value[..colon].to_owned(),
+                value: Some(value[colon + 1..].to_owned()),
+            },
+            None => SdpAttribute {
+                name: value.to_owned(),
+                value: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A media description
+pub struct SdpMedia {
+    /// The media type, like "audio" or "video"
+    pub media: String,
+
+    /// The transport port, where zero rejects or disables the stream
+    pub port: u16,
+
+    /// The number of consecutive ports, if given
+    pub port_count: Option<u16>,
+
+    /// The transport protocol, like "RTP/AVP"
+    pub protocol: String,
+
+    /// The media formats, which are payload types for RTP
+    pub formats: Vec<String>,
+
+    /// The media level connection data, which overrides the session level
+    pub connection: Option<SdpConnection>,
+
+    /// The media level attributes
+    pub attributes: Vec<SdpAttribute>,
+}
+
+impl SdpMedia {
+    fn parse(value: &str) -> Option<SdpMedia> {
+        let mut fields = value.split_whitespace();
+        let media = fields.next()?.to_owned();
+        let mut ports = fields.next()?.splitn(2, '/');
+        let port = ports.next()?.parse().ok()?;
+        let port_count = match ports.next() {
+            Some(count) => Some(count.parse().ok()?),
+            None => None,
+        };
+        Some(SdpMedia {
+            media: media,
+            port: port,
+            port_count: port_count,
+            protocol: fields.next()?.to_owned(),
+            formats: fields.map(str::to_owned).collect(),
+            connection: None,
+            attributes: vec![],
+        })
+    }
+
+    /// Get the value of the first attribute with the given name
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| attribute.value.as_ref().map_or("", |value| value.as_str()))
+    }
+
+    /// Get the codecs of the RTP payload types in their order of preference, from the "rtpmap"
+    /// and "fmtp" attributes or the static payload types
+    pub fn codecs(&self) -> Vec<SdpCodec> {
+        self.formats
+            .iter()
+            .filter_map(|format| format.parse::<u8>().ok())
+            .filter_map(|payload_type| {
+                let prefix = format!("{} ", payload_type);
+                let value = |name: &str| {
+                    self.attributes
+                        .iter()
+                        .filter(|attribute| attribute.name == name)
+                        .filter_map(|attribute| attribute.value.as_ref())
+                        .find(|value| value.starts_with(&prefix))
+                        .map(|value| value[prefix.len()..].trim())
+                };
+                let parameters = value("fmtp").map(str::to_owned);
+
+                // Like "opus/48000/2"
+                let (name, clock_rate, channels) = match value("rtpmap") {
+                    Some(rtpmap) => {
+                        let mut parts = rtpmap.split('/');
+                        let name = parts.next()?.to_owned();
+                        let clock_rate = parts.next()?.parse().ok()?;
+                        (name, clock_rate, parts.next().and_then(|channels| channels.parse().ok()))
+                    }
+                    None => {
+                        let (name, clock_rate) = SdpCodec::static_payload_type(payload_type)?;
+                        (name.to_owned(), clock_rate, None)
+                    }
+                };
+                Some(SdpCodec {
+                    payload_type: payload_type,
+                    name: name,
+                    clock_rate: clock_rate,
+                    channels: channels,
+                    parameters: parameters,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A codec of a RTP payload type
+pub struct SdpCodec {
+    /// The RTP payload type
+    pub payload_type: u8,
+
+    /// The encoding name, like "PCMU"
+    pub name: String,
+
+    /// The RTP clock rate in Hz
+    pub clock_rate: u32,
+
+    /// The number of audio channels, if given
+    pub channels: Option<u8>,
+
+    /// The format specific parameters, like "0-16" for "telephone-event"
+    pub parameters: Option<String>,
+}
+
+impl SdpCodec {
+    /// Get the encoding name and clock rate of a static RTP payload type (RFC 3551)
+    pub fn static_payload_type(payload_type: u8) -> Option<(&'static str, u32)> {
+        match payload_type {
+            0 => Some(("PCMU", 8000)),
+            3 => Some(("GSM", 8000)),
+            4 => Some(("G723", 8000)),
+            5 => Some(("DVI4", 8000)),
+            6 => Some(("DVI4", 16_000)),
+            7 => Some(("LPC", 8000)),
+            8 => Some(("PCMA", 8000)),
+            9 => Some(("G722", 8000)),
+            10 | 11 => Some(("L16", 44_100)),
+            12 => Some(("QCELP", 8000)),
+            13 => Some(("CN", 8000)),
+            14 => Some(("MPA", 90_000)),
+            15 => Some(("G728", 8000)),
+            16 => Some(("DVI4", 11_025)),
+            17 => Some(("DVI4", 22_050)),
+            18 => Some(("G729", 8000)),
+            25 => Some(("CelB", 90_000)),
+            26 => Some(("JPEG", 90_000)),
+            28 => Some(("nv", 90_000)),
+            31 => Some(("H261", 90_000)),
+            32 => Some(("MPV", 90_000)),
+            33 => Some(("MP2T", 90_000)),
+            34 => Some(("H263", 90_000)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The expected destination of the RTP and RTCP packets of a media stream
+pub struct SdpMediaEndpoint {
+    /// The media type, like "audio"
+    pub media: String,
+
+    /// The address of the receiver
+    pub address: IpAddr,
+
+    /// The RTP port
+    pub rtp_port: u16,
+
+    /// The RTCP port, which is the next higher port unless signalled otherwise
+    pub rtcp_port: Option<u16>,
+}
@@ -0,0 +1,793 @@
+//! Session Initiation Protocol related packet processing
+use prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The maximum size of an incomplete message buffered for a TCP connection
+const MAX_BUFFER_SIZE: usize = 65_536;
+
+/// The maximum amount of calls with an offered session description waiting for the answer
+const MAX_OFFERS: usize = 1024;
+
+/// The maximum amount of predicted media endpoints, where the oldest ones are dropped first
+const MAX_MEDIA_ENDPOINTS: usize = 4096;
+
+/// The compact forms of header names with their full names
+const COMPACT_FORMS: &[(&str, &str)] = &[
+    ("a", "Accept-Contact"),
+    ("b", "Referred-By"),
+    ("c", "Content-Type"),
+    ("d", "Request-Disposition"),
+    ("e", "Content-Encoding"),
+    ("f", "From"),
+    ("i", "Call-ID"),
+    ("j", "Reject-Contact"),
+    ("k", "Supported"),
+    ("l", "Content-Length"),
+    ("m", "Contact"),
+    ("n", "Identity-Info"),
+    ("o", "Event"),
+    ("r", "Refer-To"),
+    ("s", "Subject"),
+    ("t", "To"),
+    ("u", "Allow-Events"),
+    ("v", "Via"),
+    ("x", "Session-Expires"),
+    ("y", "Identity"),
+];
+
+/// The SIP parser, which decodes UDP datagrams and TCP streams
+#[derive(Debug)]
+pub struct SipParser {
+    /// The ports on which UDP datagrams and TCP streams are decoded as SIP messages
+    pub ports: HashSet<u16>,
+
+    /// The media endpoints of the established calls, which may be shared with the RTP parser
+    pub media_endpoints: SipMediaEndpoints,
+
+    /// The data of incomplete messages per TCP connection and direction
+    buffers: TcpStreams<Vec<u8>>,
+
+    /// The offered media endpoints with their number per call, which wait for the answer
+    offers: HashMap<String, (Vec<SdpMediaEndpoint>, u64)>,
+
+    /// The number of the next offer
+    next_offer: u64,
+}
+
+impl SipParser {
+    /// Create a new SIP parser for the port 5060
+    pub fn new() -> Self {
+        SipParser {
+            ports: [5060].iter().cloned().collect(),
+            media_endpoints: SipMediaEndpoints::new(),
+            buffers: TcpStreams::new(),
+            offers: HashMap::new(),
+            next_offer: 0,
+        }
+    }
+
+    /// Follow the offer and answer of the session descriptions, where the media endpoints of a
+    /// call are predicted once the INVITE is answered successfully and dropped at the end of it
+    fn follow_call(&mut self, message: &SipMessage) {
+        let call_id = match message.call_id() {
+            Some(call_id) => call_id.to_owned(),
+            None => return,
+        };
+        let method = message.cseq().map(|cseq| cseq.method);
+        match (message.method(), message.status_code(), method) {
+            (Some(&SipMethod::Invite), _, _) => {
+                let endpoints = message.media_endpoints();
+                if endpoints.is_empty() {
+                    return;
+                }
+                if self.offers.len() >= MAX_OFFERS && !self.offers.contains_key(&call_id) {
+                    let oldest = self.offers
+                        .iter()
+                        .min_by_key(|&(_, &(_, number))| number)
+                        .map(|(call_id, _)| call_id.clone());
+                    if let Some(oldest) = oldest {
+                        debug!("Dropping the offer of SIP call {}", oldest);
+                        self.offers.remove(&oldest);
+                    }
+                }
+                self.offers.insert(call_id, (endpoints, self.next_offer));
+                self.next_offer += 1;
+            }
+
+            // An offer within the successful response is answered by the ACK
+            (Some(&SipMethod::Ack), _, _) => self.media_endpoints.insert(&call_id, &message.media_endpoints()),
+
+            (Some(&SipMethod::Bye), _, _) | (Some(&SipMethod::Cancel), _, _) => {
+                self.offers.remove(&call_id);
+                self.media_endpoints.remove(&call_id);
+            }
+
+            (None, Some(200..=299), Some(SipMethod::Invite)) => {
+                let mut endpoints = self.offers
+                    .remove(&call_id)
+                    .map(|(endpoints, _)| endpoints)
+                    .unwrap_or_default();
+                endpoints.extend(message.media_endpoints());
+
+                // A re-INVITE replaces the session of the call
+                self.media_endpoints.remove(&call_id);
+                self.media_endpoints.insert(&call_id, &endpoints);
+            }
+
+            (None, Some(300..=699), Some(SipMethod::Invite)) => {
+                self.offers.remove(&call_id);
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Decode the messages of a TCP segment, where incomplete messages are buffered until the
+    /// next segment of the same direction
+    fn parse_stream(buffer: &mut Vec<u8>, input: &[u8]) -> Option<Vec<SipMessage>> {
+        buffer.extend_from_slice(input);
+
+        let mut messages = vec![];
+        let mut rest = &buffer[..];
+        while !rest.is_empty() {
+            match SipMessage::parse(rest, true) {
+                IResult::Done(remaining, message) => {
+                    messages.push(message);
+                    rest = remaining;
+                }
+                IResult::Incomplete(_) => break,
+                IResult::Error(_) => return None,
+            }
+        }
+
+        if rest.len() > MAX_BUFFER_SIZE {
+            debug!("Dropping incomplete SIP message of {} bytes", rest.len());
+            return None;
+        }
+        let position = buffer.len() - rest.len();
+        buffer.drain(..position);
+        Some(messages)
+    }
+}
+
+impl Default for SipParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for SipParser {
+    /// Parse a `SipPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let messages = match result.and_then(|vector| vector.last()) {
+            Some(any) => match (any.downcast_ref::<UdpPacket>(), any.downcast_ref::<TcpPacket>()) {
+                (Some(udp), _) => {
+                    if !self.ports.contains(&udp.header.source_port) && !self.ports.contains(&udp.header.dest_port) {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    SipMessage::parse(input, false).to_full_result().ok().map(|message| vec![message])
+                }
+                (_, Some(tcp)) => {
+                    if (!self.ports.contains(&tcp.header.source_port) && !self.ports.contains(&tcp.header.dest_port))
+                        || input.is_empty()
+                    {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    self.buffers.process(result, tcp, true, |buffer| SipParser::parse_stream(buffer, input))
+                }
+
+                // Previous result found, but not correct parent
+                _ => None,
+            },
+
+            // Parse also if no result is given, for testability
+            None => SipMessage::parse(input, false).to_full_result().ok().map(|message| vec![message]),
+        };
+
+        if let Some(ref messages) = messages {
+            for message in messages {
+                self.follow_call(message);
+            }
+        }
+
+        match messages {
+            Some(messages) => IResult::Done(&input[input.len()..], Box::new(SipPacket { messages: messages })),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for SipParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SIP")
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// The predicted media endpoints of the established SIP calls, which are the destinations of their
+/// RTP and RTCP packets. Clones share the same endpoints, so that the RTP parser can decode the
+/// media streams of the calls followed by the SIP parser.
+pub struct SipMediaEndpoints(Arc<Mutex<SipMediaEndpointMap>>);
+
+#[derive(Debug, Default)]
+/// The call identifier with its number per address and port
+struct SipMediaEndpointMap {
+    endpoints: HashMap<(IpAddr, u16), (String, u64)>,
+    next: u64,
+}
+
+impl SipMediaEndpoints {
+    /// Create a new empty set of media endpoints
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock<'a>(&'a self) -> MutexGuard<'a, SipMediaEndpointMap> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Add the RTP and RTCP endpoints of a call
+    pub fn insert(&self, call_id: &str, endpoints: &[SdpMediaEndpoint]) {
+        let mut map = self.lock();
+        for endpoint in endpoints {
+            for &port in Some(endpoint.rtp_port).iter().chain(&endpoint.rtcp_port) {
+                let key = (endpoint.address, port);
+                if map.endpoints.len() >= MAX_MEDIA_ENDPOINTS && !map.endpoints.contains_key(&key) {
+                    let oldest = map.endpoints
+                        .iter()
+                        .min_by_key(|&(_, &(_, number))| number)
+                        .map(|(key, _)| *key);
+                    if let Some(oldest) = oldest {
+                        debug!("Dropping the media endpoint {}:{}", oldest.0, oldest.1);
+                        map.endpoints.remove(&oldest);
+                    }
+                }
+                let number = map.next;
+                map.endpoints.insert(key, (call_id.to_owned(), number));
+                map.next += 1;
+            }
+        }
+    }
+
+    /// Remove all endpoints of a call
+    pub fn remove(&self, call_id: &str) {
+        self.lock().endpoints.retain(|_, entry| entry.0 != call_id);
+    }
+
+    /// Get the identifier of the call which expects media streams on the address and port
+    pub fn call_id(&self, address: IpAddr, port: u16) -> Option<String> {
+        self.lock()
+            .endpoints
+            .get(&(address, port))
+            .map(|entry| entry.0.clone())
+    }
+
+    /// Check if a call expects media streams on the address and port
+    pub fn contains(&self, address: IpAddr, port: u16) -> bool {
+        self.lock().endpoints.contains_key(&(address, port))
+    }
+
+    /// Get the amount of predicted endpoints
+    pub fn len(&self) -> usize {
+        self.lock().endpoints.len()
+    }
+
+    /// Check if no endpoints are predicted
+    pub fn is_empty(&self) -> bool {
+        self.lock().endpoints.is_empty()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The SIP messages of a UDP datagram or TCP segment
+pub struct SipPacket {
+    /// The decoded messages, where a TCP segment may contain none or multiple messages
+    pub messages: Vec<SipMessage>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a SIP request or response
+pub struct SipMessage {
+    /// The request or status line
+    pub start_line: SipStartLine,
+
+    /// The header fields in their order, where names may be in their compact form
+    pub headers: Vec<HttpHeader>,
+
+    /// The message body
+    pub body: Vec<u8>,
+}
+
+impl SipMessage {
+    /// Parse a message, where the body of stream messages is delimited by the Content-Length
+    /// header and datagram messages contain the remaining data as body
+    fn parse(input: &[u8], stream: bool) -> IResult<&[u8], SipMessage> {
+        // Empty lines are used as keep-alive between messages
+        let start = input
+            .iter()
+            .position(|&c| c != b'\r' && c != b'\n')
+            .unwrap_or(input.len());
+        let input = &input[start..];
+        if input.is_empty() {
+            return IResult::Incomplete(Needed::Unknown);
+        }
+
+        let (rest, line) = try_parse!(input, HttpHeader::parse_line);
+        let start_line = match SipStartLine::parse(line) {
+            Some(start_line) => start_line,
+            None => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        };
+        let (rest, headers) = try_parse!(rest, HttpHeader::parse);
+        let mut message = SipMessage {
+            start_line: start_line,
+            headers: headers,
+            body: vec![],
+        };
+
+        let length = match message.header("Content-Length") {
+            Some(length) => match length.parse::<usize>() {
+                Ok(length) => Some(length),
+                Err(_) => return IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+            },
+            None => None,
+        };
+        let length = match (length, stream) {
+            // The length is mandatory for streams
+            (Some(length), true) => length,
+            (None, true) => 0,
+            (Some(length), false) => length.min(rest.len()),
+            (None, false) => rest.len(),
+        };
+        if rest.len() < length {
+            return IResult::Incomplete(Needed::Size(length - rest.len()));
+        }
+        message.body = rest[..length].to_vec();
+        IResult::Done(if stream { &rest[length..] } else { &rest[rest.len()..] }, message)
+    }
+
+    /// Get the value of the first header with the given name, ignoring the case and the compact
+    /// form of the name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| SipMessage::is_header(&header.key, name))
+            .map(|header| header.value.as_str())
+    }
+
+    /// Get the values of all headers with the given name in their order, ignoring the case and
+    /// the compact form of the name
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|header| SipMessage::is_header(&header.key, name))
+            .map(|header| header.value.as_str())
+            .collect()
+    }
+
+    fn is_header(key: &str, name: &str) -> bool {
+        key.eq_ignore_ascii_case(name)
+            || COMPACT_FORMS.iter().any(|&(compact, full)| {
+                full.eq_ignore_ascii_case(name) && compact.eq_ignore_ascii_case(key)
+                    || compact.eq_ignore_ascii_case(name) && full.eq_ignore_ascii_case(key)
+            })
+    }
+
+    /// Get the request method, which is None for responses
+    pub fn method(&self) -> Option<&SipMethod> {
+        match self.start_line {
+            SipStartLine::Request { ref method, .. } => Some(method),
+            SipStartLine::Response { .. } => None,
+        }
+    }
+
+    /// Get the status code, which is None for requests
+    pub fn status_code(&self) -> Option<u16> {
+        match self.start_line {
+            SipStartLine::Request { .. } => None,
+            SipStartLine::Response { code, .. } => Some(code),
+        }
+    }
+
+    /// Get the identifier of the call
+    pub fn call_id(&self) -> Option<&str> {
+        self.header("Call-ID")
+    }
+
+    /// Get the sequence number and method of the CSeq header
+    pub fn cseq(&self) -> Option<SipCSeq> {
+        let mut parts = self.header("CSeq")?.split_whitespace();
+        let sequence = parts.next()?.parse().ok()?;
+        let method = SipMethod::from_token(parts.next()?);
+        Some(SipCSeq {
+            sequence: sequence,
+            method: method,
+        })
+    }
+
+    /// Get the initiator of the request
+    pub fn from(&self) -> Option<SipNameAddress> {
+        self.header("From").and_then(SipNameAddress::parse)
+    }
+
+    /// Get the recipient of the request
+    pub fn to(&self) -> Option<SipNameAddress> {
+        self.header("To").and_then(SipNameAddress::parse)
+    }
+
+    /// Get the contacts of all Contact headers, where the wildcard "*" is omitted
+    pub fn contacts(&self) -> Vec<SipNameAddress> {
+        self.header_values("Contact")
+            .into_iter()
+            .flat_map(SipMessage::split_list)
+            .filter(|value| value != "*")
+            .filter_map(|value| SipNameAddress::parse(&value))
+            .collect()
+    }
+
+    /// Get the hops of all Via headers, starting at the most recent hop
+    pub fn via(&self) -> Vec<SipVia> {
+        self.header_values("Via")
+            .into_iter()
+            .flat_map(SipMessage::split_list)
+            .filter_map(|value| SipVia::parse(&value))
+            .collect()
+    }
+
+    /// Get the session description of the body
+    pub fn sdp(&self) -> Option<SdpSession> {
+        let content_type = self.header("Content-Type")?;
+        if !content_type.trim().to_ascii_lowercase().starts_with("application/sdp") {
+            return None;
+        }
+        SdpSession::decode(&self.body)
+    }
+
+    /// Get the addresses and ports on which the sender of the session description expects the
+    /// RTP and RTCP packets of the call
+    pub fn media_endpoints(&self) -> Vec<SdpMediaEndpoint> {
+        self.sdp()
+            .map(|sdp| sdp.media_endpoints())
+            .unwrap_or_default()
+    }
+
+    /// Split a comma separated header value, where commas within quotes and angle brackets are
+    /// retained
+    fn split_list(value: &str) -> Vec<String> {
+        let mut values = vec![];
+        let mut current = String::new();
+        let (mut quoted, mut bracketed) = (false, false);
+        for c in value.chars() {
+            match c {
+                '"' if !bracketed => quoted = !quoted,
+                '<' if !quoted => bracketed = true,
+                '>' if !quoted => bracketed = false,
+                ',' if !quoted && !bracketed => {
+                    values.push(current.trim().to_owned());
+                    current.clear();
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        values.push(current.trim().to_owned());
+        values.retain(|value| !value.is_empty());
+        values
+    }
+
+    /// Split the parameters after a semicolon, like ";tag=1928301774;lr"
+    fn parse_params(params: &str) -> Vec<(String, Option<String>)> {
+        params
+            .split(';')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(|param| match param.find('=') {
+                Some(equals) => (
+                    param[..equals].trim().to_owned(),
+                    Some(param[equals + 1..].trim().trim_matches('"').to_owned()),
+                ),
+                None => (param.to_owned(), None),
+            })
+            .collect()
+    }
+
+    fn param<'a>(params: &'a [(String, Option<String>)], name: &str) -> Option<&'a str> {
+        params
+            .iter()
+            .find(|&&(ref key, _)| key.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_ref().map_or("", |value| value.as_str()))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The first line of a SIP message
+pub enum SipStartLine {
+    /// The request line, like "INVITE sip:bob@biloxi.com SIP/2.0"
+    Request {
+        /// The request method
+        method: SipMethod,
+
+        /// The request URI
+        uri: String,
+
+        /// The protocol version, like "SIP/2.0"
+        version: String,
+    },
+
+    /// The status line, like "SIP/2.0 200 OK"
+    Response {
+        /// The protocol version, like "SIP/2.0"
+        version: String,
+
+        /// The status code
+        code: u16,
+
+        /// The reason phrase
+        reason: String,
+    },
+}
+
+impl SipStartLine {
+    fn parse(line: &[u8]) -> Option<SipStartLine> {
+        let line = str::from_utf8(line).ok()?;
+        if line.starts_with("SIP/") {
+            let mut parts = line.splitn(3, ' ');
+            let version = parts.next()?;
+            let code = parts.next()?;
+            if code.len() != 3 || !code.bytes().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            Some(SipStartLine::Response {
+                version: version.to_owned(),
+                code: code.parse().ok()?,
+                reason: parts.next().unwrap_or("").to_owned(),
+            })
+        } else {
+            let parts: Vec<&str> = line.split(' ').collect();
+            if parts.len() != 3 || !parts[2].starts_with("SIP/") || parts[0].is_empty() || parts[1].is_empty() {
+                return None;
+            }
+            if !parts[0].bytes().all(|c| c.is_ascii_alphanumeric() || b"-.!%*_+`'~".contains(&c)) {
+                return None;
+            }
+            Some(SipStartLine::Request {
+                method: SipMethod::from_token(parts[0]),
+                uri: parts[1].to_owned(),
+                version: parts[2].to_owned(),
+            })
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The SIP request method
+pub enum SipMethod {
+    /// Initiate a session
+    Invite,
+
+    /// Confirm the final response to an INVITE
+    Ack,
+
+    /// Terminate a session
+    Bye,
+
+    /// Cancel a pending request
+    Cancel,
+
+    /// Register the contact addresses of a user
+    Register,
+
+    /// Query the capabilities
+    Options,
+
+    /// Acknowledge a provisional response
+    Prack,
+
+    /// Subscribe to an event
+    Subscribe,
+
+    /// Notify about an event
+    Notify,
+
+    /// Publish an event state
+    Publish,
+
+    /// Send information within a session
+    Info,
+
+    /// Ask the recipient to contact a third party
+    Refer,
+
+    /// Send an instant message
+    Message,
+
+    /// Modify a session before it is established
+    Update,
+
+    /// Any other method
+    Extension(String),
+}
+
+impl SipMethod {
+    /// Get the method for a token, where the method names are case-sensitive
+    pub fn from_token(token: &str) -> Self {
+        match token {
+            "INVITE" => SipMethod::Invite,
+            "ACK" => SipMethod::Ack,
+            "BYE" => SipMethod::Bye,
+            "CANCEL" => SipMethod::Cancel,
+            "REGISTER" => SipMethod::Register,
+            "OPTIONS" => SipMethod::Options,
+            "PRACK" => SipMethod::Prack,
+            "SUBSCRIBE" => SipMethod::Subscribe,
+            "NOTIFY" => SipMethod::Notify,
+            "PUBLISH" => SipMethod::Publish,
+            "INFO" => SipMethod::Info,
+            "REFER" => SipMethod::Refer,
+            "MESSAGE" => SipMethod::Message,
+            "UPDATE" => SipMethod::Update,
+            _ => SipMethod::Extension(token.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for SipMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SipMethod::Invite => write!(f, "INVITE"),
+            SipMethod::Ack => write!(f, "ACK"),
+            SipMethod::Bye => write!(f, "BYE"),
+            SipMethod::Cancel => write!(f, "CANCEL"),
+            SipMethod::Register => write!(f, "REGISTER"),
+            SipMethod::Options => write!(f, "OPTIONS"),
+            SipMethod::Prack => write!(f, "PRACK"),
+            SipMethod::Subscribe => write!(f, "SUBSCRIBE"),
+            SipMethod::Notify => write!(f, "NOTIFY"),
+            SipMethod::Publish => write!(f, "PUBLISH"),
+            SipMethod::Info => write!(f, "INFO"),
+            SipMethod::Refer => write!(f, "REFER"),
+            SipMethod::Message => write!(f, "MESSAGE"),
+            SipMethod::Update => write!(f, "UPDATE"),
+            SipMethod::Extension(ref method) => write!(f, "{}", method),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The command sequence of a request
+pub struct SipCSeq {
+    /// The sequence number within the call
+    pub sequence: u32,
+
+    /// The method of the request
+    pub method: SipMethod,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// An address with an optional display name, as used by the From, To and Contact headers
+pub struct SipNameAddress {
+    /// The display name, like "Alice"
+    pub display_name: Option<String>,
+
+    /// The URI, like "sip:alice@atlanta.com"
+    pub uri: String,
+
+    /// The header parameters, like "tag"
+    pub params: Vec<(String, Option<String>)>,
+}
+
+impl SipNameAddress {
+    fn parse(value: &str) -> Option<SipNameAddress> {
+        let value = value.trim();
+        let (display_name, uri, params) = match value.find('<') {
+            Some(start) => {
+                let end = start + value[start..].find('>')?;
+                let display_name = value[..start].trim().trim_matches('"').trim();
+                let display_name = if display_name.is_empty() {
+                    None
+                } else {
+                    Some(display_name.to_owned())
+                };
+                (display_name, &value[start + 1..end], &value[end + 1..])
+            }
+
+            // Without angle brackets, the parameters belong to the header
+            None => match value.find(';') {
+                Some(semicolon) => (None, &value[..semicolon], &value[semicolon..]),
+                None => (None, value, ""),
+            },
+        };
+        if uri.is_empty() {
+            return None;
+        }
+        Some(SipNameAddress {
+            display_name: display_name,
+            uri: uri.trim().to_owned(),
+            params: SipMessage::parse_params(params),
+        })
+    }
+
+    /// Get the tag, which identifies the dialog together with the Call-ID
+    pub fn tag(&self) -> Option<&str> {
+        SipMessage::param(&self.params, "tag")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A hop of the Via header
+pub struct SipVia {
+    /// The protocol with its version, like "SIP/2.0"
+    pub protocol: String,
+
+    /// The transport protocol, like "UDP"
+    pub transport: String,
+
+    /// The host name or address to which responses are sent
+    pub host: String,
+
+    /// The port to which responses are sent
+    pub port: Option<u16>,
+
+    /// The parameters, like "branch" or "received"
+    pub params: Vec<(String, Option<String>)>,
+}
+
+impl SipVia {
+    fn parse(value: &str) -> Option<SipVia> {
+        let value = value.trim();
+        let slash = value[..value.find(';').unwrap_or(value.len())].rfind('/')?;
+        let space = slash + value[slash..].find(|c: char| c.is_whitespace())?;
+        let protocol = value[..slash].split_whitespace().collect::<Vec<_>>().join("");
+        let transport = value[slash + 1..space].trim().to_owned();
+
+        let rest = value[space..].trim_start();
+        let (sent_by, params) = match rest.find(';') {
+            Some(semicolon) => (rest[..semicolon].trim(), &rest[semicolon..]),
+            None => (rest.trim(), ""),
+        };
+
+        // IPv6 references are enclosed in brackets, like "[2001:db8::9:1]:5060"
+        let (host, port) = if sent_by.starts_with('[') {
+            let end = sent_by.find(']')?;
+            (&sent_by[..end + 1], sent_by[end + 1..].strip_prefix(':'))
+        } else {
+            match sent_by.rfind(':') {
+                Some(colon) => (&sent_by[..colon], Some(&sent_by[colon + 1..])),
+                None => (sent_by, None),
+            }
+        };
+        if host.is_empty() || transport.is_empty() {
+            return None;
+        }
+        let port = match port {
+            Some(port) => Some(port.trim().parse().ok()?),
+            None => None,
+        };
+        Some(SipVia {
+            protocol: protocol,
+            transport: transport,
+            host: host.to_owned(),
+            port: port,
+            params: SipMessage::parse_params(params),
+        })
+    }
+
+    /// Get the branch, which identifies the transaction
+    pub fn branch(&self) -> Option<&str> {
+        SipMessage::param(&self.params, "branch")
+    }
+
+    /// Get the address from which the request was received, if it differs from the host
+    pub fn received(&self) -> Option<&str> {
+        SipMessage::param(&self.params, "received")
+    }
+}
@@ -0,0 +1,721 @@
+//! Simple Network Management Protocol related packet processing
+use prelude::*;
+
+/// The UDP port of SNMP agents
+const AGENT_PORT: u16 = 161;
+
+/// The UDP port of SNMP trap receivers
+const TRAP_PORT: u16 = 162;
+
+/// The SNMP parser
+#[derive(Debug)]
+pub struct SnmpParser;
+
+impl Parsable<PathIp> for SnmpParser {
+    /// Parse a `SnmpPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        // Check the transport protocol and ports from the parent parser (UDP)
+        let valid = match result {
+            Some(vector) => match vector.last().and_then(|any| any.downcast_ref::<UdpPacket>()) {
+                Some(udp) => {
+                    let ports = [udp.header.source_port, udp.header.dest_port];
+                    ports.contains(&AGENT_PORT) || ports.contains(&TRAP_PORT)
+                }
+                None => false,
+            },
+            // Parse also if no result is given, for testability
+            None => true,
+        };
+        if !valid {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        match BerElement::parse(input).and_then(|(message, rest)| Some((SnmpPacket::decode(&message)?, rest))) {
+            Some((packet, rest)) => IResult::Done(rest, Box::new(packet)),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for SnmpParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SNMP")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a SNMP message
+pub struct SnmpPacket {
+    /// The protocol version
+    pub version: SnmpVersion,
+
+    /// The community of version 1 and 2c or the security parameters of version 3
+    pub security: SnmpSecurity,
+
+    /// The PDU, which is encrypted for version 3 messages with privacy
+    pub data: SnmpData,
+}
+
+impl SnmpPacket {
+    /// Decode the message from its outer SEQUENCE
+    fn decode(message: &BerElement) -> Option<SnmpPacket> {
+        if !message.tag.is_universal(16) {
+            return None;
+        }
+        let fields = message.children()?;
+        let version = SnmpVersion::from_i64(SnmpPacket::integer(fields.first()?)?)?;
+
+        let (security, data) = match version {
+            SnmpVersion::V1 | SnmpVersion::V2c => {
+                if fields.len() != 3 {
+                    return None;
+                }
+                let community = String::from_utf8_lossy(SnmpPacket::octet_string(&fields[1])?).into_owned();
+                (SnmpSecurity::Community(community), SnmpData::Pdu(SnmpPdu::decode(&fields[2])?))
+            }
+            SnmpVersion::V3 => {
+                if fields.len() != 4 {
+                    return None;
+                }
+                let security = SnmpV3Security::decode(&fields[1], SnmpPacket::octet_string(&fields[2])?)?;
+                let data = if fields[3].tag.is_universal(4) {
+                    SnmpData::Encrypted(fields[3].content.to_vec())
+                } else if fields[3].tag.is_universal(16) {
+                    let scoped = fields[3].children()?;
+                    if scoped.len() != 3 {
+                        return None;
+                    }
+                    SnmpData::ScopedPdu {
+                        context_engine_id: SnmpPacket::octet_string(&scoped[0])?.to_vec(),
+                        context_name: String::from_utf8_lossy(SnmpPacket::octet_string(&scoped[1])?).into_owned(),
+                        pdu: SnmpPdu::decode(&scoped[2])?,
+                    }
+                } else {
+                    return None;
+                };
+                (SnmpSecurity::V3(security), data)
+            }
+        };
+
+        Some(SnmpPacket {
+            version: version,
+            security: security,
+            data: data,
+        })
+    }
+
+    /// Get the PDU, unless it is encrypted
+    pub fn pdu(&self) -> Option<&SnmpPdu> {
+        match self.data {
+            SnmpData::Pdu(ref pdu) | SnmpData::ScopedPdu { ref pdu, .. } => Some(pdu),
+            SnmpData::Encrypted(_) => None,
+        }
+    }
+
+    /// Get the community of version 1 and 2c messages
+    pub fn community(&self) -> Option<&str> {
+        match self.security {
+            SnmpSecurity::Community(ref community) => Some(community),
+            SnmpSecurity::V3(_) => None,
+        }
+    }
+
+    fn octet_string<'a>(element: &BerElement<'a>) -> Option<&'a [u8]> {
+        if element.tag.is_universal(4) && !element.tag.constructed {
+            Some(element.content)
+        } else {
+            None
+        }
+    }
+
+    fn integer(element: &BerElement) -> Option<i64> {
+        if element.tag.is_universal(2) {
+            element.integer()
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The SNMP version
+pub enum SnmpVersion {
+    /// SNMPv1
+    V1,
+
+    /// Community-based SNMPv2
+    V2c,
+
+    /// SNMPv3
+    V3,
+}
+
+impl SnmpVersion {
+    /// Convert an integer to a `SnmpVersion`. Returns None if the version is unknown.
+    pub fn from_i64(input: i64) -> Option<SnmpVersion> {
+        match input {
+            0 => Some(SnmpVersion::V1),
+            1 => Some(SnmpVersion::V2c),
+            3 => Some(SnmpVersion::V3),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The security information of a message
+pub enum SnmpSecurity {
+    /// The community string of version 1 and 2c
+    Community(String),
+
+    /// The header and security parameters of version 3
+    V3(SnmpV3Security),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The header and security parameters of a SNMPv3 message
+pub struct SnmpV3Security {
+    /// The message identifier to match requests and responses
+    pub message_id: i64,
+
+    /// The maximum message size the sender accepts
+    pub max_size: i64,
+
+    /// The message is authenticated
+    pub authenticated: bool,
+
+    /// The scoped PDU is encrypted
+    pub encrypted: bool,
+
+    /// The receiver should send a report on errors
+    pub reportable: bool,
+
+    /// The security model, where 3 is the user-based security model
+    pub security_model: i64,
+
+    /// The parameters of the user-based security model
+    pub usm: Option<SnmpUsm>,
+}
+
+impl SnmpV3Security {
+    fn decode(global: &BerElement, parameters: &[u8]) -> Option<SnmpV3Security> {
+        let global = global.children()?;
+        if global.len() != 4 {
+            return None;
+        }
+        let flags = *SnmpPacket::octet_string(&global[2])?.first()?;
+        let security_model = SnmpPacket::integer(&global[3])?;
+        Some(SnmpV3Security {
+            message_id: SnmpPacket::integer(&global[0])?,
+            max_size: SnmpPacket::integer(&global[1])?,
+            authenticated: flags & 0x01 != 0,
+            encrypted: flags & 0x02 != 0,
+            reportable: flags & 0x04 != 0,
+            security_model: security_model,
+            usm: if security_model == 3 {
+                SnmpUsm::decode(parameters)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The parameters of the user-based security model
+pub struct SnmpUsm {
+    /// The identifier of the authoritative engine
+    pub engine_id: Vec<u8>,
+
+    /// The number of times the authoritative engine was rebooted
+    pub engine_boots: i64,
+
+    /// The seconds since the last reboot of the authoritative engine
+    pub engine_time: i64,
+
+    /// The name of the user
+    pub user_name: String,
+
+    /// The message authentication code, which is empty for unauthenticated messages
+    pub authentication_parameters: Vec<u8>,
+
+    /// The salt of the encryption, which is empty for unencrypted messages
+    pub privacy_parameters: Vec<u8>,
+}
+
+impl SnmpUsm {
+    fn decode(parameters: &[u8]) -> Option<SnmpUsm> {
+        let (sequence, _) = BerElement::parse(parameters)?;
+        let fields = sequence.children()?;
+        if fields.len() != 6 {
+            return None;
+        }
+        Some(SnmpUsm {
+            engine_id: SnmpPacket::octet_string(&fields[0])?.to_vec(),
+            engine_boots: SnmpPacket::integer(&fields[1])?,
+            engine_time: SnmpPacket::integer(&fields[2])?,
+            user_name: String::from_utf8_lossy(SnmpPacket::octet_string(&fields[3])?).into_owned(),
+            authentication_parameters: SnmpPacket::octet_string(&fields[4])?.to_vec(),
+            privacy_parameters: SnmpPacket::octet_string(&fields[5])?.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The data of a message
+pub enum SnmpData {
+    /// The PDU of version 1 and 2c
+    Pdu(SnmpPdu),
+
+    /// The plaintext scoped PDU of version 3
+    ScopedPdu {
+        /// The engine which holds the management information
+        context_engine_id: Vec<u8>,
+
+        /// The context within the engine
+        context_name: String,
+
+        /// The PDU
+        pdu: SnmpPdu,
+    },
+
+    /// The encrypted scoped PDU of version 3
+    Encrypted(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The SNMP PDU type
+pub enum SnmpPduType {
+    /// Retrieve the values of variables
+    GetRequest,
+
+    /// Retrieve the values of the lexicographic successors of variables
+    GetNextRequest,
+
+    /// The response to a request
+    Response,
+
+    /// Modify the values of variables
+    SetRequest,
+
+    /// A SNMPv1 trap
+    Trap,
+
+    /// Retrieve the values of multiple successors of variables
+    GetBulkRequest,
+
+    /// A notification which is acknowledged by the receiver
+    InformRequest,
+
+    /// A SNMPv2 trap
+    TrapV2,
+
+    /// A report of SNMPv3 errors
+    Report,
+}
+
+impl SnmpPduType {
+    /// Convert a context-specific tag number to a `SnmpPduType`. Returns None if the type is
+    /// unknown.
+    pub fn from_u32(input: u32) -> Option<SnmpPduType> {
+        match input {
+            0 => Some(SnmpPduType::GetRequest),
+            1 => Some(SnmpPduType::GetNextRequest),
+            2 => Some(SnmpPduType::Response),
+            3 => Some(SnmpPduType::SetRequest),
+            4 => Some(SnmpPduType::Trap),
+            5 => Some(SnmpPduType::GetBulkRequest),
+            6 => Some(SnmpPduType::InformRequest),
+            7 => Some(SnmpPduType::TrapV2),
+            8 => Some(SnmpPduType::Report),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A SNMP protocol data unit
+pub enum SnmpPdu {
+    /// A request, response, SNMPv2 trap or report
+    Request {
+        /// The PDU type
+        pdu_type: SnmpPduType,
+
+        /// The identifier to match requests and responses
+        request_id: i64,
+
+        /// The error of a response
+        error_status: SnmpErrorStatus,
+
+        /// The index of the variable binding which caused the error, starting at 1
+        error_index: i64,
+
+        /// The variables with their values
+        variable_bindings: Vec<SnmpVariableBinding>,
+    },
+
+    /// A bulk request
+    GetBulkRequest {
+        /// The identifier to match requests and responses
+        request_id: i64,
+
+        /// The number of variables for which a single successor is returned
+        non_repeaters: i64,
+
+        /// The number of successors of the remaining variables
+        max_repetitions: i64,
+
+        /// The requested variables
+        variable_bindings: Vec<SnmpVariableBinding>,
+    },
+
+    /// A SNMPv1 trap
+    Trap {
+        /// The type of the object which generated the trap
+        enterprise: BerObjectIdentifier,
+
+        /// The address of the object which generated the trap
+        agent_address: Ipv4Addr,
+
+        /// The generic trap type
+        generic_trap: SnmpGenericTrap,
+
+        /// The enterprise specific trap code
+        specific_trap: i64,
+
+        /// The hundredths of seconds since the last initialization of the agent
+        timestamp: u32,
+
+        /// The variables with their values
+        variable_bindings: Vec<SnmpVariableBinding>,
+    },
+}
+
+impl SnmpPdu {
+    fn decode(pdu: &BerElement) -> Option<SnmpPdu> {
+        if pdu.tag.class != BerClass::ContextSpecific {
+            return None;
+        }
+        let pdu_type = SnmpPduType::from_u32(pdu.tag.number)?;
+        let fields = pdu.children()?;
+        if fields.len() != if pdu_type == SnmpPduType::Trap { 6 } else { 4 } {
+            return None;
+        }
+        let variable_bindings = SnmpVariableBinding::decode_list(&fields[fields.len() - 1])?;
+
+        Some(match pdu_type {
+            SnmpPduType::Trap => SnmpPdu::Trap {
+                enterprise: SnmpValue::object_identifier(&fields[0])?,
+                agent_address: match SnmpValue::decode(&fields[1])? {
+                    SnmpValue::IpAddress(address) => address,
+                    _ => return None,
+                },
+                generic_trap: SnmpGenericTrap::from_i64(SnmpPacket::integer(&fields[2])?),
+                specific_trap: SnmpPacket::integer(&fields[3])?,
+                timestamp: match SnmpValue::decode(&fields[4])? {
+                    SnmpValue::TimeTicks(ticks) => ticks,
+                    _ => return None,
+                },
+                variable_bindings: variable_bindings,
+            },
+            SnmpPduType::GetBulkRequest => SnmpPdu::GetBulkRequest {
+                request_id: SnmpPacket::integer(&fields[0])?,
+                non_repeaters: SnmpPacket::integer(&fields[1])?,
+                max_repetitions: SnmpPacket::integer(&fields[2])?,
+                variable_bindings: variable_bindings,
+            },
+            _ => SnmpPdu::Request {
+                pdu_type: pdu_type,
+                request_id: SnmpPacket::integer(&fields[0])?,
+                error_status: SnmpErrorStatus::from_i64(SnmpPacket::integer(&fields[1])?),
+                error_index: SnmpPacket::integer(&fields[2])?,
+                variable_bindings: variable_bindings,
+            },
+        })
+    }
+
+    /// Get the PDU type
+    pub fn pdu_type(&self) -> SnmpPduType {
+        match *self {
+            SnmpPdu::Request { pdu_type, .. } => pdu_type,
+            SnmpPdu::GetBulkRequest { .. } => SnmpPduType::GetBulkRequest,
+            SnmpPdu::Trap { .. } => SnmpPduType::Trap,
+        }
+    }
+
+    /// Get the variable bindings
+    pub fn variable_bindings(&self) -> &[SnmpVariableBinding] {
+        match *self {
+            SnmpPdu::Request {
+                ref variable_bindings, ..
+            }
+            | SnmpPdu::GetBulkRequest {
+                ref variable_bindings, ..
+            }
+            | SnmpPdu::Trap {
+                ref variable_bindings, ..
+            } => variable_bindings,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The error status of a response
+pub enum SnmpErrorStatus {
+    /// No error occurred
+    NoError,
+
+    /// The response would exceed the maximum message size
+    TooBig,
+
+    /// The variable does not exist (SNMPv1)
+    NoSuchName,
+
+    /// The value does not match the type of the variable
+    BadValue,
+
+    /// The variable can not be modified (SNMPv1)
+    ReadOnly,
+
+    /// An error which is not covered by another status
+    GenErr,
+
+    /// The variable is not accessible
+    NoAccess,
+
+    /// The type of the value is inconsistent with the variable
+    WrongType,
+
+    /// The length of the value is inconsistent with the variable
+    WrongLength,
+
+    /// The encoding of the value is inconsistent with the variable
+    WrongEncoding,
+
+    /// The value can never be assigned to the variable
+    WrongValue,
+
+    /// The variable does not exist and can not be created
+    NoCreation,
+
+    /// The value can not be assigned to the variable at the moment
+    InconsistentValue,
+
+    /// A resource required to assign the value is unavailable
+    ResourceUnavailable,
+
+    /// A set operation failed
+    CommitFailed,
+
+    /// A failed set operation could not be undone
+    UndoFailed,
+
+    /// The request was not authorized
+    AuthorizationError,
+
+    /// The variable can not be modified
+    NotWritable,
+
+    /// The variable can not be created at the moment
+    InconsistentName,
+
+    /// Any other error status
+    Other(i64),
+}
+
+impl SnmpErrorStatus {
+    /// Convert an integer to a `SnmpErrorStatus`
+    pub fn from_i64(input: i64) -> SnmpErrorStatus {
+        match input {
+            0 => SnmpErrorStatus::NoError,
+            1 => SnmpErrorStatus::TooBig,
+            2 => SnmpErrorStatus::NoSuchName,
+            3 => SnmpErrorStatus::BadValue,
+            4 => SnmpErrorStatus::ReadOnly,
+            5 => SnmpErrorStatus::GenErr,
+            6 => SnmpErrorStatus::NoAccess,
+            7 => SnmpErrorStatus::WrongType,
+            8 => SnmpErrorStatus::WrongLength,
+            9 => SnmpErrorStatus::WrongEncoding,
+            10 => SnmpErrorStatus::WrongValue,
+            11 => SnmpErrorStatus::NoCreation,
+            12 => SnmpErrorStatus::InconsistentValue,
+            13 => SnmpErrorStatus::ResourceUnavailable,
+            14 => SnmpErrorStatus::CommitFailed,
+            15 => SnmpErrorStatus::UndoFailed,
+            16 => SnmpErrorStatus::AuthorizationError,
+            17 => SnmpErrorStatus::NotWritable,
+            18 => SnmpErrorStatus::InconsistentName,
+            other => SnmpErrorStatus::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The generic trap type of a SNMPv1 trap
+pub enum SnmpGenericTrap {
+    /// The agent was reinitialized and its configuration may have changed
+    ColdStart,
+
+    /// The agent was reinitialized without changes of its configuration
+    WarmStart,
+
+    /// A communication link failed
+    LinkDown,
+
+    /// A communication link came up
+    LinkUp,
+
+    /// A message was not properly authenticated
+    AuthenticationFailure,
+
+    /// An EGP neighbor was marked down
+    EgpNeighborLoss,
+
+    /// An enterprise specific event, identified by the specific trap code
+    EnterpriseSpecific,
+
+    /// Any other trap type
+    Other(i64),
+}
+
+impl SnmpGenericTrap {
+    /// Convert an integer to a `SnmpGenericTrap`
+    pub fn from_i64(input: i64) -> SnmpGenericTrap {
+        match input {
+            0 => SnmpGenericTrap::ColdStart,
+            1 => SnmpGenericTrap::WarmStart,
+            2 => SnmpGenericTrap::LinkDown,
+            3 => SnmpGenericTrap::LinkUp,
+            4 => SnmpGenericTrap::AuthenticationFailure,
+            5 => SnmpGenericTrap::EgpNeighborLoss,
+            6 => SnmpGenericTrap::EnterpriseSpecific,
+            other => SnmpGenericTrap::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A variable with its value
+pub struct SnmpVariableBinding {
+    /// The object identifier of the variable
+    pub name: BerObjectIdentifier,
+
+    /// The value, which is null for requests
+    pub value: SnmpValue,
+}
+
+impl SnmpVariableBinding {
+    fn decode_list(list: &BerElement) -> Option<Vec<SnmpVariableBinding>> {
+        if !list.tag.is_universal(16) {
+            return None;
+        }
+        let mut variable_bindings = vec![];
+        for binding in list.children()? {
+            if !binding.tag.is_universal(16) {
+                return None;
+            }
+            let fields = binding.children()?;
+            if fields.len() != 2 {
+                return None;
+            }
+            variable_bindings.push(SnmpVariableBinding {
+                name: SnmpValue::object_identifier(&fields[0])?,
+                value: SnmpValue::decode(&fields[1])?,
+            });
+        }
+        Some(variable_bindings)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The value of a variable
+pub enum SnmpValue {
+    /// A signed integer
+    Integer(i64),
+
+    /// An octet string, which is often but not necessarily text
+    OctetString(Vec<u8>),
+
+    /// No value, as used within requests
+    Null,
+
+    /// An object identifier
+    ObjectIdentifier(BerObjectIdentifier),
+
+    /// An IPv4 address
+    IpAddress(Ipv4Addr),
+
+    /// A 32 bit counter, which wraps around
+    Counter32(u32),
+
+    /// A 32 bit value, which may increase or decrease
+    Gauge32(u32),
+
+    /// The hundredths of seconds since an epoch
+    TimeTicks(u32),
+
+    /// Arbitrary BER encoded data
+    Opaque(Vec<u8>),
+
+    /// A 64 bit counter, which wraps around
+    Counter64(u64),
+
+    /// The object does not exist in the agent
+    NoSuchObject,
+
+    /// The instance of the object does not exist in the agent
+    NoSuchInstance,
+
+    /// There are no further variables in the view of the agent
+    EndOfMibView,
+
+    /// Any other type by its tag
+    Other(BerTag, Vec<u8>),
+}
+
+impl SnmpValue {
+    fn decode(value: &BerElement) -> Option<SnmpValue> {
+        Some(match (value.tag.class, value.tag.constructed, value.tag.number) {
+            (BerClass::Universal, false, 2) => SnmpValue::Integer(value.integer()?),
+            (BerClass::Universal, false, 4) => SnmpValue::OctetString(value.content.to_vec()),
+            (BerClass::Universal, false, 5) => SnmpValue::Null,
+            (BerClass::Universal, false, 6) => SnmpValue::ObjectIdentifier(value.object_identifier()?),
+            (BerClass::Application, false, 0) if value.content.len() == 4 => SnmpValue::IpAddress(Ipv4Addr::new(
+                value.content[0],
+                value.content[1],
+                value.content[2],
+                value.content[3],
+            )),
+            (BerClass::Application, false, 1) => SnmpValue::Counter32(SnmpValue::unsigned32(value)?),
+            (BerClass::Application, false, 2) => SnmpValue::Gauge32(SnmpValue::unsigned32(value)?),
+            (BerClass::Application, false, 3) => SnmpValue::TimeTicks(SnmpValue::unsigned32(value)?),
+            (BerClass::Application, false, 4) => SnmpValue::Opaque(value.content.to_vec()),
+            (BerClass::Application, false, 6) => SnmpValue::Counter64(value.unsigned()?),
+            (BerClass::ContextSpecific, false, 0) => SnmpValue::NoSuchObject,
+            (BerClass::ContextSpecific, false, 1) => SnmpValue::NoSuchInstance,
+            (BerClass::ContextSpecific, false, 2) => SnmpValue::EndOfMibView,
+            _ => SnmpValue::Other(value.tag, value.content.to_vec()),
+        })
+    }
+
+    fn unsigned32(element: &BerElement) -> Option<u32> {
+        match element.unsigned()? {
+            value if value <= u32::MAX as u64 => Some(value as u32),
+            _ => None,
+        }
+    }
+
+    fn object_identifier(element: &BerElement) -> Option<BerObjectIdentifier> {
+        if element.tag.is_universal(6) {
+            element.object_identifier()
+        } else {
+            None
+        }
+    }
+}
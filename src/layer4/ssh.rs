@@ -0,0 +1,466 @@
+//! Secure Shell related packet processing
+use prelude::*;
+use std::collections::HashSet;
+
+use md5::{Digest, Md5};
+
+/// The maximum length of the identification string including the line break
+const MAX_IDENTIFICATION_LENGTH: usize = 255;
+
+/// The maximum length of a binary packet, which has to be supported by all implementations
+const MAX_PACKET_LENGTH: usize = 35_000;
+
+/// The algorithms which are broken or deprecated, like by RFC8758 and RFC9142
+const WEAK_ALGORITHMS: &[&str] = &[
+    "3des-cbc",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "des-cbc",
+    "diffie-hellman-group-exchange-sha1",
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group14-sha1",
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-md5-96-etm@openssh.com",
+    "hmac-md5-etm@openssh.com",
+    "hmac-sha1-96",
+    "hmac-sha1-96-etm@openssh.com",
+    "none",
+    "rijndael-cbc@lysator.liu.se",
+    "ssh-dss",
+    "ssh-rsa",
+];
+
+/// The SSH parser, which follows the unencrypted part of the connection up to the new keys
+#[derive(Debug)]
+pub struct SshParser {
+    /// The server ports on which TCP streams are decoded as SSH
+    pub ports: HashSet<u16>,
+
+    /// The state per TCP connection and direction
+    flows: TcpStreams<SshFlow>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+/// The state of a direction of a connection
+struct SshFlow {
+    /// The data of an incomplete line or binary packet
+    buffer: Vec<u8>,
+
+    /// The identification string was received
+    identified: bool,
+
+    /// The new keys are in use, so that the remaining data is encrypted
+    encrypted: bool,
+}
+
+impl SshParser {
+    /// Create a new SSH parser for the port 22
+    pub fn new() -> Self {
+        SshParser {
+            ports: [22].iter().cloned().collect(),
+            flows: TcpStreams::new(),
+        }
+    }
+
+    /// Decode the messages of a TCP segment, where incomplete lines and binary packets are
+    /// buffered until the next segment of the same direction
+    fn parse_stream(flow: &mut SshFlow, from_client: bool, input: &[u8]) -> Option<Vec<SshMessage>> {
+        if flow.encrypted {
+            return Some(vec![SshMessage::Encrypted(input.len())]);
+        }
+        flow.buffer.extend_from_slice(input);
+
+        let mut messages = vec![];
+        let mut offset = 0;
+        while offset < flow.buffer.len() {
+            let rest = &flow.buffer[offset..];
+            if flow.encrypted {
+                messages.push(SshMessage::Encrypted(rest.len()));
+                offset = flow.buffer.len();
+            } else if !flow.identified {
+                let line_length = match rest.iter().position(|&byte| byte == b'\n') {
+                    Some(position) => position + 1,
+                    None if rest.len() < MAX_IDENTIFICATION_LENGTH => break,
+                    None => return None,
+                };
+                let line = &rest[..line_length];
+                if line.starts_with(b"SSH-") {
+                    messages.push(SshMessage::Identification(SshIdentification::parse(line)?));
+                    flow.identified = true;
+                } else if from_client {
+                    // Only the server may send other lines before its identification
+                    return None;
+                }
+                offset += line_length;
+            } else {
+                match SshMessage::parse_binary_packet(rest) {
+                    IResult::Done(remaining, message) => {
+                        flow.encrypted = message == SshMessage::NewKeys;
+                        messages.push(message);
+                        offset += rest.len() - remaining.len();
+                    }
+                    IResult::Incomplete(_) => break,
+                    IResult::Error(_) => return None,
+                }
+            }
+        }
+        flow.buffer.drain(..offset);
+        Some(messages)
+    }
+}
+
+impl Default for SshParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for SshParser {
+    /// Parse a `SshPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        if input.is_empty() {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+        let packet = match result.and_then(|vector| vector.last()) {
+            Some(any) => match any.downcast_ref::<TcpPacket>() {
+                Some(tcp) => {
+                    let from_client = self.ports.contains(&tcp.header.dest_port);
+                    if !from_client && !self.ports.contains(&tcp.header.source_port) {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    let messages = self.flows
+                        .process(result, tcp, from_client, |flow| SshParser::parse_stream(flow, from_client, input));
+                    messages.map(|messages| SshPacket {
+                        from_client: from_client,
+                        messages: messages,
+                    })
+                }
+
+                // Previous result found, but not correct parent
+                None => None,
+            },
+
+            // Parse also if no result is given, for testability
+            None => {
+                let messages = SshParser::parse_stream(&mut SshFlow::default(), true, input);
+                messages.map(|messages| SshPacket {
+                    from_client: true,
+                    messages: messages,
+                })
+            }
+        };
+
+        match packet {
+            Some(packet) => IResult::Done(&input[input.len()..], Box::new(packet)),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for SshParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SSH")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The SSH messages of a TCP segment
+pub struct SshPacket {
+    /// The segment was sent from the client to the server
+    pub from_client: bool,
+
+    /// The decoded messages, where a segment may contain none or multiple messages
+    pub messages: Vec<SshMessage>,
+}
+
+impl SshPacket {
+    /// Get the key exchange initialization message
+    pub fn key_exchange_init(&self) -> Option<&SshKeyExchangeInit> {
+        self.messages
+            .iter()
+            .filter_map(|message| match *message {
+                SshMessage::KeyExchangeInit(ref kexinit) => Some(kexinit),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Get the HASSH fingerprint of a client or the HASSHServer fingerprint of a server, if the
+    /// segment contains the key exchange initialization
+    pub fn fingerprint(&self) -> Option<String> {
+        self.key_exchange_init().map(|kexinit| {
+            if self.from_client {
+                kexinit.hassh()
+            } else {
+                kexinit.hassh_server()
+            }
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A SSH message
+pub enum SshMessage {
+    /// The identification string, which is sent first by both sides
+    Identification(SshIdentification),
+
+    /// The algorithms supported for the key exchange (SSH_MSG_KEXINIT)
+    KeyExchangeInit(SshKeyExchangeInit),
+
+    /// The end of the key exchange, after which all messages are encrypted (SSH_MSG_NEWKEYS)
+    NewKeys,
+
+    /// The connection is closed (SSH_MSG_DISCONNECT)
+    Disconnect {
+        /// The reason code, like 2 for a protocol error
+        reason_code: u32,
+
+        /// The description of the reason
+        description: String,
+    },
+
+    /// Any other unencrypted message, like the key exchange method specific messages
+    Other {
+        /// The message number
+        message_type: u8,
+
+        /// The payload after the message number
+        payload: Vec<u8>,
+    },
+
+    /// The amount of encrypted data after the new keys
+    Encrypted(usize),
+}
+
+impl SshMessage {
+    // Parse an unencrypted binary packet without message authentication code
+    named!(parse_binary_packet<&[u8], SshMessage>,
+        do_parse!(
+            packet_length: verify!(be_u32, |length: u32| length as usize + 4 <= MAX_PACKET_LENGTH) >>
+            padding_length: verify!(be_u8, |length: u8| length >= 4 && u32::from(length) + 2 <= packet_length) >>
+            message: map_opt!(take!(packet_length - u32::from(padding_length) - 1), SshMessage::decode) >>
+            take!(padding_length) >>
+            (message)
+        )
+    );
+
+    named!(parse_disconnect<&[u8], SshMessage>,
+        do_parse!(
+            reason_code: be_u32 >>
+            description: call!(SshMessage::parse_string) >>
+            (SshMessage::Disconnect {
+                reason_code: reason_code,
+                description: String::from_utf8_lossy(description).into_owned(),
+            })
+        )
+    );
+
+    // Parse a length prefixed string
+    named!(parse_string<&[u8], &[u8]>, length_bytes!(be_u32));
+
+    fn decode(payload: &[u8]) -> Option<SshMessage> {
+        let (&message_type, data) = payload.split_first()?;
+        Some(match message_type {
+            1 => SshMessage::parse_disconnect(data).to_result().ok()?,
+            20 => SshMessage::KeyExchangeInit(SshKeyExchangeInit::parse(data).to_result().ok()?),
+            21 => SshMessage::NewKeys,
+            _ => SshMessage::Other {
+                message_type: message_type,
+                payload: data.to_vec(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The identification string, like "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13"
+pub struct SshIdentification {
+    /// The protocol version, like "2.0" or "1.99" for servers compatible with version 1
+    pub protocol_version: String,
+
+    /// The name and version of the software
+    pub software_version: String,
+
+    /// The optional comments after the software version
+    pub comments: Option<String>,
+}
+
+impl SshIdentification {
+    fn parse(line: &[u8]) -> Option<SshIdentification> {
+        let line = str::from_utf8(line).ok()?.trim_end_matches(&['\r', '\n'][..]);
+        let mut parts = line.splitn(3, '-').skip(1);
+        let protocol_version = parts.next()?;
+        let mut software = parts.next()?.splitn(2, ' ');
+        let software_version = software.next()?;
+        if protocol_version.is_empty() || software_version.is_empty() {
+            return None;
+        }
+        Some(SshIdentification {
+            protocol_version: protocol_version.to_owned(),
+            software_version: software_version.to_owned(),
+            comments: software.next().map(str::to_owned),
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The algorithms supported for the key exchange in the order of preference
+pub struct SshKeyExchangeInit {
+    /// The random value which makes the key exchange unique
+    pub cookie: [u8; 16],
+
+    /// The key exchange methods
+    pub kex_algorithms: Vec<String>,
+
+    /// The host key algorithms
+    pub server_host_key_algorithms: Vec<String>,
+
+    /// The ciphers from client to server
+    pub encryption_algorithms_client_to_server: Vec<String>,
+
+    /// The ciphers from server to client
+    pub encryption_algorithms_server_to_client: Vec<String>,
+
+    /// The message authentication codes from client to server
+    pub mac_algorithms_client_to_server: Vec<String>,
+
+    /// The message authentication codes from server to client
+    pub mac_algorithms_server_to_client: Vec<String>,
+
+    /// The compression algorithms from client to server
+    pub compression_algorithms_client_to_server: Vec<String>,
+
+    /// The compression algorithms from server to client
+    pub compression_algorithms_server_to_client: Vec<String>,
+
+    /// The languages from client to server
+    pub languages_client_to_server: Vec<String>,
+
+    /// The languages from server to client
+    pub languages_server_to_client: Vec<String>,
+
+    /// A guessed key exchange packet follows
+    pub first_kex_packet_follows: bool,
+}
+
+impl SshKeyExchangeInit {
+    named!(parse<&[u8], SshKeyExchangeInit>,
+        do_parse!(
+            cookie: take!(16) >>
+            kex_algorithms: call!(SshKeyExchangeInit::parse_name_list) >>
+            server_host_key_algorithms: call!(SshKeyExchangeInit::parse_name_list) >>
+            encryption_algorithms_client_to_server: call!(SshKeyExchangeInit::parse_name_list) >>
+            encryption_algorithms_server_to_client: call!(SshKeyExchangeInit::parse_name_list) >>
+            mac_algorithms_client_to_server: call!(SshKeyExchangeInit::parse_name_list) >>
+            mac_algorithms_server_to_client: call!(SshKeyExchangeInit::parse_name_list) >>
+            compression_algorithms_client_to_server: call!(SshKeyExchangeInit::parse_name_list) >>
+            compression_algorithms_server_to_client: call!(SshKeyExchangeInit::parse_name_list) >>
+            languages_client_to_server: call!(SshKeyExchangeInit::parse_name_list) >>
+            languages_server_to_client: call!(SshKeyExchangeInit::parse_name_list) >>
+            first_kex_packet_follows: be_u8 >>
+            (SshKeyExchangeInit {
+                cookie: {
+                    let mut array = [0; 16];
+                    array.copy_from_slice(cookie);
+                    array
+                },
+                kex_algorithms: kex_algorithms,
+                server_host_key_algorithms: server_host_key_algorithms,
+                encryption_algorithms_client_to_server: encryption_algorithms_client_to_server,
+                encryption_algorithms_server_to_client: encryption_algorithms_server_to_client,
+                mac_algorithms_client_to_server: mac_algorithms_client_to_server,
+                mac_algorithms_server_to_client: mac_algorithms_server_to_client,
+                compression_algorithms_client_to_server: compression_algorithms_client_to_server,
+                compression_algorithms_server_to_client: compression_algorithms_server_to_client,
+                languages_client_to_server: languages_client_to_server,
+                languages_server_to_client: languages_server_to_client,
+                first_kex_packet_follows: first_kex_packet_follows != 0,
+            })
+        )
+    );
+
+    // Parse a comma separated list of names
+    named!(parse_name_list<&[u8], Vec<String>>,
+        map!(map_res!(call!(SshMessage::parse_string), str::from_utf8), |names: &str| if names.is_empty() {
+            vec![]
+        } else {
+            names.split(',').map(str::to_owned).collect()
+        })
+    );
+
+    /// Get the algorithms of the HASSH fingerprint of a client, which are the key exchange
+    /// methods and the client to server ciphers, MACs and compression algorithms
+    pub fn hassh_algorithms(&self) -> String {
+        [
+            &self.kex_algorithms,
+            &self.encryption_algorithms_client_to_server,
+            &self.mac_algorithms_client_to_server,
+            &self.compression_algorithms_client_to_server,
+        ].iter()
+            .map(|list| list.join(","))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Get the HASSH fingerprint of a client, which is the MD5 digest of its algorithms
+    pub fn hassh(&self) -> String {
+        SshKeyExchangeInit::md5(&self.hassh_algorithms())
+    }
+
+    /// Get the algorithms of the HASSHServer fingerprint of a server, which are the key exchange
+    /// methods and the server to client ciphers, MACs and compression algorithms
+    pub fn hassh_server_algorithms(&self) -> String {
+        [
+            &self.kex_algorithms,
+            &self.encryption_algorithms_server_to_client,
+            &self.mac_algorithms_server_to_client,
+            &self.compression_algorithms_server_to_client,
+        ].iter()
+            .map(|list| list.join(","))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Get the HASSHServer fingerprint of a server, which is the MD5 digest of its algorithms
+    pub fn hassh_server(&self) -> String {
+        SshKeyExchangeInit::md5(&self.hassh_server_algorithms())
+    }
+
+    /// Get the offered algorithms which are broken or deprecated, like CBC mode ciphers or key
+    /// exchange methods and MACs based on SHA-1 or MD5
+    pub fn weak_algorithms(&self) -> Vec<&str> {
+        let mut weak: Vec<&str> = vec![];
+        for algorithm in self.kex_algorithms
+            .iter()
+            .chain(&self.server_host_key_algorithms)
+            .chain(&self.encryption_algorithms_client_to_server)
+            .chain(&self.encryption_algorithms_server_to_client)
+            .chain(&self.mac_algorithms_client_to_server)
+            .chain(&self.mac_algorithms_server_to_client)
+        {
+            if WEAK_ALGORITHMS.contains(&algorithm.as_str()) && !weak.contains(&algorithm.as_str()) {
+                weak.push(algorithm);
+            }
+        }
+        weak
+    }
+
+    fn md5(input: &str) -> String {
+        Md5::digest(input.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
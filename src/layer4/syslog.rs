@@ -0,0 +1,708 @@
+//! Syslog related packet processing for RFC 3164 and RFC 5424 messages
+use prelude::*;
+use std::collections::HashSet;
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The maximum size of an incomplete message buffered for a TCP connection
+const MAX_BUFFER_SIZE: usize = 65_536;
+
+/// The abbreviated month names of BSD timestamps
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// The syslog parser, which decodes UDP datagrams and octet counted or newline delimited TCP
+/// streams
+#[derive(Debug)]
+pub struct SyslogParser {
+    /// The ports on which UDP datagrams are decoded as syslog messages
+    pub udp_ports: HashSet<u16>,
+
+    /// The ports on which TCP streams are decoded as syslog messages. Streams which are
+    /// encrypted with TLS are decoded by the TLS parser instead.
+    pub tcp_ports: HashSet<u16>,
+
+    /// The data of incomplete messages per TCP connection and direction
+    buffers: TcpStreams<Vec<u8>>,
+}
+
+impl SyslogParser {
+    /// Create a new syslog parser for the UDP port 514 and the TCP ports 601 and 6514
+    pub fn new() -> Self {
+        SyslogParser {
+            udp_ports: [514].iter().cloned().collect(),
+            tcp_ports: [601, 6514].iter().cloned().collect(),
+            buffers: TcpStreams::new(),
+        }
+    }
+
+    /// Split the messages of a TCP segment, where incomplete messages are buffered until the
+    /// next segment of the same direction
+    fn parse_stream(buffer: &mut Vec<u8>, input: &[u8]) -> Option<Vec<SyslogMessage>> {
+        buffer.extend_from_slice(input);
+
+        let mut messages = vec![];
+        let mut position = 0;
+        while position < buffer.len() {
+            let data = &buffer[position..];
+            let (frame, length) = match data[0] {
+                // Octet counting, like "12 <13>1 - - - - - -"
+                b'1'..=b'9' => {
+                    let digits = match data.iter().position(|&b| b == b' ') {
+                        Some(digits) => digits,
+                        None if data.len() < 10 => break,
+                        None => return None,
+                    };
+                    let length: usize = str::from_utf8(&data[..digits]).ok()?.parse().ok()?;
+                    if length > MAX_BUFFER_SIZE {
+                        debug!("Dropping syslog message with an octet count of {}", length);
+                        return None;
+                    }
+                    if data.len() < digits + 1 + length {
+                        break;
+                    }
+                    (&data[digits + 1..digits + 1 + length], digits + 1 + length)
+                }
+
+                // Non-transparent framing with a trailing line feed or null character
+                _ => match data.iter().position(|&b| b == b'\n' || b == 0) {
+                    Some(end) => (&data[..end], end + 1),
+                    None => break,
+                },
+            };
+            if !frame.iter().all(|b| b.is_ascii_whitespace()) {
+                messages.push(SyslogMessage::decode(frame)?);
+            }
+            position += length;
+        }
+
+        if buffer.len() - position > MAX_BUFFER_SIZE {
+            debug!("Dropping incomplete syslog message of {} bytes", buffer.len() - position);
+            return None;
+        }
+        buffer.drain(..position);
+        Some(messages)
+    }
+}
+
+impl Default for SyslogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for SyslogParser {
+    /// Parse a `SyslogPacket` from an `&[u8]`
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let messages = match result.and_then(|vector| vector.last()) {
+            Some(any) => match (any.downcast_ref::<UdpPacket>(), any.downcast_ref::<TcpPacket>()) {
+                (Some(udp), _) => {
+                    let ports = [udp.header.source_port, udp.header.dest_port];
+                    if !ports.iter().any(|port| self.udp_ports.contains(port)) {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    SyslogMessage::decode(input).map(|message| vec![message])
+                }
+                (_, Some(tcp)) => {
+                    let ports = [tcp.header.source_port, tcp.header.dest_port];
+                    if !ports.iter().any(|port| self.tcp_ports.contains(port)) || input.is_empty() {
+                        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                    }
+                    self.buffers.process(result, tcp, true, |buffer| SyslogParser::parse_stream(buffer, input))
+                }
+
+                // Previous result found, but not correct parent
+                _ => None,
+            },
+
+            // Parse also if no result is given, for testability
+            None => SyslogMessage::decode(input).map(|message| vec![message]),
+        };
+
+        match messages {
+            Some(messages) => IResult::Done(&input[input.len()..], Box::new(SyslogPacket { messages: messages })),
+            None => IResult::Error(error_position!(ErrorKind::Custom(1), input)),
+        }
+    }
+}
+
+impl fmt::Display for SyslogParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Syslog")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The syslog messages of a UDP datagram or TCP segment
+pub struct SyslogPacket {
+    /// The decoded messages, where a TCP segment may contain none or multiple messages
+    pub messages: Vec<SyslogMessage>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of a syslog message
+pub struct SyslogMessage {
+    /// The facility of the originator
+    pub facility: SyslogFacility,
+
+    /// The severity of the message
+    pub severity: SyslogSeverity,
+
+    /// The version of RFC 5424 messages, which is None for BSD messages
+    pub version: Option<u8>,
+
+    /// The time at which the message was generated
+    pub timestamp: Option<SyslogTimestamp>,
+
+    /// The host name or address of the originator
+    pub hostname: Option<String>,
+
+    /// The application or tag of BSD messages, like "sshd"
+    pub app_name: Option<String>,
+
+    /// The process identifier
+    pub procid: Option<String>,
+
+    /// The type of the message
+    pub msgid: Option<String>,
+
+    /// The structured data elements of RFC 5424 messages
+    pub structured_data: Vec<SyslogStructuredData>,
+
+    /// The free form message
+    pub message: String,
+}
+
+impl SyslogMessage {
+    /// Decode a RFC 5424 message or a BSD message, where missing parts of BSD messages are
+    /// tolerated
+    pub fn decode(input: &[u8]) -> Option<SyslogMessage> {
+        let text = String::from_utf8_lossy(input);
+        let text = text.trim_end_matches(&['\n', '\r', '\0'][..]);
+        if text.is_empty() {
+            return None;
+        }
+
+        // Relays assume the priority "user.notice" for messages without it
+        let (priority, rest) = match SyslogMessage::priority(text) {
+            Some((priority, rest)) => (priority, rest),
+            None if !text.starts_with('<') => (13, text),
+            None => return None,
+        };
+        let mut message = SyslogMessage {
+            facility: SyslogFacility::from_u8(priority >> 3)?,
+            severity: SyslogSeverity::from_u8(priority & 0x07)?,
+            version: None,
+            timestamp: None,
+            hostname: None,
+            app_name: None,
+            procid: None,
+            msgid: None,
+            structured_data: vec![],
+            message: String::new(),
+        };
+        if !message.decode_rfc5424(rest) {
+            message.decode_bsd(rest);
+        }
+        Some(message)
+    }
+
+    /// Get the priority value and the remaining text
+    fn priority(text: &str) -> Option<(u8, &str)> {
+        if !text.starts_with('<') {
+            return None;
+        }
+        let end = text.find('>')?;
+        let digits = &text[1..end];
+        if digits.is_empty() || digits.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        match digits.parse::<u8>() {
+            Ok(priority) if priority <= 191 => Some((priority, &text[end + 1..])),
+            _ => None,
+        }
+    }
+
+    /// Decode the header, structured data and message of RFC 5424. Returns false if the
+    /// text is no valid RFC 5424 message.
+    fn decode_rfc5424(&mut self, text: &str) -> bool {
+        let mut fields = text.splitn(7, ' ');
+        let version = match fields.next().and_then(|version| version.parse::<u8>().ok()) {
+            Some(version) if version > 0 => version,
+            _ => return false,
+        };
+        let header: Vec<&str> = fields.by_ref().take(5).collect();
+        if header.len() != 5 {
+            return false;
+        }
+        let timestamp = match header[0] {
+            "-" => None,
+            timestamp => match SyslogTimestamp::from_rfc3339(timestamp) {
+                Some(timestamp) => Some(timestamp),
+                None => return false,
+            },
+        };
+        let (structured_data, message) = match SyslogStructuredData::parse_all(fields.next().unwrap_or("")) {
+            Some(parsed) => parsed,
+            None => return false,
+        };
+
+        let nil = |field: &str| if field == "-" { None } else { Some(field.to_owned()) };
+        self.version = Some(version);
+        self.timestamp = timestamp;
+        self.hostname = nil(header[1]);
+        self.app_name = nil(header[2]);
+        self.procid = nil(header[3]);
+        self.msgid = nil(header[4]);
+        self.structured_data = structured_data;
+        self.message = message.trim_start_matches('\u{feff}').to_owned();
+        true
+    }
+
+    /// Decode the timestamp, host name and tag of a BSD message, where every part is optional
+    fn decode_bsd(&mut self, text: &str) {
+        let mut rest = text.trim_start();
+        if let Some((timestamp, remaining)) = SyslogTimestamp::from_bsd(rest) {
+            self.timestamp = Some(timestamp);
+            rest = remaining.trim_start();
+
+            // The host name is missing if the tag follows directly
+            let token = rest.split(' ').next().unwrap_or("");
+            if !token.is_empty() && !token.ends_with(':') && !token.contains('[') && rest.len() > token.len() {
+                self.hostname = Some(token.to_owned());
+                rest = rest[token.len()..].trim_start();
+            }
+        }
+
+        // The tag consists of the application and an optional process identifier, like
+        // "sshd[1234]:"
+        let token = rest.split(' ').next().unwrap_or("");
+        let tag = if let Some(tag) = token.strip_suffix(':') {
+            Some(tag)
+        } else if token.ends_with(']') && token.contains('[') {
+            Some(token)
+        } else {
+            None
+        };
+        if let Some(tag) = tag {
+            let (app_name, procid) = match tag.find('[') {
+                Some(start) if tag.ends_with(']') => (&tag[..start], Some(&tag[start + 1..tag.len() - 1])),
+                _ => (tag, None),
+            };
+            if !app_name.is_empty() {
+                self.app_name = Some(app_name.to_owned());
+                self.procid = procid.map(str::to_owned);
+                rest = &rest[token.len()..];
+                rest = rest.strip_prefix(' ').unwrap_or(rest);
+            }
+        }
+        self.message = rest.to_owned();
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The syslog facility
+pub enum SyslogFacility {
+    /// Kernel messages
+    Kernel,
+
+    /// User-level messages
+    User,
+
+    /// The mail system
+    Mail,
+
+    /// System daemons
+    Daemon,
+
+    /// Security and authorization messages
+    Auth,
+
+    /// Messages generated internally by the syslog daemon
+    Syslog,
+
+    /// The line printer subsystem
+    Printer,
+
+    /// The network news subsystem
+    News,
+
+    /// The UUCP subsystem
+    Uucp,
+
+    /// The clock daemon
+    Cron,
+
+    /// Private security and authorization messages
+    AuthPriv,
+
+    /// The FTP daemon
+    Ftp,
+
+    /// The NTP subsystem
+    Ntp,
+
+    /// Log audit
+    Audit,
+
+    /// Log alert
+    Alert,
+
+    /// The clock daemon of some systems
+    Clock,
+
+    /// Local use 0
+    Local0,
+
+    /// Local use 1
+    Local1,
+
+    /// Local use 2
+    Local2,
+
+    /// Local use 3
+    Local3,
+
+    /// Local use 4
+    Local4,
+
+    /// Local use 5
+    Local5,
+
+    /// Local use 6
+    Local6,
+
+    /// Local use 7
+    Local7,
+}
+
+impl SyslogFacility {
+    /// Convert a u8 to a `SyslogFacility`. Returns None if the facility is invalid.
+    pub fn from_u8(input: u8) -> Option<SyslogFacility> {
+        match input {
+            0 => Some(SyslogFacility::Kernel),
+            1 => Some(SyslogFacility::User),
+            2 => Some(SyslogFacility::Mail),
+            3 => Some(SyslogFacility::Daemon),
+            4 => Some(SyslogFacility::Auth),
+            5 => Some(SyslogFacility::Syslog),
+            6 => Some(SyslogFacility::Printer),
+            7 => Some(SyslogFacility::News),
+            8 => Some(SyslogFacility::Uucp),
+            9 => Some(SyslogFacility::Cron),
+            10 => Some(SyslogFacility::AuthPriv),
+            11 => Some(SyslogFacility::Ftp),
+            12 => Some(SyslogFacility::Ntp),
+            13 => Some(SyslogFacility::Audit),
+            14 => Some(SyslogFacility::Alert),
+            15 => Some(SyslogFacility::Clock),
+            16 => Some(SyslogFacility::Local0),
+            17 => Some(SyslogFacility::Local1),
+            18 => Some(SyslogFacility::Local2),
+            19 => Some(SyslogFacility::Local3),
+            20 => Some(SyslogFacility::Local4),
+            21 => Some(SyslogFacility::Local5),
+            22 => Some(SyslogFacility::Local6),
+            23 => Some(SyslogFacility::Local7),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+/// The syslog severity, ordered from the most to the least severe
+pub enum SyslogSeverity {
+    /// The system is unusable
+    Emergency,
+
+    /// Action must be taken immediately
+    Alert,
+
+    /// Critical conditions
+    Critical,
+
+    /// Error conditions
+    Error,
+
+    /// Warning conditions
+    Warning,
+
+    /// Normal but significant conditions
+    Notice,
+
+    /// Informational messages
+    Informational,
+
+    /// Debug-level messages
+    Debug,
+}
+
+impl SyslogSeverity {
+    /// Convert a u8 to a `SyslogSeverity`. Returns None if the severity is invalid.
+    pub fn from_u8(input: u8) -> Option<SyslogSeverity> {
+        match input {
+            0 => Some(SyslogSeverity::Emergency),
+            1 => Some(SyslogSeverity::Alert),
+            2 => Some(SyslogSeverity::Critical),
+            3 => Some(SyslogSeverity::Error),
+            4 => Some(SyslogSeverity::Warning),
+            5 => Some(SyslogSeverity::Notice),
+            6 => Some(SyslogSeverity::Informational),
+            7 => Some(SyslogSeverity::Debug),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The time of a message, where BSD timestamps usually lack the year and time zone
+pub struct SyslogTimestamp {
+    /// The year, if available
+    pub year: Option<u16>,
+
+    /// The month, starting at 1
+    pub month: u8,
+
+    /// The day of the month, starting at 1
+    pub day: u8,
+
+    /// The hour
+    pub hour: u8,
+
+    /// The minute
+    pub minute: u8,
+
+    /// The second
+    pub second: u8,
+
+    /// The fraction of the second in nanoseconds
+    pub nanosecond: u32,
+
+    /// The offset to UTC in minutes, if available
+    pub utc_offset: Option<i16>,
+}
+
+impl SyslogTimestamp {
+    /// Get the time, if the year and the offset to UTC are known
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let year = self.year? as i64;
+        let utc_offset = self.utc_offset? as i64;
+
+        // Convert the civil date in the proleptic Gregorian calendar to days
+        let year = if self.month <= 2 { year - 1 } else { year };
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let mp = (self.month as i64 + 9) % 12;
+        let day_of_year = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        let seconds = days * 86_400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+            - utc_offset * 60;
+        if seconds < 0 {
+            return None;
+        }
+        Some(UNIX_EPOCH + Duration::new(seconds as u64, self.nanosecond))
+    }
+
+    /// Parse a timestamp like "2003-10-11T22:14:15.003-07:00"
+    fn from_rfc3339(text: &str) -> Option<SyslogTimestamp> {
+        let bytes = text.as_bytes();
+        if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || (bytes[10] != b'T' && bytes[10] != b't') {
+            return None;
+        }
+        let (year, month, day) = (
+            SyslogTimestamp::number(&text[..4])?,
+            SyslogTimestamp::number(&text[5..7])?,
+            SyslogTimestamp::number(&text[8..10])?,
+        );
+        let (hour, minute, second, nanosecond, rest) = SyslogTimestamp::time(&text[11..])?;
+        let utc_offset = match rest {
+            "Z" | "z" => 0,
+            _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+                let minutes = SyslogTimestamp::number(&rest[1..3])? * 60 + SyslogTimestamp::number(&rest[4..6])?;
+                match rest.as_bytes()[0] {
+                    b'+' => minutes as i16,
+                    b'-' => -(minutes as i16),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        SyslogTimestamp::validate(SyslogTimestamp {
+            year: Some(year as u16),
+            month: month as u8,
+            day: day as u8,
+            hour: hour,
+            minute: minute,
+            second: second,
+            nanosecond: nanosecond,
+            utc_offset: Some(utc_offset),
+        })
+    }
+
+    /// Parse a BSD timestamp with an optional year and fraction, like "Oct 11 22:14:15" or
+    /// "*Mar  1 2023 18:48:50.483:", or a RFC 3339 timestamp. Returns the timestamp and the
+    /// remaining text.
+    fn from_bsd(text: &str) -> Option<(SyslogTimestamp, &str)> {
+        let token = text.split(' ').next().unwrap_or("");
+        if let Some(timestamp) = SyslogTimestamp::from_rfc3339(token) {
+            return Some((timestamp, &text[token.len()..]));
+        }
+
+        // Cisco devices mark unsynchronized clocks with "*" or "."
+        let text = text.trim_start_matches(&['*', '.'][..]);
+        let month = MONTHS.iter().position(|month| text.starts_with(month))? as u8 + 1;
+        let rest = text[3..].strip_prefix(' ')?.trim_start();
+        let digits = rest.find(' ')?;
+        let day = SyslogTimestamp::number(&rest[..digits])? as u8;
+        let mut rest = &rest[digits + 1..];
+
+        let mut year = None;
+        if rest.len() > 5 && rest.as_bytes()[4] == b' ' {
+            year = Some(SyslogTimestamp::number(&rest[..4])? as u16);
+            rest = &rest[5..];
+        }
+        let (hour, minute, second, nanosecond, rest) = SyslogTimestamp::time(rest)?;
+        let timestamp = SyslogTimestamp::validate(SyslogTimestamp {
+            year: year,
+            month: month,
+            day: day,
+            hour: hour,
+            minute: minute,
+            second: second,
+            nanosecond: nanosecond,
+            utc_offset: None,
+        })?;
+        Some((timestamp, rest.strip_prefix(':').unwrap_or(rest)))
+    }
+
+    /// Parse a time like "22:14:15.003" and return the remaining text
+    fn time(text: &str) -> Option<(u8, u8, u8, u32, &str)> {
+        let bytes = text.as_bytes();
+        if bytes.len() < 8 || bytes[2] != b':' || bytes[5] != b':' {
+            return None;
+        }
+        let hour = SyslogTimestamp::number(&text[..2])? as u8;
+        let minute = SyslogTimestamp::number(&text[3..5])? as u8;
+        let second = SyslogTimestamp::number(&text[6..8])? as u8;
+        let mut rest = &text[8..];
+
+        let mut nanosecond = 0;
+        if rest.starts_with('.') {
+            let digits = rest[1..].bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 || digits > 9 {
+                return None;
+            }
+            nanosecond = SyslogTimestamp::number(&rest[1..1 + digits])? * 10u32.pow(9 - digits as u32);
+            rest = &rest[1 + digits..];
+        }
+        Some((hour, minute, second, nanosecond, rest))
+    }
+
+    fn number(text: &str) -> Option<u32> {
+        if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        text.parse().ok()
+    }
+
+    fn validate(timestamp: SyslogTimestamp) -> Option<SyslogTimestamp> {
+        let valid = (1..13).contains(&timestamp.month) && (1..32).contains(&timestamp.day) && timestamp.hour < 24
+            && timestamp.minute < 60 && timestamp.second < 61;
+        if valid {
+            Some(timestamp)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A structured data element, like `[exampleSDID@32473 iut="3" eventSource="Application"]`
+pub struct SyslogStructuredData {
+    /// The identifier of the element
+    pub id: String,
+
+    /// The parameter names and their unescaped values
+    pub parameters: Vec<(String, String)>,
+}
+
+impl SyslogStructuredData {
+    /// Parse the structured data elements and return them with the message
+    fn parse_all(text: &str) -> Option<(Vec<SyslogStructuredData>, &str)> {
+        if text == "-" {
+            return Some((vec![], ""));
+        }
+        if let Some(message) = text.strip_prefix("- ") {
+            return Some((vec![], message));
+        }
+
+        let mut elements = vec![];
+        let mut rest = text;
+        while rest.starts_with('[') {
+            let (element, remaining) = SyslogStructuredData::parse(&rest[1..])?;
+            elements.push(element);
+            rest = remaining;
+        }
+        if elements.is_empty() {
+            return None;
+        }
+        match rest {
+            "" => Some((elements, "")),
+            _ => rest.strip_prefix(' ').map(|message| (elements, message)),
+        }
+    }
+
+    /// Parse an element after the opening bracket and return the remaining text
+    fn parse(text: &str) -> Option<(SyslogStructuredData, &str)> {
+        let name_end = |text: &str| text.find(&[' ', '=', ']', '"'][..]);
+        let end = name_end(text)?;
+        if end == 0 {
+            return None;
+        }
+        let mut element = SyslogStructuredData {
+            id: text[..end].to_owned(),
+            parameters: vec![],
+        };
+
+        let mut rest = &text[end..];
+        loop {
+            if let Some(remaining) = rest.strip_prefix(']') {
+                return Some((element, remaining));
+            }
+            rest = rest.strip_prefix(' ')?;
+            let end = name_end(rest)?;
+            let name = &rest[..end];
+            rest = rest[end..].strip_prefix("=\"")?;
+
+            // Values escape quotes, backslashes and closing brackets with a backslash
+            let mut value = String::new();
+            let mut chars = rest.char_indices();
+            loop {
+                match chars.next()? {
+                    (_, '\\') => match chars.next()? {
+                        (_, c) if c == '"' || c == '\\' || c == ']' => value.push(c),
+                        (_, c) => {
+                            value.push('\\');
+                            value.push(c);
+                        }
+                    },
+                    (i, '"') => {
+                        rest = &rest[i + 1..];
+                        break;
+                    }
+                    (_, c) => value.push(c),
+                }
+            }
+            if name.is_empty() {
+                return None;
+            }
+            element.parameters.push((name.to_owned(), value));
+        }
+    }
+}
@@ -0,0 +1,544 @@
+//! WebSocket related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+#[cfg(feature = "gzip")]
+use flate2::{Decompress, FlushDecompress, Status};
+
+/// The maximum payload length of control frames
+const MAX_CONTROL_PAYLOAD: u64 = 125;
+
+#[cfg(feature = "gzip")]
+/// The trailer removed by the sender from every message compressed with permessage-deflate
+const DEFLATE_TRAILER: &[u8] = &[0x00, 0x00, 0xff, 0xff];
+
+/// The WebSocket parser, which follows connections upgraded by a HTTP opening handshake
+#[derive(Debug)]
+pub struct WebSocketParser {
+    /// The maximum size of a frame or reassembled message, larger ones stop following the
+    /// connection
+    pub max_message_size: usize,
+
+    /// The request of the opening handshake and the direction of the client per connection,
+    /// where the opening handshake is not answered yet
+    handshakes: HashMap<Option<Identifier<IpProtocol>>, (HttpRequest, bool)>,
+
+    /// The state of the upgraded connections
+    connections: HashMap<Option<Identifier<IpProtocol>>, WebSocketConnection>,
+}
+
+impl WebSocketParser {
+    /// Create a new WebSocket parser, which reassembles messages up to 10 MiB
+    pub fn new() -> Self {
+        WebSocketParser {
+            max_message_size: 10 * 1024 * 1024,
+            handshakes: HashMap::new(),
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Follow the opening handshake, which returns true if the connection was upgraded
+    fn handshake(&mut self, identifier: &Option<Identifier<IpProtocol>>, direction: bool, http: &HttpPacket) -> bool {
+        match *http {
+            HttpPacket::Request(ref request) => {
+                if request.websocket_key().is_some() {
+                    self.handshakes.insert(identifier.clone(), (request.clone(), direction));
+                }
+                false
+            }
+            HttpPacket::Response(ref response) => {
+                // Every final response answers the opening handshake
+                if !response.is_final() {
+                    return false;
+                }
+                match self.handshakes.remove(identifier) {
+                    Some((ref request, client)) if client != direction && response.accepts_websocket(request) => {
+                        let deflate = response
+                            .headers
+                            .iter()
+                            .filter(|header| header.key.eq_ignore_ascii_case("Sec-WebSocket-Extensions"))
+                            .flat_map(|header| header.value.split(','))
+                            .any(|extension| {
+                                extension
+                                    .split(';')
+                                    .next()
+                                    .is_some_and(|name| name.trim().eq_ignore_ascii_case("permessage-deflate"))
+                            });
+                        self.connections
+                            .insert(identifier.clone(), WebSocketConnection::new(client, deflate));
+                        true
+                    }
+                    Some(_) if response.websocket_accept().is_some() => {
+                        debug!("Invalid Sec-WebSocket-Accept in WebSocket opening handshake");
+                        false
+                    }
+                    _ => false,
+                }
+            }
+            // The messages are followed in their order, a switching response is always the last one
+            HttpPacket::Pipelined(ref packets) => packets
+                .iter()
+                .any(|packet| self.handshake(identifier, direction, packet)),
+            HttpPacket::Any(_) => false,
+        }
+    }
+
+    /// Process the frames of a segment, which returns None on a protocol error
+    fn process(
+        &mut self,
+        identifier: &Option<Identifier<IpProtocol>>,
+        direction: bool,
+        input: &[u8],
+    ) -> Option<(Vec<WebSocketFrame>, Vec<WebSocketMessage>)> {
+        let max_message_size = self.max_message_size;
+        let connection = self.connections.get_mut(identifier)?;
+        let from_client = direction == connection.client;
+        let deflate = connection.deflate;
+        let endpoint = &mut connection.endpoints[direction as usize];
+
+        // Continue a frame of the previous segments
+        let mut data = ::std::mem::take(&mut endpoint.buffer);
+        data.extend_from_slice(input);
+
+        let mut frames = vec![];
+        let mut messages = vec![];
+        let mut remaining = &data[..];
+        loop {
+            let (rest, frame) = match WebSocketFrame::parse(remaining) {
+                IResult::Done(rest, frame) => (rest, frame),
+                IResult::Incomplete(_) => {
+                    if WebSocketFrame::payload_length(remaining).is_some_and(|l| l > max_message_size as u64) {
+                        debug!("WebSocket frame exceeds the maximum message size");
+                        return None;
+                    }
+                    break;
+                }
+                IResult::Error(_) => return None,
+            };
+            remaining = rest;
+
+            // Clients have to mask their frames, servers must not
+            if frame.mask.is_some() != from_client || frame.rsv2 || frame.rsv3 || (frame.rsv1 && !deflate) {
+                debug!("Invalid WebSocket frame header");
+                return None;
+            }
+            if let Some(message) = endpoint.process(&frame, from_client, max_message_size)? {
+                messages.push(message);
+            }
+            frames.push(frame);
+        }
+        endpoint.buffer = remaining.to_vec();
+
+        Some((frames, messages))
+    }
+
+    /// Parse the frames of a segment, which may directly follow the response of the opening
+    /// handshake if the segment contains HTTP
+    fn parse_segment<'a>(
+        &mut self,
+        identifier: &Option<Identifier<IpProtocol>>,
+        direction: bool,
+        input: &'a [u8],
+        http: Option<&HttpPacket>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        match http {
+            Some(http) => if !self.handshake(identifier, direction, http) || input.is_empty() {
+                return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+            },
+            None => if !self.connections.contains_key(identifier) {
+                return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+            },
+        }
+
+        let packet = match self.process(identifier, direction, input) {
+            Some((frames, messages)) => WebSocketPacket {
+                frames: frames,
+                messages: messages,
+            },
+            None => {
+                // The connection can not be followed any more
+                self.connections.remove(identifier);
+                return IResult::Error(error_position!(ErrorKind::Custom(1), input));
+            }
+        };
+
+        IResult::Done(&input[input.len()..], Box::new(packet))
+    }
+}
+
+impl Default for WebSocketParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parsable<PathIp> for WebSocketParser {
+    /// Parse a `WebSocketPacket` from an `&[u8]`. The parser follows the opening handshake if the
+    /// parent is the `HttpParser`, and decodes the frames of upgraded connections if the parent
+    /// is the `TcpParser`.
+    fn parse<'a>(
+        &mut self,
+        input: &'a [u8],
+        result: Option<&ParserResultVec>,
+        _: Option<&mut PathIp>,
+    ) -> IResult<&'a [u8], ParserResult> {
+        let tcp = result.and_then(|vector| {
+            vector
+                .iter()
+                .rev()
+                .filter_map(|any| any.downcast_ref::<TcpPacket>())
+                .next()
+        });
+        let (identifier, direction) = match tcp.and_then(|tcp| {
+            connection_identifier(result, tcp.header.source_port, tcp.header.dest_port)
+        }) {
+            Some((identifier, direction)) => (Some(identifier), direction),
+            None => (None, true),
+        };
+
+        let http = result
+            .and_then(|vector| vector.last())
+            .and_then(|any| any.downcast_ref::<HttpPacket>());
+        let packet = self.parse_segment(&identifier, direction, input, http);
+
+        // The connection is closed
+        if let Some(tcp) = tcp {
+            if tcp.header.flag_fin || tcp.header.flag_rst {
+                self.handshakes.remove(&identifier);
+                self.connections.remove(&identifier);
+            }
+        }
+
+        packet
+    }
+}
+
+impl fmt::Display for WebSocketParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WebSocket")
+    }
+}
+
+#[derive(Debug)]
+/// The state of an upgraded connection
+struct WebSocketConnection {
+    /// The direction of the client, which sent the opening handshake
+    client: bool,
+
+    /// The permessage-deflate extension was negotiated
+    deflate: bool,
+
+    /// The state per endpoint, indexed by the direction of the frames sent by the endpoint
+    endpoints: [WebSocketEndpoint; 2],
+}
+
+impl WebSocketConnection {
+    fn new(client: bool, deflate: bool) -> Self {
+        WebSocketConnection {
+            client: client,
+            deflate: deflate,
+            endpoints: [WebSocketEndpoint::new(), WebSocketEndpoint::new()],
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The state of the frames sent by a single endpoint
+struct WebSocketEndpoint {
+    /// An incomplete frame of the previous segments
+    buffer: Vec<u8>,
+
+    /// A fragmented data message which is not finished yet
+    message: Option<WebSocketMessage>,
+
+    /// The decompressor, which keeps the sliding window between messages
+    #[cfg(feature = "gzip")]
+    inflater: Decompress,
+}
+
+impl WebSocketEndpoint {
+    #[cfg(feature = "gzip")]
+    fn new() -> Self {
+        WebSocketEndpoint {
+            buffer: vec![],
+            message: None,
+            inflater: Decompress::new(false),
+        }
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn new() -> Self {
+        WebSocketEndpoint {
+            buffer: vec![],
+            message: None,
+        }
+    }
+
+    /// Apply a frame, which returns the completed message if any, or None on a protocol error
+    fn process(
+        &mut self,
+        frame: &WebSocketFrame,
+        from_client: bool,
+        max_message_size: usize,
+    ) -> Option<Option<WebSocketMessage>> {
+        let mut message = match frame.opcode {
+            // Control frames may be sent in between the fragments of a message
+            WebSocketOpcode::Close | WebSocketOpcode::Ping | WebSocketOpcode::Pong => {
+                if !frame.fin || frame.rsv1 || frame.payload.len() as u64 > MAX_CONTROL_PAYLOAD {
+                    return None;
+                }
+                return Some(Some(WebSocketMessage {
+                    from_client: from_client,
+                    opcode: frame.opcode,
+                    compressed: false,
+                    data: frame.payload.clone(),
+                    decompressed: None,
+                }));
+            }
+            WebSocketOpcode::Continuation => {
+                let mut message = self.message.take()?;
+                if frame.rsv1 || message.data.len() + frame.payload.len() > max_message_size {
+                    return None;
+                }
+                message.data.extend_from_slice(&frame.payload);
+                message
+            }
+            WebSocketOpcode::Text | WebSocketOpcode::Binary => {
+                if self.message.is_some() {
+                    return None;
+                }
+                WebSocketMessage {
+                    from_client: from_client,
+                    opcode: frame.opcode,
+                    compressed: frame.rsv1,
+                    data: frame.payload.clone(),
+                    decompressed: None,
+                }
+            }
+        };
+
+        if !frame.fin {
+            self.message = Some(message);
+            return Some(None);
+        }
+        if message.compressed {
+            message.decompressed = self.decompress(&message.data, max_message_size);
+            if message.decompressed.is_none() {
+                debug!("Unable to decompress WebSocket message");
+            }
+        }
+        Some(Some(message))
+    }
+
+    /// Decompress a message compressed with permessage-deflate
+    #[cfg(feature = "gzip")]
+    fn decompress(&mut self, data: &[u8], limit: usize) -> Option<Vec<u8>> {
+        let mut input = data.to_vec();
+        input.extend_from_slice(DEFLATE_TRAILER);
+
+        let start = self.inflater.total_in();
+        let mut output = Vec::with_capacity(4096);
+        loop {
+            let consumed = (self.inflater.total_in() - start) as usize;
+            let produced = output.len();
+            if output.len() == output.capacity() {
+                output.reserve(4096);
+            }
+            match self.inflater
+                .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+            {
+                // The sender finished the stream, further messages start a new one
+                Ok(Status::StreamEnd) => {
+                    self.inflater.reset(false);
+                    break;
+                }
+                Ok(_) => {}
+                Err(_) => return None,
+            }
+            if output.len() > limit {
+                return None;
+            }
+
+            let now_consumed = (self.inflater.total_in() - start) as usize;
+            if now_consumed == input.len() && output.len() < output.capacity() {
+                break;
+            }
+            if now_consumed == consumed && output.len() == produced {
+                return None;
+            }
+        }
+        Some(output)
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn decompress(&mut self, _: &[u8], _: usize) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// Representation of the WebSocket frames of a segment
+pub struct WebSocketPacket {
+    /// The frames which were completed by the segment
+    pub frames: Vec<WebSocketFrame>,
+
+    /// The messages which were completed by the segment
+    pub messages: Vec<WebSocketMessage>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A single WebSocket frame
+pub struct WebSocketFrame {
+    /// The frame is the last fragment of a message
+    pub fin: bool,
+
+    /// The first reserved bit, which marks compressed messages with permessage-deflate
+    pub rsv1: bool,
+
+    /// The second reserved bit
+    pub rsv2: bool,
+
+    /// The third reserved bit
+    pub rsv3: bool,
+
+    /// The frame type
+    pub opcode: WebSocketOpcode,
+
+    /// The masking key of frames sent by the client
+    pub mask: Option<[u8; 4]>,
+
+    /// The unmasked payload
+    pub payload: Vec<u8>,
+}
+
+impl WebSocketFrame {
+    /// Get the status code of a close frame
+    pub fn close_code(&self) -> Option<u16> {
+        match self.opcode {
+            WebSocketOpcode::Close if self.payload.len() >= 2 => {
+                Some((self.payload[0] as u16) << 8 | self.payload[1] as u16)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the reason of a close frame
+    pub fn close_reason(&self) -> Option<String> {
+        match self.opcode {
+            WebSocketOpcode::Close if self.payload.len() > 2 => {
+                Some(String::from_utf8_lossy(&self.payload[2..]).into_owned())
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(input: &[u8]) -> IResult<&[u8], WebSocketFrame> {
+        do_parse!(
+            input,
+            first: be_u8
+                >> opcode: expr_opt!(WebSocketOpcode::from_u8(first & 0x0f))
+                >> second: be_u8
+                >> length: switch!(value!(second & 0x7f),
+                    126 => map!(be_u16, |l| l as u64) |
+                    127 => verify!(be_u64, |l: u64| l >> 63 == 0) |
+                    l => value!(l as u64)
+                ) >> mask: cond!(second & 0x80 != 0, map!(take!(4), |m: &[u8]| [m[0], m[1], m[2], m[3]]))
+                >> payload: take!(length as usize)
+                >> (WebSocketFrame {
+                    fin: first & 0x80 != 0,
+                    rsv1: first & 0x40 != 0,
+                    rsv2: first & 0x20 != 0,
+                    rsv3: first & 0x10 != 0,
+                    opcode: opcode,
+                    mask: mask,
+                    payload: match mask {
+                        Some(mask) => payload
+                            .iter()
+                            .enumerate()
+                            .map(|(i, byte)| byte ^ mask[i % 4])
+                            .collect(),
+                        None => payload.to_vec(),
+                    },
+                })
+        )
+    }
+
+    /// Get the payload length of a frame, if its header is complete
+    fn payload_length(input: &[u8]) -> Option<u64> {
+        match *input.get(1)? & 0x7f {
+            126 if input.len() >= 4 => Some((input[2] as u64) << 8 | input[3] as u64),
+            127 if input.len() >= 10 => Some(input[2..10].iter().fold(0, |l, &b| l << 8 | b as u64)),
+            126 | 127 => None,
+            length => Some(length as u64),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The WebSocket frame types
+pub enum WebSocketOpcode {
+    /// A further fragment of a message
+    Continuation,
+
+    /// A text message
+    Text,
+
+    /// A binary message
+    Binary,
+
+    /// The connection is closed
+    Close,
+
+    /// A keepalive request
+    Ping,
+
+    /// A keepalive response
+    Pong,
+}
+
+impl WebSocketOpcode {
+    /// Convert a u8 to an `WebSocketOpcode`. Returns None if the opcode is reserved.
+    pub fn from_u8(input: u8) -> Option<WebSocketOpcode> {
+        match input {
+            0 => Some(WebSocketOpcode::Continuation),
+            1 => Some(WebSocketOpcode::Text),
+            2 => Some(WebSocketOpcode::Binary),
+            8 => Some(WebSocketOpcode::Close),
+            9 => Some(WebSocketOpcode::Ping),
+            10 => Some(WebSocketOpcode::Pong),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// A complete WebSocket message, which was possibly fragmented into multiple frames
+pub struct WebSocketMessage {
+    /// The message was sent by the client
+    pub from_client: bool,
+
+    /// The message type, which is the opcode of the first frame
+    pub opcode: WebSocketOpcode,
+
+    /// The message is compressed with permessage-deflate
+    pub compressed: bool,
+
+    /// The reassembled payload as transferred
+    pub data: Vec<u8>,
+
+    /// The decompressed payload, if the message is compressed and the decompression succeeded
+    pub decompressed: Option<Vec<u8>>,
+}
+
+impl WebSocketMessage {
+    /// Get the content of a text message
+    pub fn text(&self) -> Option<String> {
+        match self.opcode {
+            WebSocketOpcode::Text if !self.compressed => Some(String::from_utf8_lossy(&self.data).into_owned()),
+            WebSocketOpcode::Text => self.decompressed
+                .as_ref()
+                .map(|data| String::from_utf8_lossy(data).into_owned()),
+            _ => None,
+        }
+    }
+}
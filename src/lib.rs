@@ -11,13 +11,22 @@
 //! ```
 #![deny(missing_docs)]
 
+extern crate base64;
+
 #[macro_use]
 extern crate log;
+extern crate md5;
 
-#[macro_use]
 extern crate nom;
 extern crate path;
 extern crate peel;
+extern crate sha1_smol;
+extern crate sha2;
+
+#[cfg(feature = "brotli")]
+extern crate brotli_decompressor;
+#[cfg(feature = "gzip")]
+extern crate flate2;
 
 pub mod layer1;
 pub mod layer2;
@@ -59,14 +68,37 @@ pub mod prelude {
     pub use layer2::*;
 
     // Transport
+    pub use layer3::dtls::*;
     pub use layer3::tcp::*;
     pub use layer3::tls::*;
     pub use layer3::udp::*;
     pub use layer3::*;
 
     // Application
+    pub use layer4::ber::*;
+    pub use layer4::dhcp::*;
+    pub use layer4::dhcpv6::*;
+    pub use layer4::dns::*;
+    pub use layer4::grpc::*;
+    pub use layer4::har::*;
+    pub use layer4::hpack::*;
     pub use layer4::http::*;
+    pub use layer4::http2::*;
+    pub use layer4::http_cookie::*;
+    pub use layer4::http_object::*;
+    pub use layer4::http_transaction::*;
+    pub use layer4::http_url::*;
+    pub use layer4::llmnr::*;
+    pub use layer4::mdns::*;
     pub use layer4::ntp::*;
+    pub use layer4::protobuf::*;
+    pub use layer4::rtp::*;
+    pub use layer4::sdp::*;
+    pub use layer4::sip::*;
+    pub use layer4::snmp::*;
+    pub use layer4::ssh::*;
+    pub use layer4::syslog::*;
+    pub use layer4::websocket::*;
 }
 
 /// Trait for default parser tree generation
@@ -86,7 +118,24 @@ pub trait NewPeelIp {
         let tcp = p.new_parser(TcpParser);
         let udp = p.new_parser(UdpParser);
         let tls = p.new_parser(TlsParser);
-        let http = p.new_parser(HttpParser);
+        let dtls = p.new_parser(DtlsParser::new());
+        let ssh = p.new_parser(SshParser::new());
+        let http = p.new_parser(HttpParser::new());
+        let websocket = p.new_parser(WebSocketParser::new());
+        let http2 = p.new_parser(Http2Parser::new());
+        let grpc = p.new_parser(GrpcParser::new());
+        let dns = p.new_parser(DnsParser::new());
+        let mdns = p.new_parser(MdnsParser);
+        let llmnr = p.new_parser(LlmnrParser);
+        let dhcp = p.new_parser(DhcpParser);
+        let dhcpv6 = p.new_parser(Dhcpv6Parser);
+        let snmp = p.new_parser(SnmpParser);
+        let syslog = p.new_parser(SyslogParser::new());
+        let sip_parser = SipParser::new();
+        let mut rtp_parser = RtpParser::new();
+        rtp_parser.media_endpoints = sip_parser.media_endpoints.clone();
+        let sip = p.new_parser(sip_parser);
+        let rtp = p.new_parser(rtp_parser);
         let ntp = p.new_parser(NtpParser);
 
         // Link the parsers
@@ -103,9 +152,27 @@ pub trait NewPeelIp {
             (ipv6, tcp),
             (ipv4, udp),
             (ipv6, udp),
+            (tcp, ssh),
             (tcp, tls),
+            (tcp, dns),
+            (tcp, syslog),
+            (tcp, sip),
+            (tcp, websocket),
+            (tcp, http2),
             (tcp, http),
+            (http2, grpc),
             (tls, http),
+            (http, websocket),
+            (udp, dns),
+            (udp, mdns),
+            (udp, llmnr),
+            (udp, dhcp),
+            (udp, dhcpv6),
+            (udp, snmp),
+            (udp, syslog),
+            (udp, sip),
+            (udp, dtls),
+            (udp, rtp),
             (udp, ntp),
         ]);
 
@@ -0,0 +1,73 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+#[test]
+fn ber_element_success() {
+    let (element, rest) = BerElement::parse(&[0x30, 0x03, 0x02, 0x01, 0x05, 0xff]).unwrap();
+    assert_eq!(element.tag,
+               BerTag {
+                   class: BerClass::Universal,
+                   constructed: true,
+                   number: 16,
+               });
+    assert_eq!(rest, &[0xff]);
+    let children = element.children().unwrap();
+    assert_eq!(children.len(), 1);
+    assert!(children[0].tag.is_universal(2));
+    assert_eq!(children[0].integer(), Some(5));
+    assert_eq!(children[0].children(), None);
+
+    // High tag number and long length form
+    let mut input = vec![0x5f, 0x81, 0x02, 0x81, 0x80];
+    input.extend_from_slice(&[0xaa; 128]);
+    let (element, rest) = BerElement::parse(&input).unwrap();
+    assert_eq!(element.tag,
+               BerTag {
+                   class: BerClass::Application,
+                   constructed: false,
+                   number: 130,
+               });
+    assert_eq!(element.content.len(), 128);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn ber_values_success() {
+    let integer = |content: &'static [u8]| {
+        BerElement {
+            tag: BerTag {
+                class: BerClass::Universal,
+                constructed: false,
+                number: 2,
+            },
+            content: content,
+        }
+    };
+    assert_eq!(integer(&[0x00]).integer(), Some(0));
+    assert_eq!(integer(&[0x7f]).integer(), Some(127));
+    assert_eq!(integer(&[0x00, 0x80]).integer(), Some(128));
+    assert_eq!(integer(&[0x80]).integer(), Some(-128));
+    assert_eq!(integer(&[0xff, 0x7f]).integer(), Some(-129));
+    assert_eq!(integer(&[]).integer(), None);
+    assert_eq!(integer(&[0x00, 0xff, 0xff, 0xff, 0xff]).unsigned(), Some(0xffffffff));
+    assert_eq!(integer(&[0xff, 0xff]).unsigned(), Some(0xffff));
+    assert_eq!(integer(&[0x01; 9]).unsigned(), None);
+
+    let oid = integer(&[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x15, 0x14]).object_identifier().unwrap();
+    assert_eq!(oid.to_string(), "1.3.6.1.4.1.311.21.20");
+    assert!(oid.starts_with(&BerObjectIdentifier(vec![1, 3, 6, 1, 4, 1])));
+    assert!(!oid.starts_with(&BerObjectIdentifier(vec![1, 3, 6, 1, 2])));
+    assert_eq!(integer(&[0x88, 0x37, 0x03]).object_identifier(),
+               Some(BerObjectIdentifier(vec![2, 999, 3])));
+    assert_eq!(integer(&[0x2b, 0x86]).object_identifier(), None);
+}
+
+#[test]
+fn ber_element_failure() {
+    assert_eq!(BerElement::parse(&[]), None);
+    assert_eq!(BerElement::parse(&[0x02]), None);
+    assert_eq!(BerElement::parse(&[0x02, 0x02, 0x01]), None);
+    assert_eq!(BerElement::parse(&[0x30, 0x80, 0x00, 0x00]), None);
+    assert_eq!(BerElement::parse(&[0x04, 0x85, 0x01, 0x00, 0x00, 0x00, 0x00]), None);
+    assert_eq!(BerElement::parse_all(&[0x05, 0x00, 0x05]), None);
+}
@@ -0,0 +1,48 @@
+//! Packets shared by the integration tests
+#![allow(dead_code)]
+
+/// An Ethernet, IPv4 and TCP packet from 10.0.0.101:51781 to 66.196.65.112 without payload
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x00, 0x00, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+/// Build a segment from the client to the given server port
+pub fn request(port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = (port >> 8) as u8;
+    packet[37] = port as u8;
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Build a segment from the given server port to the client
+pub fn response(port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = request(port, payload);
+    for i in 0..4 {
+        packet.swap(26 + i, 30 + i);
+    }
+    for i in 0..2 {
+        packet.swap(34 + i, 36 + i);
+    }
+    packet
+}
+
+/// Set the FIN flag of a segment, which closes its direction of the connection
+pub fn finish(mut packet: Vec<u8>) -> Vec<u8> {
+    packet[47] |= 0x01;
+    packet
+}
+
+/// Build an Ethernet frame of an IPv4 UDP datagram between the given hosts of 192.0.2.0/24
+pub fn udp(source: (u8, u16), destination: (u8, u16), payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00];
+    let length = payload.len() + 8;
+    packet.extend_from_slice(&[0x45, 0x00, ((length + 20) >> 8) as u8, (length + 20) as u8, 0x00, 0x00, 0x40, 0x00,
+                               0x40, 0x11, 0x00, 0x00, 192, 0, 2, source.0, 192, 0, 2, destination.0]);
+    packet.extend_from_slice(&[(source.1 >> 8) as u8, source.1 as u8, (destination.1 >> 8) as u8,
+                               destination.1 as u8, (length >> 8) as u8, length as u8, 0x00, 0x00]);
+    packet.extend_from_slice(payload);
+    packet
+}
@@ -0,0 +1,165 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x08, 0x00, 0x45, 0x00, 0x01, 0x48,
+      0x00, 0x00, 0x00, 0x00, 0xfa, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x44,
+      0x00, 0x43, 0x01, 0x34, 0x00, 0x00];
+
+fn message(op: u8, options: &[u8]) -> Vec<u8> {
+    let mut message = vec![op, 0x01, 0x06, 0x00, 0x3d, 0x1d, 0x63, 0x2a, 0x00, 0x04, 0x80, 0x00];
+    message.extend_from_slice(&[0, 0, 0, 0, 192, 168, 0, 10, 192, 168, 0, 1, 10, 0, 0, 1]);
+    message.extend_from_slice(&[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42]);
+    message.extend_from_slice(&[0; 10 + 64 + 128]);
+    if !options.is_empty() {
+        message.extend_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        message.extend_from_slice(options);
+    }
+    message
+}
+
+fn parse(input: &[u8]) -> DhcpPacket {
+    match DhcpParser.parse(input, None, None).unwrap().1.downcast::<DhcpPacket>() {
+        Ok(dhcp) => *dhcp,
+        Err(_) => panic!("no DHCP packet"),
+    }
+}
+
+#[test]
+fn parse_dhcp_discover_success() {
+    let mut parser = DhcpParser;
+    println!("{}", parser);
+    let discover = message(1,
+                           &[53, 1, 1, 61, 7, 1, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 50, 4, 192, 168, 0, 10, 12, 4,
+                             b'h', b'o', b's', b't', 60, 8, b'M', b'S', b'F', b'T', b' ', b'5', b'.', b'0', 55, 4, 1,
+                             3, 6, 15, 57, 2, 0x05, 0xdc, 82, 10, 1, 2, 0x00, 0x07, 2, 4, 0xde, 0xad, 0xbe, 0xef,
+                             0, 0, 255, 0, 0]);
+    let result = parser.parse(&discover, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&DhcpPacket {
+                   op: DhcpOperation::BootRequest,
+                   htype: 1,
+                   hlen: 6,
+                   hops: 0,
+                   xid: 0x3d1d632a,
+                   secs: 4,
+                   broadcast: true,
+                   ciaddr: Ipv4Addr::new(0, 0, 0, 0),
+                   yiaddr: Ipv4Addr::new(192, 168, 0, 10),
+                   siaddr: Ipv4Addr::new(192, 168, 0, 1),
+                   giaddr: Ipv4Addr::new(10, 0, 0, 1),
+                   chaddr: DhcpHardwareAddress::Ethernet(MacAddress(0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42)),
+                   sname: String::new(),
+                   file: String::new(),
+                   options: vec![DhcpOption::MessageType(DhcpMessageType::Discover),
+                                 DhcpOption::ClientIdentifier {
+                                     hardware_type: 1,
+                                     identifier: vec![0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42],
+                                 },
+                                 DhcpOption::RequestedIpAddress(Ipv4Addr::new(192, 168, 0, 10)),
+                                 DhcpOption::HostName("host".to_owned()),
+                                 DhcpOption::VendorClassIdentifier("MSFT 5.0".to_owned()),
+                                 DhcpOption::ParameterRequestList(vec![1, 3, 6, 15]),
+                                 DhcpOption::MaximumMessageSize(1500),
+                                 DhcpOption::RelayAgentInformation(vec![
+                                     DhcpRelayAgentOption::CircuitId(vec![0x00, 0x07]),
+                                     DhcpRelayAgentOption::RemoteId(vec![0xde, 0xad, 0xbe, 0xef]),
+                                 ])],
+                   options_truncated: false,
+               }));
+}
+
+#[test]
+fn parse_dhcp_ack_success() {
+    let ack = parse(&message(2,
+                             &[53, 1, 5, 54, 4, 192, 168, 0, 1, 51, 4, 0x00, 0x01, 0x51, 0x80, 58, 4, 0x00, 0x00,
+                               0xa8, 0xc0, 59, 4, 0x00, 0x01, 0x27, 0x50, 1, 4, 255, 255, 255, 0, 3, 4, 192, 168, 0,
+                               1, 6, 8, 8, 8, 8, 8, 1, 1, 1, 1, 15, 3, b'l', b'a', b'n', 15, 3, b'.', b'i', b'o', 99,
+                               3, 1, 2, 3, 255]));
+    assert_eq!(ack.op, DhcpOperation::BootReply);
+    assert_eq!(ack.message_type(), Some(DhcpMessageType::Ack));
+    assert_eq!(ack.options,
+               vec![DhcpOption::MessageType(DhcpMessageType::Ack),
+                    DhcpOption::ServerIdentifier(Ipv4Addr::new(192, 168, 0, 1)),
+                    DhcpOption::LeaseTime(86400),
+                    DhcpOption::RenewalTime(43200),
+                    DhcpOption::RebindingTime(75600),
+                    DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0)),
+                    DhcpOption::Router(vec![Ipv4Addr::new(192, 168, 0, 1)]),
+                    DhcpOption::DomainNameServer(vec![Ipv4Addr::new(8, 8, 8, 8), Ipv4Addr::new(1, 1, 1, 1)]),
+                    DhcpOption::DomainName("lan.io".to_owned()),
+                    DhcpOption::Other(99, vec![1, 2, 3])]);
+}
+
+#[test]
+fn parse_dhcp_overload_and_bootp_success() {
+    // The file field contains further options
+    let mut overloaded = message(2, &[53, 1, 2, 52, 1, 1, 255]);
+    overloaded[108] = 12;
+    overloaded[109] = 3;
+    overloaded[110..113].copy_from_slice(b"pxe");
+    overloaded[113] = 255;
+    let offer = parse(&overloaded);
+    assert_eq!(offer.file, "");
+    assert_eq!(offer.options,
+               vec![DhcpOption::MessageType(DhcpMessageType::Offer), DhcpOption::HostName("pxe".to_owned())]);
+
+    // Plain BOOTP without the magic cookie
+    let mut bootp = message(1, &[]);
+    bootp[44..48].copy_from_slice(b"boot");
+    let bootp = parse(&bootp);
+    assert_eq!(bootp.sname, "boot");
+    assert_eq!(bootp.message_type(), None);
+    assert!(bootp.options.is_empty());
+}
+
+#[test]
+fn parse_dhcp_failure() {
+    let mut parser = DhcpParser;
+    assert!(parser.parse(&message(1, &[])[..235], None, None).to_full_result().is_err());
+    assert!(parser.parse(&message(3, &[]), None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_dhcp_truncated_options_success() {
+    // The header is kept with the options before the malformation
+    let request = parse(&message(1, &[53, 1, 3, 50, 4, 192, 168]));
+    assert_eq!(request.xid, 0x3d1d632a);
+    assert_eq!(request.message_type(), Some(DhcpMessageType::Request));
+    assert_eq!(request.options.len(), 1);
+    assert!(request.options_truncated);
+
+    let mut overloaded = message(2, &[53, 1, 2, 52, 1, 1, 255]);
+    overloaded[108] = 12;
+    overloaded[109] = 200;
+    let offer = parse(&overloaded);
+    assert_eq!(offer.options, vec![DhcpOption::MessageType(DhcpMessageType::Offer)]);
+    assert!(offer.options_truncated);
+}
+
+#[test]
+fn parse_dhcp_hardware_address_success() {
+    let mut message = message(1, &[53, 1, 1, 255]);
+    message[1] = 32;
+    message[2] = 20;
+    let infiniband = parse(&message);
+    assert_eq!(infiniband.chaddr,
+               DhcpHardwareAddress::Other(vec![0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+
+    message[1] = 6;
+    message[2] = 4;
+    assert_eq!(parse(&message).chaddr, DhcpHardwareAddress::Other(vec![0x00, 0x0b, 0x82, 0x01]));
+}
+
+#[test]
+fn peel_dhcp_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(&message(1, &[53, 1, 1, 255]));
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<DhcpPacket>().unwrap().message_type(),
+               Some(DhcpMessageType::Discover));
+}
@@ -0,0 +1,187 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x33, 0x33, 0x00, 0x01, 0x00, 0x02, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x26, 0x11, 0x01, 0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x0b, 0x82, 0xff, 0xfe, 0x01,
+      0xfc, 0x42, 0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02,
+      0x02, 0x22, 0x02, 0x23, 0x00, 0x26, 0x00, 0x00];
+
+static SOLICIT: &'static [u8] = &[0x01, 0x10, 0x08, 0x74, 0x00, 0x01, 0x00, 0x0e, 0x00, 0x01, 0x00, 0x01, 0x1c, 0x39,
+                                  0xcf, 0x88, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x00, 0x08, 0x00, 0x02, 0x00, 0x00,
+                                  0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18, 0x00, 0x0e, 0x00, 0x00, 0x00, 0x03,
+                                  0x00, 0x0c, 0x02, 0x0b, 0x82, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+fn option(code: u16, data: &[u8]) -> Vec<u8> {
+    let mut option = vec![(code >> 8) as u8, code as u8, (data.len() >> 8) as u8, data.len() as u8];
+    option.extend_from_slice(data);
+    option
+}
+
+fn parse(input: &[u8]) -> Dhcpv6Packet {
+    match Dhcpv6Parser.parse(input, None, None).unwrap().1.downcast::<Dhcpv6Packet>() {
+        Ok(dhcpv6) => *dhcpv6,
+        Err(_) => panic!("no DHCPv6 packet"),
+    }
+}
+
+#[test]
+fn parse_dhcpv6_solicit_success() {
+    let mut parser = Dhcpv6Parser;
+    println!("{}", parser);
+    let result = parser.parse(SOLICIT, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&Dhcpv6Packet {
+                   message_type: Dhcpv6MessageType::Solicit,
+                   header: Dhcpv6Header::Client { transaction_id: 0x100874 },
+                   options: vec![Dhcpv6Option::ClientId(Dhcpv6Duid::LinkLayerTime {
+                                     hardware_type: 1,
+                                     time: 0x1c39cf88,
+                                     address: vec![0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42],
+                                 }),
+                                 Dhcpv6Option::ElapsedTime(0),
+                                 Dhcpv6Option::OptionRequest(vec![23, 24]),
+                                 Dhcpv6Option::RapidCommit,
+                                 Dhcpv6Option::IaNa {
+                                     iaid: 0x020b8201,
+                                     t1: 0,
+                                     t2: 0,
+                                     options: vec![],
+                                 }],
+               }));
+}
+
+#[test]
+fn parse_dhcpv6_reply_success() {
+    let address: Ipv6Addr = "2001:db8::10".parse().unwrap();
+    let prefix: Ipv6Addr = "2001:db8:1200::".parse().unwrap();
+    let mut ia_address = address.octets().to_vec();
+    ia_address.extend_from_slice(&[0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x1c, 0x20]);
+    ia_address.extend(option(13, b"\x00\x00Assigned"));
+    let mut ia_na = vec![0x02, 0x0b, 0x82, 0x01, 0x00, 0x00, 0x07, 0x08, 0x00, 0x00, 0x0b, 0x40];
+    ia_na.extend(option(5, &ia_address));
+    let mut ia_prefix = vec![0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x1c, 0x20, 56];
+    ia_prefix.extend_from_slice(&prefix.octets());
+    let mut ia_pd = vec![0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x07, 0x08, 0x00, 0x00, 0x0b, 0x40];
+    ia_pd.extend(option(26, &ia_prefix));
+    let mut dns_servers = "2001:4860:4860::8888".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+    dns_servers.extend_from_slice(&"2001:4860:4860::8844".parse::<Ipv6Addr>().unwrap().octets());
+
+    let mut reply = vec![0x07, 0x10, 0x08, 0x74];
+    reply.extend(option(2, &[0x00, 0x03, 0x00, 0x01, 0x00, 0x0b, 0x82, 0x0a, 0x0b, 0x0c]));
+    reply.extend(option(3, &ia_na));
+    reply.extend(option(25, &ia_pd));
+    reply.extend(option(23, &dns_servers));
+    reply.extend(option(24, b"\x03lan\x07example\x00\x04corp\x00"));
+    reply.extend(option(13, b"\x00\x06"));
+    reply.extend(option(99, &[0x01]));
+
+    let reply = parse(&reply);
+    assert_eq!(reply.message_type, Dhcpv6MessageType::Reply);
+    assert_eq!(reply.server_id(),
+               Some(&Dhcpv6Duid::LinkLayer {
+                   hardware_type: 1,
+                   address: vec![0x00, 0x0b, 0x82, 0x0a, 0x0b, 0x0c],
+               }));
+    assert_eq!(reply.client_id(), None);
+    assert_eq!(reply.options[1..],
+               [Dhcpv6Option::IaNa {
+                    iaid: 0x020b8201,
+                    t1: 1800,
+                    t2: 2880,
+                    options: vec![Dhcpv6Option::IaAddress {
+                                      address: address,
+                                      preferred_lifetime: 3600,
+                                      valid_lifetime: 7200,
+                                      options: vec![Dhcpv6Option::StatusCode {
+                                                        code: Dhcpv6StatusCode::Success,
+                                                        message: "Assigned".to_owned(),
+                                                    }],
+                                  }],
+                },
+                Dhcpv6Option::IaPd {
+                    iaid: 1,
+                    t1: 1800,
+                    t2: 2880,
+                    options: vec![Dhcpv6Option::IaPrefix {
+                                      preferred_lifetime: 3600,
+                                      valid_lifetime: 7200,
+                                      prefix_length: 56,
+                                      prefix: prefix,
+                                      options: vec![],
+                                  }],
+                },
+                Dhcpv6Option::DnsServers(vec!["2001:4860:4860::8888".parse().unwrap(),
+                                              "2001:4860:4860::8844".parse().unwrap()]),
+                Dhcpv6Option::DomainList(vec!["lan.example".to_owned(), "corp".to_owned()]),
+                Dhcpv6Option::StatusCode {
+                    code: Dhcpv6StatusCode::NoPrefixAvailable,
+                    message: String::new(),
+                },
+                Dhcpv6Option::Other(99, vec![0x01])]);
+}
+
+#[test]
+fn parse_dhcpv6_relay_forward_success() {
+    let link: Ipv6Addr = "2001:db8::1".parse().unwrap();
+    let peer: Ipv6Addr = "fe80::20b:82ff:fe01:fc42".parse().unwrap();
+    let mut inner = vec![0x0c, 0x00];
+    inner.extend_from_slice(&link.octets());
+    inner.extend_from_slice(&peer.octets());
+    inner.extend(option(18, b"eth0"));
+    inner.extend(option(37, &[0x00, 0x00, 0x0d, 0xe9, 0x01, 0x02]));
+    inner.extend(option(9, SOLICIT));
+    let mut outer = vec![0x0c, 0x01];
+    outer.extend_from_slice(&[0; 16]);
+    outer.extend_from_slice(&link.octets());
+    outer.extend(option(9, &inner));
+
+    let relay = parse(&outer);
+    assert_eq!(relay.message_type, Dhcpv6MessageType::RelayForward);
+    assert_eq!(relay.header,
+               Dhcpv6Header::Relay {
+                   hop_count: 1,
+                   link_address: Ipv6Addr::from([0; 16]),
+                   peer_address: link,
+               });
+    let inner = relay.relay_message().unwrap();
+    assert_eq!(inner.header,
+               Dhcpv6Header::Relay {
+                   hop_count: 0,
+                   link_address: link,
+                   peer_address: peer,
+               });
+    assert_eq!(inner.options[..2],
+               [Dhcpv6Option::InterfaceId(b"eth0".to_vec()),
+                Dhcpv6Option::RemoteId {
+                    enterprise_number: 3561,
+                    remote_id: vec![0x01, 0x02],
+                }]);
+    let solicit = relay.client_message();
+    assert_eq!(solicit.message_type, Dhcpv6MessageType::Solicit);
+    assert_eq!(solicit.header, Dhcpv6Header::Client { transaction_id: 0x100874 });
+    assert!(solicit.relay_message().is_none());
+    assert_eq!(&parse(SOLICIT), solicit);
+}
+
+#[test]
+fn parse_dhcpv6_failure() {
+    let mut parser = Dhcpv6Parser;
+    assert!(parser.parse(&[], None, None).to_full_result().is_err());
+    assert!(parser.parse(&SOLICIT[..3], None, None).to_full_result().is_err());
+    assert!(parser.parse(&SOLICIT[..SOLICIT.len() - 1], None, None).to_full_result().is_err());
+    assert!(parser.parse(&[0x0c, 0x00, 0x20, 0x01], None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_dhcpv6_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet.extend_from_slice(SOLICIT);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<Dhcpv6Packet>().unwrap().client_message().message_type,
+               Dhcpv6MessageType::Solicit);
+}
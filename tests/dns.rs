@@ -0,0 +1,286 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x4c,
+      0x12, 0x34, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x65, 0x08, 0x08, 0x08, 0x08, 0xd4, 0x31,
+      0x00, 0x35, 0x00, 0x38, 0x00, 0x00];
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x00, 0x35, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x03, 0xc6, 0x3d, 0x00, 0x03, 0xc6, 0x3d];
+
+static QUERY: &'static [u8] =
+    &[0x12, 0x34, 0x01, 0x20, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70,
+      0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x29, 0x04, 0xd0, 0x00, 0x00,
+      0x80, 0x00, 0x00, 0x17, 0x00, 0x08, 0x00, 0x07, 0x00, 0x01, 0x18, 0x00, 0xc0, 0x00, 0x02, 0x00, 0x0a, 0x00,
+      0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+
+static RESPONSE: &'static [u8] =
+    &[0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x03, 0x77, 0x77, 0x77, 0x07, 0x65,
+      0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0x01, 0xc0, 0x0c, 0x00,
+      0x05, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x02, 0xc0, 0x10, 0xc0, 0x10, 0x00, 0x01, 0x00, 0x01, 0x00,
+      0x00, 0x01, 0x2c, 0x00, 0x04, 0x5d, 0xb8, 0xd8, 0x22];
+
+fn decode(input: &[u8]) -> DnsMessage {
+    let result = DnsParser::new().parse(input, None, None).unwrap().1;
+    match result.downcast::<DnsPacket>() {
+        Ok(mut packet) => packet.messages.remove(0),
+        Err(_) => panic!("no DNS packet"),
+    }
+}
+
+fn name(name: &str) -> Vec<u8> {
+    let mut encoded = vec![];
+    for label in name.split('.') {
+        encoded.push(label.len() as u8);
+        encoded.extend_from_slice(label.as_bytes());
+    }
+    encoded.push(0);
+    encoded
+}
+
+fn answers(records: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut message = vec![0xab, 0xcd, 0x84, 0x00, 0x00, 0x00, 0x00, records.len() as u8, 0x00, 0x00, 0x00, 0x00];
+    for &(record_type, ref data) in records {
+        message.extend_from_slice(&name("example.org"));
+        message.extend_from_slice(&[(record_type >> 8) as u8, record_type as u8, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10]);
+        message.extend_from_slice(&[(data.len() >> 8) as u8, data.len() as u8]);
+        message.extend_from_slice(data);
+    }
+    message
+}
+
+#[test]
+fn parse_dns_query_success() {
+    let mut parser = DnsParser::new();
+    println!("{}", parser);
+    let result = parser.parse(QUERY, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&DnsPacket {
+                   messages: vec![DnsMessage {
+                                      id: 0x1234,
+                                      response: false,
+                                      opcode: DnsOpcode::Query,
+                                      authoritative: false,
+                                      truncated: false,
+                                      recursion_desired: true,
+                                      recursion_available: false,
+                                      authentic_data: true,
+                                      checking_disabled: false,
+                                      rcode: DnsResponseCode::NoError,
+                                      questions: vec![DnsQuestion {
+                                                          name: "example.com".to_owned(),
+                                                          record_type: DnsRecordType::A,
+                                                          class: DnsClass::Internet,
+                                                          unicast_response: false,
+                                                      }],
+                                      answers: vec![],
+                                      authorities: vec![],
+                                      additionals: vec![],
+                                      edns: Some(DnsEdns {
+                                          udp_payload_size: 1232,
+                                          extended_rcode: 0,
+                                          version: 0,
+                                          dnssec_ok: true,
+                                          options: vec![DnsEdnsOption::ClientSubnet {
+                                                            source_prefix_length: 24,
+                                                            scope_prefix_length: 0,
+                                                            address: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)),
+                                                        },
+                                                        DnsEdnsOption::Cookie {
+                                                            client: vec![1, 2, 3, 4, 5, 6, 7, 8],
+                                                            server: None,
+                                                        }],
+                                      }),
+                                  }],
+               }));
+}
+
+#[test]
+fn parse_dns_compressed_response_success() {
+    let message = decode(RESPONSE);
+    assert!(message.response);
+    assert!(message.recursion_available);
+    assert_eq!(message.questions[0].name, "www.example.com");
+    assert_eq!(message.answers,
+               vec![DnsRecord {
+                        name: "www.example.com".to_owned(),
+                        record_type: DnsRecordType::Cname,
+                        class: DnsClass::Internet,
+                        cache_flush: false,
+                        ttl: 300,
+                        data: DnsRecordData::Cname("example.com".to_owned()),
+                    },
+                    DnsRecord {
+                        name: "example.com".to_owned(),
+                        record_type: DnsRecordType::A,
+                        class: DnsClass::Internet,
+                        cache_flush: false,
+                        ttl: 300,
+                        data: DnsRecordData::A(Ipv4Addr::new(93, 184, 216, 34)),
+                    }]);
+}
+
+#[test]
+fn parse_dns_record_data_success() {
+    let mut mx = vec![0x00, 0x0a];
+    mx.extend_from_slice(&name("mail.example.org"));
+    let mut soa = name("ns1.example.org");
+    soa.extend_from_slice(&name("hostmaster.example.org"));
+    soa.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0x1c, 0x20, 0, 0, 0x0e, 0x10, 0, 0x12, 0x75, 0, 0, 0, 0x01, 0x2c]);
+    let mut srv = vec![0x00, 0x01, 0x00, 0x05, 0x01, 0xbb];
+    srv.extend_from_slice(&name("sip.example.org"));
+    let mut https = vec![0x00, 0x01, 0x00];
+    https.extend_from_slice(&[0x00, 0x01, 0x00, 0x06, 0x02, b'h', b'2', 0x02, b'h', b'3']);
+    https.extend_from_slice(&[0x00, 0x03, 0x00, 0x02, 0x01, 0xbb]);
+    https.extend_from_slice(&[0x00, 0x04, 0x00, 0x04, 192, 0, 2, 1]);
+    let mut rrsig = vec![0x00, 0x01, 0x0d, 0x02, 0x00, 0x00, 0x0e, 0x10];
+    rrsig.extend_from_slice(&[0x65, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00, 0x30, 0x39]);
+    rrsig.extend_from_slice(&name("example.org"));
+    rrsig.extend_from_slice(&[0xaa; 4]);
+
+    let message = decode(&answers(&[(28, vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+                                    (2, name("ns1.example.org")),
+                                    (12, name("host.example.org")),
+                                    (15, mx),
+                                    (16, vec![3, b'a', b'=', b'b', 0]),
+                                    (6, soa),
+                                    (33, srv),
+                                    (257, vec![0x80, 0x05, b'i', b's', b's', b'u', b'e', b'c', b'a']),
+                                    (65, https),
+                                    (43, vec![0x30, 0x39, 0x0d, 0x02, 0xde, 0xad]),
+                                    (48, vec![0x01, 0x01, 0x03, 0x0d, 0xbe, 0xef]),
+                                    (46, rrsig),
+                                    (99, vec![1, 2, 3]),
+                                    (1, vec![1, 2, 3])]));
+    assert!(message.authoritative);
+    let data: Vec<DnsRecordData> = message.answers.into_iter().map(|record| record.data).collect();
+    assert_eq!(data,
+               vec![DnsRecordData::Aaaa("2001:db8::1".parse().unwrap()),
+                    DnsRecordData::Ns("ns1.example.org".to_owned()),
+                    DnsRecordData::Ptr("host.example.org".to_owned()),
+                    DnsRecordData::Mx {
+                        preference: 10,
+                        exchange: "mail.example.org".to_owned(),
+                    },
+                    DnsRecordData::Txt(vec![b"a=b".to_vec(), vec![]]),
+                    DnsRecordData::Soa {
+                        mname: "ns1.example.org".to_owned(),
+                        rname: "hostmaster.example.org".to_owned(),
+                        serial: 1,
+                        refresh: 7200,
+                        retry: 3600,
+                        expire: 1_209_600,
+                        minimum: 300,
+                    },
+                    DnsRecordData::Srv {
+                        priority: 1,
+                        weight: 5,
+                        port: 443,
+                        target: "sip.example.org".to_owned(),
+                    },
+                    DnsRecordData::Caa {
+                        critical: true,
+                        tag: "issue".to_owned(),
+                        value: b"ca".to_vec(),
+                    },
+                    DnsRecordData::Svcb {
+                        priority: 1,
+                        target: ".".to_owned(),
+                        params: vec![DnsSvcParam::Alpn(vec!["h2".to_owned(), "h3".to_owned()]),
+                                     DnsSvcParam::Port(443),
+                                     DnsSvcParam::Ipv4Hint(vec![Ipv4Addr::new(192, 0, 2, 1)])],
+                    },
+                    DnsRecordData::Ds {
+                        key_tag: 12345,
+                        algorithm: 13,
+                        digest_type: 2,
+                        digest: vec![0xde, 0xad],
+                    },
+                    DnsRecordData::Dnskey {
+                        flags: 257,
+                        protocol: 3,
+                        algorithm: 13,
+                        public_key: vec![0xbe, 0xef],
+                    },
+                    DnsRecordData::Rrsig {
+                        type_covered: DnsRecordType::A,
+                        algorithm: 13,
+                        labels: 2,
+                        original_ttl: 3600,
+                        expiration: 0x6500_0000,
+                        inception: 0x6400_0000,
+                        key_tag: 12345,
+                        signer_name: "example.org".to_owned(),
+                        signature: vec![0xaa; 4],
+                    },
+                    DnsRecordData::Other(vec![1, 2, 3]),
+                    DnsRecordData::Other(vec![1, 2, 3])]);
+}
+
+#[test]
+fn parse_dns_extended_rcode_success() {
+    let mut message = vec![0x00, 0x01, 0x81, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    message.extend_from_slice(&[0x00, 0x00, 0x29, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let message = decode(&message);
+    assert_eq!(message.rcode, DnsResponseCode::BadCookie);
+    assert_eq!(message.edns.unwrap().extended_rcode, 1);
+}
+
+#[test]
+fn parse_dns_failure() {
+    let mut parser = DnsParser::new();
+    assert!(parser.parse(&QUERY[..11], None, None).to_full_result().is_err());
+    assert!(parser.parse(&QUERY[..QUERY.len() - 1], None, None).to_full_result().is_err());
+
+    // Compression pointers have to point backwards
+    let mut looped = Vec::from(&RESPONSE[..12]);
+    looped.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01]);
+    assert!(parser.parse(&looped, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_dns_udp_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(QUERY);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    let packet = result[3].downcast_ref::<DnsPacket>().unwrap();
+    assert_eq!(packet.messages[0].questions[0].name, "example.com");
+
+    // Other ports are not decoded
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet[37] = 0x36;
+    packet.extend_from_slice(QUERY);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<DnsPacket>().is_none());
+}
+
+#[test]
+fn peel_dns_tcp_success() {
+    let mut peel = PeelIp::default();
+    let mut stream = vec![0x00, QUERY.len() as u8];
+    stream.extend_from_slice(QUERY);
+    stream.extend_from_slice(&[0x00, QUERY.len() as u8]);
+    stream.extend_from_slice(QUERY);
+
+    // The first message is split over two segments
+    let mut first = Vec::from(PACKET_ETH_IPV4_TCP);
+    first.extend_from_slice(&stream[..20]);
+    let result = peel.traverse(&first, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].downcast_ref::<DnsPacket>().unwrap().messages.is_empty());
+
+    let mut second = Vec::from(PACKET_ETH_IPV4_TCP);
+    second.extend_from_slice(&stream[20..]);
+    let result = peel.traverse(&second, vec![]).result;
+    let packet = result[3].downcast_ref::<DnsPacket>().unwrap();
+    assert_eq!(packet.messages.len(), 2);
+    assert_eq!(packet.messages[1].id, 0x1234);
+}
@@ -0,0 +1,204 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::udp;
+
+static DTLS_CLIENT_HELLO: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x01, 0x00, 0x00, 0x35, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x35, 0xfe, 0xfd, 0x5b, 0x0e, 0x6a, 0x13, 0x8c, 0x44, 0x2a, 0x71, 0x0f,
+      0x9e, 0x52, 0x33, 0xd1, 0x0a, 0x7c, 0x61, 0xe8, 0x04, 0x93, 0x2f, 0xb7, 0x19, 0x5d, 0xc2, 0x86, 0x3e, 0xa0,
+      0x77, 0x08, 0xf1, 0x4b, 0x26, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x2b, 0x01, 0x00, 0x00, 0x09, 0x00, 0x10,
+      0x00, 0x05, 0x00, 0x03, 0x02, 0x68, 0x32];
+
+fn client_hello() -> TlsHandshake {
+    TlsHandshake::ClientHello(TlsClientHello {
+        version: TlsRecordVersion {
+            major: 0xfe,
+            minor: 0xfd,
+        },
+        random: DTLS_CLIENT_HELLO[27..59].to_vec(),
+        session_id: vec![],
+        cookie: Some(vec![]),
+        cipher_suites: vec![0xc02b],
+        compression_methods: vec![0],
+        extensions: vec![TlsExtension::Alpn(vec!["h2".to_owned()])],
+    })
+}
+
+#[test]
+fn parse_dtls_success() {
+    let mut parser = DtlsParser::new();
+    println!("{}", parser);
+    let parsing_result = parser.parse(DTLS_CLIENT_HELLO, None, None).unwrap().1;
+    let res = parsing_result.downcast_ref();
+    assert_eq!(Some(&DtlsPacket {
+                   records: vec![DtlsRecord::Plaintext(DtlsPlaintext {
+                                     content_type: TlsRecordContentType::Handshake,
+                                     version: TlsRecordVersion {
+                                         major: 0xfe,
+                                         minor: 0xff,
+                                     },
+                                     epoch: 0,
+                                     sequence_number: 0,
+                                     length: 65,
+                                     replay: false,
+                                     body: Some(DtlsRecordBody::Handshake(vec![DtlsHandshakeFragment {
+                                                                                   message_type:
+                                                                                       TlsHandshakeType::ClientHello,
+                                                                                   length: 53,
+                                                                                   message_seq: 0,
+                                                                                   fragment_offset: 0,
+                                                                                   fragment_length: 53,
+                                                                                   message: Some(client_hello()),
+                                                                               }])),
+                                 })],
+               }),
+               res);
+}
+
+#[test]
+fn parse_dtls_success_replay() {
+    let mut parser = DtlsParser::new();
+    parser.parse(DTLS_CLIENT_HELLO, None, None).unwrap();
+    let parsing_result = parser.parse(DTLS_CLIENT_HELLO, None, None).unwrap().1;
+    let res: Option<&DtlsPacket> = parsing_result.downcast_ref();
+    match res.unwrap().records[0] {
+        DtlsRecord::Plaintext(ref record) => assert!(record.replay),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_dtls_success_reassembly() {
+    let mut parser = DtlsParser::new();
+
+    // Split the client hello into two fragments
+    let body = &DTLS_CLIENT_HELLO[25..];
+    let mut first = vec![0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x01,
+                         0x00, 0x00, 0x35, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10];
+    first.extend_from_slice(&body[..16]);
+    let mut second = vec![0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x31, 0x01,
+                          0x00, 0x00, 0x35, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x25];
+    second.extend_from_slice(&body[16..]);
+
+    let parsing_result = parser.parse(&first, None, None).unwrap().1;
+    let res: Option<&DtlsPacket> = parsing_result.downcast_ref();
+    match res.unwrap().records[0] {
+        DtlsRecord::Plaintext(DtlsPlaintext { body: Some(DtlsRecordBody::Handshake(ref fragments)), .. }) => {
+            assert_eq!(fragments[0].fragment_length, 16);
+            assert_eq!(fragments[0].message, None);
+        }
+        _ => unreachable!(),
+    }
+
+    let parsing_result = parser.parse(&second, None, None).unwrap().1;
+    let res: Option<&DtlsPacket> = parsing_result.downcast_ref();
+    match res.unwrap().records[0] {
+        DtlsRecord::Plaintext(DtlsPlaintext { body: Some(DtlsRecordBody::Handshake(ref fragments)), .. }) => {
+            assert_eq!(fragments[0].fragment_offset, 16);
+            assert_eq!(fragments[0].message, Some(client_hello()));
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Build a record with a fragment of a client hello of the given length
+fn fragment(sequence_number: u8, message_seq: u8, length: u8, offset: u8, data: &[u8]) -> Vec<u8> {
+    let mut record = vec![0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, sequence_number, 0x00,
+                          12 + data.len() as u8, 0x01, 0x00, 0x00, length, 0x00, message_seq, 0x00, 0x00, offset,
+                          0x00, 0x00, data.len() as u8];
+    record.extend_from_slice(data);
+    record
+}
+
+/// Check if the record completed the client hello
+fn reassembled(parser: &mut DtlsParser, record: &[u8]) -> bool {
+    let parsing_result = parser.parse(record, None, None).unwrap().1;
+    let res: Option<&DtlsPacket> = parsing_result.downcast_ref();
+    match res.unwrap().records[0] {
+        DtlsRecord::Plaintext(DtlsPlaintext { body: Some(DtlsRecordBody::Handshake(ref fragments)), .. }) => {
+            fragments[0].message == Some(client_hello())
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_dtls_success_reassembly_limit() {
+    let mut parser = DtlsParser::new();
+    let body = &DTLS_CLIENT_HELLO[25..];
+
+    // Only a limited amount of messages is reassembled at the same time
+    for message_seq in 0..9 {
+        assert!(!reassembled(&mut parser, &fragment(message_seq, message_seq, 0x35, 0, &body[..16])));
+    }
+    assert!(!reassembled(&mut parser, &fragment(9, 8, 0x35, 16, &body[16..])));
+    assert!(reassembled(&mut parser, &fragment(10, 7, 0x35, 16, &body[16..])));
+
+    // A completed message makes room for the next one
+    assert!(!reassembled(&mut parser, &fragment(11, 8, 0x35, 0, &body[..16])));
+    assert!(reassembled(&mut parser, &fragment(12, 8, 0x35, 16, &body[16..])));
+}
+
+#[test]
+fn parse_dtls_success_ciphertext() {
+    let mut parser = DtlsParser::new();
+    let input = [0x2f, 0x12, 0x34, 0x00, 0x03, 0xaa, 0xbb, 0xcc];
+
+    // Not accepted without a preceding handshake
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    parser.parse(DTLS_CLIENT_HELLO, None, None).unwrap();
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res = parsing_result.downcast_ref();
+    assert_eq!(Some(&DtlsPacket {
+                   records: vec![DtlsRecord::Ciphertext(DtlsCiphertext {
+                                     connection_id: false,
+                                     epoch: 3,
+                                     sequence_number: Some(0x1234),
+                                     length: 3,
+                                 })],
+               }),
+               res);
+}
+
+#[test]
+fn parse_dtls_success_max_connections() {
+    let mut peel = PeelIp::default();
+    let ciphertext = [0x2f, 0x12, 0x34, 0x00, 0x03, 0xaa, 0xbb, 0xcc];
+    let mut is_dtls = |port: u16, payload: &[u8]| {
+        let result = peel.traverse(&udp((101, port), (201, 4433), payload), vec![]).result;
+        result.last().unwrap().downcast_ref::<DtlsPacket>().is_some()
+    };
+
+    // Too many connections start a handshake, where the least recently used ones are dropped
+    assert!(is_dtls(10000, DTLS_CLIENT_HELLO));
+    assert!(is_dtls(10001, DTLS_CLIENT_HELLO));
+    for port in 20000..24095 {
+        if port == 22000 {
+            assert!(is_dtls(10001, &ciphertext));
+        }
+        assert!(is_dtls(port, DTLS_CLIENT_HELLO));
+    }
+    assert!(!is_dtls(10000, &ciphertext));
+    assert!(is_dtls(10001, &ciphertext));
+
+    // The newest connection is still tracked
+    assert!(is_dtls(24094, &ciphertext));
+}
+
+#[test]
+fn parse_dtls_failure_wrong_version() {
+    let mut parser = DtlsParser::new();
+    let mut input = Vec::from(DTLS_CLIENT_HELLO);
+    input[2] = 0x01;
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_dtls_failure_too_small() {
+    let mut parser = DtlsParser::new();
+    assert!(parser.parse(&DTLS_CLIENT_HELLO[..20], None, None).to_full_result().is_err());
+}
@@ -0,0 +1,301 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+/// The compiled descriptor set of the `echo.Echo` service with its `Say` method
+static DESCRIPTOR_SET: &'static [u8] =
+    &[0x0a, 0xd8, 0x01, 0x0a, 0x0a, 0x65, 0x63, 0x68, 0x6f, 0x2e, 0x70, 0x72, 0x6f, 0x74, 0x6f, 0x12, 0x04, 0x65,
+      0x63, 0x68, 0x6f, 0x22, 0x76, 0x0a, 0x0b, 0x45, 0x63, 0x68, 0x6f, 0x52, 0x65, 0x71, 0x75, 0x65, 0x73, 0x74,
+      0x12, 0x0c, 0x0a, 0x04, 0x74, 0x65, 0x78, 0x74, 0x18, 0x01, 0x20, 0x01, 0x28, 0x09, 0x12, 0x0d, 0x0a, 0x05,
+      0x63, 0x6f, 0x75, 0x6e, 0x74, 0x18, 0x02, 0x20, 0x01, 0x28, 0x11, 0x12, 0x0b, 0x0a, 0x03, 0x69, 0x64, 0x73,
+      0x18, 0x03, 0x20, 0x01, 0x28, 0x0d, 0x12, 0x26, 0x0a, 0x05, 0x69, 0x6e, 0x6e, 0x65, 0x72, 0x18, 0x04, 0x20,
+      0x01, 0x28, 0x0b, 0x32, 0x17, 0x2e, 0x65, 0x63, 0x68, 0x6f, 0x2e, 0x45, 0x63, 0x68, 0x6f, 0x52, 0x65, 0x71,
+      0x75, 0x65, 0x73, 0x74, 0x2e, 0x49, 0x6e, 0x6e, 0x65, 0x72, 0x1a, 0x15, 0x0a, 0x05, 0x49, 0x6e, 0x6e, 0x65,
+      0x72, 0x12, 0x0c, 0x0a, 0x04, 0x66, 0x6c, 0x61, 0x67, 0x18, 0x01, 0x20, 0x01, 0x28, 0x08, 0x22, 0x19, 0x0a,
+      0x09, 0x45, 0x63, 0x68, 0x6f, 0x52, 0x65, 0x70, 0x6c, 0x79, 0x12, 0x0c, 0x0a, 0x04, 0x74, 0x65, 0x78, 0x74,
+      0x18, 0x01, 0x20, 0x01, 0x28, 0x09, 0x32, 0x31, 0x0a, 0x04, 0x45, 0x63, 0x68, 0x6f, 0x12, 0x29, 0x0a, 0x03,
+      0x53, 0x61, 0x79, 0x12, 0x11, 0x2e, 0x65, 0x63, 0x68, 0x6f, 0x2e, 0x45, 0x63, 0x68, 0x6f, 0x52, 0x65, 0x71,
+      0x75, 0x65, 0x73, 0x74, 0x1a, 0x0f, 0x2e, 0x65, 0x63, 0x68, 0x6f, 0x2e, 0x45, 0x63, 0x68, 0x6f, 0x52, 0x65,
+      0x70, 0x6c, 0x79];
+
+/// An `echo.EchoRequest` with the text "hi", the count -2, the ids 1 and 300 and a set flag
+static ECHO_REQUEST: &'static [u8] = &[0x0a, 0x02, 0x68, 0x69, 0x10, 0x03, 0x1a, 0x03, 0x01, 0xac, 0x02, 0x22,
+                                       0x02, 0x08, 0x01];
+
+/// The gzip compressed `ECHO_REQUEST`
+static ECHO_REQUEST_GZIP: &'static [u8] = &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xe3,
+                                            0x62, 0xca, 0xc8, 0x14, 0x60, 0x96, 0x62, 0x66, 0x5c, 0xc3, 0xa4,
+                                            0xc4, 0xc4, 0xc1, 0x08, 0x00, 0x36, 0xd5, 0x11, 0x46, 0x0f, 0x00,
+                                            0x00, 0x00];
+
+fn headers(headers: &[(&str, &str)]) -> Vec<HttpHeader> {
+    headers
+        .iter()
+        .map(|&(key, value)| HttpHeader {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            raw_value: None,
+        })
+        .collect()
+}
+
+fn message(from_client: bool, headers: Vec<HttpHeader>, body: Vec<u8>, trailers: Vec<HttpHeader>) -> Http2Message {
+    Http2Message {
+        stream_id: 1,
+        from_client: from_client,
+        promised: false,
+        headers: headers,
+        body: body,
+        trailers: trailers,
+        truncated: false,
+    }
+}
+
+fn request(body: Vec<u8>) -> Http2Message {
+    message(true,
+            headers(&[(":method", "POST"), (":path", "/echo.Echo/Say"), ("content-type", "application/grpc")]),
+            body,
+            vec![])
+}
+
+fn length_prefixed(compressed: bool, data: &[u8]) -> Vec<u8> {
+    let length = data.len();
+    let mut message = vec![compressed as u8, (length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8,
+                           length as u8];
+    message.extend_from_slice(data);
+    message
+}
+
+fn parse(parser: &mut GrpcParser, messages: Vec<Http2Message>) -> Option<Vec<GrpcMessage>> {
+    parse_frames(parser, vec![], messages)
+}
+
+fn parse_frames(parser: &mut GrpcParser, frames: Vec<Http2Frame>, messages: Vec<Http2Message>)
+                -> Option<Vec<GrpcMessage>> {
+    let result: ParserResultVec = vec![Box::new(Http2Packet {
+                                                    preface: false,
+                                                    frames: frames,
+                                                    messages: messages,
+                                                })];
+    match parser.parse(&[], Some(&result), None) {
+        IResult::Done(_, packet) => packet.downcast::<GrpcPacket>().ok().map(|packet| packet.messages),
+        _ => None,
+    }
+}
+
+fn field(number: u32, name: &str, value: ProtobufValue) -> ProtobufField {
+    ProtobufField {
+        number: number,
+        name: Some(name.to_owned()),
+        value: value,
+    }
+}
+
+#[test]
+fn parse_grpc_success_request() {
+    let mut parser = GrpcParser::new();
+    println!("{}", parser);
+    let mut body = length_prefixed(false, ECHO_REQUEST);
+    body.extend_from_slice(&length_prefixed(false, &[0x08, 0x96, 0x01]));
+    let messages = parse(&mut parser, vec![request(body)]);
+    assert_eq!(messages,
+               Some(vec![GrpcMessage {
+                             stream_id: 1,
+                             from_client: true,
+                             service: Some("echo.Echo".to_owned()),
+                             method: Some("Say".to_owned()),
+                             encoding: None,
+                             payloads: vec![GrpcPayload {
+                                                compressed: false,
+                                                data: ECHO_REQUEST.to_vec(),
+                                                decompressed: None,
+                                                fields: Some(vec![ProtobufField {
+                                                                      number: 1,
+                                                                      name: None,
+                                                                      value: ProtobufValue::Bytes(b"hi".to_vec()),
+                                                                  },
+                                                                  ProtobufField {
+                                                                      number: 2,
+                                                                      name: None,
+                                                                      value: ProtobufValue::Varint(3),
+                                                                  },
+                                                                  ProtobufField {
+                                                                      number: 3,
+                                                                      name: None,
+                                                                      value: ProtobufValue::Bytes(vec![0x01, 0xac,
+                                                                                                       0x02]),
+                                                                  },
+                                                                  ProtobufField {
+                                                                      number: 4,
+                                                                      name: None,
+                                                                      value: ProtobufValue::Bytes(vec![0x08, 0x01]),
+                                                                  }]),
+                                            },
+                                            GrpcPayload {
+                                                compressed: false,
+                                                data: vec![0x08, 0x96, 0x01],
+                                                decompressed: None,
+                                                fields: Some(vec![ProtobufField {
+                                                                      number: 1,
+                                                                      name: None,
+                                                                      value: ProtobufValue::Varint(150),
+                                                                  }]),
+                                            }],
+                             truncated: false,
+                             status: None,
+                             status_message: None,
+                         }]));
+}
+
+#[test]
+fn parse_grpc_success_descriptor() {
+    let mut parser = GrpcParser::new();
+    parser.descriptors = ProtobufDescriptorSet::from_bytes(DESCRIPTOR_SET);
+    let messages = parse(&mut parser, vec![request(length_prefixed(false, ECHO_REQUEST))]).unwrap();
+    assert_eq!(messages[0].payloads[0].fields,
+               Some(vec![field(1, "text", ProtobufValue::String("hi".to_owned())),
+                         field(2, "count", ProtobufValue::Int(-2)),
+                         field(3, "ids", ProtobufValue::Uint(1)),
+                         field(3, "ids", ProtobufValue::Uint(300)),
+                         field(4, "inner", ProtobufValue::Message(vec![field(1, "flag", ProtobufValue::Bool(true))]))]));
+}
+
+#[test]
+fn parse_grpc_success_compressed() {
+    let mut parser = GrpcParser::new();
+    parser.descriptors = ProtobufDescriptorSet::from_bytes(DESCRIPTOR_SET);
+    let mut message = request(length_prefixed(true, ECHO_REQUEST_GZIP));
+    message.headers.extend(headers(&[("grpc-encoding", "gzip")]));
+    let messages = parse(&mut parser, vec![message]).unwrap();
+    let payload = &messages[0].payloads[0];
+    assert_eq!(messages[0].encoding, Some("gzip".to_owned()));
+    assert!(payload.compressed);
+    assert_eq!(payload.data, ECHO_REQUEST_GZIP);
+    if cfg!(feature = "gzip") {
+        assert_eq!(payload.decompressed, Some(ECHO_REQUEST.to_vec()));
+        assert_eq!(payload.fields.as_ref().map(|fields| fields.len()), Some(5));
+    } else {
+        assert_eq!(payload.decompressed, None);
+        assert_eq!(payload.fields, None);
+    }
+}
+
+#[test]
+fn parse_grpc_success_response() {
+    let mut parser = GrpcParser::new();
+    parser.descriptors = ProtobufDescriptorSet::from_bytes(DESCRIPTOR_SET);
+    parse(&mut parser, vec![request(vec![])]).unwrap();
+
+    // The response does not contain the path, which is taken from the request
+    let response = message(false,
+                           headers(&[(":status", "200"), ("content-type", "application/grpc+proto")]),
+                           length_prefixed(false, &[0x0a, 0x02, 0x68, 0x69]),
+                           headers(&[("grpc-status", "0"), ("grpc-message", "all%20good%21")]));
+    let messages = parse(&mut parser, vec![response]).unwrap();
+    assert_eq!(messages[0].service, Some("echo.Echo".to_owned()));
+    assert_eq!(messages[0].method, Some("Say".to_owned()));
+    assert_eq!(messages[0].payloads[0].fields,
+               Some(vec![field(1, "text", ProtobufValue::String("hi".to_owned()))]));
+    assert_eq!(messages[0].status, Some(GrpcStatusCode::Ok));
+    assert_eq!(messages[0].status_message, Some("all good!".to_owned()));
+}
+
+#[test]
+fn parse_grpc_success_trailers_only() {
+    let mut parser = GrpcParser::new();
+    let response = message(false,
+                           headers(&[(":status", "200"),
+                                     ("content-type", "application/grpc"),
+                                     ("grpc-status", "12"),
+                                     ("grpc-message", "unknown method %E2%9C%93")]),
+                           vec![],
+                           vec![]);
+    let messages = parse(&mut parser, vec![response]).unwrap();
+    assert_eq!(messages[0].service, None);
+    assert!(messages[0].payloads.is_empty());
+    assert_eq!(messages[0].status, Some(GrpcStatusCode::Unimplemented));
+    assert_eq!(messages[0].status_message, Some("unknown method ✓".to_owned()));
+    assert_eq!(GrpcStatusCode::from_u32(42), GrpcStatusCode::Other(42));
+}
+
+/// Build a response to the call of the given stream and return its method
+fn response_method(parser: &mut GrpcParser, stream_id: u32) -> Option<String> {
+    let mut response = message(false, headers(&[(":status", "200"), ("content-type", "application/grpc")]), vec![],
+                               vec![]);
+    response.stream_id = stream_id;
+    parse(parser, vec![response]).unwrap().remove(0).method
+}
+
+#[test]
+fn parse_grpc_success_reset_stream() {
+    let mut parser = GrpcParser::new();
+    let frame = |stream_id, payload| {
+        Http2Frame {
+            length: 4,
+            flags: 0,
+            stream_id: stream_id,
+            payload: payload,
+        }
+    };
+
+    // A reset stream does not wait for its response any more
+    parse(&mut parser, vec![request(vec![])]).unwrap();
+    assert_eq!(parse_frames(&mut parser, vec![frame(1, Http2FramePayload::RstStream(Http2ErrorCode::Cancel))],
+                            vec![]),
+               None);
+    assert_eq!(response_method(&mut parser, 1), None);
+
+    // A connection error drops all calls
+    parse(&mut parser, vec![request(vec![])]).unwrap();
+    let goaway = Http2FramePayload::Goaway {
+        last_stream_id: 1,
+        error_code: Http2ErrorCode::ProtocolError,
+        debug_data: vec![],
+    };
+    parse_frames(&mut parser, vec![frame(0, goaway)], vec![]);
+    assert_eq!(response_method(&mut parser, 1), None);
+}
+
+#[test]
+fn parse_grpc_success_max_calls() {
+    let mut parser = GrpcParser::new();
+    for stream_id in 0..4097 {
+        let mut request = request(vec![]);
+        request.stream_id = 2 * stream_id + 1;
+        parse(&mut parser, vec![request]).unwrap();
+    }
+
+    // The oldest call was dropped in favor of the newest one
+    assert_eq!(response_method(&mut parser, 1), None);
+    assert_eq!(response_method(&mut parser, 3), Some("Say".to_owned()));
+    assert_eq!(response_method(&mut parser, 8193), Some("Say".to_owned()));
+}
+
+#[test]
+fn parse_grpc_success_incomplete_message() {
+    let mut parser = GrpcParser::new();
+    let mut body = length_prefixed(false, &[0x08, 0x01]);
+    body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x08]);
+    let messages = parse(&mut parser, vec![request(body)]).unwrap();
+    assert_eq!(messages[0].payloads.len(), 1);
+}
+
+#[test]
+fn parse_grpc_failure_no_grpc() {
+    let mut parser = GrpcParser::new();
+    let message = message(true,
+                          headers(&[(":method", "POST"), (":path", "/upload"), ("content-type", "application/json")]),
+                          b"{}".to_vec(),
+                          vec![]);
+    assert_eq!(parse(&mut parser, vec![message]), None);
+    assert_eq!(parse(&mut parser, vec![]), None);
+    assert!(parser.parse(&[], None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_protobuf_failure_invalid() {
+    assert!(ProtobufDescriptorSet::from_bytes(&DESCRIPTOR_SET[..100]).is_none());
+    assert!(ProtobufDescriptorSet::decode_raw(&[0x0a, 0x05, 0x68]).is_none());
+    assert!(ProtobufDescriptorSet::decode_raw(&[0x08, 0xff]).is_none());
+    assert!(ProtobufDescriptorSet::from_file("/nonexistent.desc").is_err());
+}
@@ -0,0 +1,93 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::time::Duration;
+
+mod common;
+use common::{request, response};
+
+fn record(peel: &mut PeelIp, recorder: &mut HarRecorder, packet: &[u8], ms: u64) -> bool {
+    let result = peel.traverse(packet, vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_some());
+    recorder.record(&result, Duration::from_millis(ms))
+}
+
+#[test]
+fn har_export_success() {
+    let mut peel = PeelIp::default();
+    let mut recorder = HarRecorder::new();
+    let packet = request(80, b"GET /a?q=1 HTTP/1.1\r\nHost: example.com\r\nCookie: id=7\r\n\r\n");
+    assert!(!record(&mut peel, &mut recorder, &packet, 1_248_463_230_450));
+    let packet = response(80, b"HTTP/1.1 302 Found\r\nLocation: /b\r\nSet-Cookie: s=\"x\"; Path=/; HttpOnly\r\n\
+                            Content-Length: 3\r\n\r\n\"\\\n");
+    assert!(record(&mut peel, &mut recorder, &packet, 1_248_463_230_575));
+
+    assert_eq!(recorder.entries().len(), 1);
+    assert_eq!(recorder.entries()[0].time(), Duration::from_millis(125));
+    assert_eq!(recorder.to_json(),
+               format!("{{\"log\":{{\"version\":\"1.2\",\"creator\":{{\"name\":\"peel-ip\",\"version\":\"{}\"}},\
+                        \"entries\":[{{\"startedDateTime\":\"2009-07-24T19:20:30.450Z\",\"time\":125,\
+                        \"request\":{{\"method\":\"GET\",\"url\":\"http://example.com/a?q=1\",\
+                        \"httpVersion\":\"HTTP/1.1\",\"cookies\":[{{\"name\":\"id\",\"value\":\"7\"}}],\
+                        \"headers\":[{{\"name\":\"Host\",\"value\":\"example.com\"}},\
+                        {{\"name\":\"Cookie\",\"value\":\"id=7\"}}],\
+                        \"queryString\":[{{\"name\":\"q\",\"value\":\"1\"}}],\"headersSize\":-1,\"bodySize\":0}},\
+                        \"response\":{{\"status\":302,\"statusText\":\"Found\",\"httpVersion\":\"HTTP/1.1\",\
+                        \"cookies\":[{{\"name\":\"s\",\"value\":\"x\",\"path\":\"/\",\"httpOnly\":true,\
+                        \"secure\":false}}],\"headers\":[{{\"name\":\"Location\",\"value\":\"/b\"}},\
+                        {{\"name\":\"Set-Cookie\",\"value\":\"s=\\\"x\\\"; Path=/; HttpOnly\"}},\
+                        {{\"name\":\"Content-Length\",\"value\":\"3\"}}],\
+                        \"content\":{{\"size\":3,\"mimeType\":\"\",\"text\":\"\\\"\\\\\\n\"}},\
+                        \"redirectURL\":\"/b\",\"headersSize\":-1,\"bodySize\":3}},\"cache\":{{}},\
+                        \"timings\":{{\"blocked\":-1,\"dns\":-1,\"connect\":-1,\"send\":0,\"wait\":125,\
+                        \"receive\":0,\"ssl\":-1}},\"serverIPAddress\":\"66.196.65.112\",\
+                        \"connection\":\"10.0.0.101:51781-66.196.65.112:80\"}}]}}}}",
+                       env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn har_export_success_post_data() {
+    let mut peel = PeelIp::default();
+    let mut recorder = HarRecorder::default();
+    let packet = request(80, b"POST /form HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+                           Content-Length: 12\r\n\r\na=1&b=x+y%21");
+    record(&mut peel, &mut recorder, &packet, 0);
+    let packet = response(80, b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 3\r\n\r\n\x89PN");
+    assert!(record(&mut peel, &mut recorder, &packet, 1));
+
+    let json = recorder.entries()[0].to_json();
+    assert!(json.contains("\"postData\":{\"mimeType\":\"application/x-www-form-urlencoded\",\
+                           \"params\":[{\"name\":\"a\",\"value\":\"1\"},{\"name\":\"b\",\"value\":\"x y!\"}],\
+                           \"text\":\"a=1&b=x+y%21\"}"));
+    assert!(json.contains("\"content\":{\"size\":3,\"mimeType\":\"image/png\",\"text\":\"iVBO\",\
+                           \"encoding\":\"base64\"}"));
+    assert!(json.contains("\"url\":\"/form\""));
+}
+
+#[test]
+fn har_export_failure_no_request() {
+    let mut peel = PeelIp::default();
+    let mut recorder = HarRecorder::new();
+    let packet = response(80, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+    assert!(!record(&mut peel, &mut recorder, &packet, 0));
+    assert!(recorder.entries().is_empty());
+    assert_eq!(recorder.to_json(),
+               format!("{{\"log\":{{\"version\":\"1.2\",\"creator\":{{\"name\":\"peel-ip\",\"version\":\"{}\"}},\
+                        \"entries\":[]}}}}",
+                       env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn har_export_success_connections() {
+    let mut peel = PeelIp::default();
+    let mut recorder = HarRecorder::new();
+    for &port in &[80, 8080] {
+        record(&mut peel, &mut recorder, &request(port, b"GET / HTTP/1.1\r\n\r\n"), 0);
+        record(&mut peel, &mut recorder, &response(port, b"HTTP/1.1 204 No Content\r\n\r\n"), 1);
+    }
+
+    // The same client port on different connections is distinguished
+    let connections: Vec<_> = recorder.entries().iter().map(|entry| entry.connection.clone()).collect();
+    assert_eq!(connections,
+               vec![Some("10.0.0.101:51781-66.196.65.112:80".to_owned()),
+                    Some("10.0.0.101:51781-66.196.65.112:8080".to_owned())]);
+}
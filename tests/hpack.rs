@@ -0,0 +1,127 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+fn pairs(headers: Vec<HttpHeader>) -> Vec<(String, String)> {
+    headers.into_iter().map(|h| (h.key, h.value)).collect()
+}
+
+fn header_list(list: &[(&str, &str)]) -> Vec<(String, String)> {
+    list.iter().map(|&(k, v)| (k.to_owned(), v.to_owned())).collect()
+}
+
+#[test]
+fn hpack_decode_success_requests() {
+    // RFC 7541 C.3
+    let mut decoder = HpackDecoder::new();
+    let block = [0x82, 0x86, 0x84, 0x41, 0x0f, 0x77, 0x77, 0x77, 0x2e, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65,
+                 0x2e, 0x63, 0x6f, 0x6d];
+    assert_eq!(pairs(decoder.decode(&block).unwrap()),
+               header_list(&[(":method", "GET"), (":scheme", "http"), (":path", "/"),
+                             (":authority", "www.example.com")]));
+    assert_eq!(decoder.table_size(), 57);
+
+    let block = [0x82, 0x86, 0x84, 0xbe, 0x58, 0x08, 0x6e, 0x6f, 0x2d, 0x63, 0x61, 0x63, 0x68, 0x65];
+    assert_eq!(pairs(decoder.decode(&block).unwrap()),
+               header_list(&[(":method", "GET"), (":scheme", "http"), (":path", "/"),
+                             (":authority", "www.example.com"), ("cache-control", "no-cache")]));
+    assert_eq!(decoder.table_size(), 110);
+
+    let block = [0x82, 0x87, 0x85, 0xbf, 0x40, 0x0a, 0x63, 0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x6b, 0x65, 0x79,
+                 0x0c, 0x63, 0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x76, 0x61, 0x6c, 0x75, 0x65];
+    assert_eq!(pairs(decoder.decode(&block).unwrap()),
+               header_list(&[(":method", "GET"), (":scheme", "https"), (":path", "/index.html"),
+                             (":authority", "www.example.com"), ("custom-key", "custom-value")]));
+    assert_eq!(decoder.table_size(), 164);
+    assert_eq!(decoder.table_len(), 3);
+}
+
+#[test]
+fn hpack_decode_success_huffman_requests() {
+    // RFC 7541 C.4
+    let mut decoder = HpackDecoder::default();
+    let block = [0x82, 0x86, 0x84, 0x41, 0x8c, 0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4,
+                 0xff];
+    assert_eq!(pairs(decoder.decode(&block).unwrap()),
+               header_list(&[(":method", "GET"), (":scheme", "http"), (":path", "/"),
+                             (":authority", "www.example.com")]));
+
+    let block = [0x82, 0x86, 0x84, 0xbe, 0x58, 0x86, 0xa8, 0xeb, 0x10, 0x64, 0x9c, 0xbf];
+    assert_eq!(pairs(decoder.decode(&block).unwrap())[4],
+               ("cache-control".to_owned(), "no-cache".to_owned()));
+
+    let block = [0x82, 0x87, 0x85, 0xbf, 0x40, 0x88, 0x25, 0xa8, 0x49, 0xe9, 0x5b, 0xa9, 0x7d, 0x7f, 0x89, 0x25,
+                 0xa8, 0x49, 0xe9, 0x5b, 0xb8, 0xe8, 0xb4, 0xbf];
+    assert_eq!(pairs(decoder.decode(&block).unwrap())[4],
+               ("custom-key".to_owned(), "custom-value".to_owned()));
+    assert_eq!(decoder.table_size(), 164);
+}
+
+#[test]
+fn hpack_decode_success_eviction() {
+    // RFC 7541 C.6 with a dynamic table size of 256 bytes, which is signaled by the encoder first
+    let mut decoder = HpackDecoder::new();
+    decoder.set_allowed_size(256);
+    decoder.decode(&[0x3f, 0xe1, 0x01]).unwrap();
+    let block = [0x48, 0x82, 0x64, 0x02, 0x58, 0x85, 0xae, 0xc3, 0x77, 0x1a, 0x4b, 0x61, 0x96, 0xd0, 0x7a, 0xbe,
+                 0x94, 0x10, 0x54, 0xd4, 0x44, 0xa8, 0x20, 0x05, 0x95, 0x04, 0x0b, 0x81, 0x66, 0xe0, 0x82, 0xa6,
+                 0x2d, 0x1b, 0xff, 0x6e, 0x91, 0x9d, 0x29, 0xad, 0x17, 0x18, 0x63, 0xc7, 0x8f, 0x0b, 0x97, 0xc8,
+                 0xe9, 0xae, 0x82, 0xae, 0x43, 0xd3];
+    assert_eq!(pairs(decoder.decode(&block).unwrap()),
+               header_list(&[(":status", "302"), ("cache-control", "private"),
+                             ("date", "Mon, 21 Oct 2013 20:13:21 GMT"), ("location", "https://www.example.com")]));
+    assert_eq!(decoder.table_size(), 222);
+
+    let block = [0x48, 0x83, 0x64, 0x0e, 0xff, 0xc1, 0xc0, 0xbf];
+    assert_eq!(pairs(decoder.decode(&block).unwrap())[0], (":status".to_owned(), "307".to_owned()));
+    assert_eq!(decoder.table_size(), 222);
+    assert_eq!(decoder.table_len(), 4);
+}
+
+#[test]
+fn hpack_decode_success_size_update() {
+    let mut decoder = HpackDecoder::new();
+    decoder.decode(&[0x41, 0x03, 0x61, 0x62, 0x63]).unwrap();
+    assert_eq!(decoder.table_len(), 1);
+
+    // A size update to zero empties the table
+    assert_eq!(decoder.decode(&[0x20]).unwrap(), vec![]);
+    assert_eq!(decoder.table_len(), 0);
+    assert_eq!(decoder.table_size(), 0);
+}
+
+#[test]
+fn hpack_decode_success_allowed_size() {
+    let mut decoder = HpackDecoder::new();
+    decoder.decode(&[0x41, 0x03, 0x61, 0x62, 0x63]).unwrap();
+
+    // The table is kept until the encoder signals the new size
+    decoder.set_allowed_size(0);
+    assert_eq!(decoder.table_len(), 1);
+    assert_eq!(pairs(decoder.decode(&[0xbe]).unwrap()), header_list(&[(":authority", "abc")]));
+
+    // A size update above the allowed size is a decoding error
+    assert_eq!(decoder.decode(&[0x3f, 0xe1, 0x01]), None);
+
+    assert_eq!(decoder.decode(&[0x20]).unwrap(), vec![]);
+    assert_eq!(decoder.table_len(), 0);
+}
+
+#[test]
+fn hpack_decode_failure() {
+    // Index out of range
+    assert!(HpackDecoder::new().decode(&[0xbe]).is_none());
+    assert!(HpackDecoder::new().decode(&[0x80]).is_none());
+
+    // String longer than the block
+    assert!(HpackDecoder::new().decode(&[0x40, 0x05, 0x61]).is_none());
+
+    // Padding longer than seven bits and padding which is not a prefix of the end of string symbol
+    assert!(HpackDecoder::new().decode(&[0x00, 0x82, 0x1f, 0xff, 0x00]).is_none());
+    assert!(HpackDecoder::new().decode(&[0x00, 0x81, 0x1e, 0x00]).is_none());
+
+    // Size update above the allowed size
+    assert!(HpackDecoder::new().decode(&[0x3f, 0xe2, 0x1f]).is_none());
+
+    // Integer overflow
+    assert!(HpackDecoder::new().decode(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).is_none());
+}
@@ -2,9 +2,12 @@ extern crate nom;
 extern crate peel_ip;
 use peel_ip::prelude::*;
 
+mod common;
+use common::{finish, request, response};
+
 #[test]
 fn parse_http_request_success_get() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     println!("{}", parser);
     let parsing_result =
         parser.parse(b"GET /some/path/ HTTP/1.0\r\nHost: myhost.com\r\nUser-agent: Myagent/0.1\r\n\r\nTest data",
@@ -24,17 +27,23 @@ fn parse_http_request_success_get() {
                    headers: vec![HttpHeader {
                                      key: "Host".to_owned(),
                                      value: "myhost.com".to_owned(),
+                                     raw_value: None,
                                  },
                                  HttpHeader {
                                      key: "User-agent".to_owned(),
                                      value: "Myagent/0.1".to_owned(),
+                                     raw_value: None,
                                  }],
+                   body: vec![],
+                   trailers: vec![],
+                   decoded_body: None,
+                   tls: false,
                })));
 }
 
 #[test]
 fn parse_http_request_success_post() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let parsing_result = parser.parse(b"POST / HTTP/1.1\r\nHost: abc.com\r\n\r\n", None, None)
         .unwrap()
         .1;
@@ -50,13 +59,18 @@ fn parse_http_request_success_post() {
                    headers: vec![HttpHeader {
                                      key: "Host".to_owned(),
                                      value: "abc.com".to_owned(),
+                                     raw_value: None,
                                  }],
+                   body: vec![],
+                   trailers: vec![],
+                   decoded_body: None,
+                   tls: false,
                })));
 }
 
 #[test]
 fn parse_http_request_success_methods() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let header = " / HTTP/1.1\r\nHost: abc.com\r\n\r\n";
     let methods = ["GET", "POST", "HEAD", "PUT", "DELETE", "TRACE", "OPTIONS", "CONNECT", "PATCH"];
 
@@ -69,37 +83,38 @@ fn parse_http_request_success_methods() {
 
 #[test]
 fn parse_http_request_failure_wrong_method() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     assert!(parser.parse(b"GET", None, None).to_full_result().is_err());
 
-    let input = b"GOT ";
+    // Separators like parentheses are not allowed within the method token
+    let input = b"G(T) / HTTP/1.1\r\nHost: abc.com\r\n\r\n";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_request_failure_wrong_path() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"GET HTTP/1.1";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_request_failure_wrong_version() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"GET / HTTP/1.k";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_request_failure_too_small() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"GET / HTTP/1.";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_response_success_moved() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let parsing_result = parser.parse(b"HTTP/1.1 301 Moved Permanently\r\nLocation: https://facebook.com\r\n\r\n",
                None,
                None)
@@ -117,13 +132,17 @@ fn parse_http_response_success_moved() {
                    headers: vec![HttpHeader {
                                      key: "Location".to_owned(),
                                      value: "https://facebook.com".to_owned(),
+                                     raw_value: None,
                                  }],
+                   body: vec![],
+                   trailers: vec![],
+                   decoded_body: None,
                })));
 }
 
 #[test]
 fn parse_http_response_success_ok() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let parsing_result = parser.parse(b"HTTP/1.0 200 OK\r\nHost: abc.com\r\n\r\n", None, None)
         .unwrap()
         .1;
@@ -139,27 +158,432 @@ fn parse_http_response_success_ok() {
                    headers: vec![HttpHeader {
                                      key: "Host".to_owned(),
                                      value: "abc.com".to_owned(),
+                                     raw_value: None,
                                  }],
+                   body: vec![],
+                   trailers: vec![],
+                   decoded_body: None,
                })));
 }
 
 #[test]
 fn parse_http_response_failure_wrong_protocol() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"HTTk/1.1";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_response_failure_too_small() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"HTTP/1.1";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
 
 #[test]
 fn parse_http_response_failure_wrong_status_code() {
-    let mut parser = HttpParser;
+    let mut parser = HttpParser::new();
     let input = b"HTTP/1.1 20A OK\r\n";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
+
+#[test]
+fn parse_http_request_success_content_length() {
+    let mut parser = HttpParser::new();
+    let parsing_result = parser.parse(b"POST /form HTTP/1.1\r\nContent-Length: 7\r\n\r\na=b&c=dGET", None, None)
+        .unwrap();
+    assert!(parsing_result.0.is_empty());
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.body, b"a=b&c=d"),
+        _ => unreachable!(),
+    }
+
+    // The following request starts within the same segment
+    match parser.parse(b" /next HTTP/1.1\r\n\r\n", None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.path, "/next"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_request_failure_content_length() {
+    let mut parser = HttpParser::new();
+    let input = b"POST /form HTTP/1.1\r\nContent-Length: 10\r\n\r\na=b";
+    assert!(parser.parse(input, None, None).to_full_result().is_err());
+
+    let mut parser = HttpParser::new();
+    let input = b"POST /form HTTP/1.1\r\nContent-Length: abc\r\n\r\na=b";
+    assert!(parser.parse(input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_http_response_success_chunked() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+                  4\r\nWiki\r\n6;name=value\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\n";
+    let parsing_result = parser.parse(input, None, None).unwrap();
+    assert!(parsing_result.0.is_empty());
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.body, &b"Wikipedia in \r\n\r\nchunks."[..]);
+            assert_eq!(response.trailers,
+                       vec![HttpHeader {
+                                key: "Expires".to_owned(),
+                                value: "never".to_owned(),
+                                raw_value: None,
+                            }]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_chunked_without_trailers() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.body, b"abc");
+            assert!(response.trailers.is_empty());
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_close_delimited() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.0 200 OK\r\nServer: test\r\n\r\n<html></html>";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => assert_eq!(response.body, b"<html></html>"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_final() {
+    let mut parser = HttpParser::new();
+    for &(code, is_final) in &[(100, false), (101, true), (103, false), (200, true), (404, true)] {
+        let input = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\n\r\n", code);
+        match parser.parse(input.as_bytes(), None, None).unwrap().1.downcast_ref() {
+            Some(&HttpPacket::Response(ref response)) => assert_eq!(response.is_final(), is_final),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn parse_http_response_success_close_delimited_segmented() {
+    let mut peel = PeelIp::default();
+    peel.traverse(&request(80, b"GET / HTTP/1.0\r\n\r\n"), vec![]);
+
+    // The body continues until the server closes the connection
+    let result = peel.traverse(&response(80, b"HTTP/1.0 200 OK\r\nServer: test\r\n\r\n<html>"), vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_none());
+    let result = peel.traverse(&response(80, b"<body></body>"), vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_none());
+
+    let result = peel.traverse(&finish(response(80, b"</html>")), vec![]).result;
+    match result.last().unwrap().downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => assert_eq!(response.body, b"<html><body></body></html>"),
+        _ => unreachable!(),
+    }
+
+    // A closing segment without payload completes the body as well
+    peel.traverse(&response(80, b"HTTP/1.0 200 OK\r\n\r\nabc"), vec![]);
+    let result = peel.traverse(&finish(response(80, b"")), vec![]).result;
+    match result.last().unwrap().downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => assert_eq!(response.body, b"abc"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_without_body() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 304 Not Modified\r\nContent-Length: 100\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => assert!(response.body.is_empty()),
+        _ => unreachable!(),
+    }
+
+    // Responses to HEAD requests never contain a body
+    parser.parse(b"HEAD / HTTP/1.1\r\nHost: abc.com\r\n\r\n", None, None).unwrap();
+    let input = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => assert!(response.body.is_empty()),
+        _ => unreachable!(),
+    }
+}
+
+static HELLO_GZIP: &'static [u8] = &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xf3, 0x48, 0xcd,
+                                     0xc9, 0xc9, 0xd7, 0x51, 0x28, 0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00, 0xe6,
+                                     0xc6, 0xe6, 0xeb, 0x0d, 0x00, 0x00, 0x00];
+
+fn decoded_body(parser: &mut HttpParser, encoding: &str, body: &[u8]) -> Option<Vec<u8>> {
+    let mut input = format!("HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+                            encoding,
+                            body.len())
+        .into_bytes();
+    input.extend_from_slice(body);
+    match parser.parse(&input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.body, body);
+            response.decoded_body.clone()
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn parse_http_response_success_gzip() {
+    let mut parser = HttpParser::new();
+    assert_eq!(decoded_body(&mut parser, "gzip", HELLO_GZIP), Some(b"Hello, world!".to_vec()));
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn parse_http_response_success_deflate() {
+    let mut parser = HttpParser::new();
+    let zlib = [0x78, 0x9c, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0x28, 0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04,
+                0x00, 0x20, 0x5e, 0x04, 0x8a];
+    assert_eq!(decoded_body(&mut parser, "deflate", &zlib), Some(b"Hello, world!".to_vec()));
+
+    // Raw deflate data without the zlib wrapper
+    assert_eq!(decoded_body(&mut parser, "Deflate", &zlib[2..17]), Some(b"Hello, world!".to_vec()));
+}
+
+#[test]
+#[cfg(feature = "brotli")]
+fn parse_http_response_success_brotli() {
+    let mut parser = HttpParser::new();
+    let mut brotli = vec![0xc0, 0x00, 0x10];
+    brotli.extend_from_slice(b"Hello, world!");
+    brotli.push(0x03);
+    assert_eq!(decoded_body(&mut parser, "br", &brotli), Some(b"Hello, world!".to_vec()));
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn parse_http_response_success_multiple_encodings() {
+    let mut parser = HttpParser::new();
+    assert_eq!(decoded_body(&mut parser, "identity, gzip", HELLO_GZIP), Some(b"Hello, world!".to_vec()));
+}
+
+#[test]
+fn parse_http_response_failure_decoding() {
+    let mut parser = HttpParser::new();
+    assert_eq!(decoded_body(&mut parser, "gzip", b"no gzip data"), None);
+
+    assert_eq!(decoded_body(&mut parser, "compress", HELLO_GZIP), None);
+
+    // Bodies exceeding the maximum decoded size are not decoded
+    parser.max_decoded_size = 12;
+    assert_eq!(decoded_body(&mut parser, "gzip", HELLO_GZIP), None);
+}
+
+#[test]
+fn parse_http_request_success_extension_method() {
+    let mut parser = HttpParser::new();
+    let input = b"PROPFIND /dav/ HTTP/1.1\r\nDepth: 1\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert_eq!(request.request_method, HttpRequestMethod::Extension("PROPFIND".to_owned()));
+            assert_eq!(request.path, "/dav/");
+        }
+        _ => unreachable!(),
+    }
+
+    // Method names are case-sensitive
+    match parser.parse(b"get / HTTP/1.1\r\n\r\n", None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert_eq!(request.request_method, HttpRequestMethod::Extension("get".to_owned()))
+        }
+        _ => unreachable!(),
+    }
+
+    // A misspelled method is a valid token as well
+    match parser.parse(b"GOT / HTTP/1.1\r\nHost: abc.com\r\n\r\n", None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert_eq!(request.request_method, HttpRequestMethod::Extension("GOT".to_owned()))
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_request_success_header_whitespace() {
+    let mut parser = HttpParser::new();
+    let input = b"GET / HTTP/1.1\r\nHost:abc.com\r\nAccept: \t*/* \r\nX-Empty:\r\nX-Folded: first\r\n  second\r\n\tthird\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            let values: Vec<&str> = request.headers.iter().map(|h| h.value.as_str()).collect();
+            assert_eq!(values, vec!["abc.com", "*/*", "", "first second third"]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_bare_line_feed() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\n3\nabc\n0\n\n";
+    let parsing_result = parser.parse(input, None, None).unwrap();
+    assert!(parsing_result.0.is_empty());
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.code, 200);
+            assert_eq!(response.reason, "OK");
+            assert_eq!(response.headers[0].key, "Transfer-Encoding");
+            assert_eq!(response.body, b"abc");
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_response_success_non_utf8_header() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 204\r\nContent-Disposition: attachment; filename=\"caf\xe9.txt\"\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.reason, "");
+            assert_eq!(response.headers,
+                       vec![HttpHeader {
+                                key: "Content-Disposition".to_owned(),
+                                value: "attachment; filename=\"caf\u{fffd}.txt\"".to_owned(),
+                                raw_value: Some(b"attachment; filename=\"caf\xe9.txt\"".to_vec()),
+                            }]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_request_failure_wrong_header() {
+    let mut parser = HttpParser::new();
+    let input = b"GET / HTTP/1.1\r\nHost : abc.com\r\n\r\n";
+    assert!(parser.parse(input, None, None).to_full_result().is_err());
+
+    // A folded line requires a preceding field
+    let input = b"GET / HTTP/1.1\r\n folded\r\n\r\n";
+    assert!(parser.parse(input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_http_request_success_segmented() {
+    let mut parser = HttpParser::new();
+    let input = b"POST /form HTTP/1.1\r\nHost: abc.com\r\nContent-Length: 7\r\n\r\na=b&c=d";
+
+    // The head and body are split over multiple segments
+    match parser.parse(&input[..20], None, None) {
+        IResult::Incomplete(_) => {}
+        _ => unreachable!(),
+    }
+    assert!(parser.parse(&input[20..40], None, None).is_incomplete());
+    assert!(parser.parse(&input[40..60], None, None).is_incomplete());
+    let parsing_result = parser.parse(&input[60..], None, None).unwrap();
+    assert!(parsing_result.0.is_empty());
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert_eq!(request.path, "/form");
+            assert_eq!(request.headers.len(), 2);
+            assert_eq!(request.body, b"a=b&c=d");
+        }
+        _ => unreachable!(),
+    }
+
+    // A new message does not contain the previous data
+    let input = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+    assert!(parser.parse(input, None, None).is_done());
+}
+
+#[test]
+fn parse_http_request_success_pipelined() {
+    let mut parser = HttpParser::new();
+    let input = b"GET /a HTTP/1.1\r\nHost: abc.com\r\n\r\n\
+                  POST /b HTTP/1.1\r\nContent-Length: 3\r\n\r\nx=1GET /c HT";
+    let parsing_result = parser.parse(input, None, None).unwrap();
+    assert!(parsing_result.0.is_empty());
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Pipelined(ref packets)) => {
+            assert_eq!(packets.len(), 2);
+            match (&packets[0], &packets[1]) {
+                (&HttpPacket::Request(ref first), &HttpPacket::Request(ref second)) => {
+                    assert_eq!(first.path, "/a");
+                    assert_eq!(second.path, "/b");
+                    assert_eq!(second.body, b"x=1");
+                }
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    // The incomplete third request was buffered
+    match parser.parse(b"TP/1.1\r\n\r\n", None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.path, "/c"),
+        _ => unreachable!(),
+    }
+
+    // The responses are parsed with the methods of their requests
+    let input = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\nHTTP/1.1 101 Switching Protocols\r\n\r\n\x81\x00";
+    let parsing_result = parser.parse(input, None, None).unwrap();
+    assert_eq!(parsing_result.0, b"\x81\x00");
+    match parsing_result.1.downcast_ref() {
+        Some(&HttpPacket::Pipelined(ref packets)) => assert_eq!(packets.len(), 2),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_request_success_segmented_after_garbage() {
+    let mut parser = HttpParser::new();
+    assert!(parser.parse(b"GET /first HTTP/1.1\r\n", None, None).is_incomplete());
+
+    // Data which does not continue the buffered message starts a new one
+    let input = b"\x00\x01GET /second HTTP/1.1\r\n\r\n";
+    assert!(parser.parse(input, None, None).is_err());
+    match parser.parse(&input[2..], None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.path, "/second"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_request_failure_max_header_size() {
+    let mut parser = HttpParser::new();
+    parser.max_header_size = 32;
+    assert!(parser.parse(b"GET / HTTP/1.1\r\nHost: abc.com\r\n", None, None).is_incomplete());
+    assert!(parser.parse(b"Accept: */*\r\n", None, None).is_err());
+
+    // The buffered data was dropped
+    assert!(!parser.parse(b"\r\n", None, None).is_done());
+}
+
+#[test]
+fn parse_http_request_success_max_connections() {
+    let mut peel = PeelIp::default();
+    let path = |peel: &mut PeelIp, packet: &[u8]| {
+        let result = peel.traverse(packet, vec![]).result;
+        match result.last().unwrap().downcast_ref() {
+            Some(&HttpPacket::Request(ref request)) => Some(request.path.clone()),
+            _ => None,
+        }
+    };
+    assert!(path(&mut peel, &request(8000, b"GET /first HTTP/1.1\r\n")).is_none());
+    assert!(path(&mut peel, &request(8001, b"GET /second HTTP/1.1\r\n")).is_none());
+
+    // Too many other connections buffer an incomplete head as well
+    for port in 10000..14096 {
+        if port == 12000 {
+            assert_eq!(path(&mut peel, &request(8001, b"Host: abc.com\r\n\r\n")).unwrap(), "/second");
+        }
+        peel.traverse(&request(port, b"GET / HTTP/1.1\r\n"), vec![]);
+    }
+
+    // The least recently used connection was dropped, while the other one was used in between
+    assert!(path(&mut peel, &request(8000, b"Host: abc.com\r\n\r\n")).is_none());
+    assert_eq!(path(&mut peel, &request(10001, b"Host: abc.com\r\n\r\n")).unwrap(), "/");
+}
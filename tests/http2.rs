@@ -0,0 +1,257 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::request;
+
+static PREFACE: &'static [u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// POST /upload with the authority example.com and content type text/plain
+static REQUEST_HEADERS: &'static [u8] = &[0x83, 0x86, 0x44, 0x85, 0x62, 0xda, 0xe8, 0x38, 0xe4, 0x41, 0x88, 0x2f,
+                                          0x91, 0xd3, 0x5d, 0x05, 0x5c, 0x87, 0xa7, 0x5f, 0x87, 0x49, 0x7c, 0xa5,
+                                          0x8a, 0xe8, 0x19, 0xaa];
+
+fn frame(frame_type: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let length = payload.len();
+    let mut frame = vec![(length >> 16) as u8, (length >> 8) as u8, length as u8, frame_type, flags,
+                         (stream_id >> 24) as u8, (stream_id >> 16) as u8, (stream_id >> 8) as u8, stream_id as u8];
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn preface(frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut input = Vec::from(PREFACE);
+    input.extend_from_slice(&frame(4, 0, 0, &[]));
+    for frame in frames {
+        input.extend_from_slice(frame);
+    }
+    input
+}
+
+fn parse<F: FnOnce(&Http2Packet)>(parser: &mut Http2Parser, input: &[u8], check: F) {
+    let parsing_result = parser.parse(input, None, None).unwrap();
+    assert!(parsing_result.0.is_empty());
+    check(parsing_result.1.downcast_ref().unwrap());
+}
+
+#[test]
+fn parse_http2_success_request() {
+    let mut parser = Http2Parser::new();
+    println!("{}", parser);
+    let input = preface(&[frame(1, 0x05, 1, REQUEST_HEADERS)]);
+    parse(&mut parser, &input, |packet| {
+        assert!(packet.preface);
+        assert_eq!(packet.frames.len(), 2);
+        assert_eq!(packet.frames[0],
+                   Http2Frame {
+                       length: 0,
+                       flags: 0,
+                       stream_id: 0,
+                       payload: Http2FramePayload::Settings {
+                           ack: false,
+                           settings: vec![],
+                       },
+                   });
+        assert_eq!(packet.messages.len(), 1);
+        let message = &packet.messages[0];
+        assert_eq!(message.stream_id, 1);
+        assert!(message.from_client);
+        assert_eq!(message.method(), Some("POST"));
+        assert_eq!(message.path(), Some("/upload"));
+        assert_eq!(message.header(":authority"), Some("example.com"));
+        assert_eq!(message.header("content-type"), Some("text/plain"));
+        assert_eq!(message.status(), None);
+        assert!(message.body.is_empty());
+    });
+}
+
+#[test]
+fn parse_http2_success_body() {
+    let mut parser = Http2Parser::new();
+    let input = preface(&[frame(1, 0x24, 1, &[&[0x80, 0x00, 0x00, 0x00, 0x0f][..], REQUEST_HEADERS].concat()),
+                          frame(0, 0x00, 1, b"Hello, ")]);
+    parse(&mut parser, &input, |packet| {
+        match packet.frames[1].payload {
+            Http2FramePayload::Headers { end_stream: false, end_headers: true, ref priority, ref headers } => {
+                assert_eq!(priority,
+                           &Some(Http2Priority {
+                               exclusive: true,
+                               dependency: 0,
+                               weight: 15,
+                           }));
+                assert_eq!(headers.as_ref().unwrap().len(), 5);
+            }
+            _ => unreachable!(),
+        }
+        assert!(packet.messages.is_empty());
+    });
+
+    // Padded data which ends the stream
+    let input = frame(0, 0x09, 1, b"\x03world!\x00\x00\x00");
+    parse(&mut parser, &input, |packet| {
+        assert_eq!(packet.frames[0].payload,
+                   Http2FramePayload::Data {
+                       end_stream: true,
+                       data: b"world!".to_vec(),
+                   });
+        assert_eq!(packet.messages[0].body, b"Hello, world!");
+    });
+}
+
+#[test]
+fn parse_http2_success_continuation() {
+    let mut parser = Http2Parser::new();
+    let input = preface(&[frame(1, 0x01, 3, &REQUEST_HEADERS[..10]), frame(9, 0x00, 3, &REQUEST_HEADERS[10..20])]);
+    parse(&mut parser, &input, |packet| {
+        assert!(packet.messages.is_empty());
+    });
+
+    let input = frame(9, 0x04, 3, &REQUEST_HEADERS[20..]);
+    parse(&mut parser, &input, |packet| {
+        match packet.frames[0].payload {
+            Http2FramePayload::Continuation { end_headers: true, headers: Some(ref headers) } => {
+                assert_eq!(headers.len(), 5)
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(packet.messages[0].stream_id, 3);
+        assert_eq!(packet.messages[0].path(), Some("/upload"));
+    });
+
+    // The dynamic table of the connection is used for subsequent header blocks
+    let input = frame(1, 0x05, 5, &[0x82, 0x86, 0x84, 0xbf]);
+    parse(&mut parser, &input, |packet| {
+        assert_eq!(packet.messages[0].header(":authority"), Some("example.com"));
+    });
+}
+
+#[test]
+fn parse_http2_success_segmented() {
+    let mut parser = Http2Parser::new();
+    let input = preface(&[frame(1, 0x05, 1, REQUEST_HEADERS)]);
+    parse(&mut parser, &input[..40], |packet| {
+        assert_eq!(packet.frames.len(), 1);
+    });
+    parse(&mut parser, &input[40..45], |packet| {
+        assert!(packet.frames.is_empty());
+    });
+    parse(&mut parser, &input[45..], |packet| {
+        assert_eq!(packet.frames.len(), 1);
+        assert_eq!(packet.messages.len(), 1);
+    });
+}
+
+#[test]
+fn parse_http2_success_connection_frames() {
+    let mut parser = Http2Parser::new();
+    let input = preface(&[frame(4, 0x00, 0, &[0x00, 0x01, 0x00, 0x00, 0x10, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x01]),
+                          frame(4, 0x01, 0, &[]),
+                          frame(6, 0x00, 0, &[0, 0, 0, 0, 0, 0, 0, 42]),
+                          frame(8, 0x00, 0, &[0x00, 0x00, 0xff, 0xff]),
+                          frame(2, 0x00, 1, &[0x00, 0x00, 0x00, 0x03, 0x10]),
+                          frame(3, 0x00, 1, &[0x00, 0x00, 0x00, 0x08]),
+                          frame(0xfa, 0x00, 0, b"unknown"),
+                          frame(7, 0x00, 0, &[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x62, 0x79, 0x65])]);
+    parse(&mut parser, &input, |packet| {
+        let payloads: Vec<&Http2FramePayload> = packet.frames.iter().skip(1).map(|f| &f.payload).collect();
+        assert_eq!(payloads,
+                   vec![&Http2FramePayload::Settings {
+                            ack: false,
+                            settings: vec![Http2Setting::HeaderTableSize(4096), Http2Setting::Other(9, 1)],
+                        },
+                        &Http2FramePayload::Settings {
+                            ack: true,
+                            settings: vec![],
+                        },
+                        &Http2FramePayload::Ping {
+                            ack: false,
+                            data: 42,
+                        },
+                        &Http2FramePayload::WindowUpdate(65535),
+                        &Http2FramePayload::Priority(Http2Priority {
+                            exclusive: false,
+                            dependency: 3,
+                            weight: 16,
+                        }),
+                        &Http2FramePayload::RstStream(Http2ErrorCode::Cancel),
+                        &Http2FramePayload::Other(0xfa),
+                        &Http2FramePayload::Goaway {
+                            last_stream_id: 1,
+                            error_code: Http2ErrorCode::EnhanceYourCalm,
+                            debug_data: b"bye".to_vec(),
+                        }]);
+    });
+}
+
+#[test]
+fn parse_http2_success_max_buffered_size() {
+    let mut parser = Http2Parser::new();
+    parser.max_buffered_size = 16;
+    let input = preface(&[frame(1, 0x04, 1, REQUEST_HEADERS), frame(0, 0x00, 1, b"0123456789")]);
+    parse(&mut parser, &input, |packet| assert!(packet.messages.is_empty()));
+
+    // The connection is dropped once too much data is buffered
+    parse(&mut parser, &frame(0, 0x00, 1, b"0123456789"), |packet| assert_eq!(packet.frames.len(), 1));
+    assert!(parser.parse(&frame(0, 0x01, 1, b""), None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_http2_success_max_connections() {
+    let mut peel = PeelIp::default();
+    let mut is_http2 = |port: u16, input: &[u8]| {
+        let result = peel.traverse(&request(port, input), vec![]).result;
+        result.last().unwrap().downcast_ref::<Http2Packet>().is_some()
+    };
+    let ping = frame(6, 0x00, 0, &[0, 0, 0, 0, 0, 0, 0, 0]);
+
+    // Too many connections are followed, where the least recently used ones are dropped
+    assert!(is_http2(10000, &preface(&[])));
+    assert!(is_http2(10001, &preface(&[])));
+    for port in 20000..24095 {
+        if port == 22000 {
+            assert!(is_http2(10001, &ping));
+        }
+        assert!(is_http2(port, &preface(&[])));
+    }
+    assert!(!is_http2(10000, &ping));
+    assert!(is_http2(10001, &ping));
+    assert!(is_http2(24094, &ping));
+}
+
+#[test]
+fn parse_http2_failure_no_preface() {
+    let mut parser = Http2Parser::new();
+    let input = frame(4, 0, 0, &[]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+    assert!(parser.parse(b"GET / HTTP/1.1\r\n\r\n", None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_http2_failure_malformed() {
+    // Wrong length of a PING frame
+    let mut parser = Http2Parser::new();
+    let input = preface(&[frame(6, 0x00, 0, &[0, 0, 0, 0])]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    // The connection is not followed any more
+    let input = frame(4, 0, 0, &[]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    // Data on the connection stream
+    let input = preface(&[frame(0, 0x00, 0, b"data")]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    // Frame exceeding the maximum frame size
+    let mut input = preface(&[]);
+    input.extend_from_slice(&[0x00, 0x40, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    // Interrupted header block
+    let input = preface(&[frame(1, 0x00, 1, &REQUEST_HEADERS[..10]), frame(0, 0x00, 1, b"data")]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+
+    // Header block which can not be decoded
+    let input = preface(&[frame(1, 0x04, 1, &[0xff, 0x00])]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+}
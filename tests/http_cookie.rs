@@ -0,0 +1,64 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+fn cookie(name: &str, value: &str) -> HttpCookie {
+    HttpCookie {
+        name: name.to_owned(),
+        value: value.to_owned(),
+    }
+}
+
+#[test]
+fn parse_http_cookie_success() {
+    let mut parser = HttpParser::new();
+    let input = b"GET / HTTP/1.1\r\nCookie: a=1; b=\"two\"\r\ncookie: empty=;flag\r\nCookie: ;=x\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert_eq!(request.header_values("COOKIE").len(), 3);
+            assert_eq!(request.header("cookie"), Some("a=1; b=\"two\""));
+            assert_eq!(request.cookies(),
+                       vec![cookie("a", "1"), cookie("b", "two"), cookie("empty", ""), cookie("flag", "")]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_set_cookie_success() {
+    let mut parser = HttpParser::new();
+    let input = b"HTTP/1.1 200 OK\r\nSet-Cookie: id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+                  Max-Age=2592000; Domain=.Example.com; Path=/docs; secure; HttpOnly; SameSite=Lax; Priority=High\r\n\
+                  Set-Cookie: session=\"\"; Max-Age=invalid\r\nSet-Cookie: invalid\r\nContent-Length: 0\r\n\r\n";
+    match parser.parse(input, None, None).unwrap().1.downcast_ref() {
+        Some(&HttpPacket::Response(ref response)) => {
+            assert_eq!(response.header_values("set-cookie").len(), 3);
+            assert_eq!(response.header("X-Missing"), None);
+            assert_eq!(response.set_cookies(),
+                       vec![HttpSetCookie {
+                                name: "id".to_owned(),
+                                value: "a3fWa".to_owned(),
+                                expires: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_owned()),
+                                max_age: Some(2592000),
+                                domain: Some("example.com".to_owned()),
+                                path: Some("/docs".to_owned()),
+                                secure: true,
+                                http_only: true,
+                                same_site: Some("Lax".to_owned()),
+                                extensions: vec![("Priority".to_owned(), Some("High".to_owned()))],
+                            },
+                            HttpSetCookie {
+                                name: "session".to_owned(),
+                                value: "".to_owned(),
+                                expires: None,
+                                max_age: None,
+                                domain: None,
+                                path: None,
+                                secure: false,
+                                http_only: false,
+                                same_site: None,
+                                extensions: vec![],
+                            }]);
+        }
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,161 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::{request, response};
+
+fn extract(peel: &mut PeelIp, extractor: &mut HttpObjectExtractor, packet: &[u8]) -> Vec<HttpObject> {
+    let result = peel.traverse(packet, vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_some());
+    extractor.extract(&result)
+}
+
+#[test]
+fn extract_http_object_success_download() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::new();
+    let packet = request(80, b"GET /files/a.txt HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    assert!(extract(&mut peel, &mut extractor, &packet).is_empty());
+
+    let packet = response(80, b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\
+                            Content-Disposition: attachment; filename=\"hello.txt\"\r\n\
+                            Content-Length: 11\r\n\r\nhello world");
+    let objects = extract(&mut peel, &mut extractor, &packet);
+    assert_eq!(objects.len(), 1);
+    let object = &objects[0];
+    assert!(object.connection.is_some());
+    assert_eq!(object.url, "http://example.com/files/a.txt");
+    assert_eq!(object.source, HttpObjectSource::Response);
+    assert_eq!(object.content_type, Some("text/plain".to_owned()));
+    assert_eq!(object.filename, Some("hello.txt".to_owned()));
+    assert_eq!(object.size, 11);
+    assert_eq!(object.md5, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    assert_eq!(object.sha256, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+    assert_eq!(object.data, b"hello world");
+
+    // Error responses do not transfer objects
+    let packet = request(80, b"GET /missing HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    extract(&mut peel, &mut extractor, &packet);
+    let packet = response(80, b"HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nnot found");
+    assert!(extract(&mut peel, &mut extractor, &packet).is_empty());
+}
+
+#[test]
+fn extract_http_object_success_upload() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::default();
+    let packet = request(80, b"PUT http://example.com/upload HTTP/1.1\r\nContent-Length: 4\r\n\r\ndata");
+    let objects = extract(&mut peel, &mut extractor, &packet);
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].url, "http://example.com/upload");
+    assert_eq!(objects[0].source, HttpObjectSource::Request);
+    assert_eq!(objects[0].data, b"data");
+}
+
+#[test]
+fn extract_http_object_success_multipart() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::new();
+    let body: &[u8] = b"preamble\r\n--XyZ\r\nContent-Disposition: form-data; name=\"comment\"\r\n\r\nno file\r\n\
+                        --XyZ\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"a.bin\"; \
+                        filename*=UTF-8''%E2%9C%93.bin\r\nContent-Type: application/octet-stream\r\n\r\n\
+                        \x00\x01\r\n\x02\r\n--XyZ\r\nContent-Disposition: form-data; name=\"second\"; \
+                        filename=\"b.txt\"\r\n\r\nb\r\n--XyZ--\r\nepilogue";
+    let mut input = format!("POST /form HTTP/1.1\r\nHost: example.com\r\n\
+                             Content-Type: multipart/form-data; boundary=\"XyZ\"\r\nContent-Length: {}\r\n\r\n",
+                            body.len())
+        .into_bytes();
+    input.extend_from_slice(body);
+
+    let objects = extract(&mut peel, &mut extractor, &request(80, &input));
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0].source,
+               HttpObjectSource::Multipart { name: Some("upload".to_owned()) });
+    assert_eq!(objects[0].filename, Some("✓.bin".to_owned()));
+    assert_eq!(objects[0].content_type, Some("application/octet-stream".to_owned()));
+    assert_eq!(objects[0].data, b"\x00\x01\r\n\x02");
+    assert_eq!(objects[1].filename, Some("b.txt".to_owned()));
+    assert_eq!(objects[1].data, b"b");
+}
+
+#[test]
+fn extract_http_object_success_ranges() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::new();
+    let packet = request(80, b"GET /video HTTP/1.1\r\nHost: example.com\r\nRange: bytes=6-\r\n\r\n");
+    extract(&mut peel, &mut extractor, &packet);
+    let packet = response(80, b"HTTP/1.1 206 Partial Content\r\nContent-Type: video/mp4\r\n\
+                            Content-Range: bytes 6-10/11\r\nContent-Length: 5\r\n\r\nworld");
+    assert!(extract(&mut peel, &mut extractor, &packet).is_empty());
+    assert_eq!(extractor.partial_count(), 1);
+
+    // The remaining ranges are sent as multipart/byteranges, where the ranges overlap
+    let body: &[u8] = b"--R\r\nContent-Type: video/mp4\r\nContent-Range: bytes 0-3/11\r\n\r\nhell\r\n\
+                        --R\r\nContent-Range: bytes 2-6/11\r\n\r\nllo w\r\n--R--\r\n";
+    let packet = request(80, b"GET /video HTTP/1.1\r\nHost: example.com\r\nRange: bytes=0-3,2-6\r\n\r\n");
+    extract(&mut peel, &mut extractor, &packet);
+    let mut input = format!("HTTP/1.1 206 Partial Content\r\nContent-Type: multipart/byteranges; boundary=R\r\n\
+                             Content-Length: {}\r\n\r\n",
+                            body.len())
+        .into_bytes();
+    input.extend_from_slice(body);
+    let objects = extract(&mut peel, &mut extractor, &response(80, &input));
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].url, "http://example.com/video");
+    assert_eq!(objects[0].source, HttpObjectSource::PartialContent { parts: 3 });
+    assert_eq!(objects[0].content_type, Some("video/mp4".to_owned()));
+    assert_eq!(objects[0].data, b"hello world");
+    assert_eq!(extractor.partial_count(), 0);
+}
+
+/// Request a range of an object and extract the response with the range
+fn range(peel: &mut PeelIp, extractor: &mut HttpObjectExtractor, path: &str, range: &str, data: &str)
+         -> Vec<HttpObject> {
+    let input = format!("GET {} HTTP/1.1\r\nHost: example.com\r\n\r\n", path);
+    extract(peel, extractor, &request(80, input.as_bytes()));
+    let input = format!("HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}\r\nContent-Length: {}\r\n\r\n{}",
+                        range, data.len(), data);
+    extract(peel, extractor, &response(80, input.as_bytes()))
+}
+
+#[test]
+fn extract_http_object_success_partial_eviction() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::new();
+    extractor.max_partial_size = 8;
+
+    // The oldest incomplete object is dropped if the ranges exceed the maximum size
+    assert!(range(&mut peel, &mut extractor, "/a", "0-4/11", "hello").is_empty());
+    assert!(range(&mut peel, &mut extractor, "/b", "0-4/11", "HELLO").is_empty());
+    assert_eq!(extractor.partial_count(), 1);
+    assert_eq!(extractor.partial_size(), 5);
+    assert!(range(&mut peel, &mut extractor, "/a", "5-10/11", " world").is_empty());
+    assert_eq!(extractor.partial_count(), 1);
+    let objects = range(&mut peel, &mut extractor, "/a", "0-4/11", "hello");
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].data, b"hello world");
+    assert_eq!(extractor.partial_size(), 0);
+
+    // The oldest incomplete object is dropped if there are too many of them
+    extractor.max_partial_size = 1024;
+    for i in 0..65 {
+        assert!(range(&mut peel, &mut extractor, &format!("/{}", i), "0-0/2", "a").is_empty());
+    }
+    assert_eq!(extractor.partial_count(), 64);
+    assert!(range(&mut peel, &mut extractor, "/0", "1-1/2", "b").is_empty());
+    assert_eq!(range(&mut peel, &mut extractor, "/2", "1-1/2", "b").len(), 1);
+}
+
+#[test]
+fn extract_http_object_failure_invalid_range() {
+    let mut peel = PeelIp::default();
+    let mut extractor = HttpObjectExtractor::new();
+    for range in &["bytes 0-9/11", "bytes 6-10/*", "items 6-10/11", "bytes 10-6/11"] {
+        let packet = request(80, b"GET /video HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        extract(&mut peel, &mut extractor, &packet);
+        let input = format!("HTTP/1.1 206 Partial Content\r\nContent-Range: {}\r\nContent-Length: 5\r\n\r\nworld",
+                            range);
+        assert!(extract(&mut peel, &mut extractor, &response(80, input.as_bytes())).is_empty());
+    }
+    assert_eq!(extractor.partial_count(), 0);
+}
@@ -0,0 +1,115 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::time::Duration;
+
+mod common;
+use common::{finish, request, response};
+
+fn track(peel: &mut PeelIp, tracker: &mut HttpTransactionTracker, packet: &[u8], ms: u64)
+         -> Vec<HttpTransaction> {
+    let result = peel.traverse(packet, vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_some());
+    tracker.track(&result, Duration::from_millis(ms))
+}
+
+#[test]
+fn track_http_transaction_success() {
+    let mut peel = PeelIp::default();
+    let mut tracker = HttpTransactionTracker::new();
+    let packet = request(443, b"POST /form HTTP/1.1\r\nContent-Length: 3\r\n\r\na=b");
+    assert!(track(&mut peel, &mut tracker, &packet, 1000).is_empty());
+    assert_eq!(tracker.pending_count(), 1);
+
+    let packet = response(443, b"HTTP/1.1 201 Created\r\nContent-Length: 5\r\n\r\nnew=1");
+    let transaction = track(&mut peel, &mut tracker, &packet, 1250).remove(0);
+    assert_eq!(transaction.method, HttpRequestMethod::Post);
+    assert_eq!(transaction.path, "/form");
+    assert_eq!(transaction.status, 201);
+    assert_eq!(transaction.request_size, 3);
+    assert_eq!(transaction.response_size, 5);
+    assert_eq!(transaction.request_timestamp, Duration::from_millis(1000));
+    assert_eq!(transaction.response_timestamp, Duration::from_millis(1250));
+    assert_eq!(transaction.latency, Duration::from_millis(250));
+    assert!(transaction.connection.is_some());
+    assert_eq!(tracker.pending_count(), 0);
+}
+
+#[test]
+fn track_http_transaction_success_pipelined() {
+    let mut peel = PeelIp::default();
+    let mut tracker = HttpTransactionTracker::default();
+    let packet = request(443, b"GET /first HTTP/1.1\r\nHost: abc.com\r\n\r\n");
+    assert!(track(&mut peel, &mut tracker, &packet, 10).is_empty());
+    let packet = request(443, b"HEAD /second HTTP/1.1\r\nHost: abc.com\r\n\r\n");
+    assert!(track(&mut peel, &mut tracker, &packet, 20).is_empty());
+    assert_eq!(tracker.pending_count(), 2);
+
+    // Informational responses do not complete a transaction
+    let packet = response(443, b"HTTP/1.1 100 Continue\r\nServer: test\r\n\r\n");
+    assert!(track(&mut peel, &mut tracker, &packet, 30).is_empty());
+
+    let packet = response(443, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    let transaction = track(&mut peel, &mut tracker, &packet, 40).remove(0);
+    assert_eq!(transaction.path, "/first");
+    assert_eq!(transaction.latency, Duration::from_millis(30));
+
+    let packet = response(443, b"HTTP/1.1 404 Not Found\r\nContent-Length: 100\r\n\r\n");
+    let transaction = track(&mut peel, &mut tracker, &packet, 45).remove(0);
+    assert_eq!(transaction.method, HttpRequestMethod::Head);
+    assert_eq!(transaction.path, "/second");
+    assert_eq!(transaction.status, 404);
+    assert_eq!(transaction.response_size, 0);
+    assert_eq!(transaction.latency, Duration::from_millis(25));
+}
+
+#[test]
+fn track_http_transaction_success_pipelined_segment() {
+    let mut peel = PeelIp::default();
+    let mut tracker = HttpTransactionTracker::new();
+    let packet = request(443, b"HEAD /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\n");
+    assert!(track(&mut peel, &mut tracker, &packet, 10).is_empty());
+    assert_eq!(tracker.pending_count(), 2);
+
+    // Both responses of a single segment complete their transactions
+    let packet = response(443, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n\
+                                 HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    let transactions = track(&mut peel, &mut tracker, &packet, 30);
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0].path, "/a");
+    assert_eq!(transactions[0].response_size, 0);
+    assert_eq!(transactions[1].path, "/b");
+    assert_eq!(transactions[1].response_size, 2);
+    assert_eq!(tracker.pending_count(), 0);
+}
+
+#[test]
+fn track_http_transaction_failure_no_request() {
+    let mut peel = PeelIp::default();
+    let mut tracker = HttpTransactionTracker::new();
+    let packet = response(443, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    assert!(track(&mut peel, &mut tracker, &packet, 0).is_empty());
+
+    // Results without HTTP are ignored
+    let result = peel.traverse(&request(443, b""), vec![]).result;
+    assert!(tracker.track(&result, Duration::from_millis(0)).is_empty());
+}
+
+#[test]
+fn track_http_transaction_failure_connection_closed() {
+    let mut peel = PeelIp::default();
+    let mut tracker = HttpTransactionTracker::new();
+    let packet = request(443, b"GET / HTTP/1.1\r\nHost: abc.com\r\n\r\n");
+    assert!(track(&mut peel, &mut tracker, &packet, 0).is_empty());
+
+    // Reset the connection
+    let mut packet = request(443, b"GET / HTTP/1.1\r\nHost: abc.com\r\n\r\n");
+    packet[47] = 0x04;
+    assert!(track(&mut peel, &mut tracker, &packet, 0).is_empty());
+    assert_eq!(tracker.pending_count(), 0);
+
+    // A closing segment without any data drops the pending requests as well
+    assert!(track(&mut peel, &mut tracker, &request(443, b"GET / HTTP/1.1\r\n\r\n"), 0).is_empty());
+    let result = peel.traverse(&finish(request(443, b"")), vec![]).result;
+    assert!(tracker.track(&result, Duration::from_millis(0)).is_empty());
+    assert_eq!(tracker.pending_count(), 0);
+}
@@ -0,0 +1,98 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::request as segment;
+
+fn request(input: &[u8]) -> HttpRequest {
+    let mut parser = HttpParser::new();
+    match parser.parse(input, None, None).unwrap().1.downcast::<HttpPacket>() {
+        Ok(packet) => match *packet {
+            HttpPacket::Request(request) => request,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_target_success_origin() {
+    let request = request(b"GET /a%20b/c/?q=peel+ip&lang=en%2Dus&flag&=x#top HTTP/1.1\r\nHost: abc.com\r\n\r\n");
+    assert_eq!(request.target(),
+               Some(HttpRequestTarget::Origin {
+                   path: "/a%20b/c/".to_owned(),
+                   query: Some("q=peel+ip&lang=en%2Dus&flag&=x".to_owned()),
+               }));
+    assert_eq!(request.path_segments(), vec!["a b", "c", ""]);
+    assert_eq!(request.query_params(),
+               vec![("q".to_owned(), "peel ip".to_owned()),
+                    ("lang".to_owned(), "en-us".to_owned()),
+                    ("flag".to_owned(), "".to_owned()),
+                    ("".to_owned(), "x".to_owned())]);
+}
+
+#[test]
+fn parse_http_target_success_absolute() {
+    let target = HttpRequestTarget::parse("HTTP://example.com:8080?x=1", &HttpRequestMethod::Get).unwrap();
+    assert_eq!(target,
+               HttpRequestTarget::Absolute {
+                   scheme: "http".to_owned(),
+                   authority: "example.com:8080".to_owned(),
+                   path: "/".to_owned(),
+                   query: Some("x=1".to_owned()),
+               });
+    assert_eq!(target.path(), Some("/"));
+    assert_eq!(target.query_params(), vec![("x".to_owned(), "1".to_owned())]);
+}
+
+#[test]
+fn parse_http_target_success_authority_asterisk() {
+    let request = request(b"CONNECT [::1]:443 HTTP/1.1\r\nHost: [::1]:443\r\n\r\n");
+    assert_eq!(request.target(),
+               Some(HttpRequestTarget::Authority {
+                   host: "[::1]".to_owned(),
+                   port: 443,
+               }));
+    assert!(request.path_segments().is_empty());
+    assert_eq!(HttpRequestTarget::parse("*", &HttpRequestMethod::Options),
+               Some(HttpRequestTarget::Asterisk));
+}
+
+#[test]
+fn parse_http_url_success() {
+    assert_eq!(request(b"GET /a?b HTTP/1.1\r\nHost: abc.com\r\n\r\n").url(), "http://abc.com/a?b");
+    assert_eq!(request(b"GET https://abc.com/a HTTP/1.1\r\nHost: xyz.com\r\n\r\n").url(), "https://abc.com/a");
+    assert_eq!(request(b"CONNECT abc.com:443 HTTP/1.1\r\nHost: abc.com:443\r\n\r\n").url(),
+               "http://abc.com:443");
+    assert_eq!(request(b"OPTIONS * HTTP/1.1\r\nHost: abc.com\r\n\r\n").url(), "http://abc.com");
+    assert_eq!(request(b"OPTIONS * HTTP/1.1\r\n\r\n").url(), "*");
+    assert_eq!(request(b"GET /a HTTP/1.0\r\n\r\n").url(), "/a");
+
+    // The scheme is taken from the transport
+    let mut peel = PeelIp::default();
+    let result = peel.traverse(&segment(443, b"GET /a HTTP/1.1\r\nHost: abc.com\r\n\r\n"), vec![]).result;
+    match result.last().unwrap().downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => {
+            assert!(request.tls);
+            assert_eq!(request.url(), "https://abc.com/a");
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_http_target_failure() {
+    assert_eq!(HttpRequestTarget::parse("example.com:443", &HttpRequestMethod::Get), None);
+    assert_eq!(HttpRequestTarget::parse("/path", &HttpRequestMethod::Connect), None);
+    assert_eq!(HttpRequestTarget::parse("example.com:99999", &HttpRequestMethod::Connect), None);
+    assert_eq!(HttpRequestTarget::parse("1http://example.com/", &HttpRequestMethod::Get), None);
+    assert_eq!(HttpRequestTarget::parse("http:///path", &HttpRequestMethod::Get), None);
+}
+
+#[test]
+fn percent_decode_success() {
+    assert_eq!(percent_decode("%E2%9C%93 100%"), "✓ 100%");
+    assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    assert_eq!(percent_decode("%ff"), "\u{fffd}");
+}
@@ -2,6 +2,9 @@ extern crate log;
 extern crate peel_ip;
 use peel_ip::prelude::*;
 
+mod common;
+use common::udp;
+
 extern crate time;
 use time::Duration;
 
@@ -41,6 +44,14 @@ static TLS_HEADER: &'static [u8] =
       0x2f, 0x33, 0x2e, 0x31, 0x05, 0x68, 0x32, 0x2d, 0x31, 0x34, 0x02, 0x68, 0x32, 0x75, 0x50, 0x00, 0x00, 0x00,
       0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18];
 
+static TLS_SERVER_HELLO: &'static [u8] =
+    &[0x16, 0x03, 0x03, 0x00, 0x35, 0x02, 0x00, 0x00, 0x31, 0x03, 0x03, 0x6b, 0x2e, 0x90, 0x1f, 0xd4, 0x08, 0x37,
+      0xa5, 0x5c, 0xe2, 0x19, 0x73, 0x4a, 0xbd, 0x06, 0xc8, 0x91, 0x2f, 0x55, 0xe0, 0x3b, 0x7d, 0xa4, 0x12, 0x68,
+      0xf9, 0x0c, 0x83, 0x4e, 0xb1, 0x27, 0xd6, 0x00, 0xc0, 0x2f, 0x00, 0x00, 0x09, 0x00, 0x10, 0x00, 0x05, 0x00,
+      0x03, 0x02, 0x68, 0x32];
+
+static TLS_APPLICATION_DATA: &'static [u8] = &[0x17, 0x03, 0x03, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef];
+
 static NTP_HEADER: &'static [u8] =
     &[0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
       0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -140,8 +151,185 @@ fn peel_success_tls_http() {
                        minor: 1,
                    },
                    length: 244,
+                   body: Some(TlsRecordBody::Handshake(vec![TlsHandshake::ClientHello(TlsClientHello {
+                       version: TlsRecordVersion {
+                           major: 3,
+                           minor: 3,
+                       },
+                       random: TLS_HEADER[11..43].to_vec(),
+                       session_id: TLS_HEADER[44..76].to_vec(),
+                       cookie: None,
+                       cipher_suites: vec![0xc02b, 0xc02f, 0x009e, 0xcc14, 0xcc13, 0xcc15, 0xc00a, 0xc014,
+                                           0x0039, 0xc009, 0xc013, 0x0033, 0x009c, 0x0035, 0x002f, 0x000a],
+                       compression_methods: vec![0],
+                       extensions: vec![TlsExtension::Other(0xff01, vec![0]),
+                                        TlsExtension::ServerName(vec!["asecuritysite.com".to_owned()]),
+                                        TlsExtension::Other(0x0017, vec![]),
+                                        TlsExtension::Other(0x0023, vec![]),
+                                        TlsExtension::Other(0x000d, TLS_HEADER[157..179].to_vec()),
+                                        TlsExtension::Other(0x0005, TLS_HEADER[183..188].to_vec()),
+                                        TlsExtension::Other(0x3374, vec![]),
+                                        TlsExtension::Other(0x0012, vec![]),
+                                        TlsExtension::Alpn(vec!["http/1.1".to_owned(),
+                                                                "spdy/3.1".to_owned(),
+                                                                "h2-14".to_owned(),
+                                                                "h2".to_owned()]),
+                                        TlsExtension::Other(0x7550, vec![]),
+                                        TlsExtension::Other(0x000b, vec![1, 0]),
+                                        TlsExtension::Other(0x000a, TLS_HEADER[243..249].to_vec())],
+                   })])),
                }));
-    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any));
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Port(443))));
+}
+
+#[test]
+fn peel_success_tls_http_port() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = 0x20;
+    packet[37] = 0xfb;
+    packet.extend_from_slice(TLS_APPLICATION_DATA);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Port(8443))));
+
+    // Unknown ports are not detected
+    packet[37] = 0xfc;
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+}
+
+#[test]
+fn peel_success_tls_http_alpn() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = 0x27;
+    packet[37] = 0x0f;
+    let mut server_hello = packet.clone();
+    server_hello.extend_from_slice(TLS_SERVER_HELLO);
+    let result = peel.traverse(&server_hello, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Alpn("h2".to_owned()))));
+
+    // The negotiated protocol is remembered for the connection
+    packet.extend_from_slice(TLS_APPLICATION_DATA);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Alpn("h2".to_owned()))));
+}
+
+fn client_hello(protocols: &[&str]) -> Vec<u8> {
+    let mut alpn = vec![];
+    for protocol in protocols {
+        alpn.push(protocol.len() as u8);
+        alpn.extend_from_slice(protocol.as_bytes());
+    }
+    let mut hello = vec![0x03, 0x03];
+    hello.extend_from_slice(&[0; 32]);
+    hello.extend_from_slice(&[0x00, 0x00, 0x02, 0x13, 0x01, 0x01, 0x00, 0x00, alpn.len() as u8 + 6]);
+    hello.extend_from_slice(&[0x00, 0x10, 0x00, alpn.len() as u8 + 2, 0x00, alpn.len() as u8]);
+    hello.extend_from_slice(&alpn);
+    let mut record = vec![0x16, 0x03, 0x01, 0x00, hello.len() as u8 + 4, 0x01, 0x00, 0x00, hello.len() as u8];
+    record.extend_from_slice(&hello);
+    record
+}
+
+#[test]
+fn peel_success_tls_http_alpn_offered() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = 0x27;
+    packet[37] = 0x0f;
+    let mut hello = packet.clone();
+    hello.extend_from_slice(&client_hello(&["h2", "http/1.1"]));
+    let result = peel.traverse(&hello, vec![]).result;
+    assert_eq!(result.len(), 5);
+    let offered = || HttpPacket::Any(HttpDetection::Offered(vec!["h2".to_owned(), "http/1.1".to_owned()]));
+    assert_eq!(result[4].downcast_ref(), Some(&offered()));
+
+    // The server hello is split into two segments, so the offered protocols are still used
+    let mut server_hello = packet.clone();
+    server_hello.extend_from_slice(&TLS_SERVER_HELLO[..20]);
+    let result = peel.traverse(&server_hello, vec![]).result;
+    assert_eq!(result[4].downcast_ref(), Some(&offered()));
+
+    // A server hello within a single segment takes precedence
+    let mut server_hello = packet.clone();
+    server_hello.extend_from_slice(TLS_SERVER_HELLO);
+    let result = peel.traverse(&server_hello, vec![]).result;
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Alpn("h2".to_owned()))));
+}
+
+#[test]
+fn peel_success_tls_http_alpn_offered_other() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&client_hello(&["imap"]));
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+
+    // Mixed protocols fall back to the port
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[35] = 0x01;
+    packet.extend_from_slice(&client_hello(&["h2", "imap"]));
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any(HttpDetection::Port(443))));
+}
+
+#[test]
+fn peel_success_http_segmented() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"GET / HTTP/1.1\r\nHo");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 3);
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"st: abc.com\r\n\r\n");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.headers[0].value, "abc.com"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn peel_success_http2() {
+    let mut peel = PeelIp::default();
+    let mut request = Vec::from(PACKET_ETH_IPV4_TCP);
+    request[36] = 0x00;
+    request[37] = 0x50;
+    let mut response = request.clone();
+    for i in 0..4 {
+        response.swap(26 + i, 30 + i);
+    }
+    for i in 0..2 {
+        response.swap(34 + i, 36 + i);
+    }
+
+    // Preface, empty SETTINGS and HEADERS of GET / for the authority example.com
+    request.extend_from_slice(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n");
+    request.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x01,
+                                0x05, 0x00, 0x00, 0x00, 0x01, 0x82, 0x86, 0x84, 0x41, 0x88, 0x2f, 0x91, 0xd3,
+                                0x5d, 0x05, 0x5c, 0x87, 0xa7]);
+    let result = peel.traverse(&request, vec![]).result;
+    assert_eq!(result.len(), 4);
+    let packet: &Http2Packet = result[3].downcast_ref().unwrap();
+    assert!(packet.preface);
+    assert_eq!(packet.messages[0].header(":authority"), Some("example.com"));
+
+    // SETTINGS, HEADERS with status 200 and DATA
+    response.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+                                 0x04, 0x00, 0x00, 0x00, 0x01, 0x88, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00,
+                                 0x00, 0x01, 0x6f, 0x6b]);
+    let result = peel.traverse(&response, vec![]).result;
+    assert_eq!(result.len(), 4);
+    let packet: &Http2Packet = result[3].downcast_ref().unwrap();
+    assert!(!packet.messages[0].from_client);
+    assert_eq!(packet.messages[0].status(), Some(200));
+    assert_eq!(packet.messages[0].body, b"ok");
 }
 
 #[test]
@@ -189,9 +377,9 @@ fn peel_success_ntp() {
     assert_eq!(result.len(), 4);
     assert_eq!(result[3].downcast_ref(),
                Some(&NtpPacket {
-                   li: 0,
+                   li: NtpLeapIndicator::NoWarning,
                    version: 4,
-                   mode: 3,
+                   mode: NtpMode::Client,
                    stratum: 0,
                    poll: 0,
                    precision: 0,
@@ -202,10 +390,52 @@ fn peel_success_ntp() {
                    ts_orig: 0,
                    ts_recv: 0,
                    ts_xmit: 14710388140573593600,
+                   extensions: vec![],
                    auth: Some((1, NTP_HEADER[52..].to_vec())),
                }));
 }
 
+/// Build a SIP message of a call between 192.0.2.101 and 192.0.2.201 with an optional session
+/// description of the sender
+fn sip(start_line: &str, cseq: &str, sdp: Option<&str>) -> Vec<u8> {
+    let body = sdp.map(|sdp| format!("v=0\r\nc=IN IP4 {}\r\nm=audio 49172 RTP/AVP 0\r\n", sdp));
+    let mut message = format!("{}\r\nCall-ID: 3848276298220188511@atlanta.example.com\r\nCSeq: {}\r\n",
+                              start_line, cseq);
+    match body {
+        Some(body) => {
+            message += &format!("Content-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+        }
+        None => message += "Content-Length: 0\r\n\r\n",
+    }
+    message.into_bytes()
+}
+
+#[test]
+fn peel_success_sip_rtp() {
+    let mut peel = PeelIp::default();
+    let rtp = udp((201, 49172), (101, 49172), &[0x80, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xa0, 0x12, 0x34,
+                                                 0x56, 0x78, 0xff, 0xff]);
+    let is_rtp = |peel: &mut PeelIp| {
+        let result = peel.traverse(&rtp, vec![]).result;
+        result.last().and_then(|any| any.downcast_ref::<RtpPacket>()).is_some()
+    };
+
+    // The media is not detected before the call is established
+    let invite = sip("INVITE sip:bob@biloxi.example.com SIP/2.0", "1 INVITE", Some("192.0.2.101"));
+    let result = peel.traverse(&udp((101, 5060), (201, 5060), &invite), vec![]).result;
+    assert!(result[3].downcast_ref::<SipPacket>().is_some());
+    assert!(!is_rtp(&mut peel));
+
+    let ok = sip("SIP/2.0 200 OK", "1 INVITE", Some("192.0.2.201"));
+    peel.traverse(&udp((201, 5060), (101, 5060), &ok), vec![]);
+    assert!(is_rtp(&mut peel));
+
+    // The media is not followed any more after the end of the call
+    let bye = sip("BYE sip:alice@atlanta.example.com SIP/2.0", "2 BYE", None);
+    peel.traverse(&udp((201, 5060), (101, 5060), &bye), vec![]);
+    assert!(!is_rtp(&mut peel));
+}
+
 #[test]
 fn peel_success_icmpv6() {
     let mut peel = PeelIp::default();
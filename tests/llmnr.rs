@@ -0,0 +1,46 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x01, 0x00, 0x5e, 0x00, 0x00, 0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x2e,
+      0x12, 0x34, 0x00, 0x00, 0x01, 0x11, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x65, 0xe0, 0x00, 0x00, 0xfc, 0xd4, 0x31,
+      0x14, 0xeb, 0x00, 0x1a, 0x00, 0x00];
+
+static QUERY: &'static [u8] = &[0x8f, 0x2e, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x77,
+                                 0x70, 0x61, 0x64, 0x00, 0x00, 0x01, 0x00, 0x01];
+
+#[test]
+fn parse_llmnr_success() {
+    let mut parser = LlmnrParser;
+    println!("{}", parser);
+    let result = parser.parse(QUERY, None, None).unwrap().1;
+    let packet = result.downcast_ref::<LlmnrPacket>().unwrap();
+    assert!(!packet.conflict);
+    assert!(!packet.tentative);
+    assert_eq!(packet.message.id, 0x8f2e);
+    assert_eq!(packet.message.questions[0].name, "wpad");
+
+    // A tentative response with conflict
+    let mut response = Vec::from(QUERY);
+    response[2] = 0x85;
+    response[7] = 0x01;
+    response.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1e, 0x00, 0x04, 10, 0, 0, 7]);
+    let result = parser.parse(&response, None, None).unwrap().1;
+    let packet = result.downcast_ref::<LlmnrPacket>().unwrap();
+    assert!(packet.conflict);
+    assert!(packet.tentative);
+    assert!(!packet.message.authoritative);
+    assert!(!packet.message.recursion_desired);
+    assert_eq!(packet.message.answers[0].data, DnsRecordData::A(Ipv4Addr::new(10, 0, 0, 7)));
+}
+
+#[test]
+fn peel_llmnr_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(QUERY);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].downcast_ref::<LlmnrPacket>().is_some());
+}
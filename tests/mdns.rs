@@ -0,0 +1,91 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x01, 0x00, 0x5e, 0x00, 0x00, 0xfb, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x4c,
+      0x12, 0x34, 0x40, 0x00, 0xff, 0x11, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x65, 0xe0, 0x00, 0x00, 0xfb, 0x14, 0xe9,
+      0x14, 0xe9, 0x00, 0x38, 0x00, 0x00];
+
+fn name(name: &str) -> Vec<u8> {
+    let mut encoded = vec![];
+    for label in name.split('.') {
+        encoded.push(label.len() as u8);
+        encoded.extend_from_slice(label.as_bytes());
+    }
+    encoded.push(0);
+    encoded
+}
+
+fn record(owner: &str, record_type: u16, class: u16, data: &[u8]) -> Vec<u8> {
+    let mut record = name(owner);
+    record.extend_from_slice(&[(record_type >> 8) as u8, record_type as u8, (class >> 8) as u8, class as u8]);
+    record.extend_from_slice(&[0x00, 0x00, 0x11, 0x94, (data.len() >> 8) as u8, data.len() as u8]);
+    record.extend_from_slice(data);
+    record
+}
+
+fn announcement() -> Vec<u8> {
+    let mut srv = vec![0x00, 0x00, 0x00, 0x00, 0x02, 0x77];
+    srv.extend_from_slice(&name("printer.local"));
+    let mut message = vec![0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01];
+    message.extend_from_slice(&record("_ipp._tcp.local", 12, 0x0001, &name("Office.Printer._ipp._tcp.local")));
+    message.extend_from_slice(&record("Office.Printer._ipp._tcp.local", 33, 0x8001, &srv));
+    message.extend_from_slice(&record("Office.Printer._ipp._tcp.local",
+                                      16,
+                                      0x8001,
+                                      b"\x09txtvers=1\x0brp=printers\x05Color"));
+    message.extend_from_slice(&record("printer.local", 1, 0x8001, &[10, 0, 0, 101]));
+    message
+}
+
+#[test]
+fn parse_mdns_success() {
+    let mut parser = MdnsParser;
+    println!("{}", parser);
+    let result = parser.parse(&announcement(), None, None).unwrap().1;
+    let packet = result.downcast_ref::<MdnsPacket>().unwrap();
+    assert!(packet.message.response);
+    assert!(!packet.message.answers[0].cache_flush);
+    assert!(packet.message.answers[1].cache_flush);
+    assert_eq!(packet.message.answers[1].class, DnsClass::Internet);
+    assert_eq!(packet.services(),
+               vec![DnsSdService {
+                        instance: "Office.Printer".to_owned(),
+                        service_type: "_ipp._tcp".to_owned(),
+                        domain: "local".to_owned(),
+                        host: Some("printer.local".to_owned()),
+                        port: Some(631),
+                        txt: vec![("txtvers".to_owned(), Some("1".to_owned())),
+                                  ("rp".to_owned(), Some("printers".to_owned())),
+                                  ("Color".to_owned(), None)],
+                        addresses: vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 101))],
+                    }]);
+}
+
+#[test]
+fn parse_mdns_unicast_response_question() {
+    let mut message = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    message.extend_from_slice(&name("_services._dns-sd._udp.local"));
+    message.extend_from_slice(&[0x00, 0x0c, 0x80, 0x01]);
+    let result = MdnsParser.parse(&message, None, None).unwrap().1;
+    let packet = result.downcast_ref::<MdnsPacket>().unwrap();
+    assert_eq!(packet.message.questions,
+               vec![DnsQuestion {
+                        name: "_services._dns-sd._udp.local".to_owned(),
+                        record_type: DnsRecordType::Ptr,
+                        class: DnsClass::Internet,
+                        unicast_response: true,
+                    }]);
+    assert!(packet.services().is_empty());
+}
+
+#[test]
+fn peel_mdns_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(&announcement());
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<MdnsPacket>().unwrap().services().len(), 1);
+}
@@ -1,6 +1,7 @@
 extern crate nom;
 extern crate peel_ip;
 use peel_ip::prelude::*;
+use std::time::{Duration, UNIX_EPOCH};
 
 static NTP_HEADER: &'static [u8] =
     &[0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -15,9 +16,9 @@ fn parse_ntp_success() {
     let parsing_result = parser.parse(NTP_HEADER, None, None).unwrap().1;
     let res = parsing_result.downcast_ref();
     assert_eq!(Some(&NtpPacket {
-                   li: 0,
+                   li: NtpLeapIndicator::NoWarning,
                    version: 4,
-                   mode: 3,
+                   mode: NtpMode::Client,
                    stratum: 0,
                    poll: 0,
                    precision: 0,
@@ -28,6 +29,7 @@ fn parse_ntp_success() {
                    ts_orig: 0,
                    ts_recv: 0,
                    ts_xmit: 14710388140573593600,
+                   extensions: vec![],
                    auth: Some((1, NTP_HEADER[52..].to_vec())),
                }),
                res);
@@ -38,3 +40,229 @@ fn parse_ntp_failure_too_small() {
     let mut parser = NtpParser;
     assert!(parser.parse(&NTP_HEADER[..47], None, None).to_full_result().is_err());
 }
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x4c,
+      0x12, 0x34, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x65, 0xa2, 0x9f, 0xc8, 0x01, 0xd4, 0x31,
+      0x00, 0x7b, 0x00, 0x38, 0x00, 0x00];
+
+fn ntp_packet(mode: u8, stratum: u8, ref_id: u32, timestamps: [u64; 4]) -> Vec<u8> {
+    let mut packet = vec![0x20 | mode, stratum, 6, 0xe9];
+    packet.extend_from_slice(&[0; 8]);
+    for shift in &[24, 16, 8, 0] {
+        packet.push((ref_id >> shift) as u8);
+    }
+    for timestamp in &timestamps {
+        for shift in (0..8).rev() {
+            packet.push((timestamp >> (shift * 8)) as u8);
+        }
+    }
+    packet
+}
+
+fn parse(packet: &[u8]) -> NtpPacket {
+    match NtpParser.parse(packet, None, None).unwrap().1.downcast::<NtpPacket>() {
+        Ok(ntp) => *ntp,
+        Err(_) => panic!("no NTP packet"),
+    }
+}
+
+#[test]
+fn ntp_timestamps() {
+    let ntp = parse(NTP_HEADER);
+    assert_eq!(ntp.li, NtpLeapIndicator::NoWarning);
+    assert_eq!(ntp.mode, NtpMode::Client);
+    assert_eq!(ntp.reference_time(), None);
+    assert_eq!(
+        ntp.transmit_time(),
+        Some(UNIX_EPOCH + Duration::new(1_216_040_339, 168_000_221))
+    );
+
+    // Era 1 starts on 2036-02-07
+    assert_eq!(
+        ntp_timestamp_to_system_time(0x0000_0001_8000_0000),
+        Some(UNIX_EPOCH + Duration::new(2_085_978_497, 500_000_000))
+    );
+    assert_eq!(
+        ntp_timestamp_to_system_time(0x8000_0000_0000_0000),
+        Some(UNIX_EPOCH - Duration::from_secs(61_505_152))
+    );
+}
+
+#[test]
+fn ntp_reference() {
+    let gps = parse(&ntp_packet(4, 1, 0x4750_5300, [0; 4]));
+    assert_eq!(gps.reference(false), NtpReference::Clock("GPS".to_owned()));
+
+    let kiss = parse(&ntp_packet(4, 0, 0x5241_5445, [0; 4]));
+    assert_eq!(kiss.reference(false), NtpReference::KissOfDeath(NtpKissCode::Rate));
+    let kiss = parse(&ntp_packet(4, 0, 0x5846_4f4f, [0; 4]));
+    assert_eq!(
+        kiss.reference(false),
+        NtpReference::KissOfDeath(NtpKissCode::Other("XFOO".to_owned()))
+    );
+
+    let server = parse(&ntp_packet(4, 2, 0xc0a8_0001, [0; 4]));
+    assert_eq!(server.reference(false), NtpReference::Address(Ipv4Addr::new(192, 168, 0, 1)));
+    assert_eq!(server.reference(true), NtpReference::Hash(0xc0a8_0001));
+}
+
+#[test]
+fn ntp_exchange_offset_and_delay() {
+    let mut peel = PeelIp::default();
+    let mut tracker = NtpExchangeTracker::new();
+    let t1 = 0xe000_0000_0000_0000;
+    let t2 = t1 + 0x1_8000_0000;
+    let t3 = t2 + 0x4000_0000;
+
+    let mut request = Vec::from(PACKET_ETH_IPV4_UDP);
+    request.extend_from_slice(&ntp_packet(3, 0, 0, [0, 0, 0, t1]));
+    let result = peel.traverse(&request, vec![]).result;
+    assert!(tracker.track(&result, Duration::from_secs(100)).is_none());
+    assert_eq!(tracker.pending_count(), 1);
+
+    // The response of the server
+    let mut response = Vec::from(PACKET_ETH_IPV4_UDP);
+    for i in 0..4 {
+        response.swap(26 + i, 30 + i);
+    }
+    response.swap(34, 36);
+    response.swap(35, 37);
+    response.extend_from_slice(&ntp_packet(4, 2, 0xc0a8_0001, [t1, t1, t2, t3]));
+    let result = peel.traverse(&response, vec![]).result;
+    let exchange = tracker.track(&result, Duration::new(100, 500_000_000)).unwrap();
+    assert_eq!(exchange.request_timestamp, Duration::from_secs(100));
+    assert_eq!(exchange.response_timestamp, Duration::new(100, 500_000_000));
+    assert_eq!(exchange.offset, 1.375);
+    assert_eq!(exchange.delay, 0.25);
+    assert!(exchange.connection.is_some());
+    assert_eq!(tracker.pending_count(), 0);
+
+    // Unknown responses are ignored
+    assert!(tracker.track(&result, Duration::from_secs(101)).is_none());
+}
+
+#[test]
+fn ntp_exchange_timeout() {
+    let mut peel = PeelIp::default();
+    let mut tracker = NtpExchangeTracker::new();
+    let request = |t1: u64| {
+        let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+        packet.extend_from_slice(&ntp_packet(3, 0, 0, [0, 0, 0, t1]));
+        packet
+    };
+    let result = peel.traverse(&request(1), vec![]).result;
+    assert!(tracker.track(&result, Duration::from_secs(0)).is_none());
+    let result = peel.traverse(&request(2), vec![]).result;
+    assert!(tracker.track(&result, Duration::from_secs(30)).is_none());
+    assert_eq!(tracker.pending_count(), 2);
+
+    // Requests without a response are dropped after the timeout
+    let result = peel.traverse(&request(3), vec![]).result;
+    assert!(tracker.track(&result, Duration::from_secs(61)).is_none());
+    assert_eq!(tracker.pending_count(), 2);
+
+    let mut response = Vec::from(PACKET_ETH_IPV4_UDP);
+    for i in 0..4 {
+        response.swap(26 + i, 30 + i);
+    }
+    response.swap(34, 36);
+    response.swap(35, 37);
+    response.extend_from_slice(&ntp_packet(4, 2, 0, [2, 2, 3, 4]));
+    let result = peel.traverse(&response, vec![]).result;
+    assert!(tracker.track(&result, Duration::from_secs(100)).is_none());
+    assert_eq!(tracker.pending_count(), 1);
+
+    // The oldest requests make room if too many requests are pending
+    for t1 in 10..4107 {
+        let result = peel.traverse(&request(t1), vec![]).result;
+        assert!(tracker.track(&result, Duration::from_millis(100_000 + t1)).is_none());
+    }
+    assert_eq!(tracker.pending_count(), 4096);
+}
+
+#[test]
+fn ntp_nts_extension_fields() {
+    let mut packet = ntp_packet(3, 0, 0, [0, 0, 0, 0xe000_0000_0000_0000]);
+    packet.extend_from_slice(&[0x01, 0x04, 0x00, 0x24]);
+    packet.extend_from_slice(&[0xaa; 32]);
+    packet.extend_from_slice(&[0x02, 0x04, 0x00, 0x0c, 1, 2, 3, 4, 5, 6, 7, 8]);
+    packet.extend_from_slice(&[0x03, 0x04, 0x00, 0x0c, 0, 0, 0, 0, 0, 0, 0, 0]);
+    packet.extend_from_slice(&[0x04, 0x04, 0x00, 0x20, 0x00, 0x06, 0x00, 0x10]);
+    packet.extend_from_slice(&[9, 9, 9, 9, 9, 9, 0, 0]);
+    packet.extend_from_slice(&[0xbb; 16]);
+
+    let ntp = parse(&packet);
+    assert_eq!(ntp.auth, None);
+    assert_eq!(ntp.extensions,
+               vec![NtpExtensionField::UniqueIdentifier(vec![0xaa; 32]),
+                    NtpExtensionField::NtsCookie(vec![1, 2, 3, 4, 5, 6, 7, 8]),
+                    NtpExtensionField::NtsCookiePlaceholder(8),
+                    NtpExtensionField::NtsAuthenticator {
+                        nonce: vec![9; 6],
+                        ciphertext: vec![0xbb; 16],
+                    }]);
+
+    // Unknown extension fields followed by a MAC
+    let mut packet = ntp_packet(3, 0, 0, [0; 4]);
+    packet.extend_from_slice(&[0x20, 0x05, 0x00, 0x08, 1, 2, 3, 4]);
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x07]);
+    packet.extend_from_slice(&[0xcc; 20]);
+    let ntp = parse(&packet);
+    assert_eq!(ntp.extensions, vec![NtpExtensionField::Other(0x2005, vec![1, 2, 3, 4])]);
+    assert_eq!(ntp.auth, Some((7, vec![0xcc; 20])));
+}
+
+#[test]
+fn ntp_control_message() {
+    let request = [0x16, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let result = NtpParser.parse(&request, None, None).unwrap().1;
+    let message = result.downcast_ref::<NtpControlMessage>().unwrap();
+    assert!(!message.response);
+    assert_eq!(message.opcode, NtpControlOpcode::ReadVariables);
+    assert_eq!(message.sequence, 1);
+    assert!(message.data.is_empty());
+
+    let mut response = vec![0x16, 0x82, 0x00, 0x01, 0x06, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0d];
+    response.extend_from_slice(b"version=\"4.2\"");
+    let result = NtpParser.parse(&response, None, None).unwrap().1;
+    assert_eq!(result.downcast_ref(),
+               Some(&NtpControlMessage {
+                   li: NtpLeapIndicator::NoWarning,
+                   version: 2,
+                   response: true,
+                   error: false,
+                   more: false,
+                   opcode: NtpControlOpcode::ReadVariables,
+                   sequence: 1,
+                   status: 0x0618,
+                   association_id: 0,
+                   offset: 0,
+                   data: b"version=\"4.2\"".to_vec(),
+                   auth: None,
+               }));
+}
+
+#[test]
+fn ntp_private_monlist() {
+    let mut request = vec![0x17, 0x00, 0x03, 0x2a, 0x00, 0x00, 0x00, 0x00];
+    request.extend_from_slice(&[0; 40]);
+    let result = NtpParser.parse(&request, None, None).unwrap().1;
+    let message = result.downcast_ref::<NtpPrivateMessage>().unwrap();
+    assert_eq!(message.request_code, NtpPrivateRequestCode::MonitorGetList1);
+    assert!(message.request_code.is_monlist());
+    assert!(!message.is_monlist_response());
+
+    let mut response = vec![0xd7, 0x00, 0x03, 0x2a, 0x00, 0x02, 0x00, 0x48];
+    response.extend_from_slice(&[0x11; 144]);
+    let result = NtpParser.parse(&response, None, None).unwrap().1;
+    let message = result.downcast_ref::<NtpPrivateMessage>().unwrap();
+    assert!(message.response);
+    assert!(message.more);
+    assert_eq!(message.version, 2);
+    assert_eq!(message.implementation, 3);
+    assert_eq!(message.item_count, 2);
+    assert_eq!(message.item_size, 72);
+    assert_eq!(message.data.len(), 144);
+    assert!(message.is_monlist_response());
+}
@@ -0,0 +1,260 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::time::Duration;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x43, 0x08, 0x00, 0x45, 0x00, 0x00, 0x47,
+      0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00, 0x0a, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0x12,
+      0xc0, 0x14, 0x00, 0x33, 0x00, 0x00];
+
+static RTCP_SR_SDES_BYE: &'static [u8] =
+    &[0x81, 0xc8, 0x00, 0x0c, 0x11, 0x11, 0x11, 0x11, 0xe4, 0xa0, 0x1c, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x01,
+      0x5f, 0x90, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x3e, 0x80, 0x22, 0x22, 0x22, 0x22, 0x40, 0xff, 0xff, 0xff,
+      0x00, 0x01, 0x00, 0x05, 0x00, 0x00, 0x00, 0x10, 0x12, 0x34, 0x56, 0x78, 0x00, 0x01, 0x00, 0x00, 0x81, 0xca,
+      0x00, 0x04, 0x11, 0x11, 0x11, 0x11, 0x01, 0x09, 0x75, 0x73, 0x65, 0x72, 0x40, 0x68, 0x6f, 0x73, 0x74, 0x00,
+      0x81, 0xcb, 0x00, 0x02, 0x11, 0x11, 0x11, 0x11, 0x03, 0x62, 0x79, 0x65];
+
+/// Build a RTP packet of the PCMU payload type
+fn rtp(sequence: u16, timestamp: u32) -> Vec<u8> {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(&[0x80, 0x00, (sequence >> 8) as u8, sequence as u8, (timestamp >> 24) as u8,
+                               (timestamp >> 16) as u8, (timestamp >> 8) as u8, timestamp as u8, 0x12, 0x34,
+                               0x56, 0x78, 0xff, 0xff]);
+    packet
+}
+
+/// Build a tree which decodes the UDP datagrams with the given RTP parser
+fn tree(rtp: RtpParser) -> PeelIp {
+    let mut p = Peel::new();
+    let eth = p.new_parser(EthernetParser);
+    let ipv4 = p.new_parser(Ipv4Parser);
+    let udp = p.new_parser(UdpParser);
+    let rtp = p.new_parser(rtp);
+    p.link_nodes(&[(eth, ipv4), (ipv4, udp), (udp, rtp)]);
+    p.data = Some(Path::new());
+    p
+}
+
+/// Build a tree which detects RTP heuristically
+fn heuristic() -> PeelIp {
+    let mut rtp = RtpParser::new();
+    rtp.heuristic = true;
+    tree(rtp)
+}
+
+fn track(peel: &mut PeelIp, tracker: &mut RtpStreamTracker, sequence: u16, timestamp: u32, ms: u64)
+         -> (u64, u64, f64) {
+    let result = peel.traverse(&rtp(sequence, timestamp), vec![]).result;
+    assert_eq!(result.last().unwrap().downcast_ref::<RtpPacket>().unwrap().sequence_number, sequence);
+    let stream = tracker.track(&result, Duration::from_millis(ms)).unwrap();
+    (stream.received, stream.sequence_errors, stream.jitter)
+}
+
+#[test]
+fn parse_rtp_success() {
+    let mut parser = RtpParser::new();
+    println!("{}", parser);
+    let input = [0xb1, 0xe0, 0x12, 0x34, 0x00, 0x00, 0x0a, 0x0b, 0xde, 0xad, 0xbe, 0xef, 0x01, 0x02, 0x03, 0x04,
+                 0xbe, 0xde, 0x00, 0x01, 0x10, 0xaa, 0x00, 0x00, 0x01, 0x02, 0x03, 0x00, 0x00, 0x03];
+    let result = parser.parse(&input, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&RtpPacket {
+                   version: 2,
+                   padding: true,
+                   marker: true,
+                   payload_type: 96,
+                   sequence_number: 0x1234,
+                   timestamp: 0x0a0b,
+                   ssrc: 0xdeadbeef,
+                   csrc: vec![0x01020304],
+                   extension: Some(RtpExtension {
+                       profile: 0xbede,
+                       data: vec![0x10, 0xaa, 0x00, 0x00],
+                   }),
+                   payload: vec![1, 2, 3],
+               }));
+}
+
+#[test]
+fn parse_rtcp_compound_success() {
+    let mut parser = RtpParser::new();
+    let result = parser.parse(RTCP_SR_SDES_BYE, None, None).unwrap();
+    assert!(result.0.is_empty());
+    let packet = result.1.downcast_ref::<RtcpCompoundPacket>().unwrap();
+    assert_eq!(packet.packets.len(), 3);
+    match packet.packets[0] {
+        RtcpPacket::SenderReport(ref report) => {
+            assert_eq!(report.ssrc, 0x11111111);
+            assert!(report.ntp_time().is_some());
+            assert_eq!(report.rtp_timestamp, 90_000);
+            assert_eq!(report.packet_count, 100);
+            assert_eq!(report.octet_count, 16_000);
+            assert!(report.extension.is_empty());
+            assert_eq!(report.reports,
+                       vec![RtcpReportBlock {
+                                ssrc: 0x22222222,
+                                fraction_lost: 64,
+                                cumulative_lost: -1,
+                                highest_sequence: 0x10005,
+                                jitter: 16,
+                                last_sender_report: 0x12345678,
+                                delay_since_last_sender_report: 0x10000,
+                            }]);
+        }
+        _ => panic!("no sender report"),
+    }
+    assert_eq!(packet.cname(0x11111111), Some("user@host"));
+    assert_eq!(packet.cname(0x22222222), None);
+    assert_eq!(packet.packets[2],
+               RtcpPacket::Goodbye(RtcpGoodbye {
+                   sources: vec![0x11111111],
+                   reason: Some("bye".to_owned()),
+               }));
+}
+
+#[test]
+fn parse_rtcp_app_success() {
+    let mut parser = RtpParser::new();
+    let input = [0x80, 0xc9, 0x00, 0x01, 0x33, 0x33, 0x33, 0x33, 0xa3, 0xcc, 0x00, 0x03, 0x33, 0x33, 0x33, 0x33,
+                 0x54, 0x45, 0x53, 0x54, 0x01, 0x02, 0x00, 0x02, 0x81, 0xcd, 0x00, 0x02, 0x33, 0x33, 0x33, 0x33,
+                 0x44, 0x44, 0x44, 0x44];
+    let result = parser.parse(&input, None, None).unwrap();
+    assert_eq!(result.1.downcast_ref(),
+               Some(&RtcpCompoundPacket {
+                   packets: vec![RtcpPacket::ReceiverReport(RtcpReceiverReport {
+                                     ssrc: 0x33333333,
+                                     reports: vec![],
+                                     extension: vec![],
+                                 }),
+                                 RtcpPacket::ApplicationDefined(RtcpApplicationDefined {
+                                     subtype: 3,
+                                     ssrc: 0x33333333,
+                                     name: "TEST".to_owned(),
+                                     data: vec![1, 2],
+                                 }),
+                                 RtcpPacket::Other {
+                                     packet_type: 205,
+                                     count: 1,
+                                     data: vec![0x33, 0x33, 0x33, 0x33, 0x44, 0x44, 0x44, 0x44],
+                                 }],
+               }));
+}
+
+#[test]
+fn parse_rtp_failure() {
+    let mut parser = RtpParser::new();
+    // Wrong version
+    assert!(parser.parse(&[0x40, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0], None, None).to_full_result().is_err());
+    // Missing contributing sources
+    assert!(parser.parse(&[0x82, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0], None, None).to_full_result().is_err());
+    // Padding longer than the packet
+    let input = [0xa0, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0x20];
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+    // RTCP length beyond the datagram
+    assert!(parser.parse(&RTCP_SR_SDES_BYE[..80], None, None).to_full_result().is_err());
+    assert!(parser.parse(&[], None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_rtp_success() {
+    // Dynamic ports are neither configured nor detected by default
+    let mut peel = PeelIp::default();
+    assert_eq!(peel.traverse(&rtp(1, 160), vec![]).result.len(), 3);
+
+    let mut peel = heuristic();
+    let result = peel.traverse(&rtp(1, 160), vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<RtpPacket>().unwrap().payload, vec![0xff, 0xff]);
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(RTCP_SR_SDES_BYE);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result[3].downcast_ref::<RtcpCompoundPacket>().unwrap().packets.len(), 3);
+
+    // Uncommon payload types are not detected heuristically
+    let mut packet = rtp(1, 160);
+    packet[43] = 50;
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 3);
+
+    // But on the media endpoints of a session description
+    let endpoint = |address| SdpMediaEndpoint {
+        media: "audio".to_owned(),
+        address: IpAddr::V4(address),
+        rtp_port: 49172,
+        rtcp_port: Some(49173),
+    };
+    let rtp = RtpParser::new();
+    rtp.media_endpoints.insert("call", &[endpoint(Ipv4Addr::new(192, 168, 0, 2))]);
+    let endpoints = rtp.media_endpoints.clone();
+    let mut peel = tree(rtp);
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 3);
+    endpoints.insert("call", &[endpoint(Ipv4Addr::new(192, 168, 0, 1))]);
+    assert_eq!(endpoints.len(), 4);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result[3].downcast_ref::<RtpPacket>().unwrap().payload_type, 50);
+}
+
+#[test]
+fn track_rtp_statistics_success() {
+    let mut peel = heuristic();
+    let mut tracker = RtpStreamTracker::new();
+    assert_eq!(track(&mut peel, &mut tracker, 1, 0, 1000), (1, 0, 0.0));
+    assert_eq!(track(&mut peel, &mut tracker, 2, 160, 1020), (2, 0, 0.0));
+    assert_eq!(track(&mut peel, &mut tracker, 4, 480, 1070), (3, 0, 5.0));
+    assert_eq!(track(&mut peel, &mut tracker, 3, 320, 1075), (4, 0, 17.1875));
+    assert_eq!(track(&mut peel, &mut tracker, 10_000, 2000, 1080), (4, 1, 17.1875));
+    assert_eq!(track(&mut peel, &mut tracker, 5, 640, 1080), (5, 1, 33.61328125));
+    assert_eq!(track(&mut peel, &mut tracker, 8, 1120, 1140), (6, 1, 31.512451171875));
+
+    let streams = tracker.streams();
+    assert_eq!(streams.len(), 1);
+    let stream = streams[0];
+    assert_eq!(stream.ssrc, 0x12345678);
+    assert_eq!(stream.payload_type, 0);
+    assert_eq!(stream.first_timestamp, Duration::from_millis(1000));
+    assert_eq!(stream.last_timestamp, Duration::from_millis(1140));
+    assert_eq!(stream.expected(), 8);
+    assert_eq!(stream.lost(), 2);
+    assert_eq!(stream.jitter_seconds(), Some(31.512451171875 / 8000.0));
+}
+
+#[test]
+fn track_rtp_restart_success() {
+    let mut peel = heuristic();
+    let mut tracker = RtpStreamTracker::default();
+    assert_eq!(track(&mut peel, &mut tracker, 65_535, 0, 0).0, 1);
+    assert_eq!(track(&mut peel, &mut tracker, 0, 160, 20).0, 2);
+    assert_eq!(track(&mut peel, &mut tracker, 30_000, 320, 40), (2, 1, 0.0));
+    assert_eq!(track(&mut peel, &mut tracker, 30_001, 480, 60), (1, 1, 0.0));
+
+    let stream = tracker.streams()[0];
+    assert_eq!(stream.base_sequence, 30_001);
+    assert_eq!(stream.cycles, 0);
+    assert_eq!(stream.expected(), 1);
+    assert_eq!(stream.lost(), 0);
+}
+
+#[test]
+fn track_rtp_eviction_success() {
+    let mut peel = heuristic();
+    let mut tracker = RtpStreamTracker::new();
+    let mut track_ssrc = |ssrc: u32, ms: u64| {
+        let mut packet = rtp(1, 0);
+        packet[50..54].copy_from_slice(&[(ssrc >> 24) as u8, (ssrc >> 16) as u8, (ssrc >> 8) as u8, ssrc as u8]);
+        let result = peel.traverse(&packet, vec![]).result;
+        tracker.track(&result, Duration::from_millis(ms)).unwrap().ssrc
+    };
+    for ssrc in 0..4096 {
+        assert_eq!(track_ssrc(ssrc, u64::from(ssrc)), ssrc);
+    }
+    assert_eq!(track_ssrc(0, 5000), 0);
+
+    // The longest inactive stream makes room for a new one
+    assert_eq!(track_ssrc(4096, 5001), 4096);
+    let streams = tracker.streams();
+    assert_eq!(streams.len(), 4096);
+    assert!(streams.iter().any(|stream| stream.ssrc == 0));
+    assert!(!streams.iter().any(|stream| stream.ssrc == 1));
+}
@@ -0,0 +1,257 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::net::{IpAddr, Ipv4Addr};
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x13, 0xc4, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x03, 0xc6, 0x3d, 0x00, 0x03, 0xc6, 0x3d];
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x43, 0x08, 0x00, 0x45, 0x00, 0x00, 0x47,
+      0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00, 0x0a, 0xc0, 0xa8, 0x00, 0x01, 0x13, 0xc4,
+      0x13, 0xc4, 0x00, 0x33, 0x00, 0x00];
+
+static INVITE: &'static [u8] = b"INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: \"Alice\" <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710@pc33.atlanta.com\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Type: application/sdp\r\n\
+Content-Length: 239\r\n\
+\r\n\
+v=0\r\n\
+o=alice 2890844526 2890844526 IN IP4 pc33.atlanta.com\r\n\
+s=-\r\n\
+c=IN IP4 192.0.2.101\r\n\
+t=0 0\r\n\
+m=audio 49172 RTP/AVP 0 101\r\n\
+a=rtpmap:101 telephone-event/8000\r\n\
+a=fmtp:101 0-16\r\n\
+m=video 51372 RTP/AVP 96\r\n\
+a=rtpmap:96 H264/90000\r\n\
+a=rtcp:53020\r\n";
+
+fn parse(input: &[u8]) -> SipMessage {
+    match SipParser::new().parse(input, None, None).unwrap().1.downcast::<SipPacket>() {
+        Ok(mut sip) => sip.messages.remove(0),
+        Err(_) => panic!("no sip packet"),
+    }
+}
+
+#[test]
+fn parse_sip_request_success() {
+    let mut parser = SipParser::new();
+    println!("{}", parser);
+    let result = parser.parse(INVITE, None, None).unwrap();
+    assert!(result.0.is_empty());
+    let packet = result.1.downcast_ref::<SipPacket>().unwrap();
+    assert_eq!(packet.messages.len(), 1);
+    let message = &packet.messages[0];
+    assert_eq!(message.start_line,
+               SipStartLine::Request {
+                   method: SipMethod::Invite,
+                   uri: "sip:bob@biloxi.com".to_owned(),
+                   version: "SIP/2.0".to_owned(),
+               });
+    assert_eq!(message.method(), Some(&SipMethod::Invite));
+    assert_eq!(message.status_code(), None);
+    assert_eq!(message.header("max-forwards"), Some("70"));
+    assert_eq!(message.call_id(), Some("a84b4c76e66710@pc33.atlanta.com"));
+    assert_eq!(message.cseq(),
+               Some(SipCSeq {
+                   sequence: 314159,
+                   method: SipMethod::Invite,
+               }));
+
+    let from = message.from().unwrap();
+    assert_eq!(from.display_name, Some("Alice".to_owned()));
+    assert_eq!(from.uri, "sip:alice@atlanta.com");
+    assert_eq!(from.tag(), Some("1928301774"));
+    let to = message.to().unwrap();
+    assert_eq!(to.display_name, Some("Bob".to_owned()));
+    assert_eq!(to.tag(), None);
+    assert_eq!(message.contacts()[0].uri, "sip:alice@pc33.atlanta.com");
+
+    let via = message.via();
+    assert_eq!(via.len(), 1);
+    assert_eq!(via[0].transport, "UDP");
+    assert_eq!(via[0].host, "pc33.atlanta.com");
+    assert_eq!(via[0].port, None);
+    assert_eq!(via[0].branch(), Some("z9hG4bK776asdhds"));
+    assert_eq!(message.body.len(), 239);
+}
+
+#[test]
+fn parse_sip_sdp_success() {
+    let message = parse(INVITE);
+    let sdp = message.sdp().unwrap();
+    assert_eq!(sdp.version, 0);
+    assert_eq!(sdp.origin.as_ref().unwrap().address, "pc33.atlanta.com");
+    assert_eq!(sdp.session_name, "-");
+    assert_eq!(sdp.media.len(), 2);
+    assert_eq!(sdp.media[0].codecs(),
+               vec![SdpCodec {
+                        payload_type: 0,
+                        name: "PCMU".to_owned(),
+                        clock_rate: 8000,
+                        channels: None,
+                        parameters: None,
+                    },
+                    SdpCodec {
+                        payload_type: 101,
+                        name: "telephone-event".to_owned(),
+                        clock_rate: 8000,
+                        channels: None,
+                        parameters: Some("0-16".to_owned()),
+                    }]);
+    assert_eq!(sdp.media[1].codecs()[0].name, "H264");
+
+    let address = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 101));
+    assert_eq!(message.media_endpoints(),
+               vec![SdpMediaEndpoint {
+                        media: "audio".to_owned(),
+                        address: address,
+                        rtp_port: 49172,
+                        rtcp_port: Some(49173),
+                    },
+                    SdpMediaEndpoint {
+                        media: "video".to_owned(),
+                        address: address,
+                        rtp_port: 51372,
+                        rtcp_port: Some(53020),
+                    }]);
+}
+
+#[test]
+fn parse_sip_response_compact_success() {
+    let message = parse(b"SIP/2.0 200 OK\r\n\
+                          v: SIP/2.0/UDP server10.biloxi.com;branch=z9hG4bK4b43c2ff8.1, \
+                          SIP/2.0/TCP [2001:db8::9:1]:5061;branch=z9hG4bKnashds8;received=192.0.2.3\r\n\
+                          Via: SIP/2.0/UDP pc33.atlanta.com:5070;branch=z9hG4bK776asdhds\r\n\
+                          t: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+                          f: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+                          i: a84b4c76e66710@pc33.atlanta.com\r\n\
+                          CSeq: 314159 INVITE\r\n\
+                          m: <sip:bob@192.0.2.4>, \"Bob, mobile\" <sip:bob@192.0.2.5>;expires=60\r\n\
+                          l: 0\r\n\r\n");
+    assert_eq!(message.start_line,
+               SipStartLine::Response {
+                   version: "SIP/2.0".to_owned(),
+                   code: 200,
+                   reason: "OK".to_owned(),
+               });
+    assert_eq!(message.status_code(), Some(200));
+    assert_eq!(message.method(), None);
+    assert_eq!(message.header("Content-Length"), Some("0"));
+    assert_eq!(message.call_id(), Some("a84b4c76e66710@pc33.atlanta.com"));
+    assert_eq!(message.to().unwrap().tag(), Some("a6c85cf"));
+    assert_eq!(message.from().unwrap().tag(), Some("1928301774"));
+
+    let via = message.via();
+    assert_eq!(via.len(), 3);
+    assert_eq!(via[0].host, "server10.biloxi.com");
+    assert_eq!(via[1].transport, "TCP");
+    assert_eq!(via[1].host, "[2001:db8::9:1]");
+    assert_eq!(via[1].port, Some(5061));
+    assert_eq!(via[1].received(), Some("192.0.2.3"));
+    assert_eq!(via[2].port, Some(5070));
+
+    let contacts = message.contacts();
+    assert_eq!(contacts.len(), 2);
+    assert_eq!(contacts[1].display_name, Some("Bob, mobile".to_owned()));
+    assert_eq!(contacts[1].uri, "sip:bob@192.0.2.5");
+    assert!(message.body.is_empty());
+    assert!(message.sdp().is_none());
+}
+
+/// Build a message of the call of the INVITE fixture with an optional session description
+fn call_message(start_line: &str, cseq: &str, sdp: Option<&str>) -> Vec<u8> {
+    let mut message = format!("{}\r\nCall-ID: a84b4c76e66710@pc33.atlanta.com\r\nCSeq: {}\r\n", start_line, cseq);
+    match sdp {
+        Some(sdp) => {
+            message += &format!("Content-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}", sdp.len(), sdp)
+        }
+        None => message += "Content-Length: 0\r\n\r\n",
+    }
+    message.into_bytes()
+}
+
+#[test]
+fn parse_sip_media_endpoints_success() {
+    let mut parser = SipParser::new();
+    let endpoints = parser.media_endpoints.clone();
+    let address = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 201));
+    let answer = "v=0\r\nc=IN IP4 192.0.2.201\r\nm=audio 3456 RTP/AVP 0\r\n";
+
+    // The offer is followed once it is answered
+    parser.parse(INVITE, None, None).unwrap();
+    assert!(endpoints.is_empty());
+    parser.parse(&call_message("SIP/2.0 180 Ringing", "314159 INVITE", None), None, None).unwrap();
+    assert!(endpoints.is_empty());
+    parser.parse(&call_message("SIP/2.0 200 OK", "314159 INVITE", Some(answer)), None, None).unwrap();
+    assert_eq!(endpoints.len(), 6);
+    assert!(endpoints.contains(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 101)), 53020));
+    assert_eq!(endpoints.call_id(address, 3457), Some("a84b4c76e66710@pc33.atlanta.com".to_owned()));
+    assert!(!endpoints.contains(address, 49172));
+
+    // The call ends with the BYE
+    parser.parse(&call_message("BYE sip:alice@pc33.atlanta.com SIP/2.0", "1 BYE", None), None, None).unwrap();
+    assert!(endpoints.is_empty());
+
+    // A rejected offer is dropped
+    parser.parse(INVITE, None, None).unwrap();
+    parser.parse(&call_message("SIP/2.0 486 Busy Here", "314159 INVITE", None), None, None).unwrap();
+    parser.parse(&call_message("SIP/2.0 200 OK", "314159 INVITE", Some(answer)), None, None).unwrap();
+    assert_eq!(endpoints.len(), 2);
+}
+
+#[test]
+fn parse_sip_failure() {
+    let mut parser = SipParser::new();
+    assert!(parser.parse(b"", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"SIP/2.0 2000 OK\r\n\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"OPTIONS sip:a SIP/2.0\r\nVia", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"\x00\x01\x02\x03", None, None).to_full_result().is_err());
+    assert_eq!(SipMethod::from_token("FOO"), SipMethod::Extension("FOO".to_owned()));
+}
+
+#[test]
+fn peel_sip_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(b"OPTIONS sip:carol@chicago.com SIP/2.0\r\nCSeq: 63104 OPTIONS\r\n\r\n");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    let message = &result[3].downcast_ref::<SipPacket>().unwrap().messages[0];
+    assert_eq!(message.method(), Some(&SipMethod::Options));
+    assert_eq!(message.cseq().unwrap().sequence, 63104);
+}
+
+#[test]
+fn peel_sip_tcp_success() {
+    let mut peel = PeelIp::default();
+    let mut stream = Vec::from(INVITE);
+    stream.extend_from_slice(b"\r\nBYE sip:bob@192.0.2.4 SIP/2.0\r\nContent-Length: 0\r\n\r\n");
+
+    // The first message is split within its body
+    let mut first = Vec::from(PACKET_ETH_IPV4_TCP);
+    first.extend_from_slice(&stream[..400]);
+    let result = peel.traverse(&first, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].downcast_ref::<SipPacket>().unwrap().messages.is_empty());
+
+    let mut second = Vec::from(PACKET_ETH_IPV4_TCP);
+    second.extend_from_slice(&stream[400..]);
+    let result = peel.traverse(&second, vec![]).result;
+    let packet = result[3].downcast_ref::<SipPacket>().unwrap();
+    assert_eq!(packet.messages.len(), 2);
+    assert_eq!(packet.messages[0].media_endpoints().len(), 2);
+    assert_eq!(packet.messages[1].method(), Some(&SipMethod::Bye));
+}
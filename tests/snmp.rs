@@ -0,0 +1,240 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x43, 0x08, 0x00, 0x45, 0x00, 0x00, 0x47,
+      0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00, 0x0a, 0xc0, 0xa8, 0x00, 0x01, 0xd4, 0x31,
+      0x00, 0xa1, 0x00, 0x33, 0x00, 0x00];
+
+static GET_REQUEST: &'static [u8] = &[0x30, 0x29, 0x02, 0x01, 0x01, 0x04, 0x06, 0x70, 0x75, 0x62, 0x6c, 0x69, 0x63,
+                                      0xa0, 0x1c, 0x02, 0x04, 0x12, 0x34, 0x56, 0x78, 0x02, 0x01, 0x00, 0x02, 0x01,
+                                      0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01,
+                                      0x01, 0x00, 0x05, 0x00];
+
+static SYS_DESCR: &'static [u8] = &[0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    if content.len() < 0x80 {
+        element.push(content.len() as u8);
+    } else {
+        element.extend_from_slice(&[0x82, (content.len() >> 8) as u8, content.len() as u8]);
+    }
+    element.extend_from_slice(content);
+    element
+}
+
+fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    tlv(0x30, &elements.concat())
+}
+
+fn varbind(value: Vec<u8>) -> Vec<u8> {
+    sequence(&[SYS_DESCR.to_vec(), value])
+}
+
+fn parse(input: &[u8]) -> SnmpPacket {
+    match SnmpParser.parse(input, None, None).unwrap().1.downcast::<SnmpPacket>() {
+        Ok(snmp) => *snmp,
+        Err(_) => panic!("no SNMP packet"),
+    }
+}
+
+fn sys_descr() -> BerObjectIdentifier {
+    BerObjectIdentifier(vec![1, 3, 6, 1, 2, 1, 1, 1, 0])
+}
+
+#[test]
+fn parse_snmp_get_request_success() {
+    let mut parser = SnmpParser;
+    println!("{}", parser);
+    let result = parser.parse(GET_REQUEST, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&SnmpPacket {
+                   version: SnmpVersion::V2c,
+                   security: SnmpSecurity::Community("public".to_owned()),
+                   data: SnmpData::Pdu(SnmpPdu::Request {
+                       pdu_type: SnmpPduType::GetRequest,
+                       request_id: 0x12345678,
+                       error_status: SnmpErrorStatus::NoError,
+                       error_index: 0,
+                       variable_bindings: vec![SnmpVariableBinding {
+                                                   name: sys_descr(),
+                                                   value: SnmpValue::Null,
+                                               }],
+                   }),
+               }));
+}
+
+#[test]
+fn parse_snmp_response_values_success() {
+    let bindings = sequence(&[varbind(tlv(0x02, &[0xff, 0x38])),
+                              varbind(tlv(0x04, b"Linux router")),
+                              varbind(tlv(0x06, &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x09])),
+                              varbind(tlv(0x40, &[192, 168, 0, 1])),
+                              varbind(tlv(0x41, &[0x00, 0xff, 0xff, 0xff, 0xff])),
+                              varbind(tlv(0x42, &[0x3b, 0x9a, 0xca, 0x00])),
+                              varbind(tlv(0x43, &[0x01, 0x00])),
+                              varbind(tlv(0x44, &[0x9f, 0x78, 0x04, 0x3f, 0x80, 0x00, 0x00])),
+                              varbind(tlv(0x46, &[0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])),
+                              varbind(tlv(0x80, &[])),
+                              varbind(tlv(0x81, &[])),
+                              varbind(tlv(0x82, &[])),
+                              varbind(tlv(0x47, &[0x01]))]);
+    let pdu = tlv(0xa2, &[tlv(0x02, &[0x01]), tlv(0x02, &[0x02]), tlv(0x02, &[0x03]), bindings].concat());
+    let response = parse(&sequence(&[tlv(0x02, &[0x00]), tlv(0x04, b"private"), pdu]));
+    assert_eq!(response.version, SnmpVersion::V1);
+    assert_eq!(response.community(), Some("private"));
+
+    let pdu = response.pdu().unwrap();
+    assert_eq!(pdu.pdu_type(), SnmpPduType::Response);
+    match *pdu {
+        SnmpPdu::Request {
+            error_status,
+            error_index,
+            ..
+        } => {
+            assert_eq!(error_status, SnmpErrorStatus::NoSuchName);
+            assert_eq!(error_index, 3);
+        }
+        _ => panic!("no response"),
+    }
+    let values: Vec<&SnmpValue> = pdu.variable_bindings().iter().map(|binding| &binding.value).collect();
+    assert_eq!(values,
+               vec![&SnmpValue::Integer(-200),
+                    &SnmpValue::OctetString(b"Linux router".to_vec()),
+                    &SnmpValue::ObjectIdentifier(BerObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 9])),
+                    &SnmpValue::IpAddress(Ipv4Addr::new(192, 168, 0, 1)),
+                    &SnmpValue::Counter32(0xffffffff),
+                    &SnmpValue::Gauge32(1_000_000_000),
+                    &SnmpValue::TimeTicks(256),
+                    &SnmpValue::Opaque(vec![0x9f, 0x78, 0x04, 0x3f, 0x80, 0x00, 0x00]),
+                    &SnmpValue::Counter64(0x8000000000000000),
+                    &SnmpValue::NoSuchObject,
+                    &SnmpValue::NoSuchInstance,
+                    &SnmpValue::EndOfMibView,
+                    &SnmpValue::Other(BerTag {
+                                          class: BerClass::Application,
+                                          constructed: false,
+                                          number: 7,
+                                      },
+                                      vec![0x01])]);
+}
+
+#[test]
+fn parse_snmp_bulk_and_traps_success() {
+    let bulk = tlv(0xa5,
+                   &[tlv(0x02, &[0x2a]), tlv(0x02, &[0x00]), tlv(0x02, &[0x0a]), sequence(&[varbind(vec![0x05, 0x00])])]
+                       .concat());
+    let bulk = parse(&sequence(&[tlv(0x02, &[0x01]), tlv(0x04, b"public"), bulk]));
+    assert_eq!(bulk.pdu(),
+               Some(&SnmpPdu::GetBulkRequest {
+                   request_id: 42,
+                   non_repeaters: 0,
+                   max_repetitions: 10,
+                   variable_bindings: vec![SnmpVariableBinding {
+                                               name: sys_descr(),
+                                               value: SnmpValue::Null,
+                                           }],
+               }));
+
+    let trap = tlv(0xa4,
+                   &[tlv(0x06, &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x09]),
+                     tlv(0x40, &[10, 0, 0, 1]),
+                     tlv(0x02, &[0x03]),
+                     tlv(0x02, &[0x00]),
+                     tlv(0x43, &[0x30, 0x39]),
+                     sequence(&[])]
+                       .concat());
+    let trap = parse(&sequence(&[tlv(0x02, &[0x00]), tlv(0x04, b"public"), trap]));
+    assert_eq!(trap.pdu(),
+               Some(&SnmpPdu::Trap {
+                   enterprise: BerObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 9]),
+                   agent_address: Ipv4Addr::new(10, 0, 0, 1),
+                   generic_trap: SnmpGenericTrap::LinkUp,
+                   specific_trap: 0,
+                   timestamp: 12345,
+                   variable_bindings: vec![],
+               }));
+
+    let inform = tlv(0xa6,
+                     &[tlv(0x02, &[0x01]), tlv(0x02, &[0x00]), tlv(0x02, &[0x00]), sequence(&[])].concat());
+    let inform = parse(&sequence(&[tlv(0x02, &[0x01]), tlv(0x04, b"public"), inform]));
+    assert_eq!(inform.pdu().unwrap().pdu_type(), SnmpPduType::InformRequest);
+}
+
+#[test]
+fn parse_snmp_v3_success() {
+    let global = sequence(&[tlv(0x02, &[0x30, 0x39]), tlv(0x02, &[0x00, 0xff, 0xe3]), tlv(0x04, &[0x05]),
+                            tlv(0x02, &[0x03])]);
+    let usm = sequence(&[tlv(0x04, &[0x80, 0x00, 0x1f, 0x88, 0x04]),
+                         tlv(0x02, &[0x07]),
+                         tlv(0x02, &[0x01, 0x00]),
+                         tlv(0x04, b"admin"),
+                         tlv(0x04, &[0xaa; 12]),
+                         tlv(0x04, &[])]);
+    let scoped = sequence(&[tlv(0x04, &[0x80, 0x00, 0x1f, 0x88, 0x04]),
+                            tlv(0x04, b""),
+                            tlv(0xa1, &[tlv(0x02, &[0x01]), tlv(0x02, &[0x00]), tlv(0x02, &[0x00]),
+                                        sequence(&[varbind(vec![0x05, 0x00])])].concat())]);
+    let message = parse(&sequence(&[tlv(0x02, &[0x03]), global.clone(), tlv(0x04, &usm), scoped]));
+    assert_eq!(message.version, SnmpVersion::V3);
+    assert_eq!(message.community(), None);
+    assert_eq!(message.security,
+               SnmpSecurity::V3(SnmpV3Security {
+                   message_id: 12345,
+                   max_size: 65507,
+                   authenticated: true,
+                   encrypted: false,
+                   reportable: true,
+                   security_model: 3,
+                   usm: Some(SnmpUsm {
+                       engine_id: vec![0x80, 0x00, 0x1f, 0x88, 0x04],
+                       engine_boots: 7,
+                       engine_time: 256,
+                       user_name: "admin".to_owned(),
+                       authentication_parameters: vec![0xaa; 12],
+                       privacy_parameters: vec![],
+                   }),
+               }));
+    match message.data {
+        SnmpData::ScopedPdu {
+            ref context_engine_id,
+            ref context_name,
+            ref pdu,
+        } => {
+            assert_eq!(context_engine_id, &[0x80, 0x00, 0x1f, 0x88, 0x04]);
+            assert_eq!(context_name, "");
+            assert_eq!(pdu.pdu_type(), SnmpPduType::GetNextRequest);
+        }
+        _ => panic!("no scoped PDU"),
+    }
+
+    let encrypted = parse(&sequence(&[tlv(0x02, &[0x03]), global, tlv(0x04, &usm), tlv(0x04, &[0x42; 40])]));
+    assert_eq!(encrypted.data, SnmpData::Encrypted(vec![0x42; 40]));
+    assert_eq!(encrypted.pdu(), None);
+}
+
+#[test]
+fn parse_snmp_failure() {
+    let mut parser = SnmpParser;
+    assert!(parser.parse(&GET_REQUEST[..20], None, None).to_full_result().is_err());
+    let mut input = GET_REQUEST.to_vec();
+    input[4] = 0x02;
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+    let mut input = GET_REQUEST.to_vec();
+    input[13] = 0xa9;
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+    assert!(parser.parse(&[0x04, 0x00], None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_snmp_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(GET_REQUEST);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<SnmpPacket>().unwrap().community(), Some("public"));
+}
@@ -0,0 +1,152 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::{request, response};
+
+static CLIENT_ALGORITHMS: &'static [&'static str] =
+    &["curve25519-sha256,diffie-hellman-group14-sha1", "ssh-ed25519,ssh-rsa", "aes128-ctr,aes128-cbc", "aes128-ctr",
+      "hmac-sha2-256,hmac-sha1-96", "hmac-sha2-256", "none,zlib@openssh.com", "none", "", ""];
+
+static SERVER_ALGORITHMS: &'static [&'static str] =
+    &["curve25519-sha256,diffie-hellman-group14-sha1", "ssh-ed25519", "aes256-gcm@openssh.com",
+      "aes256-gcm@openssh.com", "hmac-sha2-512", "hmac-sha2-512", "none", "none", "", ""];
+
+/// Build an unencrypted binary packet
+fn binary_packet(payload: &[u8]) -> Vec<u8> {
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let length = 1 + payload.len() + padding;
+    let mut packet = vec![(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8,
+                          padding as u8];
+    packet.extend_from_slice(payload);
+    packet.extend(vec![0; padding]);
+    packet
+}
+
+/// Build a binary packet with a key exchange initialization
+fn kexinit(algorithms: &[&str]) -> Vec<u8> {
+    let mut payload = vec![20];
+    payload.extend_from_slice(&[0xaa; 16]);
+    for list in algorithms {
+        payload.extend_from_slice(&[0, 0, (list.len() >> 8) as u8, list.len() as u8]);
+        payload.extend_from_slice(list.as_bytes());
+    }
+    payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+    binary_packet(&payload)
+}
+
+fn parse(input: &[u8]) -> SshPacket {
+    match SshParser::new().parse(input, None, None).unwrap().1.downcast::<SshPacket>() {
+        Ok(ssh) => *ssh,
+        Err(_) => panic!("no ssh packet"),
+    }
+}
+
+#[test]
+fn parse_ssh_identification_success() {
+    let mut parser = SshParser::new();
+    println!("{}", parser);
+    let mut input = b"SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6\r\n".to_vec();
+    input.extend(kexinit(CLIENT_ALGORITHMS));
+    let result = parser.parse(&input, None, None).unwrap();
+    assert!(result.0.is_empty());
+    let packet = result.1.downcast_ref::<SshPacket>().unwrap();
+    assert!(packet.from_client);
+    assert_eq!(packet.messages.len(), 2);
+    assert_eq!(packet.messages[0],
+               SshMessage::Identification(SshIdentification {
+                   protocol_version: "2.0".to_owned(),
+                   software_version: "OpenSSH_8.9p1".to_owned(),
+                   comments: Some("Ubuntu-3ubuntu0.6".to_owned()),
+               }));
+    assert_eq!(packet.fingerprint(), Some("ae7e0f35adffdeff60593472c25a87ee".to_owned()));
+
+    let packet = parse(b"SSH-1.99-Cisco-1.25\n");
+    assert_eq!(packet.messages,
+               vec![SshMessage::Identification(SshIdentification {
+                        protocol_version: "1.99".to_owned(),
+                        software_version: "Cisco-1.25".to_owned(),
+                        comments: None,
+                    })]);
+}
+
+#[test]
+fn parse_ssh_kexinit_success() {
+    let mut input = b"SSH-2.0-dropbear_2022.83\r\n".to_vec();
+    input.extend(kexinit(CLIENT_ALGORITHMS));
+    let packet = parse(&input);
+    let kexinit = packet.key_exchange_init().unwrap();
+    assert_eq!(kexinit.cookie, [0xaa; 16]);
+    assert_eq!(kexinit.kex_algorithms, vec!["curve25519-sha256", "diffie-hellman-group14-sha1"]);
+    assert_eq!(kexinit.server_host_key_algorithms, vec!["ssh-ed25519", "ssh-rsa"]);
+    assert_eq!(kexinit.compression_algorithms_client_to_server, vec!["none", "zlib@openssh.com"]);
+    assert!(kexinit.languages_client_to_server.is_empty());
+    assert!(!kexinit.first_kex_packet_follows);
+    assert_eq!(kexinit.hassh_algorithms(),
+               "curve25519-sha256,diffie-hellman-group14-sha1;aes128-ctr,aes128-cbc;\
+                hmac-sha2-256,hmac-sha1-96;none,zlib@openssh.com");
+    assert_eq!(kexinit.hassh_server_algorithms(),
+               "curve25519-sha256,diffie-hellman-group14-sha1;aes128-ctr;hmac-sha2-256;none");
+    assert_eq!(kexinit.weak_algorithms(),
+               vec!["diffie-hellman-group14-sha1", "ssh-rsa", "aes128-cbc", "hmac-sha1-96"]);
+}
+
+#[test]
+fn parse_ssh_failure() {
+    let mut parser = SshParser::new();
+    assert!(parser.parse(b"", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"GET / HTTP/1.1\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"SSH-2.0-\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(&[b'A'; 300], None, None).to_full_result().is_err());
+
+    // Padding shorter than four octets
+    let mut input = b"SSH-2.0-OpenSSH_9.6\r\n".to_vec();
+    input.extend_from_slice(&[0x00, 0x00, 0x00, 0x0c, 0x02, 0x15, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                              0x00, 0x00]);
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_ssh_success() {
+    let mut peel = PeelIp::default();
+
+    // The client identification and key exchange initialization are split
+    let mut stream = b"SSH-2.0-OpenSSH_8.9p1\r\n".to_vec();
+    stream.extend(kexinit(CLIENT_ALGORITHMS));
+    let result = peel.traverse(&request(22, &stream[..40]), vec![]).result;
+    assert_eq!(result.len(), 4);
+    let packet = result[3].downcast_ref::<SshPacket>().unwrap();
+    assert!(packet.from_client);
+    assert_eq!(packet.messages.len(), 1);
+    let result = peel.traverse(&request(22, &stream[40..]), vec![]).result;
+    let packet = result[3].downcast_ref::<SshPacket>().unwrap();
+    assert_eq!(packet.fingerprint(), Some("ae7e0f35adffdeff60593472c25a87ee".to_owned()));
+
+    // The server may send other lines before its identification
+    let result = peel.traverse(&response(22, b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n"), vec![]).result;
+    let packet = result[3].downcast_ref::<SshPacket>().unwrap();
+    assert!(!packet.from_client);
+    assert_eq!(packet.messages.len(), 1);
+
+    // All data after the new keys is encrypted
+    let mut stream = kexinit(SERVER_ALGORITHMS);
+    stream.extend(binary_packet(&[31, 0x01, 0x02]));
+    stream.extend(binary_packet(&[21]));
+    stream.extend_from_slice(&[0x14; 36]);
+    let result = peel.traverse(&response(22, &stream), vec![]).result;
+    let packet = result[3].downcast_ref::<SshPacket>().unwrap();
+    assert_eq!(packet.fingerprint(), Some("f9fd5b59b873c9856ea98a6f59d093d9".to_owned()));
+    assert_eq!(packet.messages[1],
+               SshMessage::Other {
+                   message_type: 31,
+                   payload: vec![0x01, 0x02],
+               });
+    assert_eq!(&packet.messages[2..], &[SshMessage::NewKeys, SshMessage::Encrypted(36)]);
+    let result = peel.traverse(&response(22, &[0x17; 20]), vec![]).result;
+    assert_eq!(result[3].downcast_ref::<SshPacket>().unwrap().messages,
+               vec![SshMessage::Encrypted(20)]);
+}
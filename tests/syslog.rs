@@ -0,0 +1,198 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::time::{Duration, UNIX_EPOCH};
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x02, 0x59, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x03, 0xc6, 0x3d, 0x00, 0x03, 0xc6, 0x3d];
+
+static PACKET_ETH_IPV4_UDP: &'static [u8] =
+    &[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x43, 0x08, 0x00, 0x45, 0x00, 0x00, 0x47,
+      0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00, 0x0a, 0xc0, 0xa8, 0x00, 0x01, 0xd4, 0x31,
+      0x02, 0x02, 0x00, 0x33, 0x00, 0x00];
+
+fn parse(input: &[u8]) -> SyslogMessage {
+    match SyslogParser::new().parse(input, None, None).unwrap().1.downcast::<SyslogPacket>() {
+        Ok(mut syslog) => syslog.messages.remove(0),
+        Err(_) => panic!("no syslog packet"),
+    }
+}
+
+#[test]
+fn parse_syslog_rfc5424_success() {
+    let mut parser = SyslogParser::new();
+    println!("{}", parser);
+    let input = b"<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - \xef\xbb\xbf'su root' failed\n";
+    let result = parser.parse(input, None, None).unwrap();
+    assert!(result.0.is_empty());
+    assert_eq!(result.1.downcast_ref(),
+               Some(&SyslogPacket {
+                   messages: vec![SyslogMessage {
+                                      facility: SyslogFacility::Auth,
+                                      severity: SyslogSeverity::Critical,
+                                      version: Some(1),
+                                      timestamp: Some(SyslogTimestamp {
+                                          year: Some(2003),
+                                          month: 10,
+                                          day: 11,
+                                          hour: 22,
+                                          minute: 14,
+                                          second: 15,
+                                          nanosecond: 3_000_000,
+                                          utc_offset: Some(0),
+                                      }),
+                                      hostname: Some("mymachine.example.com".to_owned()),
+                                      app_name: Some("su".to_owned()),
+                                      procid: None,
+                                      msgid: Some("ID47".to_owned()),
+                                      structured_data: vec![],
+                                      message: "'su root' failed".to_owned(),
+                                  }],
+               }));
+}
+
+#[test]
+fn parse_syslog_structured_data_success() {
+    let message = parse(b"<165>1 2003-10-11T22:14:15.003-07:00 host evntslog 1234 - [exampleSDID@32473 iut=\"3\" \
+                          eventSource=\"App\\\"lication\\]\"][examplePriority@32473 class=\"high\"] An event");
+    assert_eq!(message.facility, SyslogFacility::Local4);
+    assert_eq!(message.severity, SyslogSeverity::Notice);
+    assert_eq!(message.procid, Some("1234".to_owned()));
+    assert_eq!(message.msgid, None);
+    assert_eq!(message.structured_data,
+               vec![SyslogStructuredData {
+                        id: "exampleSDID@32473".to_owned(),
+                        parameters: vec![("iut".to_owned(), "3".to_owned()),
+                                         ("eventSource".to_owned(), "App\"lication]".to_owned())],
+                    },
+                    SyslogStructuredData {
+                        id: "examplePriority@32473".to_owned(),
+                        parameters: vec![("class".to_owned(), "high".to_owned())],
+                    }]);
+    assert_eq!(message.message, "An event");
+    assert_eq!(message.timestamp.unwrap().utc_offset, Some(-420));
+    assert_eq!(message.timestamp.unwrap().to_system_time(),
+               Some(UNIX_EPOCH + Duration::new(1_065_935_655, 3_000_000)));
+
+    let message = parse(b"<14>1 - - - - - [origin ip=\"10.0.0.1\"]");
+    assert_eq!(message.timestamp, None);
+    assert_eq!(message.hostname, None);
+    assert_eq!(message.structured_data.len(), 1);
+    assert_eq!(message.message, "");
+}
+
+#[test]
+fn parse_syslog_bsd_success() {
+    let message = parse(b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed for lonvick");
+    assert_eq!(message.version, None);
+    assert_eq!(message.timestamp,
+               Some(SyslogTimestamp {
+                   year: None,
+                   month: 10,
+                   day: 11,
+                   hour: 22,
+                   minute: 14,
+                   second: 15,
+                   nanosecond: 0,
+                   utc_offset: None,
+               }));
+    assert_eq!(message.timestamp.unwrap().to_system_time(), None);
+    assert_eq!(message.hostname, Some("mymachine".to_owned()));
+    assert_eq!(message.app_name, Some("su".to_owned()));
+    assert_eq!(message.message, "'su root' failed for lonvick");
+
+    let message = parse(b"<86>Feb  5 17:32:18 10.0.0.99 sshd[1234]: Accepted publickey for root\n");
+    assert_eq!(message.facility, SyslogFacility::AuthPriv);
+    assert_eq!(message.severity, SyslogSeverity::Informational);
+    assert_eq!(message.timestamp.unwrap().day, 5);
+    assert_eq!(message.hostname, Some("10.0.0.99".to_owned()));
+    assert_eq!(message.app_name, Some("sshd".to_owned()));
+    assert_eq!(message.procid, Some("1234".to_owned()));
+    assert_eq!(message.message, "Accepted publickey for root");
+
+    // Missing host name, year and fraction of network devices
+    let message = parse(b"<189>*Mar  1 2023 18:48:50.483: %SYS-5-CONFIG_I: Configured from console");
+    assert_eq!(message.timestamp.unwrap().year, Some(2023));
+    assert_eq!(message.timestamp.unwrap().nanosecond, 483_000_000);
+    assert_eq!(message.hostname, None);
+    assert_eq!(message.app_name, Some("%SYS-5-CONFIG_I".to_owned()));
+    assert_eq!(message.message, "Configured from console");
+
+    // High precision timestamps of rsyslog
+    let message = parse(b"<13>2023-06-01T08:00:00.123456+02:00 web nginx: started");
+    assert_eq!(message.timestamp.unwrap().nanosecond, 123_456_000);
+    assert_eq!(message.hostname, Some("web".to_owned()));
+
+    // Missing timestamp and priority
+    let message = parse(b"<13>myapp: test");
+    assert_eq!(message.timestamp, None);
+    assert_eq!(message.app_name, Some("myapp".to_owned()));
+    assert_eq!(message.message, "test");
+    let message = parse(b"plain text");
+    assert_eq!(message.facility, SyslogFacility::User);
+    assert_eq!(message.severity, SyslogSeverity::Notice);
+    assert_eq!(message.app_name, None);
+    assert_eq!(message.message, "plain text");
+}
+
+#[test]
+fn parse_syslog_failure() {
+    let mut parser = SyslogParser::new();
+    assert!(parser.parse(b"", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"<192>test", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"<>test", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"<13 test", None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_syslog_success() {
+    let mut peel = PeelIp::default();
+    let mut packet = Vec::from(PACKET_ETH_IPV4_UDP);
+    packet.extend_from_slice(b"<13>Oct 11 22:14:15 host app: hello");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref::<SyslogPacket>().unwrap().messages[0].message, "hello");
+}
+
+#[test]
+fn peel_syslog_tcp_success() {
+    let mut peel = PeelIp::default();
+    let message = "<13>1 - host app - - - first";
+    let mut stream = format!("{} {}", message.len(), message).into_bytes();
+    stream.extend_from_slice(b"<14>Oct 11 22:14:15 host app: second\n");
+    stream.extend_from_slice(b"<15>Oct 11 22:14:16 host app: third\n");
+
+    // The second message is split over two segments
+    let mut first = Vec::from(PACKET_ETH_IPV4_TCP);
+    first.extend_from_slice(&stream[..50]);
+    let result = peel.traverse(&first, vec![]).result;
+    assert_eq!(result.len(), 4);
+    let packet = result[3].downcast_ref::<SyslogPacket>().unwrap();
+    assert_eq!(packet.messages.len(), 1);
+    assert_eq!(packet.messages[0].message, "first");
+
+    let mut second = Vec::from(PACKET_ETH_IPV4_TCP);
+    second.extend_from_slice(&stream[50..]);
+    let result = peel.traverse(&second, vec![]).result;
+    let packet = result[3].downcast_ref::<SyslogPacket>().unwrap();
+    assert_eq!(packet.messages.len(), 2);
+    assert_eq!(packet.messages[0].message, "second");
+    assert_eq!(packet.messages[1].severity, SyslogSeverity::Debug);
+}
+
+#[test]
+fn peel_syslog_tcp_octet_count_failure() {
+    let mut peel = PeelIp::default();
+    for stream in &[&b"18446744073709551615 <13>1 - host app - - - overflow"[..],
+                    &b"99999999999999999999999 <13>1 - host app - - - overflow"[..],
+                    &b"65537 <13>1 - host app - - - too long"[..]] {
+        let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+        packet.extend_from_slice(stream);
+        let result = peel.traverse(&packet, vec![]).result;
+        assert!(result.iter().all(|any| any.downcast_ref::<SyslogPacket>().is_none()));
+    }
+}
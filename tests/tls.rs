@@ -31,6 +31,33 @@ fn parse_tls_success() {
                        minor: 1,
                    },
                    length: 244,
+                   body: Some(TlsRecordBody::Handshake(vec![TlsHandshake::ClientHello(TlsClientHello {
+                       version: TlsRecordVersion {
+                           major: 3,
+                           minor: 3,
+                       },
+                       random: TLS_HEADER[11..43].to_vec(),
+                       session_id: TLS_HEADER[44..76].to_vec(),
+                       cookie: None,
+                       cipher_suites: vec![0xc02b, 0xc02f, 0x009e, 0xcc14, 0xcc13, 0xcc15, 0xc00a, 0xc014,
+                                           0x0039, 0xc009, 0xc013, 0x0033, 0x009c, 0x0035, 0x002f, 0x000a],
+                       compression_methods: vec![0],
+                       extensions: vec![TlsExtension::Other(0xff01, vec![0]),
+                                        TlsExtension::ServerName(vec!["asecuritysite.com".to_owned()]),
+                                        TlsExtension::Other(0x0017, vec![]),
+                                        TlsExtension::Other(0x0023, vec![]),
+                                        TlsExtension::Other(0x000d, TLS_HEADER[157..179].to_vec()),
+                                        TlsExtension::Other(0x0005, TLS_HEADER[183..188].to_vec()),
+                                        TlsExtension::Other(0x3374, vec![]),
+                                        TlsExtension::Other(0x0012, vec![]),
+                                        TlsExtension::Alpn(vec!["http/1.1".to_owned(),
+                                                                "spdy/3.1".to_owned(),
+                                                                "h2-14".to_owned(),
+                                                                "h2".to_owned()]),
+                                        TlsExtension::Other(0x7550, vec![]),
+                                        TlsExtension::Other(0x000b, vec![1, 0]),
+                                        TlsExtension::Other(0x000a, TLS_HEADER[243..249].to_vec())],
+                   })])),
                }),
                res);
 }
@@ -64,3 +91,87 @@ fn parse_tls_failure_too_small() {
     let input = [20, 0];
     assert!(parser.parse(&input, None, None).to_full_result().is_err());
 }
+
+#[test]
+fn parse_tls_success_change_cipher_spec() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x14, 0x03, 0x03, 0x00, 0x01, 0x01], None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body, Some(TlsRecordBody::ChangeCipherSpec(1)));
+}
+
+#[test]
+fn parse_tls_success_alert() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28], None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body,
+               Some(TlsRecordBody::Alert(TlsAlert {
+                   level: TlsAlertLevel::Fatal,
+                   description: TlsAlertDescription::HandshakeFailure,
+               })));
+}
+
+#[test]
+fn parse_tls_success_alert_encrypted() {
+    let mut parser = TlsParser;
+    let mut input = vec![0x15, 0x03, 0x03, 0x00, 0x1a];
+    input.extend_from_slice(&[0xab; 26]);
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body, None);
+}
+
+#[test]
+fn parse_tls_success_heartbeat() {
+    let mut parser = TlsParser;
+    let mut input = vec![0x18, 0x03, 0x02, 0x00, 0x17, 0x01, 0x00, 0x04, 0x61, 0x62, 0x63, 0x64];
+    input.extend_from_slice(&[0; 16]);
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body,
+               Some(TlsRecordBody::Heartbeat(TlsHeartbeat {
+                   message_type: TlsHeartbeatMessageType::Request,
+                   payload_length: 4,
+                   payload_overflow: false,
+               })));
+}
+
+#[test]
+fn parse_tls_success_heartbleed() {
+    let mut parser = TlsParser;
+    let input = [0x18, 0x03, 0x02, 0x00, 0x03, 0x01, 0x40, 0x00];
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body,
+               Some(TlsRecordBody::Heartbeat(TlsHeartbeat {
+                   message_type: TlsHeartbeatMessageType::Request,
+                   payload_length: 16384,
+                   payload_overflow: true,
+               })));
+}
+
+#[test]
+fn parse_tls_success_client_hello_helpers() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(TLS_HEADER, None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    match res.unwrap().body {
+        Some(TlsRecordBody::Handshake(ref messages)) => match messages[0] {
+            TlsHandshake::ClientHello(ref hello) => {
+                assert_eq!(hello.server_name(), Some("asecuritysite.com"));
+                assert_eq!(hello.alpn_protocols(), vec!["http/1.1", "spdy/3.1", "h2-14", "h2"]);
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_tls_success_handshake_incomplete() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&TLS_HEADER[..100], None, None).unwrap().1;
+    let res: Option<&TlsPacket> = parsing_result.downcast_ref();
+    assert_eq!(res.unwrap().body, None);
+}
@@ -0,0 +1,213 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+mod common;
+use common::{finish, request, response};
+
+static HANDSHAKE_REQUEST: &'static [u8] = b"GET /chat HTTP/1.1\r\nHost: server.example.com\r\nUpgrade: websocket\r\n\
+                                            Connection: keep-alive, Upgrade\r\n\
+                                            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                                            Sec-WebSocket-Extensions: permessage-deflate\r\n\
+                                            Sec-WebSocket-Version: 13\r\n\r\n";
+
+static HANDSHAKE_RESPONSE: &'static [u8] = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                                             Connection: Upgrade\r\n\
+                                             Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n";
+
+static HANDSHAKE_RESPONSE_DEFLATE: &'static [u8] = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                                                     Connection: Upgrade\r\n\
+                                                     Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\
+                                                     Sec-WebSocket-Extensions: permessage-deflate; \
+                                                     server_no_context_takeover\r\n\r\n";
+
+/// A masked text message "Hello"
+static MASKED_HELLO: &'static [u8] = &[0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+
+fn upgrade(peel: &mut PeelIp, response_head: &[u8]) {
+    let result = peel.traverse(&request(80, HANDSHAKE_REQUEST), vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_some());
+    let result = peel.traverse(&response(80, response_head), vec![]).result;
+    assert!(result.last().unwrap().downcast_ref::<HttpPacket>().is_some());
+}
+
+fn websocket(peel: &mut PeelIp, packet: &[u8]) -> Option<WebSocketPacket> {
+    let mut result = peel.traverse(packet, vec![]).result;
+    result
+        .pop()
+        .and_then(|any| any.downcast::<WebSocketPacket>().ok())
+        .map(|packet| *packet)
+}
+
+fn message(from_client: bool, opcode: WebSocketOpcode, data: &[u8]) -> WebSocketMessage {
+    WebSocketMessage {
+        from_client: from_client,
+        opcode: opcode,
+        compressed: false,
+        data: data.to_vec(),
+        decompressed: None,
+    }
+}
+
+#[test]
+fn websocket_handshake_accept() {
+    let mut parser = HttpParser::new();
+    let request = parser.parse(HANDSHAKE_REQUEST, None, None).unwrap().1;
+    let response = parser.parse(HANDSHAKE_RESPONSE, None, None).unwrap().1;
+    match (request.downcast_ref(), response.downcast_ref()) {
+        (Some(&HttpPacket::Request(ref request)), Some(&HttpPacket::Response(ref response))) => {
+            assert_eq!(request.websocket_key(), Some("dGhlIHNhbXBsZSBub25jZQ=="));
+            assert_eq!(request.websocket_accept_key(),
+                       Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_owned()));
+            assert_eq!(response.websocket_accept(), Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+            assert!(response.accepts_websocket(request));
+        }
+        _ => unreachable!(),
+    }
+
+    // Not an upgrade without the Connection header
+    let request = parser.parse(b"GET / HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: abc\r\n\r\n",
+                               None,
+                               None)
+        .unwrap()
+        .1;
+    match request.downcast_ref() {
+        Some(&HttpPacket::Request(ref request)) => assert_eq!(request.websocket_key(), None),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_websocket_success() {
+    let mut peel = PeelIp::default();
+    assert!(websocket(&mut peel, &request(80, HANDSHAKE_REQUEST)).is_none());
+
+    // Frames of the server may follow the response directly
+    let mut input = Vec::from(HANDSHAKE_RESPONSE);
+    input.extend_from_slice(&[0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+    let packet = websocket(&mut peel, &response(80, &input)).unwrap();
+    assert_eq!(packet.frames,
+               vec![WebSocketFrame {
+                        fin: true,
+                        rsv1: false,
+                        rsv2: false,
+                        rsv3: false,
+                        opcode: WebSocketOpcode::Text,
+                        mask: None,
+                        payload: b"Hello".to_vec(),
+                    }]);
+    assert_eq!(packet.messages, vec![message(false, WebSocketOpcode::Text, b"Hello")]);
+
+    let packet = websocket(&mut peel, &request(80, MASKED_HELLO)).unwrap();
+    assert_eq!(packet.frames[0].mask, Some([0x37, 0xfa, 0x21, 0x3d]));
+    assert_eq!(packet.messages, vec![message(true, WebSocketOpcode::Text, b"Hello")]);
+    assert_eq!(packet.messages[0].text(), Some("Hello".to_owned()));
+}
+
+#[test]
+fn parse_websocket_success_fragmented() {
+    let mut peel = PeelIp::default();
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+
+    // A ping may be sent in between the fragments of a message
+    let packet = websocket(&mut peel,
+                           &response(80, &[0x01, 0x03, 0x48, 0x65, 0x6c, 0x89, 0x02, 0x68, 0x69, 0x80, 0x02, 0x6c,
+                                       0x6f]))
+        .unwrap();
+    assert_eq!(packet.frames.len(), 3);
+    assert_eq!(packet.messages,
+               vec![message(false, WebSocketOpcode::Ping, b"hi"), message(false, WebSocketOpcode::Text, b"Hello")]);
+}
+
+#[test]
+fn parse_websocket_success_segmented() {
+    let mut peel = PeelIp::default();
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+
+    // A binary message with an extended payload length of 256 bytes
+    let mut frame = vec![0x82, 0x7e, 0x01, 0x00];
+    frame.extend((0..256).map(|i| i as u8));
+    let packet = websocket(&mut peel, &response(80, &frame[..100])).unwrap();
+    assert!(packet.frames.is_empty());
+    assert!(packet.messages.is_empty());
+
+    let packet = websocket(&mut peel, &response(80, &frame[100..])).unwrap();
+    assert_eq!(packet.messages, vec![message(false, WebSocketOpcode::Binary, &frame[4..])]);
+}
+
+#[test]
+fn parse_websocket_success_deflate() {
+    let mut peel = PeelIp::default();
+    upgrade(&mut peel, HANDSHAKE_RESPONSE_DEFLATE);
+
+    // The second message refers to the first one via the sliding window
+    let packet = websocket(&mut peel,
+                           &response(80, &[0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00, 0xc1, 0x05, 0xf2, 0x00,
+                                       0x11, 0x00, 0x00]))
+        .unwrap();
+    assert_eq!(packet.messages.len(), 2);
+    for message in &packet.messages {
+        assert!(message.compressed);
+        if cfg!(feature = "gzip") {
+            assert_eq!(message.decompressed, Some(b"Hello".to_vec()));
+            assert_eq!(message.text(), Some("Hello".to_owned()));
+        } else {
+            assert_eq!(message.decompressed, None);
+        }
+    }
+}
+
+#[test]
+fn parse_websocket_success_close() {
+    let mut peel = PeelIp::default();
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+    let packet = websocket(&mut peel, &response(80, &[0x88, 0x06, 0x03, 0xe8, 0x62, 0x79, 0x65, 0x21])).unwrap();
+    assert_eq!(packet.frames[0].opcode, WebSocketOpcode::Close);
+    assert_eq!(packet.frames[0].close_code(), Some(1000));
+    assert_eq!(packet.frames[0].close_reason(), Some("bye!".to_owned()));
+}
+
+#[test]
+fn parse_websocket_failure_invalid_accept() {
+    let mut peel = PeelIp::default();
+    let mut head = Vec::from(HANDSHAKE_RESPONSE);
+    head[HANDSHAKE_RESPONSE.len() - 6] = b'x';
+    upgrade(&mut peel, &head);
+    assert!(websocket(&mut peel, &request(80, MASKED_HELLO)).is_none());
+}
+
+#[test]
+fn parse_websocket_failure_answered_handshake() {
+    // A final response rejects the opening handshake
+    let mut peel = PeelIp::default();
+    peel.traverse(&request(80, HANDSHAKE_REQUEST), vec![]);
+    peel.traverse(&response(80, b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n"), vec![]);
+    peel.traverse(&response(80, HANDSHAKE_RESPONSE), vec![]);
+    assert!(websocket(&mut peel, &request(80, MASKED_HELLO)).is_none());
+
+    // A closed connection drops the opening handshake
+    let mut peel = PeelIp::default();
+    peel.traverse(&request(80, HANDSHAKE_REQUEST), vec![]);
+    peel.traverse(&finish(request(80, b"")), vec![]);
+    peel.traverse(&response(80, HANDSHAKE_RESPONSE), vec![]);
+    assert!(websocket(&mut peel, &request(80, MASKED_HELLO)).is_none());
+}
+
+#[test]
+fn parse_websocket_failure_protocol_error() {
+    let mut peel = PeelIp::default();
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+
+    // Clients have to mask their frames, which stops following the connection
+    assert!(websocket(&mut peel, &request(80, &[0x81, 0x02, 0x68, 0x69])).is_none());
+    assert!(websocket(&mut peel, &request(80, MASKED_HELLO)).is_none());
+
+    // Compression was not negotiated
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+    assert!(websocket(&mut peel, &response(80, &[0xc1, 0x02, 0x68, 0x69])).is_none());
+
+    // Continuation without a preceding fragment and reserved opcodes
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+    assert!(websocket(&mut peel, &response(80, &[0x80, 0x02, 0x68, 0x69])).is_none());
+    upgrade(&mut peel, HANDSHAKE_RESPONSE);
+    assert!(websocket(&mut peel, &response(80, &[0x83, 0x02, 0x68, 0x69])).is_none());
+}